@@ -0,0 +1,100 @@
+// Query latency, to_vec throughput and per-feature FFI overhead
+//
+// Run with `cargo bench`; the GOL is the one GEODESK_BENCH_GOL names, or
+// else the test GOL with the `test-data` feature. Without either the
+// benchmarks are skipped. Compare against a saved baseline
+// (`cargo bench -- --save-baseline main`, then `--baseline main`) to catch
+// regressions in the bridge.
+
+use criterion::{black_box, criterion_group, Criterion, Throughput};
+use geodesk_rs::{BoundingBox, GeoDesk};
+use std::ops::ControlFlow;
+
+/// Queries of increasing result size: few nodes, many ways with tags, and
+/// everything
+const QUERIES: [&str; 3] = ["na[amenity=cafe]", "w[highway]", "*"];
+
+/// The benchmark GOL and its bounds, if there is one
+fn try_open() -> Option<(GeoDesk, BoundingBox)> {
+    let geodesk = match std::env::var_os("GEODESK_BENCH_GOL") {
+        Some(path) => Some(
+            GeoDesk::open(&path)
+                .unwrap_or_else(|e| panic!("GEODESK_BENCH_GOL {}: {}", path.to_string_lossy(), e)),
+        ),
+        #[cfg(feature = "test-data")]
+        None => geodesk_rs::test_data::TestGol::try_open(),
+        #[cfg(not(feature = "test-data"))]
+        None => None,
+    }?;
+    let bounds = geodesk.metadata().unwrap().bounds;
+    Some((geodesk, bounds))
+}
+
+fn open() -> (GeoDesk, BoundingBox) {
+    try_open().expect("checked in main")
+}
+
+fn query_latency(c: &mut Criterion) {
+    let (geodesk, bbox) = open();
+    let mut group = c.benchmark_group("query");
+    for goql in QUERIES {
+        group.bench_function(goql, |b| {
+            b.iter(|| geodesk.query(black_box(goql), bbox).unwrap().count())
+        });
+    }
+    group.finish();
+}
+
+fn to_vec_throughput(c: &mut Criterion) {
+    let (geodesk, bbox) = open();
+    let mut group = c.benchmark_group("to_vec");
+    for goql in QUERIES {
+        let result = geodesk.query(goql, bbox).unwrap();
+        group.throughput(Throughput::Elements(result.count() as u64));
+        group.bench_function(goql, |b| b.iter(|| result.to_vec().unwrap()));
+    }
+    group.finish();
+}
+
+/// Cost of getting each feature across the bridge: visiting it in place,
+/// streaming it, or collecting and copying it
+fn ffi_per_feature(c: &mut Criterion) {
+    let (geodesk, bbox) = open();
+    let goql = "w[highway]";
+    let count = geodesk.query(goql, bbox).unwrap().count();
+    let mut group = c.benchmark_group("per_feature");
+    group.throughput(Throughput::Elements(count as u64));
+    group.bench_function("for_each", |b| {
+        b.iter(|| {
+            let mut sum = 0i64;
+            geodesk
+                .for_each(goql, bbox, |feature| {
+                    sum = sum.wrapping_add(feature.id());
+                    ControlFlow::Continue(())
+                })
+                .unwrap();
+            sum
+        })
+    });
+    group.bench_function("query_iter", |b| {
+        b.iter(|| geodesk.query_iter(goql, bbox).unwrap().count())
+    });
+    group.bench_function("query_to_vec", |b| {
+        b.iter(|| geodesk.query(goql, bbox).unwrap().to_vec().unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, query_latency, to_vec_throughput, ffi_per_feature);
+
+fn main() {
+    if try_open().is_none() {
+        eprintln!(
+            "Skipping benchmarks: set GEODESK_BENCH_GOL to a small GOL \
+             or enable the test-data feature"
+        );
+        return;
+    }
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
@@ -1,28 +1,72 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn main() {
     // Get the directory where the build script is located
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let manifest_path = PathBuf::from(&manifest_dir);
-    let bridge_dir = manifest_path.join("src/bridge");
 
     println!("cargo:rerun-if-changed=src/bridge/geodesk_bridge.h");
     println!("cargo:rerun-if-changed=src/bridge/geodesk_bridge.cpp");
     println!("cargo:rerun-if-changed=src/lib.rs");
     println!("cargo:rerun-if-changed=CMakeLists.txt");
 
+    // With the `system-geodesk` feature, link an installed GeoDESK instead
+    // of building it; only the bridge itself is compiled
+    #[cfg(feature = "system-geodesk")]
+    {
+        println!("cargo:rerun-if-env-changed=GEODESK_DIR");
+        println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
+        let include_dirs = system::locate().unwrap_or_else(|e| {
+            panic!(
+                "System GeoDESK not found ({}); set GEODESK_DIR to its install prefix",
+                e
+            )
+        });
+        compile_bridge(manifest_path.join("src/bridge"), &include_dirs);
+    }
+
+    #[cfg(not(feature = "system-geodesk"))]
+    {
+        let out_dir = env::var("OUT_DIR").unwrap();
+        let out_path = PathBuf::from(&out_dir);
+
+        build_from_source(&manifest_path, &out_path);
+    }
+    link_platform_libs();
+}
+
+/// Build GeoDESK with CMake and compile the bridge with cxx-build
+#[cfg_attr(feature = "system-geodesk", allow(dead_code))]
+fn build_from_source(manifest_path: &Path, out_path: &Path) {
+    let bridge_dir = manifest_path.join("src/bridge");
+
     // Use CMake to build GeoDESK and the bridge
     // This automatically fetches GeoDESK via FetchContent
-    let out_dir = env::var("OUT_DIR").unwrap();
-    let out_path = PathBuf::from(&out_dir);
-
     println!("cargo:warning=Building GeoDESK and bridge with CMake...");
 
-    let dst = cmake::Config::new(&manifest_path)
+    let mut config = cmake::Config::new(manifest_path);
+    config
         .define("CMAKE_BUILD_TYPE", "Release")
-        .define("BUILD_SHARED_LIBS", "OFF")
-        .build();
+        .define("BUILD_SHARED_LIBS", "OFF");
+
+    // With the `vendored` feature, FetchContent uses a local checkout
+    // and never touches the network
+    #[cfg(feature = "vendored")]
+    let source_dir = {
+        let source_dir = vendored::source_dir(manifest_path)
+            .unwrap_or_else(|e| panic!("Vendored GeoDESK sources not found ({})", e));
+        println!(
+            "cargo:warning=Using vendored GeoDESK sources from: {}",
+            source_dir.display()
+        );
+        config
+            .define("FETCHCONTENT_SOURCE_DIR_GEODESK", &source_dir)
+            .define("FETCHCONTENT_FULLY_DISCONNECTED", "ON");
+        source_dir
+    };
+
+    let dst = config.build();
 
     println!("cargo:warning=CMake build completed at: {}", dst.display());
 
@@ -33,37 +77,57 @@ fn main() {
 
     // Also check in the CMake build directory
     let cmake_build_dir = out_path.join("build");
-    println!("cargo:rustc-link-search=native={}/lib", cmake_build_dir.display());
-    println!("cargo:rustc-link-search=native={}", cmake_build_dir.display());
+    println!(
+        "cargo:rustc-link-search=native={}/lib",
+        cmake_build_dir.display()
+    );
+    println!(
+        "cargo:rustc-link-search=native={}",
+        cmake_build_dir.display()
+    );
 
     // Link to GeoDESK (static library built by CMake)
     println!("cargo:rustc-link-lib=static=geodesk");
 
-    // Get the GeoDESK include directory from CMake build
+    // Get the GeoDESK include directory from CMake build (FetchContent
+    // does not copy a local checkout into _deps)
+    #[cfg(not(feature = "vendored"))]
     let geodesk_include = cmake_build_dir.join("_deps/geodesk-src/include");
-
-    // Build the cxx bridge with proper includes
-    let mut build = cxx_build::bridge("src/lib.rs");
-    build
-        .file(bridge_dir.join("geodesk_bridge.cpp"))
-        .flag_if_supported("-std=c++20")
-        .flag_if_supported("/std:c++20") // MSVC
-        .include(&bridge_dir);
+    #[cfg(feature = "vendored")]
+    let geodesk_include = source_dir.join("include");
 
     // Add GeoDESK include path if it exists
+    let mut include_dirs = Vec::new();
     if geodesk_include.exists() {
-        build.include(&geodesk_include);
-        println!("cargo:warning=Using GeoDESK headers from: {}", geodesk_include.display());
+        println!(
+            "cargo:warning=Using GeoDESK headers from: {}",
+            geodesk_include.display()
+        );
+        include_dirs.push(geodesk_include);
     } else {
         // Try to find it in the install prefix
         let alt_include = dst.join("include");
         if alt_include.exists() {
-            build.include(&alt_include);
+            include_dirs.push(alt_include);
         }
     }
 
+    compile_bridge(bridge_dir, &include_dirs);
+}
+
+/// Compile the bridge and the cxx glue against the given GeoDESK headers
+fn compile_bridge(bridge_dir: PathBuf, include_dirs: &[PathBuf]) {
+    let mut build = cxx_build::bridge("src/lib.rs");
+    build
+        .file(bridge_dir.join("geodesk_bridge.cpp"))
+        .flag_if_supported("-std=c++20")
+        .flag_if_supported("/std:c++20") // MSVC
+        .include(&bridge_dir)
+        .includes(include_dirs);
     build.compile("geodesk_bridge_cxx");
+}
 
+fn link_platform_libs() {
     // On Linux, link to standard C++ library and other dependencies
     #[cfg(target_os = "linux")]
     {
@@ -87,3 +151,76 @@ fn main() {
     }
 }
 
+#[cfg(feature = "vendored")]
+mod vendored {
+    use std::env;
+    use std::path::{Path, PathBuf};
+
+    /// Where the crate looks for bundled sources if GEODESK_SOURCE_DIR is unset
+    const BUNDLED_DIR: &str = "vendor/libgeodesk";
+
+    /// Find a GeoDESK source checkout to build without network access
+    ///
+    /// GEODESK_SOURCE_DIR takes precedence; otherwise the sources must be
+    /// bundled in `vendor/libgeodesk` next to the manifest. Either way the
+    /// directory must hold libgeodesk's `CMakeLists.txt`.
+    pub fn source_dir(manifest_path: &Path) -> Result<PathBuf, String> {
+        println!("cargo:rerun-if-env-changed=GEODESK_SOURCE_DIR");
+        let dir = match env::var_os("GEODESK_SOURCE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => manifest_path.join(BUNDLED_DIR),
+        };
+        if !dir.join("CMakeLists.txt").exists() {
+            return Err(format!(
+                "{} is not a libgeodesk checkout; set GEODESK_SOURCE_DIR or bundle it in {}",
+                dir.display(),
+                BUNDLED_DIR
+            ));
+        }
+        Ok(dir)
+    }
+}
+
+#[cfg(feature = "system-geodesk")]
+mod system {
+    use std::env;
+    use std::path::PathBuf;
+
+    /// Find the headers and static library of an installed GeoDESK
+    ///
+    /// GEODESK_DIR takes precedence and must point to an install prefix
+    /// with `include/geodesk/geodesk.h` and the library in `lib` (or
+    /// `lib64`); otherwise pkg-config is asked for `geodesk`, which also
+    /// emits the link flags. Returns the include directories.
+    pub fn locate() -> Result<Vec<PathBuf>, String> {
+        if let Some(dir) = env::var_os("GEODESK_DIR") {
+            let dir = PathBuf::from(dir);
+            let include = dir.join("include");
+            if !include.join("geodesk/geodesk.h").exists() {
+                return Err(format!(
+                    "{} has no include/geodesk/geodesk.h",
+                    dir.display()
+                ));
+            }
+            let lib_dir = ["lib", "lib64"]
+                .iter()
+                .map(|lib| dir.join(lib))
+                .find(|lib| lib.join("libgeodesk.a").exists() || lib.join("geodesk.lib").exists())
+                .ok_or_else(|| format!("{} has no static geodesk library", dir.display()))?;
+            println!("cargo:warning=Using system GeoDESK from: {}", dir.display());
+            println!("cargo:rustc-link-search=native={}", lib_dir.display());
+            println!("cargo:rustc-link-lib=static=geodesk");
+            return Ok(vec![include]);
+        }
+
+        let library = pkg_config::Config::new()
+            .statik(true)
+            .probe("geodesk")
+            .map_err(|e| e.to_string())?;
+        println!(
+            "cargo:warning=Using system GeoDESK {} from pkg-config",
+            library.version
+        );
+        Ok(library.include_paths)
+    }
+}
@@ -1,12 +1,13 @@
 // Example: Custom GOQL queries
-use geodesk_rs::{BoundingBox, GeoDesk};
+use geodesk_rs::testing::bboxes;
+use geodesk_rs::GeoDesk;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Open the GOL file
     let geodesk = GeoDesk::open("../planet-latest.osm.gol")?;
 
     // Define bounding box for a city (using Montreal as example)
-    let bbox = BoundingBox::new(-73.9781, 45.4042, -73.4766, 45.7042);
+    let bbox = bboxes::MONTREAL;
 
     println!("Demonstrating various GOQL queries...\n");
 
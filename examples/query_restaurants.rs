@@ -1,13 +1,13 @@
 // Example: Query restaurants in Montreal
-use geodesk_rs::{GeoDesk, BoundingBox};
+use geodesk_rs::testing::bboxes;
+use geodesk_rs::GeoDesk;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Open the GOL file
     let geodesk = GeoDesk::open("../planet-latest.osm.gol")?;
 
     // Define bounding box for Montreal, Quebec
-    // West: -73.9781°, South: 45.4042°, East: -73.4766°, North: 45.7042°
-    let montreal_bbox = BoundingBox::new(-73.9781, 45.4042, -73.4766, 45.7042);
+    let montreal_bbox = bboxes::MONTREAL;
 
     println!("Querying restaurants in Montreal...");
 
@@ -23,7 +23,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("\n{}. {}", i + 1, restaurant.name);
         println!("   ID: {}", restaurant.id);
         println!("   Type: {}", restaurant.type_name);
-        println!("   Location: {:.4}°, {:.4}°", restaurant.lon, restaurant.lat);
+        println!(
+            "   Location: {:.4}°, {:.4}°",
+            restaurant.lon, restaurant.lat
+        );
 
         // Print some interesting tags
         if let Some(cuisine) = restaurant.tag("cuisine") {
@@ -39,4 +42,3 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
@@ -1,13 +1,13 @@
 // Example: Query road network and bus stops in Copenhagen
-use geodesk_rs::{GeoDesk, BoundingBox};
+use geodesk_rs::testing::bboxes;
+use geodesk_rs::GeoDesk;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Open the GOL file
     let geodesk = GeoDesk::open("../planet-latest.osm.gol")?;
 
     // Define bounding box for Copenhagen, Denmark
-    // West: 12.45°E, South: 55.61°N, East: 12.65°E, North: 55.73°N
-    let copenhagen_bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+    let copenhagen_bbox = bboxes::COPENHAGEN;
 
     println!("Querying Copenhagen transportation infrastructure...\n");
 
@@ -48,4 +48,3 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
@@ -1,11 +1,12 @@
-use geodesk_rs::{BoundingBox, GeoDesk};
+use geodesk_rs::testing::bboxes;
+use geodesk_rs::GeoDesk;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Open the GOL file
     let geodesk = GeoDesk::open("../planet-latest.osm.gol")?;
 
     // Define a bounding box for Copenhagen, Denmark
-    let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+    let bbox = bboxes::COPENHAGEN;
 
     println!("Querying roads in Copenhagen...");
 
@@ -45,12 +46,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             if !nodes.is_empty() {
                 println!("\nFirst node:");
                 let first = &nodes[0];
-                println!("  ID: {}, Lon: {:.6}, Lat: {:.6}", first.id, first.lon, first.lat);
+                println!(
+                    "  ID: {}, Lon: {:.6}, Lat: {:.6}",
+                    first.id, first.lon, first.lat
+                );
 
                 if nodes.len() > 1 {
                     println!("\nLast node:");
                     let last = &nodes[nodes.len() - 1];
-                    println!("  ID: {}, Lon: {:.6}, Lat: {:.6}", last.id, last.lon, last.lat);
+                    println!(
+                        "  ID: {}, Lon: {:.6}, Lat: {:.6}",
+                        last.id, last.lon, last.lat
+                    );
                 }
 
                 // Calculate total length (approximate)
@@ -73,4 +80,3 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
@@ -0,0 +1,16 @@
+// GOQL parsing and translation in Rust, which needs no GOL
+#![no_main]
+
+use geodesk_rs::query::Query;
+use geodesk_rs::testing::bboxes;
+use geodesk_rs::translate::{to_overpass, to_sql, SqlSchema};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|goql: &str| {
+    if let Ok(query) = goql.parse::<Query>() {
+        assert_eq!(query.to_string().parse::<Query>(), Ok(query));
+    }
+    let _ = to_overpass(goql, bboxes::BERLIN);
+    let _ = to_sql(goql, &SqlSchema::default());
+    let _ = Query::from_overpass(goql);
+});
@@ -0,0 +1,35 @@
+// Queries through the bridge on the GOL named by GEODESK_FUZZ_GOL:
+//
+//   GEODESK_FUZZ_GOL=monaco.gol cargo +nightly fuzz run query
+//
+// The input is four coordinates followed by the GOQL string. Malformed
+// queries and boxes must fail with an error; a crash in the engine is a bug.
+#![no_main]
+
+use geodesk_rs::{BoundingBox, GeoDesk};
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+fn geodesk() -> &'static GeoDesk {
+    static GEODESK: OnceLock<GeoDesk> = OnceLock::new();
+    GEODESK.get_or_init(|| {
+        let path = std::env::var_os("GEODESK_FUZZ_GOL").expect("set GEODESK_FUZZ_GOL");
+        GeoDesk::open(path).unwrap()
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 32 {
+        return;
+    }
+    let (coords, goql) = data.split_at(32);
+    let coord = |i: usize| f64::from_le_bytes(coords[i * 8..i * 8 + 8].try_into().unwrap());
+    let Ok(goql) = std::str::from_utf8(goql) else {
+        return;
+    };
+    let bbox = BoundingBox::new(coord(0), coord(1), coord(2), coord(3));
+    if let Ok(result) = geodesk().query(goql, bbox) {
+        let _ = result.to_vec();
+    }
+    let _ = geodesk().count_by_type(goql, bbox);
+});
@@ -0,0 +1,120 @@
+#ifndef GEODESK_H
+#define GEODESK_H
+
+/* Generated with cbindgen from src/capi.rs; do not edit */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct GeoDesk GeoDesk;
+
+// Features matched by `geodesk_query`, read one at a time
+typedef struct GeodeskResult GeodeskResult;
+
+// A feature as seen from C; strings and counts refer to the feature the
+// result is positioned at
+typedef struct GeodeskFeature {
+  int64_t id;
+  // 0 = node, 1 = way, 2 = relation
+  uint8_t type_code;
+  double lon;
+  double lat;
+  // Empty if the feature has no name
+  const char *name;
+  size_t tag_count;
+  // Number of way nodes (0 for nodes and relations)
+  size_t node_count;
+} GeodeskFeature;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Open a GOL file; returns NULL on failure
+//
+// # Safety
+// `path` must be a NUL-terminated string.
+GeoDesk *geodesk_open(const char *path);
+
+// Close a GOL opened with `geodesk_open`, after freeing its results
+//
+// # Safety
+// `geodesk` must come from `geodesk_open`, or be NULL.
+void geodesk_close(GeoDesk *geodesk);
+
+// Find the features matching a GOQL query in a bounding box; returns NULL
+// on failure
+//
+// # Safety
+// `geodesk` must be open and `goql` a NUL-terminated string; the result
+// must be freed before the GOL is closed.
+GeodeskResult *geodesk_query(const GeoDesk *geodesk,
+                             const char *goql,
+                             double west,
+                             double south,
+                             double east,
+                             double north);
+
+// Count the features matching a GOQL query in a bounding box; returns -1
+// on failure
+//
+// # Safety
+// `geodesk` must be open and `goql` a NUL-terminated string.
+int64_t geodesk_count(const GeoDesk *geodesk,
+                      const char *goql,
+                      double west,
+                      double south,
+                      double east,
+                      double north);
+
+// Advance to the next feature; returns NULL after the last one, or on
+// failure (check `geodesk_last_error`)
+//
+// The feature and its strings stay valid until the next call.
+//
+// # Safety
+// `result` must come from `geodesk_query`.
+const GeodeskFeature *geodesk_result_next(GeodeskResult *result);
+
+// Value of a tag of the current feature, or NULL if it doesn't have it
+//
+// # Safety
+// `result` must come from `geodesk_query` and `key` be a NUL-terminated
+// string.
+const char *geodesk_feature_tag(const GeodeskResult *result, const char *key);
+
+// Key and value of the current feature's tag at `index`; returns false if
+// there is no such tag
+//
+// # Safety
+// `result` must come from `geodesk_query`; `key` and `value` must be
+// writable.
+bool geodesk_feature_tag_at(const GeodeskResult *result,
+                            size_t index,
+                            const char **key,
+                            const char **value);
+
+// Coordinates of the current way's node at `index`; returns false if
+// there is no such node
+//
+// # Safety
+// `result` must come from `geodesk_query`; `lon` and `lat` must be
+// writable.
+bool geodesk_feature_node_at(const GeodeskResult *result, size_t index, double *lon, double *lat);
+
+// Free a result from `geodesk_query`
+//
+// # Safety
+// `result` must come from `geodesk_query`, or be NULL.
+void geodesk_result_free(GeodeskResult *result);
+
+// Message of the last error on this thread, or NULL if the last call
+// succeeded; valid until the next call
+const char *geodesk_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GEODESK_H */
@@ -0,0 +1,111 @@
+// Structured postal addresses from addr:* tags
+
+use crate::Feature;
+use std::fmt;
+
+/// A postal address assembled from a feature's `addr:*` tags
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Address {
+    pub housenumber: Option<String>,
+    /// `addr:street`, or `addr:place` for addresses without a street
+    pub street: Option<String>,
+    pub city: Option<String>,
+    pub postcode: Option<String>,
+    /// Usually an ISO 3166-1 alpha-2 code
+    pub country: Option<String>,
+}
+
+impl fmt::Display for Address {
+    /// One-line form, e.g. `Vesterbrogade 3, 1620 København V, DK`; parts
+    /// that are missing are left out
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |parts: &[&Option<String>], separator: &str| {
+            parts
+                .iter()
+                .filter_map(|p| p.as_deref())
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        let line = [
+            join(&[&self.street, &self.housenumber], " "),
+            join(&[&self.postcode, &self.city], " "),
+            self.country.clone().unwrap_or_default(),
+        ];
+        let parts: Vec<&str> = line
+            .iter()
+            .map(String::as_str)
+            .filter(|p| !p.is_empty())
+            .collect();
+        f.write_str(&parts.join(", "))
+    }
+}
+
+impl Feature {
+    /// The feature's address, or `None` if it has no `addr:*` tags
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::{GeoDesk, BoundingBox};
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+    /// for shop in geodesk.query("na[shop]", bbox).unwrap().to_vec().unwrap() {
+    ///     if let Some(address) = shop.address() {
+    ///         println!("{}: {}", shop.name, address);
+    ///     }
+    /// }
+    /// ```
+    pub fn address(&self) -> Option<Address> {
+        let get = |key: &str| {
+            self.tag(key)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let address = Address {
+            housenumber: get("addr:housenumber"),
+            street: get("addr:street").or_else(|| get("addr:place")),
+            city: get("addr:city"),
+            postcode: get("addr:postcode"),
+            country: get("addr:country"),
+        };
+        (address != Address::default()).then_some(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_tags(tags: &[(&str, &str)]) -> Feature {
+        Feature {
+            id: 1,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_address() {
+        let feature = with_tags(&[
+            ("addr:street", "Vesterbrogade"),
+            ("addr:housenumber", "3"),
+            ("addr:postcode", "1620"),
+            ("addr:city", "København V"),
+            ("addr:country", "DK"),
+        ]);
+        let address = feature.address().unwrap();
+        assert_eq!(address.to_string(), "Vesterbrogade 3, 1620 København V, DK");
+
+        let partial = with_tags(&[("addr:place", "Hvide Sande"), ("addr:housenumber", "12")]);
+        assert_eq!(partial.address().unwrap().to_string(), "Hvide Sande 12");
+        assert_eq!(with_tags(&[("amenity", "cafe")]).address(), None);
+    }
+}
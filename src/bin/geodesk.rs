@@ -0,0 +1,196 @@
+// Command-line interface for querying GOL files
+//
+// Build with `cargo install geodesk-rs --features cli`.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use geodesk_rs::export::dialect::{AxisOrder, Crs, ZCoordinate};
+use geodesk_rs::export::{CsvWriter, Dialect, FeatureWriter, GeoJsonWriter};
+use geodesk_rs::{BoundingBox, GeoDesk, Watermark};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "geodesk",
+    version,
+    about = "Query OpenStreetMap data in GOL files"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a GOQL query and print the match count or the matching features
+    Query {
+        /// Path to the GOL file
+        gol: PathBuf,
+        /// GOQL query, e.g. "na[amenity=cafe]"
+        goql: String,
+        /// Bounding box as west,south,east,north (defaults to the whole world)
+        #[arg(long, allow_hyphen_values = true)]
+        bbox: Option<BoundingBox>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = QueryFormat::Count)]
+        format: QueryFormat,
+    },
+    /// Stream the features matching a GOQL query into a file
+    Export {
+        /// Path to the GOL file
+        gol: PathBuf,
+        /// GOQL query, e.g. "w[highway]"
+        goql: String,
+        /// Bounding box as west,south,east,north (defaults to the whole world)
+        #[arg(long, allow_hyphen_values = true)]
+        bbox: Option<BoundingBox>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Geojson)]
+        format: ExportFormat,
+        /// Output file (defaults to stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Tag keys to write as CSV columns
+        #[arg(long, value_delimiter = ',')]
+        columns: Vec<String>,
+        /// Write latitude before longitude
+        #[arg(long)]
+        lat_lon: bool,
+        /// Write a third coordinate (0) for consumers that require 3D input
+        #[arg(long)]
+        z: bool,
+        /// Declare the coordinate reference system in GeoJSON output
+        #[arg(long, value_enum, default_value_t = CrsFormat::None)]
+        crs: CrsFormat,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum QueryFormat {
+    /// Print the number of matching features
+    Count,
+    /// Print matching features as a GeoJSON FeatureCollection
+    Geojson,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// GeoJSON FeatureCollection
+    Geojson,
+    /// Comma-separated values, one row per feature
+    Csv,
+    /// FlatGeobuf (requires the `fgb` feature)
+    Fgb,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CrsFormat {
+    /// No declaration (RFC 7946)
+    None,
+    /// urn:ogc:def:crs:OGC:1.3:CRS84
+    Crs84,
+    /// urn:ogc:def:crs:EPSG::4326
+    Epsg4326,
+}
+
+fn world() -> BoundingBox {
+    BoundingBox::new(-180.0, -90.0, 180.0, 90.0)
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    match cli.command {
+        Command::Query {
+            gol,
+            goql,
+            bbox,
+            format,
+        } => {
+            let geodesk = GeoDesk::open(&gol)?;
+            let result = geodesk.query(&goql, bbox.unwrap_or_else(world))?;
+            match format {
+                QueryFormat::Count => println!("{}", result.count()),
+                QueryFormat::Geojson => {
+                    let mut writer = GeoJsonWriter::new(BufWriter::new(io::stdout().lock()))?
+                        .with_watermark(result.watermark());
+                    for feature in result.to_vec()? {
+                        writer.write_feature(&feature)?;
+                    }
+                    writer.finish()?;
+                    println!();
+                }
+            }
+        }
+        Command::Export {
+            gol,
+            goql,
+            bbox,
+            format,
+            out,
+            columns,
+            lat_lon,
+            z,
+            crs,
+        } => {
+            let geodesk = GeoDesk::open(&gol)?;
+            let out: Box<dyn Write> = match out {
+                Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(BufWriter::new(io::stdout().lock())),
+            };
+            let dialect = Dialect {
+                axis_order: if lat_lon {
+                    AxisOrder::LatLon
+                } else {
+                    AxisOrder::LonLat
+                },
+                z: if z {
+                    ZCoordinate::Zero
+                } else {
+                    ZCoordinate::Omit
+                },
+                crs: match crs {
+                    CrsFormat::None => Crs::Implicit,
+                    CrsFormat::Crs84 => Crs::Crs84,
+                    CrsFormat::Epsg4326 => Crs::Epsg4326,
+                },
+            };
+            let watermark = geodesk.watermark();
+            let mut writer = feature_writer(format, out, &columns, dialect, watermark)?;
+            for feature in geodesk.query_iter(&goql, bbox.unwrap_or_else(world))? {
+                writer.write_feature(&feature?)?;
+            }
+            writer.finish()?;
+        }
+    }
+    Ok(())
+}
+
+fn feature_writer(
+    format: ExportFormat,
+    out: Box<dyn Write>,
+    columns: &[String],
+    dialect: Dialect,
+    watermark: Watermark,
+) -> Result<Box<dyn FeatureWriter>, Box<dyn std::error::Error>> {
+    Ok(match format {
+        ExportFormat::Geojson => {
+            Box::new(GeoJsonWriter::with_dialect(out, dialect)?.with_watermark(watermark))
+        }
+        ExportFormat::Csv => Box::new(CsvWriter::with_dialect(out, columns, dialect)?),
+        #[cfg(feature = "fgb")]
+        ExportFormat::Fgb => Box::new(geodesk_rs::export::FlatGeobufWriter::new(out, "features")?),
+        #[cfg(not(feature = "fgb"))]
+        ExportFormat::Fgb => return Err("FlatGeobuf output requires the `fgb` feature".into()),
+    })
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
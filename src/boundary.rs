@@ -0,0 +1,265 @@
+// Boundary relations assembled into polygons
+
+use crate::error::GeoDeskError;
+use crate::geometry::MemberGeometry;
+use crate::query::quote;
+use crate::refs::{Detail, FeatureRef};
+use crate::testing::bboxes;
+use crate::zonal::Polygon;
+use crate::{BoundingBox, GeoDesk, Node};
+use std::ops::ControlFlow;
+
+/// How [`GeoDesk::boundary_polygon`] finds a boundary relation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoundaryRef {
+    /// The relation with this OSM id
+    Id(i64),
+    /// The relation with this `name` tag
+    Name(String),
+}
+
+impl From<i64> for BoundaryRef {
+    fn from(id: i64) -> Self {
+        BoundaryRef::Id(id)
+    }
+}
+
+impl From<&str> for BoundaryRef {
+    fn from(name: &str) -> Self {
+        BoundaryRef::Name(name.to_string())
+    }
+}
+
+impl From<String> for BoundaryRef {
+    fn from(name: String) -> Self {
+        BoundaryRef::Name(name)
+    }
+}
+
+/// A boundary relation as polygons, from [`GeoDesk::boundary_polygon`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Boundary {
+    pub id: i64,
+    pub name: String,
+    /// One polygon per closed outer ring, with the inner rings inside it
+    /// as holes
+    pub polygons: Vec<Polygon>,
+}
+
+impl Boundary {
+    /// Assemble the outer and inner way members of a relation into
+    /// polygons
+    ///
+    /// Ways are joined end to end by node id into rings. Rings that can't
+    /// be closed, as where an extract cuts through the boundary, are left
+    /// out.
+    pub fn from_members(id: i64, name: &str, members: &[MemberGeometry]) -> Self {
+        let ways = |inner: bool| -> Vec<&[Node]> {
+            members
+                .iter()
+                .filter(|m| m.is_line() && (m.role == "inner") == inner)
+                .filter(|m| inner || matches!(m.role.as_str(), "outer" | ""))
+                .map(|m| m.nodes.as_slice())
+                .collect()
+        };
+        let mut polygons: Vec<Polygon> =
+            stitch(ways(false)).into_iter().map(Polygon::new).collect();
+        for hole in stitch(ways(true)) {
+            let (lon, lat) = hole[0];
+            if let Some(polygon) = polygons.iter_mut().find(|p| p.contains(lon, lat)) {
+                polygon.holes.push(hole);
+            }
+        }
+        Self {
+            id,
+            name: name.to_string(),
+            polygons,
+        }
+    }
+
+    /// Check if a point lies inside the boundary
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        self.polygons.iter().any(|p| p.contains(lon, lat))
+    }
+
+    /// Bounding box of the outer rings
+    pub fn bounds(&self) -> BoundingBox {
+        self.polygons.iter().map(Polygon::bounds).fold(
+            BoundingBox::new(f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |a, b| {
+                BoundingBox::new(
+                    a.west.min(b.west),
+                    a.south.min(b.south),
+                    a.east.max(b.east),
+                    a.north.max(b.north),
+                )
+            },
+        )
+    }
+
+    /// Convert to a `geo-types` multi-polygon
+    #[cfg(feature = "geo")]
+    pub fn to_multi_polygon(&self) -> geo_types::MultiPolygon<f64> {
+        let ring = |ring: &Vec<(f64, f64)>| geo_types::LineString::from(ring.clone());
+        self.polygons
+            .iter()
+            .map(|polygon| {
+                let holes = polygon.holes.iter().map(ring).collect();
+                geo_types::Polygon::new(ring(&polygon.exterior), holes)
+            })
+            .collect()
+    }
+}
+
+/// Join ways sharing end nodes into closed rings of (lon, lat) pairs
+fn stitch(mut ways: Vec<&[Node]>) -> Vec<Vec<(f64, f64)>> {
+    let mut rings = Vec::new();
+    while let Some(first) = ways.pop() {
+        let mut ring: Vec<&Node> = first.iter().collect();
+        while ring.len() < 2 || ring[0].id != ring[ring.len() - 1].id {
+            let end = ring[ring.len() - 1].id;
+            let Some(i) = ways
+                .iter()
+                .position(|w| w[0].id == end || w[w.len() - 1].id == end)
+            else {
+                break;
+            };
+            let next = ways.swap_remove(i);
+            if next[0].id == end {
+                ring.extend(next.iter().skip(1));
+            } else {
+                ring.extend(next.iter().rev().skip(1));
+            }
+        }
+        if ring.len() >= 4 && ring[0].id == ring[ring.len() - 1].id {
+            rings.push(ring.iter().map(|n| (n.lon, n.lat)).collect());
+        }
+    }
+    rings
+}
+
+impl GeoDesk {
+    /// Find a boundary relation by id or name and assemble it into
+    /// polygons (see [`Boundary::from_members`])
+    ///
+    /// Boundaries are looked up across the whole GOL. If several share a
+    /// name, the one with the lowest `admin_level` (the largest) is
+    /// returned; pass the id to pick another. Returns `None` if no
+    /// relation tagged `boundary` matches.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let copenhagen = geodesk.boundary_polygon("København").unwrap().unwrap();
+    /// let cafes = geodesk
+    ///     .query("na[amenity=cafe]", copenhagen.bounds())
+    ///     .unwrap()
+    ///     .to_vec()
+    ///     .unwrap();
+    /// let inside = cafes.iter().filter(|c| copenhagen.contains(c.lon, c.lat));
+    /// println!("{} cafes", inside.count());
+    /// ```
+    pub fn boundary_polygon<B: Into<BoundaryRef>>(
+        &self,
+        boundary: B,
+    ) -> Result<Option<Boundary>, GeoDeskError> {
+        let boundary = boundary.into();
+        let goql_query = match &boundary {
+            BoundaryRef::Id(_) => "r[boundary]".to_string(),
+            BoundaryRef::Name(name) => format!("r[boundary][name={}]", quote(name)),
+        };
+        // Lowest admin level found so far, with the assembled relation
+        let mut best: Option<(u32, Boundary)> = None;
+        let mut error = None;
+        self.for_each(&goql_query, bboxes::WORLD, |relation| {
+            if let BoundaryRef::Id(id) = &boundary {
+                if relation.id() != *id {
+                    return ControlFlow::Continue(());
+                }
+            }
+            match assemble(&relation) {
+                Ok((level, assembled)) => {
+                    if best.as_ref().is_none_or(|(best, _)| level < *best) {
+                        best = Some((level, assembled));
+                    }
+                }
+                Err(e) => {
+                    error = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+            match &boundary {
+                BoundaryRef::Id(_) => ControlFlow::Break(()),
+                BoundaryRef::Name(_) => ControlFlow::Continue(()),
+            }
+        })?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok(best.map(|(_, boundary)| boundary)),
+        }
+    }
+}
+
+// Admin level (u32::MAX if none) and polygons of a boundary relation
+fn assemble(relation: &FeatureRef<'_>) -> Result<(u32, Boundary), GeoDeskError> {
+    let level = relation
+        .tag("admin_level")?
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(u32::MAX);
+    let hydrated = relation.hydrate(Detail::MEMBERS)?;
+    let name = relation.name()?;
+    Ok((
+        level,
+        Boundary::from_members(relation.id(), &name, &hydrated.members),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn way(role: &str, nodes: &[(i64, f64, f64)]) -> MemberGeometry {
+        MemberGeometry {
+            id: nodes[0].0,
+            type_name: "way".to_string(),
+            role: role.to_string(),
+            lon: 0.0,
+            lat: 0.0,
+            nodes: nodes
+                .iter()
+                .map(|&(id, lon, lat)| Node { id, lon, lat })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_from_members() {
+        // An outer ring of three ways, one reversed, with a hole
+        let members = vec![
+            way("outer", &[(1, 0.0, 0.0), (2, 4.0, 0.0)]),
+            way("outer", &[(3, 4.0, 4.0), (2, 4.0, 0.0)]),
+            way("outer", &[(3, 4.0, 4.0), (4, 0.0, 4.0), (1, 0.0, 0.0)]),
+            way(
+                "inner",
+                &[
+                    (10, 1.0, 1.0),
+                    (11, 2.0, 1.0),
+                    (12, 2.0, 2.0),
+                    (10, 1.0, 1.0),
+                ],
+            ),
+            // Unclosed, as if cut off by the edge of an extract
+            way("outer", &[(20, 9.0, 9.0), (21, 9.5, 9.0)]),
+            way("subarea", &[(30, 0.0, 0.0), (31, 1.0, 1.0)]),
+        ];
+        let boundary = Boundary::from_members(7, "Test", &members);
+        assert_eq!(boundary.polygons.len(), 1);
+        assert_eq!(boundary.polygons[0].exterior.len(), 5);
+        assert_eq!(boundary.polygons[0].holes.len(), 1);
+        assert!(boundary.contains(3.0, 3.0));
+        assert!(!boundary.contains(1.5, 1.2));
+        assert_eq!(boundary.bounds(), BoundingBox::new(0.0, 0.0, 4.0, 4.0));
+    }
+}
@@ -1,23 +1,428 @@
 #include "geodesk_bridge.h"
 #include "geodesk-rs/src/lib.rs.h" // Include generated cxx.rs header for FeatureData definition
+#include <algorithm>
+#include <cerrno>
+#include <chrono>
+#include <cmath>
+#include <filesystem>
 #include <geodesk/geodesk.h>
+#include <geodesk/query/TileIndexWalker.h>
+#include <numbers>
 #include <sstream>
 #include <stdexcept>
+#include <utility>
+
+#if defined(__linux__) || defined(__APPLE__)
+#include <fcntl.h>
+#include <sys/mman.h>
+#include <unistd.h>
+#endif
 
 using namespace geodesk;
 
 namespace geodesk_bridge {
 
+// Convert text read from the GOL into a Rust string. Invalid UTF-8 is
+// replaced with U+FFFD, or rejected if the store was opened with the strict
+// UTF-8 policy.
+static rust::String to_rust_string(const std::string &text, bool strict,
+                                   const Feature &feature, const char *what) {
+  if (!strict) {
+    return rust::String::lossy(text);
+  }
+  try {
+    return rust::String(text);
+  } catch (const std::invalid_argument &) {
+    throw std::runtime_error(std::string("Invalid UTF-8 in ") + what + " of " +
+                             feature.typeName() + " " +
+                             std::to_string(feature.id()));
+  }
+}
+
+// Copy the attributes of a feature into `data`, along with its tags and
+// way nodes unless left out
+static void fill_feature_data(Feature &feature, FeatureData &data,
+                              bool strict_utf8, bool tags = true,
+                              bool geometry = true,
+                              size_t max_nodes = SIZE_MAX) {
+  data.id = feature.id();
+  data.type_name = feature.typeName();
+  data.lon = feature.lon();
+  data.lat = feature.lat();
+
+  // Get name tag if available
+  TagValue nameTag = feature["name"];
+  if (nameTag) {
+    data.name = to_rust_string(std::string(nameTag), strict_utf8, feature,
+                               "name");
+  } else {
+    data.name = "";
+  }
+
+  // Collect all tags
+  if (tags) {
+    for (Tag tag : feature.tags()) {
+      data.tag_keys.push_back(
+          to_rust_string(std::string(tag.key()), strict_utf8, feature, "tag key"));
+      data.tag_values.push_back(to_rust_string(std::string(tag.value()),
+                                               strict_utf8, feature,
+                                               "tag value"));
+    }
+  }
+
+  // If this is a way, collect its nodes (geometry) immediately
+  // This avoids the need for a separate query later
+  if (geometry && feature.isWay()) {
+    Nodes nodes = feature.nodes();
+    for (Node node : nodes) {
+      if (data.nodes.size() == max_nodes) {
+        break;
+      }
+      NodeData node_data;
+      node_data.id = node.id();
+      node_data.lon = node.lon();
+      node_data.lat = node.lat();
+      data.nodes.push_back(node_data);
+    }
+  }
+}
+
+// Radius of the sphere of Web Mercator (EPSG:3857), and the latitude at
+// which its square plane ends
+static const double WEB_MERCATOR_RADIUS = 6378137.0;
+static const double WEB_MERCATOR_MAX_LAT = 85.0511287798;
+
+static void project_web_mercator(double &lon, double &lat) {
+  using std::numbers::pi;
+  double clamped =
+      std::clamp(lat, -WEB_MERCATOR_MAX_LAT, WEB_MERCATOR_MAX_LAT);
+  lon = lon * pi / 180.0 * WEB_MERCATOR_RADIUS;
+  lat = std::log(std::tan(pi / 4.0 + clamped * pi / 360.0)) *
+        WEB_MERCATOR_RADIUS;
+}
+
+static void unproject_web_mercator(double &x, double &y) {
+  using std::numbers::pi;
+  x = x / WEB_MERCATOR_RADIUS * 180.0 / pi;
+  y = (2.0 * std::atan(std::exp(y / WEB_MERCATOR_RADIUS)) - pi / 2.0) * 180.0 /
+      pi;
+}
+
+// Replace the degrees of a feature and its nodes with Web Mercator meters
+static void to_web_mercator(FeatureData &data) {
+  project_web_mercator(data.lon, data.lat);
+  for (NodeData &node : data.nodes) {
+    project_web_mercator(node.lon, node.lat);
+  }
+}
+
+// Meters per degree of latitude, for simplification tolerances
+static const double METERS_PER_DEGREE = 111320.0;
+
+// Distance in meters from p to the segment from a to b, with longitudes
+// scaled by `x_scale` meters per degree
+static double segment_distance(const NodeData &p, const NodeData &a,
+                               const NodeData &b, double x_scale) {
+  double px = (p.lon - a.lon) * x_scale;
+  double py = (p.lat - a.lat) * METERS_PER_DEGREE;
+  double dx = (b.lon - a.lon) * x_scale;
+  double dy = (b.lat - a.lat) * METERS_PER_DEGREE;
+  double length2 = dx * dx + dy * dy;
+  double t = length2 > 0 ? std::clamp((px * dx + py * dy) / length2, 0.0, 1.0)
+                         : 0.0;
+  return std::hypot(px - t * dx, py - t * dy);
+}
+
+// Drop the nodes of a way that lie within `tolerance` meters of the line
+// between the nodes kept around them (Douglas-Peucker); the end nodes are
+// always kept, so rings stay closed
+static void simplify_nodes(rust::Vec<NodeData> &nodes, double tolerance) {
+  size_t n = nodes.size();
+  if (n < 3) {
+    return;
+  }
+  double x_scale =
+      std::cos(nodes[0].lat * std::numbers::pi / 180.0) * METERS_PER_DEGREE;
+  std::vector<bool> keep(n, false);
+  keep[0] = keep[n - 1] = true;
+  std::vector<std::pair<size_t, size_t>> spans{{0, n - 1}};
+  while (!spans.empty()) {
+    auto [first, last] = spans.back();
+    spans.pop_back();
+    double max_distance = 0;
+    size_t farthest = first;
+    for (size_t i = first + 1; i < last; i++) {
+      double distance =
+          segment_distance(nodes[i], nodes[first], nodes[last], x_scale);
+      if (distance > max_distance) {
+        max_distance = distance;
+        farthest = i;
+      }
+    }
+    if (max_distance > tolerance) {
+      keep[farthest] = true;
+      spans.push_back({first, farthest});
+      spans.push_back({farthest, last});
+    }
+  }
+  rust::Vec<NodeData> simplified;
+  for (size_t i = 0; i < n; i++) {
+    if (keep[i]) {
+      simplified.push_back(nodes[i]);
+    }
+  }
+  nodes = std::move(simplified);
+}
+
+// Nesting depth of sub-relations whose members are collected (route
+// masters contain routes, which contain ways)
+static const int MAX_MEMBER_DEPTH = 2;
+
+// Append the point or line geometry of each member of a relation,
+// descending into sub-relations up to `depth` levels
+static void collect_members(Feature &relation, rust::Vec<MemberGeometry> &out,
+                            int depth, bool strict_utf8) {
+  Features members = relation.members();
+  for (Feature member : members) {
+    if (member.isRelation()) {
+      if (depth > 0) {
+        collect_members(member, out, depth - 1, strict_utf8);
+      }
+      continue;
+    }
+    MemberGeometry geometry;
+    geometry.id = member.id();
+    geometry.type_name = member.typeName();
+    geometry.role = to_rust_string(std::string(member.role()), strict_utf8,
+                                   relation, "member role");
+    geometry.lon = member.lon();
+    geometry.lat = member.lat();
+    if (member.isWay()) {
+      Nodes nodes = member.nodes();
+      for (Node node : nodes) {
+        NodeData node_data;
+        node_data.id = node.id();
+        node_data.lon = node.lon();
+        node_data.lat = node.lat();
+        geometry.nodes.push_back(node_data);
+      }
+    }
+    out.push_back(std::move(geometry));
+  }
+}
+
+class FeatureHandle : public Feature {
+public:
+  FeatureHandle(const Feature &feature, bool strict_utf8)
+      : Feature(feature), strict_utf8(strict_utf8) {}
+
+  bool strict_utf8;
+};
+
+class TagCursor::Impl {
+public:
+  explicit Impl(const FeatureHandle &handle)
+      : feature(handle), strict_utf8(handle.strict_utf8), tags(feature.tags()),
+        current(tags.begin()), end(tags.end()) {}
+
+  Feature feature;
+  bool strict_utf8;
+  Tags tags;
+  decltype(std::declval<const Tags &>().begin()) current;
+  decltype(std::declval<const Tags &>().end()) end;
+  bool started = false;
+};
+
+TagCursor::TagCursor(const FeatureHandle &handle)
+    : pImpl(std::make_unique<Impl>(handle)) {}
+TagCursor::~TagCursor() = default;
+
+bool TagCursor::next() {
+  if (pImpl->started && pImpl->current != pImpl->end) {
+    ++pImpl->current;
+  }
+  pImpl->started = true;
+  return pImpl->current != pImpl->end;
+}
+
+std::string_view TagCursor::key() const {
+  Key key = (*pImpl->current).key();
+  return std::string_view(key);
+}
+
+rust::String TagCursor::value() const {
+  return to_rust_string(std::string((*pImpl->current).value()),
+                        pImpl->strict_utf8, pImpl->feature, "tag value");
+}
+
+class FeatureList::Impl {
+public:
+  std::vector<FeatureHandle> handles;
+  bool stopped = false;
+};
+
+FeatureList::FeatureList() : pImpl(std::make_unique<Impl>()) {}
+FeatureList::~FeatureList() = default;
+
+size_t FeatureList::size() const { return pImpl->handles.size(); }
+
+const FeatureHandle &FeatureList::at(size_t index) const {
+  return pImpl->handles.at(index);
+}
+
+void FeatureList::set_stopped() { pImpl->stopped = true; }
+
+bool FeatureList::is_stopped() const { return pImpl->stopped; }
+
+void FeatureList::add(const Feature &feature, bool strict_utf8) {
+  pImpl->handles.emplace_back(feature, strict_utf8);
+}
+
+// A tile index entry whose page is 0 refers to a tile that was left out
+// of this file (e.g. a regional extract of a planet GOL)
+static bool tile_present(const geodesk::FeatureStore *store, Tip tip) {
+  return store->tilePage(tip) != 0;
+}
+
+// Convert a bounding box passed from Rust, which may come straight from
+// user input. Coordinates beyond the world are clamped to it, as they would
+// overflow the fixed-point coordinates of the Box; NaN is rejected.
+static Box to_box(const BoundingBox &bbox) {
+  if (std::isnan(bbox.west) || std::isnan(bbox.south) ||
+      std::isnan(bbox.east) || std::isnan(bbox.north)) {
+    throw std::invalid_argument("Bounding box coordinates must be numbers");
+  }
+  return Box::ofWSEN(std::clamp(bbox.west, -180.0, 180.0),
+                     std::clamp(bbox.south, -90.0, 90.0),
+                     std::clamp(bbox.east, -180.0, 180.0),
+                     std::clamp(bbox.north, -90.0, 90.0));
+}
+
+// Visit every tile of the store that intersects `box`
+template <typename F>
+static void walk_tiles(const geodesk::FeatureStore *store, const Box &box,
+                       F &&visit) {
+  TileIndexWalker walker(store->tileIndex(), store->zoomLevels(), box,
+                         nullptr);
+  while (walker.next()) {
+    visit(walker.currentTile(), walker.currentTip());
+  }
+}
+
+// Zoom level of the grid a stoppable scan checks for cancellation on. A
+// GOQL iterator can walk many tiles without yielding a feature, so checks
+// every so many features alone don't stop a scan for something rare.
+static const int CHECK_ZOOM = 8;
+
+// Split `box` along the tile grid at CHECK_ZOOM into disjoint cells
+static std::vector<Box> check_cells(const Box &box) {
+  const int64_t size = int64_t(1) << (32 - CHECK_ZOOM);
+  auto grid_start = [size](int32_t v) {
+    int64_t offset = int64_t(v) - INT32_MIN;
+    return offset - offset % size + INT32_MIN;
+  };
+  std::vector<Box> cells;
+  for (int64_t y = grid_start(box.minY()); y <= box.maxY(); y += size) {
+    for (int64_t x = grid_start(box.minX()); x <= box.maxX(); x += size) {
+      cells.emplace_back(
+          static_cast<int32_t>(std::max<int64_t>(x, box.minX())),
+          static_cast<int32_t>(std::max<int64_t>(y, box.minY())),
+          static_cast<int32_t>(std::min<int64_t>(x + size - 1, box.maxX())),
+          static_cast<int32_t>(std::min<int64_t>(y + size - 1, box.maxY())));
+    }
+  }
+  return cells;
+}
+
+// How a scan of the features of a query ended
+enum class ScanEnd { Complete, Stopped, Ended };
+
+// Pass the features of `query` in `box` to `visit` until it returns false,
+// asking `control` whether to stop every 256 features and, unless it never
+// stops, before each cell of check_cells
+template <typename F>
+static ScanEnd scan_features(const Features &query, const Box &box,
+                             const QueryControl &control, F &&visit) {
+  if (!query_can_stop(control)) {
+    for (Feature feature : query(box)) {
+      if (!visit(feature)) {
+        return ScanEnd::Ended;
+      }
+    }
+    return ScanEnd::Complete;
+  }
+  std::vector<Box> cells = check_cells(box);
+  uint64_t seen = 0;
+  for (const Box &cell : cells) {
+    if (query_should_stop(control)) {
+      return ScanEnd::Stopped;
+    }
+    for (Feature feature : query(cell)) {
+      // A feature found in several cells is visited in the one holding the
+      // south-west corner of its bounds within the box
+      if (cells.size() > 1) {
+        Box bounds = feature.bounds();
+        int32_t x = std::max(bounds.minX(), box.minX());
+        int32_t y = std::max(bounds.minY(), box.minY());
+        if (x < cell.minX() || x > cell.maxX() || y < cell.minY() ||
+            y > cell.maxY()) {
+          continue;
+        }
+      }
+      // Asking Rust costs a call across the bridge, so only every 256
+      // features
+      if ((++seen & 0xFF) == 0 && query_should_stop(control)) {
+        return ScanEnd::Stopped;
+      }
+      if (!visit(feature)) {
+        return ScanEnd::Ended;
+      }
+    }
+  }
+  return ScanEnd::Complete;
+}
+
 // FeatureStore implementation
 class FeatureStore::Impl {
 public:
-  Impl(const std::string &gol_path) : features(gol_path.c_str()) {}
+  Impl(const std::string &gol_path, bool strict_utf8)
+      : path(gol_path), features(gol_path.c_str()), strict_utf8(strict_utf8) {}
 
+  std::string path;
   Features features;
+  bool strict_utf8;
 };
 
-FeatureStore::FeatureStore(const std::string &gol_path)
-    : pImpl(std::make_unique<Impl>(gol_path)) {}
+// Pass read-ahead and prefetch hints for the GOL file to the OS
+static void advise_file(const std::string &path, const StoreOptions &options) {
+#if defined(__linux__)
+  if (options.access == 0 && !options.prefetch) {
+    return;
+  }
+  int fd = open(path.c_str(), O_RDONLY);
+  if (fd < 0) {
+    return;
+  }
+  if (options.access == 1) {
+    posix_fadvise(fd, 0, 0, POSIX_FADV_RANDOM);
+  } else if (options.access == 2) {
+    posix_fadvise(fd, 0, 0, POSIX_FADV_SEQUENTIAL);
+  }
+  if (options.prefetch) {
+    posix_fadvise(fd, 0, 0, POSIX_FADV_WILLNEED);
+  }
+  close(fd);
+#else
+  (void)path;
+  (void)options;
+#endif
+}
+
+FeatureStore::FeatureStore(const std::string &gol_path,
+                           const StoreOptions &options)
+    : pImpl(std::make_unique<Impl>(gol_path, options.strict_utf8)) {
+  advise_file(gol_path, options);
+}
 
 FeatureStore::~FeatureStore() = default;
 
@@ -36,7 +441,7 @@ FeatureStore::query(const std::string &goql_query,
 
   try {
     // Create bounding box
-    Box box = Box::ofWSEN(bbox.west, bbox.south, bbox.east, bbox.north);
+    Box box = to_box(bbox);
 
     // Apply query filter and bounding box
     Features filtered = pImpl->features(goql_query.c_str())(box);
@@ -44,54 +449,511 @@ FeatureStore::query(const std::string &goql_query,
     // Iterate through results and collect data
     for (Feature feature : filtered) {
       FeatureData data;
-      data.id = feature.id();
-      data.type_name = feature.typeName();
-      data.lon = feature.lon();
-      data.lat = feature.lat();
-
-      // Get name tag if available
-      TagValue nameTag = feature["name"];
-      if (nameTag) {
-        data.name = std::string(nameTag);
-      } else {
-        data.name = "";
-      }
-
-      // Collect all tags
-      Tags tags = feature.tags();
-      for (Tag tag : tags) {
-        data.tag_keys.push_back(std::string(tag.key()));
-        data.tag_values.push_back(std::string(tag.value()));
-      }
-
-      // If this is a way, collect its nodes (geometry) immediately
-      // This avoids the need for a separate query later
-      if (feature.isWay()) {
-        Nodes nodes = feature.nodes();
-        for (Node node : nodes) {
-          NodeData node_data;
-          node_data.id = node.id();
-          node_data.lon = node.lon();
-          node_data.lat = node.lat();
-          data.nodes.push_back(node_data);
+      fill_feature_data(feature, data, pImpl->strict_utf8);
+      result->add_feature(std::move(data));
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+
+  return result;
+}
+
+std::unique_ptr<FeatureResult>
+FeatureStore::query(const std::string &goql_query, const BoundingBox &bbox,
+                    const QuerySettings &settings,
+                    const QueryControl &control) const {
+  using Clock = std::chrono::steady_clock;
+  auto result = std::make_unique<FeatureResult>();
+  try {
+    Clock::time_point start = Clock::now();
+    Clock::duration copying{};
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str());
+    uint64_t tiles = 0;
+    if (settings.collect_stats) {
+      // Only reads the tile index, not the tiles
+      walk_tiles(pImpl->features.store(), box, [&](Tile, Tip tip) {
+        tiles += tile_present(pImpl->features.store(), tip);
+      });
+    }
+    uint64_t examined = 0;
+    uint64_t matched = 0;
+    ScanEnd end = scan_features(filtered, box, control, [&](Feature feature) {
+      examined++;
+      // Tag patterns are matched in Rust, with its linear-time regex engine
+      if (settings.match_tags &&
+          !query_matches_tags(control,
+                              FeatureHandle(feature, pImpl->strict_utf8))) {
+        return true;
+      }
+      matched++;
+      uint64_t limit = feature.isWay()        ? settings.max_way_nodes
+                       : feature.isRelation() ? settings.max_relation_members
+                                              : 0;
+      uint64_t size = 0;
+      if (limit > 0 && feature.isRelation()) {
+        size = feature.members().count();
+      }
+      FeatureData data;
+      if (size <= limit || settings.truncate_oversized) {
+        Clock::time_point copy_start;
+        if (settings.collect_stats) {
+          copy_start = Clock::now();
+        }
+        // Copy one node past the limit, which tells whether a way is over it
+        fill_feature_data(feature, data, pImpl->strict_utf8, true, true,
+                          limit > 0 && feature.isWay() ? limit + 1 : SIZE_MAX);
+        if (settings.collect_stats) {
+          copying += Clock::now() - copy_start;
+        }
+        if (limit > 0 && feature.isWay() && data.nodes.size() > limit) {
+          size = feature.nodes().count();
+        }
+      }
+      if (limit > 0 && size > limit) {
+        OversizedFeature oversized;
+        oversized.id = feature.id();
+        oversized.type_name = feature.typeName();
+        oversized.size = size;
+        oversized.truncated = settings.truncate_oversized;
+        result->add_oversized(std::move(oversized));
+        if (!settings.truncate_oversized) {
+          return true;
+        }
+        // Relations are kept whole, as results don't include their members
+        if (feature.isWay()) {
+          data.nodes.truncate(limit);
         }
       }
+      if (settings.max_results > 0 && result->count() == settings.max_results) {
+        result->set_truncated();
+        return false;
+      }
+      if (settings.simplify_tolerance > 0) {
+        simplify_nodes(data.nodes, settings.simplify_tolerance);
+      }
+      result->add_feature(std::move(data), settings.web_mercator);
+      return true;
+    });
+    if (end == ScanEnd::Stopped) {
+      result->set_stopped();
+    }
+    if (settings.collect_stats) {
+      auto nanos = [](Clock::duration d) {
+        return static_cast<uint64_t>(
+            std::chrono::duration_cast<std::chrono::nanoseconds>(d).count());
+      };
+      QueryStatsData stats;
+      stats.tiles = tiles;
+      stats.examined = examined;
+      stats.matched = matched;
+      stats.matching_nanos = nanos(Clock::now() - start - copying);
+      stats.copying_nanos = nanos(copying);
+      result->set_stats(stats);
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+  return result;
+}
+
+std::unique_ptr<FeatureResult>
+FeatureStore::query_counted(const std::string &goql_query,
+                            const BoundingBox &bbox, size_t limit) const {
+  auto result = std::make_unique<FeatureResult>();
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str())(box);
+    for (Feature feature : filtered) {
+      if (result->count() < limit) {
+        FeatureData data;
+        fill_feature_data(feature, data, pImpl->strict_utf8);
+        result->add_feature(std::move(data));
+      } else {
+        result->skip_feature(feature);
+      }
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+  return result;
+}
 
+std::unique_ptr<FeatureResult>
+FeatureStore::query_containing(const std::string &goql_query, double lon,
+                               double lat) const {
+  auto result = std::make_unique<FeatureResult>();
+  try {
+    Features filtered =
+        pImpl->features(goql_query.c_str()).containingLonLat(lon, lat);
+    for (Feature feature : filtered) {
+      FeatureData data;
+      fill_feature_data(feature, data, pImpl->strict_utf8);
       result->add_feature(std::move(data));
     }
   } catch (const std::exception &e) {
     throw std::runtime_error(std::string("Query failed: ") + e.what());
   }
+  return result;
+}
+
+TypeCounts FeatureStore::count_by_type(const std::string &goql_query,
+                                       const BoundingBox &bbox) const {
+  TypeCounts counts{};
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str())(box);
+    for (Feature feature : filtered) {
+      if (feature.isNode()) {
+        counts.nodes++;
+      } else if (feature.isWay()) {
+        counts.ways++;
+      } else {
+        counts.relations++;
+      }
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+  return counts;
+}
 
+rust::Vec<RelationGeometry>
+FeatureStore::relation_geometries(const std::string &goql_query,
+                                  const BoundingBox &bbox) const {
+  rust::Vec<RelationGeometry> result;
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str())(box).relations();
+    for (Feature relation : filtered) {
+      RelationGeometry geometry;
+      geometry.id = relation.id();
+      collect_members(relation, geometry.members, MAX_MEMBER_DEPTH,
+                      pImpl->strict_utf8);
+      result.push_back(std::move(geometry));
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
   return result;
 }
 
+rust::Vec<RelationGeometry>
+FeatureStore::relation_members(const std::string &goql_query,
+                               const BoundingBox &bbox) const {
+  rust::Vec<RelationGeometry> result;
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str())(box).relations();
+    for (Feature relation : filtered) {
+      RelationGeometry geometry;
+      geometry.id = relation.id();
+      Features members = relation.members();
+      for (Feature member : members) {
+        MemberGeometry entry;
+        entry.id = member.id();
+        entry.type_name = member.typeName();
+        entry.role = to_rust_string(std::string(member.role()),
+                                    pImpl->strict_utf8, relation,
+                                    "member role");
+        if (!member.isRelation()) {
+          entry.lon = member.lon();
+          entry.lat = member.lat();
+        }
+        geometry.members.push_back(std::move(entry));
+      }
+      result.push_back(std::move(geometry));
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+  return result;
+}
+
+std::unique_ptr<FeatureList>
+FeatureStore::query_handles(const std::string &goql_query,
+                            const BoundingBox &bbox,
+                            const QueryControl &control) const {
+  auto list = std::make_unique<FeatureList>();
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str());
+    ScanEnd end = scan_features(filtered, box, control, [&](Feature feature) {
+      list->add(feature, pImpl->strict_utf8);
+      return true;
+    });
+    if (end == ScanEnd::Stopped) {
+      list->set_stopped();
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+  return list;
+}
+
+bool FeatureStore::for_each(const std::string &goql_query,
+                            const BoundingBox &bbox, FeatureVisitor &visitor,
+                            const QueryControl &control) const {
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str());
+    ScanEnd end = scan_features(filtered, box, control, [&](Feature feature) {
+      FeatureHandle handle(feature, pImpl->strict_utf8);
+      return visit_feature(visitor, handle);
+    });
+    return end == ScanEnd::Stopped;
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+}
+
+std::unique_ptr<FeatureResult>
+FeatureStore::query_filtered(const std::string &goql_query,
+                             const BoundingBox &bbox, FeatureFilter &filter,
+                             const QueryControl &control) const {
+  auto result = std::make_unique<FeatureResult>();
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str());
+    ScanEnd end = scan_features(filtered, box, control, [&](Feature feature) {
+      FeatureHandle handle(feature, pImpl->strict_utf8);
+      FilterVerdict verdict = filter_feature(filter, handle);
+      if (verdict == FilterVerdict::Keep) {
+        FeatureData data;
+        fill_feature_data(feature, data, pImpl->strict_utf8);
+        result->add_feature(std::move(data));
+      }
+      return verdict != FilterVerdict::Stop;
+    });
+    if (end == ScanEnd::Stopped) {
+      result->set_stopped();
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+  return result;
+}
+
+rust::Vec<FeatureBounds>
+FeatureStore::bounds(const std::string &goql_query,
+                     const BoundingBox &bbox) const {
+  rust::Vec<FeatureBounds> result;
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str())(box);
+    for (Feature feature : filtered) {
+      Box bounds = feature.bounds();
+      FeatureBounds data;
+      data.id = feature.id();
+      data.west = Mercator::lonFromX(bounds.minX());
+      data.south = Mercator::latFromY(bounds.minY());
+      data.east = Mercator::lonFromX(bounds.maxX());
+      data.north = Mercator::latFromY(bounds.maxY());
+      result.push_back(data);
+    }
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+  return result;
+}
+
+// Whether features store their OSM version, timestamp and changeset.
+// GOL format 2 (the only one gol build writes) drops them on import, so
+// this is false until a format version adds them.
+static bool has_osm_metadata() { return false; }
+
+GolMetadata FeatureStore::metadata() const {
+  GolMetadata meta;
+  const geodesk::FeatureStore *store = pImpl->features.store();
+  const geodesk::FeatureStore::Header *header = store->header();
+
+  meta.revision = header->revision;
+  meta.revision_timestamp = header->revisionTimestamp;
+  meta.format_version = (header->versionHigh << 16) | header->versionLow;
+  meta.osm_metadata = has_osm_metadata();
+
+  for (const auto &indexed : store->indexedKeys()) {
+    meta.indexed_keys.push_back(
+        store->strings().getGlobalString(indexed.key)->toString());
+  }
+
+  // Coverage is the union of the bounds of all tiles present in the GOL
+  double west = 180, south = 90, east = -180, north = -90;
+  walk_tiles(store, Box::ofWorld(), [&](Tile tile, Tip tip) {
+    if (!tile_present(store, tip)) {
+      return;
+    }
+    Box bounds = tile.bounds();
+    west = std::min(west, Mercator::lonFromX(bounds.minX()));
+    south = std::min(south, Mercator::latFromY(bounds.minY()));
+    east = std::max(east, Mercator::lonFromX(bounds.maxX()));
+    north = std::max(north, Mercator::latFromY(bounds.maxY()));
+    meta.tile_count++;
+  });
+  if (meta.tile_count > 0) {
+    meta.west = west;
+    meta.south = south;
+    meta.east = east;
+    meta.north = north;
+  }
+  return meta;
+}
+
+// Number of bytes of the file currently held in the OS page cache, or -1
+static int64_t resident_bytes(const std::string &path, uint64_t size) {
+#if defined(__linux__) || defined(__APPLE__)
+  if (size == 0) {
+    return 0;
+  }
+  int fd = open(path.c_str(), O_RDONLY);
+  if (fd < 0) {
+    return -1;
+  }
+  void *addr = mmap(nullptr, size, PROT_READ, MAP_SHARED, fd, 0);
+  close(fd);
+  if (addr == MAP_FAILED) {
+    return -1;
+  }
+  long page_size = sysconf(_SC_PAGESIZE);
+  size_t pages = (size + page_size - 1) / page_size;
+#ifdef __APPLE__
+  std::vector<char> residency(pages);
+#else
+  std::vector<unsigned char> residency(pages);
+#endif
+  int64_t resident = -1;
+  if (mincore(addr, size, residency.data()) == 0) {
+    resident = 0;
+    for (auto page : residency) {
+      if (page & 1) {
+        resident += page_size;
+      }
+    }
+  }
+  munmap(addr, size);
+  return resident;
+#else
+  (void)path;
+  (void)size;
+  return -1;
+#endif
+}
+
+StoreDebugInfo FeatureStore::debug_info() const {
+  // Reached through a bridge function that can't fail, so report what
+  // can't be read as unknown
+  StoreDebugInfo info;
+  info.file_path = rust::String::lossy(pImpl->path);
+  std::error_code ec;
+  uint64_t size = std::filesystem::file_size(pImpl->path, ec);
+  info.mapping_size = ec ? 0 : size;
+  try {
+    info.resident_bytes = resident_bytes(pImpl->path, info.mapping_size);
+  } catch (...) {
+    info.resident_bytes = -1;
+  }
+  return info;
+}
+
+bool FeatureStore::release_memory() const {
+#if defined(__linux__)
+  int fd = open(pImpl->path.c_str(), O_RDONLY);
+  if (fd < 0) {
+    return false;
+  }
+  bool released = posix_fadvise(fd, 0, 0, POSIX_FADV_DONTNEED) == 0;
+  close(fd);
+  return released;
+#else
+  return false;
+#endif
+}
+
+uint32_t FeatureStore::revision() const {
+  return pImpl->features.store()->header()->revision;
+}
+
+int64_t FeatureStore::revision_timestamp() const {
+  return pImpl->features.store()->header()->revisionTimestamp;
+}
+
+rust::Vec<TileData> FeatureStore::tiles(const BoundingBox &bbox) const {
+  rust::Vec<TileData> tiles;
+  const geodesk::FeatureStore *store = pImpl->features.store();
+  Box box = to_box(bbox);
+  walk_tiles(store, box, [&](Tile tile, Tip tip) {
+    TileData data;
+    data.zoom = static_cast<uint8_t>(tile.zoom());
+    data.column = static_cast<uint32_t>(tile.column());
+    data.row = static_cast<uint32_t>(tile.row());
+    data.page = store->tilePage(tip);
+    data.present = data.page != 0;
+    tiles.push_back(data);
+  });
+  return tiles;
+}
+
+// FeatureCursor implementation
+class FeatureCursor::Impl {
+public:
+  Impl(Features f, bool strict_utf8)
+      : features(std::move(f)), it(features.begin()), end(features.end()),
+        strict_utf8(strict_utf8) {}
+
+  Features features;
+  decltype(std::declval<Features &>().begin()) it;
+  decltype(std::declval<Features &>().end()) end;
+  bool strict_utf8;
+};
+
+std::unique_ptr<FeatureCursor>
+FeatureStore::open_cursor(const std::string &goql_query,
+                          const BoundingBox &bbox) const {
+  try {
+    Box box = to_box(bbox);
+    Features filtered = pImpl->features(goql_query.c_str())(box);
+    return std::unique_ptr<FeatureCursor>(new FeatureCursor(
+        std::make_unique<FeatureCursor::Impl>(std::move(filtered),
+                                              pImpl->strict_utf8)));
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+}
+
+FeatureCursor::FeatureCursor(std::unique_ptr<Impl> impl)
+    : pImpl(std::move(impl)) {}
+
+FeatureCursor::~FeatureCursor() = default;
+
+bool FeatureCursor::next(FeatureData &out) {
+  try {
+    if (pImpl->it == pImpl->end) {
+      return false;
+    }
+    Feature feature = *pImpl->it;
+    fill_feature_data(feature, out, pImpl->strict_utf8);
+    ++pImpl->it;
+    return true;
+  } catch (const std::exception &e) {
+    throw std::runtime_error(std::string("Query failed: ") + e.what());
+  }
+}
+
 // FeatureResult implementation
 FeatureResult::FeatureResult() = default;
 FeatureResult::~FeatureResult() = default;
 
 size_t FeatureResult::count() const { return features.size(); }
 
+TypeCounts FeatureResult::counts_by_type() const {
+  TypeCounts counts;
+  counts.nodes = nodes;
+  counts.ways = ways;
+  counts.relations = relations;
+  return counts;
+}
+
 std::unique_ptr<FeatureData> FeatureResult::get(size_t index) const {
   if (index >= features.size()) {
     throw std::out_of_range("Feature index out of range");
@@ -101,13 +963,188 @@ std::unique_ptr<FeatureData> FeatureResult::get(size_t index) const {
 
 std::vector<FeatureData> FeatureResult::to_vector() const { return features; }
 
-void FeatureResult::add_feature(FeatureData &&feature) {
+std::vector<FeatureData> FeatureResult::range(size_t start,
+                                              size_t count) const {
+  start = std::min(start, features.size());
+  size_t end = start + std::min(count, features.size() - start);
+  return std::vector<FeatureData>(features.begin() + start,
+                                  features.begin() + end);
+}
+
+rust::Vec<FeatureKey> FeatureResult::keys() const {
+  rust::Vec<FeatureKey> keys;
+  keys.reserve(features.size());
+  for (const FeatureData &feature : features) {
+    FeatureKey key;
+    key.type_code = feature.type_name == "node"  ? 0
+                    : feature.type_name == "way" ? 1
+                                                 : 2;
+    key.id = feature.id;
+    keys.push_back(key);
+  }
+  return keys;
+}
+
+std::vector<FeatureData>
+FeatureResult::select(rust::Slice<const size_t> indices) const {
+  std::vector<FeatureData> selected;
+  selected.reserve(indices.size());
+  for (size_t index : indices) {
+    if (index >= features.size()) {
+      throw std::out_of_range("Feature index out of range");
+    }
+    selected.push_back(features[index]);
+  }
+  return selected;
+}
+
+void FeatureResult::add_feature(FeatureData &&feature, bool web_mercator) {
+  if (feature.type_name == "node") {
+    nodes++;
+  } else if (feature.type_name == "way") {
+    ways++;
+  } else {
+    relations++;
+  }
+  auto extend = [this](double lon, double lat) {
+    west = std::min(west, lon);
+    south = std::min(south, lat);
+    east = std::max(east, lon);
+    north = std::max(north, lat);
+  };
+  extend(feature.lon, feature.lat);
+  for (const NodeData &node : feature.nodes) {
+    extend(node.lon, node.lat);
+  }
+  if (web_mercator) {
+    to_web_mercator(feature);
+    projected = true;
+  }
   features.push_back(std::move(feature));
 }
 
+FeatureBounds FeatureResult::bounds() const {
+  FeatureBounds bounds{};
+  bounds.west = west;
+  bounds.south = south;
+  bounds.east = east;
+  bounds.north = north;
+  return bounds;
+}
+
+// Andrew's monotone chain
+rust::Vec<NodeData> FeatureResult::convex_hull() const {
+  std::vector<NodeData> points;
+  for (const FeatureData &feature : features) {
+    if (feature.nodes.empty()) {
+      NodeData point{};
+      point.lon = feature.lon;
+      point.lat = feature.lat;
+      points.push_back(point);
+    } else {
+      points.insert(points.end(), feature.nodes.begin(), feature.nodes.end());
+    }
+  }
+  if (projected) {
+    for (NodeData &point : points) {
+      unproject_web_mercator(point.lon, point.lat);
+    }
+  }
+  std::sort(points.begin(), points.end(),
+            [](const NodeData &a, const NodeData &b) {
+              return a.lon < b.lon || (a.lon == b.lon && a.lat < b.lat);
+            });
+  points.erase(std::unique(points.begin(), points.end(),
+                           [](const NodeData &a, const NodeData &b) {
+                             return a.lon == b.lon && a.lat == b.lat;
+                           }),
+               points.end());
+  rust::Vec<NodeData> result;
+  if (points.size() < 3) {
+    for (const NodeData &point : points) {
+      result.push_back(point);
+    }
+    return result;
+  }
+  // Positive if o, a, b turn counter-clockwise
+  auto cross = [](const NodeData &o, const NodeData &a, const NodeData &b) {
+    return (a.lon - o.lon) * (b.lat - o.lat) -
+           (a.lat - o.lat) * (b.lon - o.lon);
+  };
+  std::vector<NodeData> hull(2 * points.size());
+  size_t k = 0;
+  // Lower hull, then upper hull
+  for (size_t i = 0; i < points.size(); i++) {
+    while (k >= 2 && cross(hull[k - 2], hull[k - 1], points[i]) <= 0) {
+      k--;
+    }
+    hull[k++] = points[i];
+  }
+  for (size_t i = points.size() - 1, lower = k + 1; i > 0; i--) {
+    while (k >= lower && cross(hull[k - 2], hull[k - 1], points[i - 1]) <= 0) {
+      k--;
+    }
+    hull[k++] = points[i - 1];
+  }
+  // The last point repeats the first
+  for (size_t i = 0; i + 1 < k; i++) {
+    result.push_back(hull[i]);
+  }
+  return result;
+}
+
+void FeatureResult::add_oversized(OversizedFeature &&feature) {
+  oversized.push_back(std::move(feature));
+}
+
+rust::Vec<OversizedFeature> FeatureResult::oversized_features() const {
+  rust::Vec<OversizedFeature> copy;
+  for (const OversizedFeature &feature : oversized) {
+    copy.push_back(feature);
+  }
+  return copy;
+}
+
+void FeatureResult::set_truncated() { truncated = true; }
+
+bool FeatureResult::is_truncated() const { return truncated; }
+
+void FeatureResult::set_stopped() { stopped = true; }
+
+bool FeatureResult::is_stopped() const { return stopped; }
+
+void FeatureResult::set_stats(const QueryStatsData &stats) {
+  stats_tiles = stats.tiles;
+  stats_examined = stats.examined;
+  stats_matched = stats.matched;
+  stats_matching_nanos = stats.matching_nanos;
+  stats_copying_nanos = stats.copying_nanos;
+}
+
+QueryStatsData FeatureResult::stats() const {
+  QueryStatsData stats;
+  stats.tiles = stats_tiles;
+  stats.examined = stats_examined;
+  stats.matched = stats_matched;
+  stats.matching_nanos = stats_matching_nanos;
+  stats.copying_nanos = stats_copying_nanos;
+  return stats;
+}
+
+void FeatureResult::skip_feature(const Feature &feature) {
+  if (feature.isNode()) {
+    nodes++;
+  } else if (feature.isWay()) {
+    ways++;
+  } else {
+    relations++;
+  }
+}
+
 // Factory functions for cxx.rs
-std::unique_ptr<FeatureStore> create_feature_store(rust::Str gol_path) {
-  return std::make_unique<FeatureStore>(std::string(gol_path));
+std::unique_ptr<FeatureStore>
+create_feature_store(rust::Str gol_path, const StoreOptions &options) {
+  return std::make_unique<FeatureStore>(std::string(gol_path), options);
 }
 
 std::unique_ptr<FeatureResult>
@@ -125,11 +1162,285 @@ std::unique_ptr<FeatureResult> query_with_goql(const FeatureStore &store,
   return store.query(std::string(goql_query), bbox);
 }
 
+std::unique_ptr<FeatureResult>
+query_with_settings(const FeatureStore &store, rust::Str goql_query,
+                    double west, double south, double east, double north,
+                    const QuerySettings &settings,
+                    const QueryControl &control) {
+  BoundingBox bbox{west, south, east, north};
+  return store.query(std::string(goql_query), bbox, settings, control);
+}
+
+std::unique_ptr<FeatureResult> query_counted(const FeatureStore &store,
+                                             rust::Str goql_query, double west,
+                                             double south, double east,
+                                             double north, size_t limit) {
+  BoundingBox bbox{west, south, east, north};
+  return store.query_counted(std::string(goql_query), bbox, limit);
+}
+
+std::unique_ptr<FeatureCursor> query_cursor(const FeatureStore &store,
+                                            rust::Str goql_query, double west,
+                                            double south, double east,
+                                            double north) {
+  BoundingBox bbox{west, south, east, north};
+  return store.open_cursor(std::string(goql_query), bbox);
+}
+
+bool cursor_next(FeatureCursor &cursor, FeatureData &out) {
+  return cursor.next(out);
+}
+
+GolMetadata store_metadata(const FeatureStore &store) {
+  return store.metadata();
+}
+
+StoreDebugInfo store_debug_info(const FeatureStore &store) {
+  return store.debug_info();
+}
+
+uint32_t store_revision(const FeatureStore &store) {
+  return store.revision();
+}
+
+int64_t store_revision_timestamp(const FeatureStore &store) {
+  return store.revision_timestamp();
+}
+
+bool store_release_memory(const FeatureStore &store) {
+  return store.release_memory();
+}
+
+int32_t create_memory_file(rust::Slice<const uint8_t> data) {
+#if defined(__linux__)
+  int fd = memfd_create("geodesk-gol", MFD_CLOEXEC);
+  if (fd < 0) {
+    throw std::runtime_error("Failed to create an in-memory file");
+  }
+  const uint8_t *p = data.data();
+  size_t left = data.size();
+  while (left > 0) {
+    ssize_t written = write(fd, p, left);
+    if (written < 0) {
+      if (errno == EINTR) {
+        continue;
+      }
+      close(fd);
+      throw std::runtime_error("Failed to fill an in-memory file");
+    }
+    p += written;
+    left -= static_cast<size_t>(written);
+  }
+  return fd;
+#else
+  (void)data;
+  throw std::runtime_error("In-memory files are only supported on Linux");
+#endif
+}
+
+rust::Vec<TileData> store_tiles(const FeatureStore &store, double west,
+                                double south, double east, double north) {
+  BoundingBox bbox{west, south, east, north};
+  return store.tiles(bbox);
+}
+
+TypeCounts count_by_type(const FeatureStore &store, rust::Str goql_query,
+                         double west, double south, double east,
+                         double north) {
+  BoundingBox bbox{west, south, east, north};
+  return store.count_by_type(std::string(goql_query), bbox);
+}
+
+std::unique_ptr<FeatureResult> query_containing(const FeatureStore &store,
+                                                rust::Str goql_query,
+                                                double lon, double lat) {
+  return store.query_containing(std::string(goql_query), lon, lat);
+}
+
+rust::Vec<RelationGeometry>
+query_relation_geometries(const FeatureStore &store, rust::Str goql_query,
+                          double west, double south, double east,
+                          double north) {
+  BoundingBox bbox{west, south, east, north};
+  return store.relation_geometries(std::string(goql_query), bbox);
+}
+
+rust::Vec<RelationGeometry>
+query_relation_members(const FeatureStore &store, rust::Str goql_query,
+                       double west, double south, double east, double north) {
+  BoundingBox bbox{west, south, east, north};
+  return store.relation_members(std::string(goql_query), bbox);
+}
+
+rust::Vec<OversizedFeature> result_oversized(const FeatureResult &result) {
+  return result.oversized_features();
+}
+
+bool result_truncated(const FeatureResult &result) {
+  return result.is_truncated();
+}
+
+bool result_stopped(const FeatureResult &result) { return result.is_stopped(); }
+
+QueryStatsData result_stats(const FeatureResult &result) {
+  return result.stats();
+}
+
+rust::Vec<FeatureBounds> query_bounds(const FeatureStore &store,
+                                      rust::Str goql_query, double west,
+                                      double south, double east, double north) {
+  BoundingBox bbox{west, south, east, north};
+  return store.bounds(std::string(goql_query), bbox);
+}
+
+std::unique_ptr<FeatureList>
+query_handles(const FeatureStore &store, rust::Str goql_query, double west,
+              double south, double east, double north,
+              const QueryControl &control) {
+  BoundingBox bbox{west, south, east, north};
+  return store.query_handles(std::string(goql_query), bbox, control);
+}
+
+bool query_for_each(const FeatureStore &store, rust::Str goql_query,
+                    double west, double south, double east, double north,
+                    FeatureVisitor &visitor, const QueryControl &control) {
+  BoundingBox bbox{west, south, east, north};
+  return store.for_each(std::string(goql_query), bbox, visitor, control);
+}
+
+std::unique_ptr<FeatureResult>
+query_filtered(const FeatureStore &store, rust::Str goql_query, double west,
+               double south, double east, double north, FeatureFilter &filter,
+               const QueryControl &control) {
+  BoundingBox bbox{west, south, east, north};
+  return store.query_filtered(std::string(goql_query), bbox, filter, control);
+}
+
+size_t list_len(const FeatureList &list) { return list.size(); }
+
+bool list_stopped(const FeatureList &list) { return list.is_stopped(); }
+
+const FeatureHandle &list_get(const FeatureList &list, size_t index) {
+  return list.at(index);
+}
+
+int64_t handle_id(const FeatureHandle &handle) { return handle.id(); }
+
+uint8_t handle_type(const FeatureHandle &handle) {
+  return handle.isNode() ? 0 : handle.isWay() ? 1 : 2;
+}
+
+double handle_lon(const FeatureHandle &handle) { return handle.lon(); }
+
+double handle_lat(const FeatureHandle &handle) { return handle.lat(); }
+
+FeatureBounds handle_bounds(const FeatureHandle &handle) {
+  Box bounds = handle.bounds();
+  FeatureBounds data;
+  data.id = handle.id();
+  data.west = Mercator::lonFromX(bounds.minX());
+  data.south = Mercator::latFromY(bounds.minY());
+  data.east = Mercator::lonFromX(bounds.maxX());
+  data.north = Mercator::latFromY(bounds.maxY());
+  return data;
+}
+
+bool handle_osm_metadata(const FeatureHandle &handle, OsmMetadata &out) {
+  (void)handle;
+  if (!has_osm_metadata()) {
+    return false;
+  }
+  out = OsmMetadata{};
+  return true;
+}
+
+rust::String handle_name(const FeatureHandle &handle) {
+  TagValue name = handle["name"];
+  return name ? to_rust_string(std::string(name), handle.strict_utf8, handle,
+                               "name")
+              : rust::String();
+}
+
+bool feature_tag(const FeatureHandle &handle, rust::Str key,
+                 rust::String &value) {
+  TagValue tag = handle[std::string(key).c_str()];
+  if (!tag) {
+    return false;
+  }
+  value = to_rust_string(std::string(tag), handle.strict_utf8, handle,
+                         "tag value");
+  return true;
+}
+
+void handle_hydrate(const FeatureHandle &handle, bool tags, bool geometry,
+                    FeatureData &out) {
+  Feature feature = handle;
+  fill_feature_data(feature, out, handle.strict_utf8, tags, geometry);
+}
+
+std::unique_ptr<TagCursor> handle_tags(const FeatureHandle &handle) {
+  return std::make_unique<TagCursor>(handle);
+}
+
+bool tag_cursor_next(TagCursor &cursor) { return cursor.next(); }
+
+rust::Str tag_cursor_key(const TagCursor &cursor, const FeatureHandle &handle) {
+  std::string_view key = cursor.key();
+  // Keys are borrowed rather than copied, so they can't be repaired
+  try {
+    return rust::Str(key.data(), key.size());
+  } catch (const std::invalid_argument &) {
+    throw std::runtime_error("Invalid UTF-8 in tag key of " +
+                             std::string(handle.typeName()) + " " +
+                             std::to_string(handle.id()));
+  }
+}
+
+rust::String tag_cursor_value(const TagCursor &cursor) {
+  return cursor.value();
+}
+
+rust::Vec<MemberGeometry> handle_members(const FeatureHandle &handle) {
+  rust::Vec<MemberGeometry> members;
+  Feature feature = handle;
+  if (feature.isRelation()) {
+    collect_members(feature, members, 0, handle.strict_utf8);
+  }
+  return members;
+}
+
 size_t result_count(const FeatureResult &result) { return result.count(); }
 
+TypeCounts result_counts_by_type(const FeatureResult &result) {
+  return result.counts_by_type();
+}
+
 std::unique_ptr<std::vector<FeatureData>>
 result_to_vec(const FeatureResult &result) {
   return std::make_unique<std::vector<FeatureData>>(result.to_vector());
 }
 
+std::unique_ptr<std::vector<FeatureData>>
+result_range(const FeatureResult &result, size_t start, size_t count) {
+  return std::make_unique<std::vector<FeatureData>>(
+      result.range(start, count));
+}
+
+rust::Vec<FeatureKey> result_keys(const FeatureResult &result) {
+  return result.keys();
+}
+
+FeatureBounds result_bounds(const FeatureResult &result) {
+  return result.bounds();
+}
+
+rust::Vec<NodeData> result_convex_hull(const FeatureResult &result) {
+  return result.convex_hull();
+}
+
+std::unique_ptr<std::vector<FeatureData>>
+result_select(const FeatureResult &result, rust::Slice<const size_t> indices) {
+  return std::make_unique<std::vector<FeatureData>>(result.select(indices));
+}
+
 } // namespace geodesk_bridge
@@ -2,19 +2,74 @@
 
 #include "rust/cxx.h"
 #include <cstdint>
+#include <exception>
+#include <limits>
 #include <memory>
+#include <new>
 #include <string>
+#include <string_view>
 #include <vector>
 
+// Exceptions thrown by bridge functions declared with `Result` become Rust
+// errors. cxx only catches std::exception by default; anything else would
+// terminate the process, which a long-running server can't afford.
+namespace rust {
+namespace behavior {
+template <typename Try, typename Fail>
+static void trycatch(Try &&func, Fail &&fail) noexcept try {
+  func();
+} catch (const std::bad_alloc &) {
+  fail("Out of memory");
+} catch (const std::exception &e) {
+  fail(e.what());
+} catch (...) {
+  fail("Unknown C++ exception");
+}
+} // namespace behavior
+} // namespace rust
+
+namespace geodesk {
+class Feature;
+}
+
 namespace geodesk_bridge {
 
 // Forward declarations
 class FeatureStore;
 class FeatureResult;
+class FeatureCursor;
+class FeatureList;
+
+// A feature of an open store, referring directly into the GOL; defined in
+// the implementation file as a geodesk::Feature
+class FeatureHandle;
+class TagCursor;
+
+// Rust closure receiving the features of GeoDesk::for_each
+struct FeatureVisitor;
+
+// Rust predicate of GeoDesk::query_filtered
+struct FeatureFilter;
+
+// Rust-side cancellation token, deadline and tag patterns of a query
+struct QueryControl;
 
 // Note: FeatureData and NodeData are defined by cxx.rs in the generated bridge code
 struct FeatureData;
 struct NodeData;
+struct GolMetadata;
+struct OsmMetadata;
+struct StoreDebugInfo;
+struct TileData;
+struct StoreOptions;
+struct TypeCounts;
+struct FeatureBounds;
+struct FeatureKey;
+struct MemberGeometry;
+struct RelationGeometry;
+struct QuerySettings;
+struct OversizedFeature;
+struct QueryStatsData;
 
 // Structure to represent a bounding box
 struct BoundingBox {
@@ -27,7 +82,7 @@ struct BoundingBox {
 // Main wrapper class for Features collection
 class FeatureStore {
 public:
-  FeatureStore(const std::string &gol_path);
+  FeatureStore(const std::string &gol_path, const StoreOptions &options);
   ~FeatureStore();
 
   // Query amenities within a bounding box
@@ -39,6 +94,80 @@ public:
   std::unique_ptr<FeatureResult> query(const std::string &goql_query,
                                        const BoundingBox &bbox) const;
 
+  // Generic query, skipping or truncating features over the size limits
+  // and stopping early when `control` asks to
+  std::unique_ptr<FeatureResult> query(const std::string &goql_query,
+                                       const BoundingBox &bbox,
+                                       const QuerySettings &settings,
+                                       const QueryControl &control) const;
+
+  // Features matching a GOQL query whose geometry contains a point
+  std::unique_ptr<FeatureResult> query_containing(const std::string &goql_query,
+                                                  double lon, double lat) const;
+
+  // Count all matches but only collect the first `limit`
+  std::unique_ptr<FeatureResult> query_counted(const std::string &goql_query,
+                                               const BoundingBox &bbox,
+                                               size_t limit) const;
+
+  // Count matches by feature type without collecting them
+  TypeCounts count_by_type(const std::string &goql_query,
+                           const BoundingBox &bbox) const;
+
+  // Member geometries of the relations matching a GOQL query
+  rust::Vec<RelationGeometry>
+  relation_geometries(const std::string &goql_query,
+                      const BoundingBox &bbox) const;
+
+  // Direct members of the relations matching a GOQL query, relations
+  // included, without way nodes
+  rust::Vec<RelationGeometry>
+  relation_members(const std::string &goql_query,
+                   const BoundingBox &bbox) const;
+
+  // Handles to the features matching a GOQL query, without copying their
+  // tags or geometry
+  std::unique_ptr<FeatureList> query_handles(const std::string &goql_query,
+                                             const BoundingBox &bbox,
+                                             const QueryControl &control) const;
+
+  // Pass each feature matching a GOQL query to a Rust visitor, until it
+  // asks to stop; returns true if `control` stopped the query
+  bool for_each(const std::string &goql_query, const BoundingBox &bbox,
+                FeatureVisitor &visitor, const QueryControl &control) const;
+
+  // Collect the features matching a GOQL query that a Rust predicate
+  // accepts; the predicate sees each feature before it is copied
+  std::unique_ptr<FeatureResult> query_filtered(const std::string &goql_query,
+                                                const BoundingBox &bbox,
+                                                FeatureFilter &filter,
+                                                const QueryControl &control) const;
+
+  // Bounding boxes of the features matching a GOQL query
+  rust::Vec<FeatureBounds> bounds(const std::string &goql_query,
+                                  const BoundingBox &bbox) const;
+
+  // Lazily iterate a GOQL query without collecting the results
+  std::unique_ptr<FeatureCursor> open_cursor(const std::string &goql_query,
+                                             const BoundingBox &bbox) const;
+
+  // Header information and tile extent of the GOL
+  GolMetadata metadata() const;
+
+  // File and memory-mapping state, for diagnostics
+  StoreDebugInfo debug_info() const;
+
+  // Revision number from the GOL header
+  uint32_t revision() const;
+  int64_t revision_timestamp() const;
+
+  // Ask the OS to drop the file's pages from the page cache
+  bool release_memory() const;
+
+  // Tiles of the tile index intersecting the bounding box, including
+  // tiles that are absent from this file
+  rust::Vec<TileData> tiles(const BoundingBox &bbox) const;
+
 private:
   class Impl;
   std::unique_ptr<Impl> pImpl;
@@ -51,18 +180,119 @@ public:
   ~FeatureResult();
 
   size_t count() const;
+  TypeCounts counts_by_type() const;
   std::unique_ptr<FeatureData> get(size_t index) const;
   std::vector<FeatureData> to_vector() const;
+  // Up to `count` features starting at `start`
+  std::vector<FeatureData> range(size_t start, size_t count) const;
+  rust::Vec<FeatureKey> keys() const;
+  std::vector<FeatureData> select(rust::Slice<const size_t> indices) const;
+
+  // Bounds of the features' locations and nodes in degrees, kept as they
+  // are added (west > east while empty)
+  FeatureBounds bounds() const;
+  // Convex hull of the features' nodes (and locations of features without
+  // nodes) in degrees, counter-clockwise
+  rust::Vec<NodeData> convex_hull() const;
+
+  // Internal method to add features; with `web_mercator`, the feature is
+  // projected after its degrees have extended the bounds
+  void add_feature(FeatureData &&feature, bool web_mercator = false);
 
-  // Internal method to add features
-  void add_feature(FeatureData &&feature);
+  // Count a feature without collecting it
+  void skip_feature(const geodesk::Feature &feature);
+
+  // Record a feature that exceeded a size limit
+  void add_oversized(OversizedFeature &&feature);
+  rust::Vec<OversizedFeature> oversized_features() const;
+
+  // Record that more features matched than were collected
+  void set_truncated();
+  bool is_truncated() const;
+
+  // Record that the query was cancelled or timed out
+  void set_stopped();
+  bool is_stopped() const;
+
+  // Matches and time spent matching and copying features
+  // (QuerySettings::collect_stats)
+  void set_stats(const QueryStatsData &stats);
+  QueryStatsData stats() const;
 
 private:
   std::vector<FeatureData> features;
+  std::vector<OversizedFeature> oversized;
+  bool truncated = false;
+  bool stopped = false;
+  bool projected = false;
+  uint64_t stats_tiles = 0;
+  uint64_t stats_examined = 0;
+  uint64_t stats_matched = 0;
+  uint64_t stats_matching_nanos = 0;
+  uint64_t stats_copying_nanos = 0;
+  uint64_t nodes = 0;
+  uint64_t ways = 0;
+  uint64_t relations = 0;
+  double west = std::numeric_limits<double>::infinity();
+  double south = std::numeric_limits<double>::infinity();
+  double east = -std::numeric_limits<double>::infinity();
+  double north = -std::numeric_limits<double>::infinity();
+};
+
+// Streaming cursor over the features matched by a query
+class FeatureCursor {
+public:
+  ~FeatureCursor();
+
+  // Fill `out` with the next feature; returns false once exhausted
+  bool next(FeatureData &out);
+
+private:
+  friend class FeatureStore;
+  class Impl;
+  explicit FeatureCursor(std::unique_ptr<Impl> impl);
+  std::unique_ptr<Impl> pImpl;
+};
+
+// Position in the tags of a feature
+class TagCursor {
+public:
+  explicit TagCursor(const FeatureHandle &handle);
+  ~TagCursor();
+
+  // Move to the next tag (the first on the first call); false once
+  // exhausted
+  bool next();
+  std::string_view key() const;
+  rust::String value() const;
+
+private:
+  class Impl;
+  std::unique_ptr<Impl> pImpl;
+};
+
+// Handles collected by a query
+class FeatureList {
+public:
+  FeatureList();
+  ~FeatureList();
+
+  size_t size() const;
+  const FeatureHandle &at(size_t index) const;
+  void add(const geodesk::Feature &feature, bool strict_utf8);
+
+  // Record that the query was cancelled or timed out
+  void set_stopped();
+  bool is_stopped() const;
+
+private:
+  class Impl;
+  std::unique_ptr<Impl> pImpl;
 };
 
 // C++ factory functions for cxx.rs
-std::unique_ptr<FeatureStore> create_feature_store(rust::Str gol_path);
+std::unique_ptr<FeatureStore>
+create_feature_store(rust::Str gol_path, const StoreOptions &options);
 
 std::unique_ptr<FeatureResult>
 query_amenities_in_bbox(const FeatureStore &store, rust::Str amenity_type,
@@ -73,9 +303,132 @@ std::unique_ptr<FeatureResult> query_with_goql(const FeatureStore &store,
                                                double west, double south,
                                                double east, double north);
 
+std::unique_ptr<FeatureResult>
+query_with_settings(const FeatureStore &store, rust::Str goql_query,
+                    double west, double south, double east, double north,
+                    const QuerySettings &settings,
+                    const QueryControl &control);
+
+std::unique_ptr<FeatureResult> query_counted(const FeatureStore &store,
+                                             rust::Str goql_query, double west,
+                                             double south, double east,
+                                             double north, size_t limit);
+
+std::unique_ptr<FeatureCursor> query_cursor(const FeatureStore &store,
+                                            rust::Str goql_query, double west,
+                                            double south, double east,
+                                            double north);
+
+bool cursor_next(FeatureCursor &cursor, FeatureData &out);
+
+GolMetadata store_metadata(const FeatureStore &store);
+
+StoreDebugInfo store_debug_info(const FeatureStore &store);
+
+uint32_t store_revision(const FeatureStore &store);
+int64_t store_revision_timestamp(const FeatureStore &store);
+
+bool store_release_memory(const FeatureStore &store);
+
+// Copy data into an anonymous in-memory file and return its descriptor
+int32_t create_memory_file(rust::Slice<const uint8_t> data);
+
+rust::Vec<TileData> store_tiles(const FeatureStore &store, double west,
+                                double south, double east, double north);
+
+TypeCounts count_by_type(const FeatureStore &store, rust::Str goql_query,
+                         double west, double south, double east,
+                         double north);
+
+std::unique_ptr<FeatureResult> query_containing(const FeatureStore &store,
+                                                rust::Str goql_query,
+                                                double lon, double lat);
+
+rust::Vec<RelationGeometry>
+query_relation_geometries(const FeatureStore &store, rust::Str goql_query,
+                          double west, double south, double east,
+                          double north);
+
+rust::Vec<RelationGeometry>
+query_relation_members(const FeatureStore &store, rust::Str goql_query,
+                       double west, double south, double east, double north);
+
+rust::Vec<FeatureBounds> query_bounds(const FeatureStore &store,
+                                      rust::Str goql_query, double west,
+                                      double south, double east, double north);
+
+std::unique_ptr<FeatureList>
+query_handles(const FeatureStore &store, rust::Str goql_query, double west,
+              double south, double east, double north,
+              const QueryControl &control);
+
+bool query_for_each(const FeatureStore &store, rust::Str goql_query,
+                    double west, double south, double east, double north,
+                    FeatureVisitor &visitor, const QueryControl &control);
+
+std::unique_ptr<FeatureResult>
+query_filtered(const FeatureStore &store, rust::Str goql_query, double west,
+               double south, double east, double north, FeatureFilter &filter,
+               const QueryControl &control);
+
+size_t list_len(const FeatureList &list);
+
+bool list_stopped(const FeatureList &list);
+
+const FeatureHandle &list_get(const FeatureList &list, size_t index);
+
+int64_t handle_id(const FeatureHandle &handle);
+
+uint8_t handle_type(const FeatureHandle &handle);
+
+double handle_lon(const FeatureHandle &handle);
+
+double handle_lat(const FeatureHandle &handle);
+
+FeatureBounds handle_bounds(const FeatureHandle &handle);
+
+rust::String handle_name(const FeatureHandle &handle);
+
+bool handle_osm_metadata(const FeatureHandle &handle, OsmMetadata &out);
+
+bool feature_tag(const FeatureHandle &handle, rust::Str key,
+                 rust::String &value);
+
+void handle_hydrate(const FeatureHandle &handle, bool tags, bool geometry,
+                    FeatureData &out);
+
+rust::Vec<MemberGeometry> handle_members(const FeatureHandle &handle);
+
+std::unique_ptr<TagCursor> handle_tags(const FeatureHandle &handle);
+
+bool tag_cursor_next(TagCursor &cursor);
+
+rust::Str tag_cursor_key(const TagCursor &cursor, const FeatureHandle &handle);
+
+rust::String tag_cursor_value(const TagCursor &cursor);
+
 size_t result_count(const FeatureResult &result);
 
+TypeCounts result_counts_by_type(const FeatureResult &result);
+
+rust::Vec<OversizedFeature> result_oversized(const FeatureResult &result);
+bool result_truncated(const FeatureResult &result);
+bool result_stopped(const FeatureResult &result);
+QueryStatsData result_stats(const FeatureResult &result);
+
 std::unique_ptr<std::vector<FeatureData>>
 result_to_vec(const FeatureResult &result);
 
+std::unique_ptr<std::vector<FeatureData>>
+result_range(const FeatureResult &result, size_t start, size_t count);
+
+rust::Vec<FeatureKey> result_keys(const FeatureResult &result);
+
+FeatureBounds result_bounds(const FeatureResult &result);
+
+rust::Vec<NodeData> result_convex_hull(const FeatureResult &result);
+
+std::unique_ptr<std::vector<FeatureData>>
+result_select(const FeatureResult &result, rust::Slice<const size_t> indices);
+
 } // namespace geodesk_bridge
@@ -0,0 +1,412 @@
+// Building GOL files from OSM PBF extracts, and extracts of GOLs, with the
+// GeoDESK gol tool
+
+use crate::boundary::Boundary;
+use crate::error::GeoDeskError;
+use crate::zonal::Polygon;
+use crate::{BoundingBox, GeoDesk};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Settings of a GOL build, passed to `gol build`
+///
+/// Unset settings keep the tool's defaults. See the GeoDESK documentation
+/// of `gol build` for what each one does.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::builder::BuildOptions;
+///
+/// let options = BuildOptions::new()
+///     .threads(8)
+///     .max_tiles(16_000)
+///     .indexed_keys(["amenity", "building", "highway", "shop"])
+///     .updatable(true);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    tool: Option<PathBuf>,
+    threads: Option<usize>,
+    max_tiles: Option<u32>,
+    min_tile_density: Option<u32>,
+    tile_zoom_levels: Option<String>,
+    indexed_keys: Vec<String>,
+    areas: Option<String>,
+    updatable: bool,
+    id_indexing: bool,
+    extra: Vec<(String, String)>,
+}
+
+impl BuildOptions {
+    /// The tool's defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path of the `gol` executable; by default `GEODESK_GOL_TOOL` or
+    /// `gol` on the `PATH`
+    pub fn tool<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.tool = Some(path.into());
+        self
+    }
+
+    /// Number of threads to build with
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Most tiles the GOL may have
+    pub fn max_tiles(mut self, tiles: u32) -> Self {
+        self.max_tiles = Some(tiles);
+        self
+    }
+
+    /// Fewest nodes a tile needs to get tiles of its own at the next zoom
+    /// level
+    pub fn min_tile_density(mut self, nodes: u32) -> Self {
+        self.min_tile_density = Some(nodes);
+        self
+    }
+
+    /// Zoom levels of the tile pyramid, such as `"4,6,8,10,12"`
+    pub fn tile_zoom_levels(mut self, levels: &str) -> Self {
+        self.tile_zoom_levels = Some(levels.to_string());
+        self
+    }
+
+    /// Keys to index, so queries filtering on them skip other features
+    pub fn indexed_keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.indexed_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Rules deciding which closed ways are areas, in the tool's syntax
+    pub fn areas(mut self, rules: &str) -> Self {
+        self.areas = Some(rules.to_string());
+        self
+    }
+
+    /// Keep the data needed to update the GOL later
+    pub fn updatable(mut self, updatable: bool) -> Self {
+        self.updatable = updatable;
+        self
+    }
+
+    /// Build an index of feature ids, for looking features up by id
+    pub fn id_indexing(mut self, id_indexing: bool) -> Self {
+        self.id_indexing = id_indexing;
+        self
+    }
+
+    /// Pass any other `--name=value` option to the tool
+    pub fn option(mut self, name: &str, value: &str) -> Self {
+        self.extra.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn tool_path(&self) -> PathBuf {
+        self.tool
+            .clone()
+            .or_else(|| env::var_os("GEODESK_GOL_TOOL").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("gol"))
+    }
+
+    // Arguments of `gol build` after the file names
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |name: &str, value: String| args.push(format!("--{}={}", name, value));
+        if let Some(threads) = self.threads {
+            push("threads", threads.to_string());
+        }
+        if let Some(tiles) = self.max_tiles {
+            push("max-tiles", tiles.to_string());
+        }
+        if let Some(density) = self.min_tile_density {
+            push("min-tile-density", density.to_string());
+        }
+        if let Some(levels) = &self.tile_zoom_levels {
+            push("tile-zoom-levels", levels.clone());
+        }
+        if !self.indexed_keys.is_empty() {
+            push("indexed-keys", self.indexed_keys.join(","));
+        }
+        if let Some(areas) = &self.areas {
+            push("areas", areas.clone());
+        }
+        if self.updatable {
+            push("updatable", "yes".to_string());
+        }
+        if self.id_indexing {
+            push("id-indexing", "yes".to_string());
+        }
+        for (name, value) in &self.extra {
+            push(name.trim_start_matches('-'), value.clone());
+        }
+        args
+    }
+}
+
+/// Builds GOL files from OSM PBF extracts
+///
+/// libgeodesk only reads GOLs, so builds run the GeoDESK `gol` tool
+/// (<https://www.geodesk.com/download>) as a separate process. It must be
+/// installed, at the path given with [`BuildOptions::tool`], named by
+/// `GEODESK_GOL_TOOL` or on the `PATH`; otherwise builds fail with
+/// [`GeoDeskError::ToolNotFound`].
+pub struct GolBuilder;
+
+impl GolBuilder {
+    /// Build a GOL from a PBF extract, replacing `output` if it exists
+    ///
+    /// Fails with [`GeoDeskError::InvalidPath`] if the input doesn't
+    /// exist, with [`GeoDeskError::ToolNotFound`] if the `gol` tool isn't
+    /// installed, and with [`GeoDeskError::Engine`] if it can't be run or
+    /// reports an error (with its message).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::builder::{BuildOptions, GolBuilder};
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// GolBuilder::from_pbf("denmark-latest.osm.pbf", "denmark.gol", &BuildOptions::new())
+    ///     .unwrap();
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let cafes = geodesk.query("na[amenity=cafe]", bboxes::COPENHAGEN).unwrap();
+    /// ```
+    pub fn from_pbf<I: AsRef<Path>, O: AsRef<Path>>(
+        input: I,
+        output: O,
+        options: &BuildOptions,
+    ) -> Result<(), GeoDeskError> {
+        let (input, output) = (input.as_ref(), output.as_ref());
+        if !input.is_file() {
+            return Err(GeoDeskError::InvalidPath(input.to_path_buf()));
+        }
+        let mut args = vec![OsString::from("build"), output.into(), input.into()];
+        args.extend(options.args().into_iter().map(OsString::from));
+        let context = format!("GOL build of {} failed", input.display());
+        run_tool(&options.tool_path(), &args, &context)
+    }
+}
+
+/// Run the gol tool, with the last line of its error output on failure
+fn run_tool(tool: &Path, args: &[OsString], context: &str) -> Result<(), GeoDeskError> {
+    let result = Command::new(tool)
+        .args(args)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => GeoDeskError::ToolNotFound {
+                tool: tool.to_path_buf(),
+            },
+            _ => GeoDeskError::Engine(format!(
+                "{}: failed to run {}: {}",
+                context,
+                tool.display(),
+                e
+            )),
+        })?;
+    if result.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    let message = stderr.lines().rev().find(|l| !l.trim().is_empty());
+    Err(GeoDeskError::Engine(format!(
+        "{}: {} ({})",
+        context,
+        message.unwrap_or("no error message").trim(),
+        result.status
+    )))
+}
+
+/// The area of a GOL extract, from [`GeoDesk::extract`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractArea {
+    Bbox(BoundingBox),
+    /// Polygons such as a [`Boundary`]'s, with holes
+    Polygons(Vec<Polygon>),
+}
+
+impl From<BoundingBox> for ExtractArea {
+    fn from(bbox: BoundingBox) -> Self {
+        ExtractArea::Bbox(bbox)
+    }
+}
+
+impl From<Polygon> for ExtractArea {
+    fn from(polygon: Polygon) -> Self {
+        ExtractArea::Polygons(vec![polygon])
+    }
+}
+
+impl From<Boundary> for ExtractArea {
+    fn from(boundary: Boundary) -> Self {
+        ExtractArea::Polygons(boundary.polygons)
+    }
+}
+
+impl ExtractArea {
+    // The gol tool option restricting a command to the area
+    fn arg(&self) -> String {
+        match self {
+            ExtractArea::Bbox(b) => {
+                format!("--bbox={},{},{},{}", b.west, b.south, b.east, b.north)
+            }
+            ExtractArea::Polygons(polygons) => {
+                let ring = |ring: &[(f64, f64)]| {
+                    let mut points: Vec<String> = ring
+                        .iter()
+                        .map(|(lon, lat)| format!("{} {}", lon, lat))
+                        .collect();
+                    if ring.first() != ring.last() {
+                        points.push(points[0].clone());
+                    }
+                    format!("({})", points.join(","))
+                };
+                let polygons: Vec<String> = polygons
+                    .iter()
+                    .filter(|p| !p.exterior.is_empty())
+                    .map(|p| {
+                        let mut rings = vec![ring(&p.exterior)];
+                        rings.extend(p.holes.iter().filter(|h| !h.is_empty()).map(|h| ring(h)));
+                        format!("({})", rings.join(","))
+                    })
+                    .collect();
+                format!("--area=MULTIPOLYGON({})", polygons.join(","))
+            }
+        }
+    }
+}
+
+impl GeoDesk {
+    /// Write the tiles of the GOL that cover an area into a new, smaller
+    /// GOL, such as a city for a mobile app from a planet file
+    ///
+    /// Tiles are copied whole, so the extract also holds features near
+    /// the area, and features crossing its edge keep their full geometry.
+    /// Runs the GeoDESK `gol` tool as a subprocess (see [`GolBuilder`]),
+    /// saving the tiles to a temporary tile set and loading them into
+    /// `output`. Fails with [`GeoDeskError::ToolNotFound`] if the tool
+    /// isn't installed, and with [`GeoDeskError::InvalidPath`] if `output`
+    /// is the GOL itself.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let planet = GeoDesk::open("planet.gol").unwrap();
+    /// let copenhagen = planet.boundary_polygon("København").unwrap().unwrap();
+    /// planet.extract(copenhagen, "copenhagen.gol").unwrap();
+    /// ```
+    pub fn extract<A: Into<ExtractArea>, P: AsRef<Path>>(
+        &self,
+        area: A,
+        output: P,
+    ) -> Result<(), GeoDeskError> {
+        let source = self.debug_state().path;
+        let output = output.as_ref();
+        if output == source {
+            return Err(GeoDeskError::InvalidPath(output.to_path_buf()));
+        }
+        let area = area.into().arg();
+        let tiles = env::temp_dir().join(format!(
+            "geodesk-extract-{}-{}",
+            process::id(),
+            EXTRACTS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tool = BuildOptions::new().tool_path();
+        let save: [OsString; 4] = [
+            "save".into(),
+            source.into(),
+            tiles.clone().into(),
+            (&area).into(),
+        ];
+        let load: [OsString; 4] = [
+            "load".into(),
+            output.into(),
+            tiles.clone().into(),
+            area.into(),
+        ];
+        let context = "GOL extract failed";
+        let result = run_tool(&tool, &save, context).and_then(|_| run_tool(&tool, &load, context));
+        // The tile set is only an intermediate
+        let _ = fs::remove_dir_all(&tiles);
+        result
+    }
+}
+
+/// Extracts started by this process, to keep their tile sets apart
+static EXTRACTS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_args() {
+        let options = BuildOptions::new()
+            .threads(4)
+            .indexed_keys(["amenity", "highway"])
+            .updatable(true)
+            .option("--rtree-branch-size", "16");
+        assert_eq!(
+            options.args(),
+            [
+                "--threads=4",
+                "--indexed-keys=amenity,highway",
+                "--updatable=yes",
+                "--rtree-branch-size=16"
+            ]
+        );
+        assert!(BuildOptions::new().args().is_empty());
+        assert_eq!(
+            BuildOptions::new().tool("/opt/gol").tool_path(),
+            PathBuf::from("/opt/gol")
+        );
+
+        let missing = GolBuilder::from_pbf("no-such.osm.pbf", "out.gol", &options);
+        assert!(matches!(missing, Err(GeoDeskError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn test_tool_not_found() {
+        let options = BuildOptions::new().tool("/nonexistent/gol");
+        let input = env::temp_dir().join(format!("geodesk-builder-{}.osm.pbf", process::id()));
+        fs::write(&input, b"").unwrap();
+        let result = GolBuilder::from_pbf(&input, "out.gol", &options);
+        let _ = fs::remove_file(&input);
+        match result {
+            Err(GeoDeskError::ToolNotFound { tool }) => {
+                assert_eq!(tool, PathBuf::from("/nonexistent/gol"))
+            }
+            other => panic!("expected ToolNotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_extract_area() {
+        let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+        assert_eq!(
+            ExtractArea::from(bbox).arg(),
+            "--bbox=12.45,55.61,12.65,55.73"
+        );
+        let mut square = Polygon::new(vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+        square
+            .holes
+            .push(vec![(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 1.0)]);
+        assert_eq!(
+            ExtractArea::from(square).arg(),
+            "--area=MULTIPOLYGON(((0 0,4 0,4 4,0 4,0 0),(1 1,2 1,2 2,1 1)))"
+        );
+    }
+}
@@ -0,0 +1,537 @@
+// Caching query results, on disk in a compact binary encoding or in
+// memory for repeated queries
+
+use crate::error::GeoDeskError;
+use crate::{BoundingBox, Feature, FeatureType, GeoDesk, Node, QueryResult, Watermark};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Start of every cache file
+const MAGIC: &[u8; 4] = b"GDKC";
+/// Version of the encoding, bumped whenever the layout changes
+const VERSION: u8 = 1;
+/// Features converted at a time while writing a cache
+const BATCH_SIZE: usize = 10_000;
+
+fn invalid(message: &str) -> GeoDeskError {
+    GeoDeskError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn write_varint<W: Write>(out: &mut W, mut value: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    out.write_all(&buf[..len])
+}
+
+fn write_signed<W: Write>(out: &mut W, value: i64) -> io::Result<()> {
+    // Zigzag, so small negative numbers (like id deltas) stay short
+    write_varint(out, ((value << 1) ^ (value >> 63)) as u64)
+}
+
+fn write_str<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+    write_varint(out, s.len() as u64)?;
+    out.write_all(s.as_bytes())
+}
+
+fn write_f64<W: Write>(out: &mut W, value: f64) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> Result<Option<u8>, GeoDeskError> {
+    let mut byte = [0u8; 1];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64, GeoDeskError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(reader)?
+            .ok_or_else(|| GeoDeskError::Io(io::ErrorKind::UnexpectedEof.into()))?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+fn read_signed<R: Read>(reader: &mut R) -> Result<i64, GeoDeskError> {
+    let value = read_varint(reader)?;
+    Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+fn read_str<R: Read>(reader: &mut R) -> Result<String, GeoDeskError> {
+    let len = read_varint(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(GeoDeskError::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    String::from_utf8(bytes).map_err(|_| invalid("string is not UTF-8"))
+}
+
+fn read_f64<R: Read>(reader: &mut R) -> Result<f64, GeoDeskError> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+impl Feature {
+    /// Append the feature to a cache in the encoding of
+    /// [`QueryResult::serialize_to`], without the header
+    ///
+    /// Strings and ids are stored as varints, node ids as deltas, and
+    /// coordinates as the exact `f64` values.
+    pub fn write_cache<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let type_code = match self.feature_type() {
+            FeatureType::Node => 0u8,
+            FeatureType::Way => 1,
+            FeatureType::Relation => 2,
+        };
+        out.write_all(&[type_code])?;
+        write_signed(out, self.id)?;
+        write_str(out, &self.name)?;
+        write_f64(out, self.lon)?;
+        write_f64(out, self.lat)?;
+        write_varint(out, self.tags.len() as u64)?;
+        for (key, value) in &self.tags {
+            write_str(out, key)?;
+            write_str(out, value)?;
+        }
+        write_varint(out, self.nodes.len() as u64)?;
+        let mut previous = 0i64;
+        for node in &self.nodes {
+            write_signed(out, node.id.wrapping_sub(previous))?;
+            write_f64(out, node.lon)?;
+            write_f64(out, node.lat)?;
+            previous = node.id;
+        }
+        Ok(())
+    }
+
+    /// Read the next feature written by [`Feature::write_cache`]
+    ///
+    /// Returns `None` at the end of the input. To read a whole cache file,
+    /// including its header, use a [`CacheReader`].
+    pub fn from_cache<R: Read>(reader: &mut R) -> Result<Option<Feature>, GeoDeskError> {
+        let type_name = match read_byte(reader)? {
+            None => return Ok(None),
+            Some(0) => FeatureType::Node,
+            Some(1) => FeatureType::Way,
+            Some(2) => FeatureType::Relation,
+            Some(_) => return Err(invalid("unknown feature type")),
+        };
+        let id = read_signed(reader)?;
+        let name = read_str(reader)?;
+        let lon = read_f64(reader)?;
+        let lat = read_f64(reader)?;
+        // Counts come from the file, so don't trust them for allocations
+        let tag_count = read_varint(reader)?;
+        let mut tags = Vec::with_capacity(tag_count.min(64) as usize);
+        for _ in 0..tag_count {
+            tags.push((read_str(reader)?, read_str(reader)?));
+        }
+        let node_count = read_varint(reader)?;
+        let mut nodes = Vec::with_capacity(node_count.min(4096) as usize);
+        let mut previous = 0i64;
+        for _ in 0..node_count {
+            let id = previous.wrapping_add(read_signed(reader)?);
+            nodes.push(Node {
+                id,
+                lon: read_f64(reader)?,
+                lat: read_f64(reader)?,
+            });
+            previous = id;
+        }
+        Ok(Some(Feature {
+            id,
+            type_name: type_name.as_str().to_string(),
+            name,
+            lon,
+            lat,
+            tags,
+            nodes,
+        }))
+    }
+}
+
+impl QueryResult {
+    /// Write the features to a cache that [`CacheReader`] loads back
+    /// without touching the GOL
+    ///
+    /// The cache starts with the result's [`Watermark`], so jobs reading
+    /// it can tell whether the data has changed since. Features are
+    /// converted in batches while writing; wrap files in a `BufWriter`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::cache::CacheReader;
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    /// use std::fs::File;
+    /// use std::io::{BufReader, BufWriter};
+    ///
+    /// let geodesk = GeoDesk::open("planet.gol").unwrap();
+    /// let hospitals = geodesk.query("na[amenity=hospital]", bboxes::WORLD).unwrap();
+    /// let file = File::create("hospitals.cache").unwrap();
+    /// hospitals.serialize_to(BufWriter::new(file)).unwrap();
+    ///
+    /// // In a later job
+    /// let file = File::open("hospitals.cache").unwrap();
+    /// let reader = CacheReader::new(BufReader::new(file)).unwrap();
+    /// if reader.watermark() == geodesk.watermark() {
+    ///     let hospitals: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+    /// }
+    /// ```
+    pub fn serialize_to<W: Write>(&self, mut out: W) -> Result<(), GeoDeskError> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        out.write_all(&self.watermark.revision.to_le_bytes())?;
+        let millis = self
+            .watermark
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        out.write_all(&millis.to_le_bytes())?;
+        out.write_all(&(self.count() as u64).to_le_bytes())?;
+        for batch in self.chunks(BATCH_SIZE) {
+            for feature in batch? {
+                feature.write_cache(&mut out)?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Features read back from a cache written by [`QueryResult::serialize_to`]
+pub struct CacheReader<R> {
+    reader: R,
+    watermark: Watermark,
+    len: u64,
+    read: u64,
+}
+
+impl<R: Read> CacheReader<R> {
+    /// Read the header of a cache
+    ///
+    /// Fails with an I/O error of kind `InvalidData` if the input isn't a
+    /// cache or was written by an incompatible version.
+    pub fn new(mut reader: R) -> Result<Self, GeoDeskError> {
+        let mut header = [0u8; 25];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a feature cache"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("unsupported feature cache version"));
+        }
+        let field = |start: usize| -> [u8; 8] { header[start..start + 8].try_into().unwrap() };
+        let revision = u32::from_le_bytes(header[5..9].try_into().unwrap());
+        let millis = u64::from_le_bytes(field(9));
+        Ok(Self {
+            reader,
+            watermark: Watermark {
+                revision,
+                timestamp: UNIX_EPOCH + Duration::from_millis(millis),
+            },
+            len: u64::from_le_bytes(field(17)),
+            read: 0,
+        })
+    }
+
+    /// The data revision the cached result was read from
+    pub fn watermark(&self) -> Watermark {
+        self.watermark
+    }
+
+    /// Number of features in the cache
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Check if the cache holds no features
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<R: Read> Iterator for CacheReader<R> {
+    type Item = Result<Feature, GeoDeskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.read == self.len {
+            return None;
+        }
+        self.read += 1;
+        match Feature::from_cache(&mut self.reader) {
+            Ok(Some(feature)) => Some(Ok(feature)),
+            Ok(None) => {
+                // Stop after reporting the truncation once
+                self.read = self.len;
+                Some(Err(GeoDeskError::Io(io::ErrorKind::UnexpectedEof.into())))
+            }
+            Err(e) => {
+                self.read = self.len;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// A query and its bounding box, compared bit for bit
+type QueryKey = (String, [u64; 4]);
+
+fn query_key(goql_query: &str, bbox: &BoundingBox) -> QueryKey {
+    let bits = [bbox.west, bbox.south, bbox.east, bbox.north].map(f64::to_bits);
+    (goql_query.to_string(), bits)
+}
+
+/// Cached results, least recently used first in `order`
+#[derive(Default)]
+struct Lru {
+    revision: u32,
+    tick: u64,
+    entries: HashMap<QueryKey, (u64, Arc<Vec<Feature>>)>,
+    order: BTreeMap<u64, QueryKey>,
+    hits: u64,
+    misses: u64,
+}
+
+impl Lru {
+    fn get(&mut self, key: &QueryKey) -> Option<Arc<Vec<Feature>>> {
+        self.tick += 1;
+        let (used, features) = self.entries.get_mut(key)?;
+        let key = self.order.remove(used)?;
+        *used = self.tick;
+        self.order.insert(self.tick, key);
+        Some(features.clone())
+    }
+
+    fn insert(&mut self, key: QueryKey, features: Arc<Vec<Feature>>, capacity: usize) {
+        self.tick += 1;
+        if let Some((used, _)) = self.entries.insert(key.clone(), (self.tick, features)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, key);
+        while self.entries.len() > capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// A [`GeoDesk`] that keeps the features of the most recently used
+/// queries in memory
+///
+/// Identical queries (the same GOQL string and bounding box) are answered
+/// from the cache, as when a tile server renders the same hot tiles over
+/// and over. When the data revision changes, as after applying updates,
+/// the cache is emptied. The cache is shared by all threads using the
+/// wrapper; queries that miss run without holding its lock.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::cache::CachedGeoDesk;
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::GeoDesk;
+///
+/// let geodesk = CachedGeoDesk::new(GeoDesk::open("denmark.gol").unwrap(), 1_000);
+/// let cafes = geodesk.query("na[amenity=cafe]", bboxes::COPENHAGEN).unwrap();
+/// let again = geodesk.query("na[amenity=cafe]", bboxes::COPENHAGEN).unwrap();
+/// assert_eq!(cafes.len(), again.len());
+/// assert_eq!(geodesk.hits(), 1);
+/// ```
+pub struct CachedGeoDesk {
+    geodesk: GeoDesk,
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+impl CachedGeoDesk {
+    /// Cache the results of up to `capacity` distinct queries (none with
+    /// 0)
+    pub fn new(geodesk: GeoDesk, capacity: usize) -> Self {
+        Self {
+            geodesk,
+            capacity,
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// The wrapped GOL, for queries that shouldn't be cached
+    pub fn geodesk(&self) -> &GeoDesk {
+        &self.geodesk
+    }
+
+    /// Unwrap the GOL, dropping the cache
+    pub fn into_inner(self) -> GeoDesk {
+        self.geodesk
+    }
+
+    fn lru(&self) -> std::sync::MutexGuard<'_, Lru> {
+        // A panic while holding the lock can't leave the cache half-updated
+        self.lru.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The features matching a GOQL query in a bounding box, from the
+    /// cache if the same query was run before
+    pub fn query(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+    ) -> Result<Arc<Vec<Feature>>, GeoDeskError> {
+        let key = query_key(goql_query, &bbox);
+        let revision = self.geodesk.watermark().revision;
+        {
+            let mut lru = self.lru();
+            if lru.revision != revision {
+                lru.clear();
+                lru.revision = revision;
+            }
+            if let Some(features) = lru.get(&key) {
+                lru.hits += 1;
+                return Ok(features);
+            }
+            lru.misses += 1;
+        }
+        let result = self.geodesk.query(goql_query, bbox)?;
+        let features = Arc::new(result.to_vec()?);
+        let mut lru = self.lru();
+        // Don't cache a result read before the data changed under us
+        if self.capacity > 0 && lru.revision == result.watermark().revision {
+            lru.insert(key, features.clone(), self.capacity);
+        }
+        Ok(features)
+    }
+
+    /// Number of queries answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.lru().hits
+    }
+
+    /// Number of queries that had to be run
+    pub fn misses(&self) -> u64 {
+        self.lru().misses
+    }
+
+    /// Number of queries whose results are cached
+    pub fn len(&self) -> usize {
+        self.lru().entries.len()
+    }
+
+    /// Check if no results are cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop all cached results
+    pub fn clear(&self) {
+        self.lru().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_round_trip() {
+        let way = Feature {
+            id: 4_000_000_123,
+            type_name: "way".to_string(),
+            name: "Nørrebrogade".to_string(),
+            lon: 12.5531,
+            lat: 55.6953,
+            tags: vec![
+                ("highway".to_string(), "secondary".to_string()),
+                ("name".to_string(), "Nørrebrogade".to_string()),
+            ],
+            nodes: vec![
+                Node {
+                    id: 100,
+                    lon: 12.55,
+                    lat: 55.69,
+                },
+                Node {
+                    id: 42,
+                    lon: 12.5562,
+                    lat: 55.7006,
+                },
+            ],
+        };
+        let node = Feature {
+            id: -1,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: -73.5,
+            lat: 45.5,
+            tags: Vec::new(),
+            nodes: Vec::new(),
+        };
+        let mut buf = Vec::new();
+        way.write_cache(&mut buf).unwrap();
+        node.write_cache(&mut buf).unwrap();
+
+        let mut reader = buf.as_slice();
+        let read = Feature::from_cache(&mut reader).unwrap().unwrap();
+        assert_eq!((read.id, read.type_name.as_str()), (way.id, "way"));
+        assert_eq!(read.tags, way.tags);
+        assert_eq!(read.nodes.len(), 2);
+        assert_eq!(read.nodes[1].id, 42);
+        assert_eq!(read.nodes[1].lat, 55.7006);
+        let read = Feature::from_cache(&mut reader).unwrap().unwrap();
+        assert_eq!((read.id, read.lon), (-1, -73.5));
+        assert!(Feature::from_cache(&mut reader).unwrap().is_none());
+
+        // A record cut short is an error, not the end of the input
+        let mut truncated = &buf[..buf.len() / 2];
+        assert!(Feature::from_cache(&mut truncated).is_err());
+        assert!(CacheReader::new(&b"not a cache at all, surely"[..]).is_err());
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut lru = Lru::default();
+        let bbox = BoundingBox::new(0.0, 0.0, 1.0, 1.0);
+        let features = Arc::new(Vec::new());
+        lru.insert(query_key("n", &bbox), features.clone(), 2);
+        lru.insert(query_key("w", &bbox), features.clone(), 2);
+        // Using the first makes the second the least recently used
+        assert!(lru.get(&query_key("n", &bbox)).is_some());
+        lru.insert(query_key("r", &bbox), features, 2);
+        assert_eq!(lru.entries.len(), 2);
+        assert!(lru.get(&query_key("w", &bbox)).is_none());
+        assert!(lru.get(&query_key("n", &bbox)).is_some());
+        let other = BoundingBox::new(0.0, 0.0, 1.0, 2.0);
+        assert!(lru.get(&query_key("n", &other)).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+// Cancelling queries from another thread, or after a timeout
+
+use crate::options::TagPattern;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Shared flag for aborting queries run with
+/// [`QueryOptions::cancellation`](crate::options::QueryOptions::cancellation)
+///
+/// Clones share the flag, so one clone can be kept by whoever decides to
+/// cancel (such as the handler of a client connection) while another is
+/// passed to the query.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::cancel::CancellationToken;
+/// use geodesk_rs::options::QueryOptions;
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::{GeoDesk, GeoDeskError};
+///
+/// let geodesk = GeoDesk::open("planet.gol").unwrap();
+/// let token = CancellationToken::new();
+/// let options = QueryOptions::new().cancellation(token.clone());
+///
+/// let canceller = token.clone();
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_secs(1));
+///     canceller.cancel();
+/// });
+/// match geodesk.query_with("*", bboxes::COPENHAGEN, &options) {
+///     Err(GeoDeskError::Cancelled) => println!("gave up"),
+///     result => println!("{} features", result.unwrap().count()),
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// A token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the queries using this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if [`cancel`](Self::cancel) was called
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Cancellation token, deadline and tag patterns of a query, as passed
+/// through the bridge
+pub struct QueryControl {
+    cancel: Option<CancellationToken>,
+    deadline: Option<Instant>,
+    patterns: Vec<TagPattern>,
+}
+
+impl QueryControl {
+    pub(crate) fn new(cancel: Option<CancellationToken>, deadline: Option<Instant>) -> Self {
+        Self {
+            cancel,
+            deadline,
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Only keep features matching these patterns
+    pub(crate) fn with_patterns(mut self, patterns: Vec<TagPattern>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    pub(crate) fn patterns(&self) -> &[TagPattern] {
+        &self.patterns
+    }
+
+    /// Check if the token was cancelled (rather than the deadline passed)
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|c| c.is_cancelled())
+    }
+}
+
+/// Called from the C++ query loop; returns `true` to stop the query
+pub(crate) fn query_should_stop(control: &QueryControl) -> bool {
+    control.is_cancelled() || control.deadline.is_some_and(|d| Instant::now() >= d)
+}
+
+/// Called from the C++ query loop once per query; if `false`, the loop
+/// scans the query area in one pass instead of cell by cell
+pub(crate) fn query_can_stop(control: &QueryControl) -> bool {
+    control.cancel.is_some() || control.deadline.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_control() {
+        let token = CancellationToken::new();
+        let control = QueryControl::new(Some(token.clone()), None);
+        assert!(!query_should_stop(&control));
+        token.cancel();
+        assert!(query_should_stop(&control));
+        assert!(control.is_cancelled());
+
+        let past = QueryControl::new(None, Some(Instant::now()));
+        assert!(query_should_stop(&past));
+        assert!(!past.is_cancelled());
+        assert!(!query_should_stop(&QueryControl::new(None, None)));
+        assert!(query_can_stop(&control) && query_can_stop(&past));
+        assert!(!query_can_stop(&QueryControl::new(None, None)));
+    }
+}
@@ -0,0 +1,300 @@
+// C ABI over the high-level API, for services written in other languages
+//
+// The header is include/geodesk.h, generated with
+// `cbindgen --config cbindgen.toml --output include/geodesk.h`. Build the
+// library with `cargo rustc --release --features capi --crate-type cdylib`
+// (or `staticlib`).
+
+use crate::error::GeoDeskError;
+use crate::{BoundingBox, Features, GeoDesk, Node};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A string for C, with any NUL bytes (invalid in C strings) dropped
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+/// Run the body of an exported function, recording its error for
+/// `geodesk_last_error` and returning `fallback` instead; panics can't
+/// unwind into C, so they are reported the same way
+fn guard<T>(fallback: T, f: impl FnOnce() -> Result<T, GeoDeskError>) -> T {
+    let result = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(GeoDeskError::from_panic(payload)));
+    let (value, error) = match result {
+        Ok(value) => (value, None),
+        Err(e) => (fallback, Some(c_string(&e.to_string()))),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = error);
+    value
+}
+
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, GeoDeskError> {
+    if s.is_null() {
+        return Err(GeoDeskError::Engine(format!("{} is NULL", name)));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| GeoDeskError::Engine(format!("{} is not valid UTF-8", name)))
+}
+
+/// A feature as seen from C; strings and counts refer to the feature the
+/// result is positioned at
+#[repr(C)]
+pub struct GeodeskFeature {
+    pub id: i64,
+    /// 0 = node, 1 = way, 2 = relation
+    pub type_code: u8,
+    pub lon: f64,
+    pub lat: f64,
+    /// Empty if the feature has no name
+    pub name: *const c_char,
+    pub tag_count: usize,
+    /// Number of way nodes (0 for nodes and relations)
+    pub node_count: usize,
+}
+
+/// Features matched by `geodesk_query`, read one at a time
+pub struct GeodeskResult {
+    features: Features,
+    name: CString,
+    tags: Vec<(CString, CString)>,
+    nodes: Vec<Node>,
+    current: GeodeskFeature,
+}
+
+/// Open a GOL file; returns NULL on failure
+///
+/// # Safety
+/// `path` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_open(path: *const c_char) -> *mut GeoDesk {
+    guard(ptr::null_mut(), || {
+        let geodesk = GeoDesk::open(str_arg(path, "path")?)?;
+        Ok(Box::into_raw(Box::new(geodesk)))
+    })
+}
+
+/// Close a GOL opened with `geodesk_open`, after freeing its results
+///
+/// # Safety
+/// `geodesk` must come from `geodesk_open`, or be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_close(geodesk: *mut GeoDesk) {
+    if !geodesk.is_null() {
+        drop(Box::from_raw(geodesk));
+    }
+}
+
+/// Find the features matching a GOQL query in a bounding box; returns NULL
+/// on failure
+///
+/// # Safety
+/// `geodesk` must be open and `goql` a NUL-terminated string; the result
+/// must be freed before the GOL is closed.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_query(
+    geodesk: *const GeoDesk,
+    goql: *const c_char,
+    west: f64,
+    south: f64,
+    east: f64,
+    north: f64,
+) -> *mut GeodeskResult {
+    guard(ptr::null_mut(), || {
+        let geodesk = geodesk
+            .as_ref()
+            .ok_or_else(|| GeoDeskError::Engine("geodesk is NULL".into()))?;
+        let bbox = BoundingBox::new(west, south, east, north);
+        let features = geodesk.features(str_arg(goql, "goql")?, bbox)?;
+        Ok(Box::into_raw(Box::new(GeodeskResult {
+            features,
+            name: CString::default(),
+            tags: Vec::new(),
+            nodes: Vec::new(),
+            current: GeodeskFeature {
+                id: 0,
+                type_code: 0,
+                lon: 0.0,
+                lat: 0.0,
+                name: ptr::null(),
+                tag_count: 0,
+                node_count: 0,
+            },
+        })))
+    })
+}
+
+/// Count the features matching a GOQL query in a bounding box; returns -1
+/// on failure
+///
+/// # Safety
+/// `geodesk` must be open and `goql` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_count(
+    geodesk: *const GeoDesk,
+    goql: *const c_char,
+    west: f64,
+    south: f64,
+    east: f64,
+    north: f64,
+) -> i64 {
+    guard(-1, || {
+        let geodesk = geodesk
+            .as_ref()
+            .ok_or_else(|| GeoDeskError::Engine("geodesk is NULL".into()))?;
+        let bbox = BoundingBox::new(west, south, east, north);
+        Ok(geodesk.query(str_arg(goql, "goql")?, bbox)?.count() as i64)
+    })
+}
+
+/// Advance to the next feature; returns NULL after the last one, or on
+/// failure (check `geodesk_last_error`)
+///
+/// The feature and its strings stay valid until the next call.
+///
+/// # Safety
+/// `result` must come from `geodesk_query`.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_result_next(result: *mut GeodeskResult) -> *const GeodeskFeature {
+    guard(ptr::null(), || {
+        let Some(result) = result.as_mut() else {
+            return Ok(ptr::null());
+        };
+        let Some(feature) = result.features.next().transpose()? else {
+            return Ok(ptr::null());
+        };
+        result.name = c_string(&feature.name);
+        result.tags = feature
+            .tags
+            .iter()
+            .map(|(key, value)| (c_string(key), c_string(value)))
+            .collect();
+        result.current = GeodeskFeature {
+            id: feature.id,
+            type_code: match feature.type_name.as_str() {
+                "node" => 0,
+                "way" => 1,
+                _ => 2,
+            },
+            lon: feature.lon,
+            lat: feature.lat,
+            name: result.name.as_ptr(),
+            tag_count: result.tags.len(),
+            node_count: feature.nodes.len(),
+        };
+        result.nodes = feature.nodes;
+        Ok(&result.current)
+    })
+}
+
+/// Value of a tag of the current feature, or NULL if it doesn't have it
+///
+/// # Safety
+/// `result` must come from `geodesk_query` and `key` be a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_feature_tag(
+    result: *const GeodeskResult,
+    key: *const c_char,
+) -> *const c_char {
+    if result.is_null() || key.is_null() {
+        return ptr::null();
+    }
+    let key = CStr::from_ptr(key);
+    (*result)
+        .tags
+        .iter()
+        .find(|(k, _)| k.as_c_str() == key)
+        .map_or(ptr::null(), |(_, value)| value.as_ptr())
+}
+
+/// Key and value of the current feature's tag at `index`; returns false if
+/// there is no such tag
+///
+/// # Safety
+/// `result` must come from `geodesk_query`; `key` and `value` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_feature_tag_at(
+    result: *const GeodeskResult,
+    index: usize,
+    key: *mut *const c_char,
+    value: *mut *const c_char,
+) -> bool {
+    match result.as_ref().and_then(|result| result.tags.get(index)) {
+        Some((k, v)) => {
+            *key = k.as_ptr();
+            *value = v.as_ptr();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Coordinates of the current way's node at `index`; returns false if
+/// there is no such node
+///
+/// # Safety
+/// `result` must come from `geodesk_query`; `lon` and `lat` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_feature_node_at(
+    result: *const GeodeskResult,
+    index: usize,
+    lon: *mut f64,
+    lat: *mut f64,
+) -> bool {
+    match result.as_ref().and_then(|result| result.nodes.get(index)) {
+        Some(node) => {
+            *lon = node.lon;
+            *lat = node.lat;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Free a result from `geodesk_query`
+///
+/// # Safety
+/// `result` must come from `geodesk_query`, or be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn geodesk_result_free(result: *mut GeodeskResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+/// Message of the last error on this thread, or NULL if the last call
+/// succeeded; valid until the next call
+#[no_mangle]
+pub extern "C" fn geodesk_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors() {
+        let geodesk = unsafe { geodesk_open(ptr::null()) };
+        assert!(geodesk.is_null());
+        let message = unsafe { CStr::from_ptr(geodesk_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "path is NULL");
+
+        let count = unsafe { geodesk_count(ptr::null(), c"na".as_ptr(), 0.0, 0.0, 1.0, 1.0) };
+        assert_eq!(count, -1);
+        assert_eq!(guard(0, || Ok(1)), 1);
+        assert!(geodesk_last_error().is_null());
+        assert_eq!(guard(0, || panic!("boom")), 0);
+        assert!(!geodesk_last_error().is_null());
+    }
+}
@@ -0,0 +1,222 @@
+// Kinds of points of interest, from the OSM tags that select them
+
+use crate::Feature;
+use std::fmt;
+use std::str::FromStr;
+
+/// A kind of place, selected by tags
+///
+/// Categories are matched in the order of [`Category::ALL`], so a feature
+/// tagged both `amenity=cafe` and `shop=coffee` is [`Food`](Category::Food).
+///
+/// # Example
+/// ```
+/// use geodesk_rs::categories::Category;
+/// use geodesk_rs::Feature;
+///
+/// let pharmacy = Feature {
+///     id: 1,
+///     type_name: "node".into(),
+///     name: "Apoteket".into(),
+///     lon: 12.57,
+///     lat: 55.68,
+///     tags: vec![("amenity".into(), "pharmacy".into())],
+///     nodes: Vec::new(),
+/// };
+/// assert_eq!(pharmacy.category(), Some(Category::Health));
+/// assert_eq!("health".parse::<Category>().unwrap(), Category::Health);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Category {
+    Food,
+    Health,
+    Education,
+    Money,
+    Transport,
+    Lodging,
+    Sights,
+    Leisure,
+    Shopping,
+}
+
+impl Category {
+    /// Every category, in the order they are matched
+    pub const ALL: &'static [Category] = &[
+        Category::Food,
+        Category::Health,
+        Category::Education,
+        Category::Money,
+        Category::Transport,
+        Category::Lodging,
+        Category::Sights,
+        Category::Leisure,
+        Category::Shopping,
+    ];
+
+    /// Lowercase name, as used in URLs and JSON
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Food => "food",
+            Category::Health => "health",
+            Category::Education => "education",
+            Category::Money => "money",
+            Category::Transport => "transport",
+            Category::Lodging => "lodging",
+            Category::Sights => "sights",
+            Category::Leisure => "leisure",
+            Category::Shopping => "shopping",
+        }
+    }
+
+    /// Keys with the values that select the category; an empty list of
+    /// values accepts any value
+    pub fn tags(self) -> &'static [(&'static str, &'static [&'static str])] {
+        match self {
+            Category::Food => &[(
+                "amenity",
+                &[
+                    "restaurant",
+                    "cafe",
+                    "fast_food",
+                    "bar",
+                    "pub",
+                    "ice_cream",
+                    "food_court",
+                ],
+            )],
+            Category::Health => &[(
+                "amenity",
+                &["hospital", "clinic", "doctors", "dentist", "pharmacy"],
+            )],
+            Category::Education => &[(
+                "amenity",
+                &["school", "university", "college", "kindergarten", "library"],
+            )],
+            Category::Money => &[("amenity", &["bank", "atm", "bureau_de_change"])],
+            Category::Transport => &[
+                (
+                    "amenity",
+                    &["fuel", "parking", "bicycle_rental", "car_rental"],
+                ),
+                ("railway", &["station", "halt"]),
+                ("highway", &["bus_stop"]),
+            ],
+            Category::Lodging => &[(
+                "tourism",
+                &["hotel", "hostel", "motel", "guest_house", "camp_site"],
+            )],
+            Category::Sights => &[(
+                "tourism",
+                &["attraction", "museum", "gallery", "viewpoint", "zoo"],
+            )],
+            Category::Leisure => &[(
+                "leisure",
+                &["park", "playground", "sports_centre", "swimming_pool"],
+            )],
+            Category::Shopping => &[("shop", &[])],
+        }
+    }
+
+    /// Look up a category by name, ignoring case
+    pub fn by_name(name: &str) -> Option<Category> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The first category whose tags a feature has
+    pub fn of(feature: &Feature) -> Option<Category> {
+        Self::ALL.iter().copied().find(|c| c.matches(feature))
+    }
+
+    /// Check if a feature has one of the category's tags
+    pub fn matches(self, feature: &Feature) -> bool {
+        self.tags().iter().any(|(key, values)| {
+            feature
+                .tag(key)
+                .is_some_and(|v| values.is_empty() || values.contains(&v))
+        })
+    }
+
+    /// GOQL query selecting the nodes and areas of the category
+    pub fn goql(self) -> String {
+        let selectors: Vec<String> = self
+            .tags()
+            .iter()
+            .map(|(key, values)| {
+                if values.is_empty() {
+                    format!("na[{}]", key)
+                } else {
+                    format!("na[{}={}]", key, values.join(","))
+                }
+            })
+            .collect();
+        selectors.join(", ")
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::by_name(s).ok_or_else(|| format!("Unknown category: {}", s))
+    }
+}
+
+impl Feature {
+    /// The category of the feature, if its tags place it in one
+    pub fn category(&self) -> Option<Category> {
+        Category::of(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(tags: &[(&str, &str)]) -> Feature {
+        Feature {
+            id: 1,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 12.57,
+            lat: 55.68,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_category() {
+        assert_eq!(
+            place(&[("amenity", "cafe")]).category(),
+            Some(Category::Food)
+        );
+        assert_eq!(
+            place(&[("railway", "halt")]).category(),
+            Some(Category::Transport)
+        );
+        assert_eq!(
+            place(&[("shop", "coffee"), ("amenity", "cafe")]).category(),
+            Some(Category::Food)
+        );
+        assert_eq!(place(&[("amenity", "bench")]).category(), None);
+
+        for &category in Category::ALL {
+            assert_eq!(category.name().parse::<Category>(), Ok(category));
+            assert_eq!(category.to_string(), category.name());
+        }
+        assert!("groceries".parse::<Category>().is_err());
+    }
+}
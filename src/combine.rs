@@ -0,0 +1,211 @@
+// Set operations on query results
+
+use crate::error::GeoDeskError;
+use crate::provenance::{Identity, IdentityKey};
+use crate::{ffi, Feature, FeatureType, QueryResult};
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// How [`Combined`] selects features from its two results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperation {
+    /// Features in either result
+    Union,
+    /// Features of the first result that are also in the second
+    Intersection,
+    /// Features of the first result that are not in the second
+    Difference,
+}
+
+/// Two query results combined by an [`Identity`] (type and id unless set
+/// with [`with_identity`](Self::with_identity)), from
+/// [`QueryResult::union`], [`QueryResult::intersect`] and
+/// [`QueryResult::difference`]
+///
+/// Nothing is computed until the combination is read; counting it only
+/// compares ids, and [`to_vec`](Self::to_vec) converts just the features
+/// selected. Combining by [`Identity::Geometry`] converts both results to
+/// compare their geometries. Features keep the order of the first result,
+/// followed (for a union) by those only in the second.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::GeoDesk;
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+/// let cafes = geodesk.query("na[amenity=cafe]", bboxes::COPENHAGEN).unwrap();
+/// let accessible = geodesk
+///     .query("na[wheelchair=yes]", bboxes::COPENHAGEN)
+///     .unwrap();
+/// let to_survey = cafes.difference(&accessible);
+/// println!("{} cafes to survey", to_survey.count().unwrap());
+/// ```
+pub struct Combined<'a> {
+    first: &'a QueryResult,
+    second: &'a QueryResult,
+    operation: SetOperation,
+    identity: Identity,
+}
+
+impl Combined<'_> {
+    /// Set what makes features of the two results the same
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// The set operation
+    pub fn operation(&self) -> SetOperation {
+        self.operation
+    }
+
+    // Identity keys of the features of a result, in result order
+    fn identity_keys(&self, result: &QueryResult) -> Result<Vec<IdentityKey>, GeoDeskError> {
+        let keys = ffi::bridge::result_keys(&result.result);
+        match keys.iter().map(|key| self.identity.key_of(key)).collect() {
+            Some(keys) => Ok(keys),
+            None => {
+                let features = result.to_vec()?;
+                Ok(features.iter().map(|f| self.identity.key(f)).collect())
+            }
+        }
+    }
+
+    // Indices of the features selected from each result
+    fn select(&self) -> Result<Selection, GeoDeskError> {
+        let first = self.identity_keys(self.first)?;
+        let second = self.identity_keys(self.second)?;
+        let mut seen = HashSet::with_capacity(first.len());
+        let (from_first, from_second) = match self.operation {
+            SetOperation::Union => (
+                unique(&first, &mut seen, |_| true),
+                unique(&second, &mut seen, |_| true),
+            ),
+            SetOperation::Intersection | SetOperation::Difference => {
+                let keep = self.operation == SetOperation::Intersection;
+                let other: HashSet<_> = second.iter().copied().collect();
+                let from_first = unique(&first, &mut seen, |key| other.contains(key) == keep);
+                (from_first, Vec::new())
+            }
+        };
+        Ok(Selection {
+            from_first,
+            from_second,
+        })
+    }
+
+    /// Number of features selected
+    pub fn count(&self) -> Result<usize, GeoDeskError> {
+        let selection = self.select()?;
+        Ok(selection.from_first.len() + selection.from_second.len())
+    }
+
+    /// Check if no features are selected
+    pub fn is_empty(&self) -> Result<bool, GeoDeskError> {
+        Ok(self.count()? == 0)
+    }
+
+    /// Types and ids of the features selected
+    pub fn keys(&self) -> Result<Vec<(FeatureType, i64)>, GeoDeskError> {
+        let selection = self.select()?;
+        let mut keys = Vec::with_capacity(selection.from_first.len() + selection.from_second.len());
+        for (result, indices) in [
+            (self.first, selection.from_first),
+            (self.second, selection.from_second),
+        ] {
+            let all = ffi::bridge::result_keys(&result.result);
+            keys.extend(indices.iter().map(|&i| (all[i].feature_type(), all[i].id)));
+        }
+        Ok(keys)
+    }
+
+    /// Convert the features selected
+    pub fn to_vec(&self) -> Result<Vec<Feature>, GeoDeskError> {
+        let selection = self.select()?;
+        let mut features =
+            Vec::with_capacity(selection.from_first.len() + selection.from_second.len());
+        for (result, indices) in [
+            (self.first, selection.from_first),
+            (self.second, selection.from_second),
+        ] {
+            if indices.is_empty() {
+                continue;
+            }
+            let selected = ffi::bridge::result_select(&result.result, &indices)?;
+            features.extend(selected.iter().map(|f| Feature::from(f.clone())));
+        }
+        Ok(features)
+    }
+}
+
+struct Selection {
+    from_first: Vec<usize>,
+    from_second: Vec<usize>,
+}
+
+// Indices of the keys that pass `keep` and were not seen before
+fn unique<K: Copy + Eq + Hash>(
+    keys: &[K],
+    seen: &mut HashSet<K>,
+    keep: impl Fn(&K) -> bool,
+) -> Vec<usize> {
+    keys.iter()
+        .enumerate()
+        .filter(|(_, key)| keep(key) && seen.insert(**key))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+impl QueryResult {
+    /// Features in this result or `other` (each once)
+    pub fn union<'a>(&'a self, other: &'a QueryResult) -> Combined<'a> {
+        self.combine(other, SetOperation::Union)
+    }
+
+    /// Features in both this result and `other`
+    pub fn intersect<'a>(&'a self, other: &'a QueryResult) -> Combined<'a> {
+        self.combine(other, SetOperation::Intersection)
+    }
+
+    /// Features in this result but not in `other`
+    pub fn difference<'a>(&'a self, other: &'a QueryResult) -> Combined<'a> {
+        self.combine(other, SetOperation::Difference)
+    }
+
+    fn combine<'a>(&'a self, other: &'a QueryResult, operation: SetOperation) -> Combined<'a> {
+        Combined {
+            first: self,
+            second: other,
+            operation,
+            identity: Identity::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_selection() {
+        let key = |type_code, id| ffi::bridge::FeatureKey { type_code, id };
+        let first = [key(0, 1), key(1, 1), key(0, 1), key(0, 2)];
+        let second = [key(1, 1), key(2, 5)];
+
+        let mut seen = HashSet::new();
+        assert_eq!(unique(&first, &mut seen, |_| true), vec![0, 1, 3]);
+        assert_eq!(unique(&second, &mut seen, |_| true), vec![1]);
+
+        let other: HashSet<_> = second.into_iter().collect();
+        let mut seen = HashSet::new();
+        assert_eq!(
+            unique(&first, &mut seen, |k| !other.contains(k)),
+            vec![0, 3]
+        );
+
+        // By id alone, node 1 and way 1 are one feature
+        let by_id = [IdentityKey::Id(1), IdentityKey::Id(1), IdentityKey::Id(2)];
+        assert_eq!(unique(&by_id, &mut HashSet::new(), |_| true), vec![0, 2]);
+    }
+}
@@ -0,0 +1,279 @@
+//! Transformations between coordinate reference systems by EPSG code
+//!
+//! Enabled by the `crs` feature. Transformations are computed natively,
+//! without PROJ, for these systems only:
+//! - EPSG:4326, longitude and latitude on WGS-84
+//! - EPSG:3857 (and the older 900913), Web Mercator
+//! - EPSG:32601 to 32660 and 32701 to 32760, UTM zones on WGS-84
+//! - EPSG:25828 to 25838, UTM zones 28 to 38 on ETRS89
+//!
+//! Other codes fail with [`GeoDeskError::UnsupportedCrs`].
+
+use crate::error::GeoDeskError;
+use crate::projection::{from_web_mercator, to_web_mercator};
+use crate::{BoundingBox, Feature, Node};
+
+/// Semi-major axis of the WGS-84 ellipsoid, in meters
+const WGS84_A: f64 = 6_378_137.0;
+/// Flattening of the WGS-84 ellipsoid
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// Scale factor on the central meridian of UTM zones
+const UTM_K0: f64 = 0.9996;
+/// False easting of UTM zones, and false northing of southern ones
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// Points sampled along each edge by [`BoundingBox::transform`], as edges
+/// are curved in the target system
+const EDGE_SAMPLES: usize = 16;
+
+/// A coordinate reference system that features can be transformed to
+///
+/// ETRS89 is treated as equal to WGS-84, which it stays within a meter of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crs {
+    /// Longitude and latitude in degrees (EPSG:4326)
+    Wgs84,
+    /// Web Mercator in meters (EPSG:3857)
+    WebMercator,
+    /// A UTM zone in meters (EPSG:326xx and 327xx on WGS-84, 258xx on
+    /// ETRS89)
+    Utm { zone: u8, north: bool },
+}
+
+impl Crs {
+    /// The system with the given EPSG code
+    pub fn from_epsg(code: u32) -> Result<Crs, GeoDeskError> {
+        match code {
+            4326 => Ok(Crs::Wgs84),
+            3857 | 900913 => Ok(Crs::WebMercator),
+            32601..=32660 => Ok(Crs::Utm {
+                zone: (code - 32600) as u8,
+                north: true,
+            }),
+            32701..=32760 => Ok(Crs::Utm {
+                zone: (code - 32700) as u8,
+                north: false,
+            }),
+            25828..=25838 => Ok(Crs::Utm {
+                zone: (code - 25800) as u8,
+                north: true,
+            }),
+            _ => Err(GeoDeskError::UnsupportedCrs { epsg: code }),
+        }
+    }
+
+    /// Project a longitude and latitude into this system
+    pub fn from_lon_lat(self, lon: f64, lat: f64) -> (f64, f64) {
+        match self {
+            Crs::Wgs84 => (lon, lat),
+            Crs::WebMercator => to_web_mercator(lon, lat),
+            Crs::Utm { zone, north } => utm_forward(zone, north, lon, lat),
+        }
+    }
+
+    /// Longitude and latitude of coordinates in this system
+    pub fn to_lon_lat(self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Crs::Wgs84 => (x, y),
+            Crs::WebMercator => from_web_mercator(x, y),
+            Crs::Utm { zone, north } => utm_inverse(zone, north, x, y),
+        }
+    }
+}
+
+/// Transform a point between two systems given by EPSG code
+pub fn transform_point(from: u32, to: u32, x: f64, y: f64) -> Result<(f64, f64), GeoDeskError> {
+    let (from, to) = (Crs::from_epsg(from)?, Crs::from_epsg(to)?);
+    let (lon, lat) = from.to_lon_lat(x, y);
+    Ok(to.from_lon_lat(lon, lat))
+}
+
+impl Feature {
+    /// A copy of the feature with its location and nodes in the system
+    /// with EPSG code `to_epsg`
+    ///
+    /// `lon` and `lat` of the copy hold the projected x and y (easting
+    /// and northing for UTM).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let buildings = geodesk.query("w[building]", bboxes::COPENHAGEN).unwrap();
+    /// for feature in buildings.to_vec().unwrap() {
+    ///     // ETRS89 / UTM zone 32N, the Danish national grid
+    ///     let projected = feature.transform(25832).unwrap();
+    ///     println!("{} {:.1} {:.1}", projected.id, projected.lon, projected.lat);
+    /// }
+    /// ```
+    pub fn transform(&self, to_epsg: u32) -> Result<Feature, GeoDeskError> {
+        let to = Crs::from_epsg(to_epsg)?;
+        let (x, y) = to.from_lon_lat(self.lon, self.lat);
+        Ok(Feature {
+            lon: x,
+            lat: y,
+            nodes: self
+                .nodes
+                .iter()
+                .map(|node| {
+                    let (x, y) = to.from_lon_lat(node.lon, node.lat);
+                    Node {
+                        id: node.id,
+                        lon: x,
+                        lat: y,
+                    }
+                })
+                .collect(),
+            ..self.clone()
+        })
+    }
+}
+
+impl BoundingBox {
+    /// The box covering this one in another system, both given by EPSG
+    /// code
+    ///
+    /// Straight edges become curves in most projections, so points along
+    /// each edge are transformed, not only the corners.
+    pub fn transform(&self, from: u32, to: u32) -> Result<BoundingBox, GeoDeskError> {
+        let (from, to) = (Crs::from_epsg(from)?, Crs::from_epsg(to)?);
+        let mut result = BoundingBox::new(f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for i in 0..=EDGE_SAMPLES {
+            let t = i as f64 / EDGE_SAMPLES as f64;
+            let x = self.west + (self.east - self.west) * t;
+            let y = self.south + (self.north - self.south) * t;
+            for (px, py) in [
+                (x, self.south),
+                (x, self.north),
+                (self.west, y),
+                (self.east, y),
+            ] {
+                let (lon, lat) = from.to_lon_lat(px, py);
+                let (tx, ty) = to.from_lon_lat(lon, lat);
+                result.west = result.west.min(tx);
+                result.south = result.south.min(ty);
+                result.east = result.east.max(tx);
+                result.north = result.north.max(ty);
+            }
+        }
+        Ok(result)
+    }
+}
+
+// Coefficients of the Krüger series for the transverse Mercator projection
+// (third order in the third flattening, accurate to well below a
+// millimeter within a zone)
+struct Kruger {
+    a: f64,
+    alpha: [f64; 3],
+    beta: [f64; 3],
+    delta: [f64; 3],
+}
+
+fn kruger() -> Kruger {
+    let n = WGS84_F / (2.0 - WGS84_F);
+    let (n2, n3) = (n * n, n * n * n);
+    Kruger {
+        a: WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+        alpha: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0,
+            13.0 * n2 / 48.0 - 3.0 * n3 / 5.0,
+            61.0 * n3 / 240.0,
+        ],
+        beta: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+            n2 / 48.0 + n3 / 15.0,
+            17.0 * n3 / 480.0,
+        ],
+        delta: [
+            2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+            7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+            56.0 * n3 / 15.0,
+        ],
+    }
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    (zone as f64 * 6.0 - 183.0).to_radians()
+}
+
+fn utm_forward(zone: u8, north: bool, lon: f64, lat: f64) -> (f64, f64) {
+    let k = kruger();
+    let n = WGS84_F / (2.0 - WGS84_F);
+    let e = 2.0 * n.sqrt() / (1.0 + n);
+    let (phi, lambda) = (lat.to_radians(), lon.to_radians() - central_meridian(zone));
+    let t = (phi.sin().atanh() - e * (e * phi.sin()).atanh()).sinh();
+    let xi = (t / lambda.cos()).atan();
+    let eta = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+    let (mut easting, mut northing) = (eta, xi);
+    for (j, alpha) in k.alpha.iter().enumerate() {
+        let j2 = 2.0 * (j + 1) as f64;
+        easting += alpha * (j2 * xi).cos() * (j2 * eta).sinh();
+        northing += alpha * (j2 * xi).sin() * (j2 * eta).cosh();
+    }
+    let false_northing = if north { 0.0 } else { UTM_FALSE_NORTHING_SOUTH };
+    (
+        UTM_FALSE_EASTING + UTM_K0 * k.a * easting,
+        false_northing + UTM_K0 * k.a * northing,
+    )
+}
+
+fn utm_inverse(zone: u8, north: bool, easting: f64, northing: f64) -> (f64, f64) {
+    let k = kruger();
+    let false_northing = if north { 0.0 } else { UTM_FALSE_NORTHING_SOUTH };
+    let xi = (northing - false_northing) / (UTM_K0 * k.a);
+    let eta = (easting - UTM_FALSE_EASTING) / (UTM_K0 * k.a);
+    let (mut xi1, mut eta1) = (xi, eta);
+    for (j, beta) in k.beta.iter().enumerate() {
+        let j2 = 2.0 * (j + 1) as f64;
+        xi1 -= beta * (j2 * xi).sin() * (j2 * eta).cosh();
+        eta1 -= beta * (j2 * xi).cos() * (j2 * eta).sinh();
+    }
+    let chi = (xi1.sin() / eta1.cosh()).asin();
+    let mut phi = chi;
+    for (j, delta) in k.delta.iter().enumerate() {
+        phi += delta * (2.0 * (j + 1) as f64 * chi).sin();
+    }
+    let lambda = central_meridian(zone) + (eta1.sinh() / xi1.cos()).atan();
+    (lambda.to_degrees(), phi.to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utm() {
+        // The CN Tower, at 630084 E 4833438 N in zone 17N
+        let (lon, lat) = (-79.387_138_9, 43.642_566_7);
+        let (e, n) = transform_point(4326, 32617, lon, lat).unwrap();
+        assert!((e - 630_084.0).abs() < 1.0, "{}", e);
+        assert!((n - 4_833_438.0).abs() < 1.0, "{}", n);
+        let back = transform_point(32617, 4326, e, n).unwrap();
+        assert!((back.0 - lon).abs() < 1e-8 && (back.1 - lat).abs() < 1e-8);
+
+        // Southern hemisphere and the equator on the central meridian
+        let (e, n) = transform_point(4326, 32733, 15.0, -0.0).unwrap();
+        assert!((e - 500_000.0).abs() < 1e-6 && (n - 10_000_000.0).abs() < 1e-6);
+
+        assert!(matches!(
+            Crs::from_epsg(2154),
+            Err(GeoDeskError::UnsupportedCrs { epsg: 2154 })
+        ));
+    }
+
+    #[test]
+    fn test_bbox_transform() {
+        let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+        let utm = bbox.transform(4326, 32632).unwrap();
+        let corner = transform_point(4326, 32632, 12.45, 55.61).unwrap();
+        assert!(utm.west <= corner.0 && utm.south <= corner.1);
+        // Wider than the box itself, as meridians converge away from 9°E
+        assert!(utm.east - utm.west > 13_000.0 && utm.east - utm.west < 14_000.0);
+        let back = utm.transform(32632, 4326).unwrap();
+        assert!(back.west <= bbox.west + 1e-9 && back.north >= bbox.north - 1e-9);
+    }
+}
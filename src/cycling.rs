@@ -0,0 +1,222 @@
+// Cycling infrastructure of ways, read from the cycleway, oneway:bicycle and
+// surface tags
+
+use crate::error::GeoDeskError;
+use crate::{BoundingBox, Feature, GeoDesk, QueryResult};
+
+/// `cycleway` values that are infrastructure for cyclists
+const FACILITY_VALUES: &str =
+    "lane,track,shared_lane,share_busway,opposite_lane,opposite_track,opposite_share_busway";
+
+/// What a road offers cyclists on one side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CycleFacility {
+    /// A painted lane on the carriageway (`lane`, `opposite_lane`)
+    Lane,
+    /// A track separated from the carriageway (`track`, `opposite_track`)
+    Track,
+    /// Markings on a lane shared with motor traffic (`shared_lane`)
+    SharedLane,
+    /// A bus lane open to cyclists (`share_busway`)
+    Busway,
+    /// A cycleway mapped as a way of its own (`separate`)
+    Separate,
+    /// Explicitly nothing (`no`, `none`)
+    No,
+}
+
+impl CycleFacility {
+    /// The facility a `cycleway` value describes, if it is a known one
+    pub fn from_value(value: &str) -> Option<CycleFacility> {
+        match value {
+            "lane" | "opposite_lane" => Some(CycleFacility::Lane),
+            "track" | "opposite_track" => Some(CycleFacility::Track),
+            "shared_lane" => Some(CycleFacility::SharedLane),
+            "share_busway" | "opposite_share_busway" => Some(CycleFacility::Busway),
+            "separate" => Some(CycleFacility::Separate),
+            "no" | "none" => Some(CycleFacility::No),
+            _ => None,
+        }
+    }
+
+    /// Check if the facility is infrastructure on the road itself
+    pub fn is_infrastructure(self) -> bool {
+        !matches!(self, CycleFacility::Separate | CycleFacility::No)
+    }
+}
+
+/// Directions cyclists may ride along a way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CyclingDirection {
+    Both,
+    /// Only in the direction of the way's nodes
+    Forward,
+    /// Only against the direction of the way's nodes
+    Backward,
+}
+
+/// How a way serves cyclists
+///
+/// Sides follow the direction of the way: `cycleway:left` and
+/// `cycleway:right` override `cycleway:both`, which overrides `cycleway`.
+/// The direction comes from `oneway:bicycle`, or else from `oneway` and
+/// roundabouts, except that an `opposite*` cycleway opens a one-way street
+/// to contraflow cycling.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::cycling::{CycleFacility, CyclingDirection};
+/// use geodesk_rs::Feature;
+///
+/// let street = Feature {
+///     id: 1,
+///     type_name: "way".into(),
+///     name: "Nørrebrogade".into(),
+///     lon: 12.55,
+///     lat: 55.69,
+///     tags: vec![
+///         ("highway".into(), "secondary".into()),
+///         ("oneway".into(), "yes".into()),
+///         ("oneway:bicycle".into(), "no".into()),
+///         ("cycleway:right".into(), "track".into()),
+///         ("surface".into(), "asphalt".into()),
+///     ],
+///     nodes: Vec::new(),
+/// };
+/// let cycling = street.cycling();
+/// assert_eq!(cycling.right, Some(CycleFacility::Track));
+/// assert_eq!(cycling.left, None);
+/// assert_eq!(cycling.direction, CyclingDirection::Both);
+/// assert!(cycling.has_infrastructure());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CyclingAttributes {
+    /// The way is meant for cyclists (`highway=cycleway` or
+    /// `bicycle=designated`)
+    pub dedicated: bool,
+    /// Facility on the left side of the way
+    pub left: Option<CycleFacility>,
+    /// Facility on the right side of the way
+    pub right: Option<CycleFacility>,
+    pub direction: CyclingDirection,
+    /// Value of the `surface` tag, such as `asphalt` or `gravel`
+    pub surface: Option<String>,
+}
+
+impl CyclingAttributes {
+    /// Read the cycling tags of a way
+    pub fn of(feature: &Feature) -> CyclingAttributes {
+        let both = feature
+            .tag("cycleway:both")
+            .or_else(|| feature.tag("cycleway"))
+            .and_then(CycleFacility::from_value);
+        let side = |key| feature.tag(key).map_or(both, CycleFacility::from_value);
+        let contraflow = [
+            "cycleway",
+            "cycleway:left",
+            "cycleway:right",
+            "cycleway:both",
+        ]
+        .iter()
+        .any(|key| feature.tag(key).is_some_and(|v| v.starts_with("opposite")));
+        let direction = match feature.tag("oneway:bicycle").or_else(|| {
+            if contraflow {
+                Some("no")
+            } else if feature.tag("junction") == Some("roundabout") {
+                Some(feature.tag("oneway").unwrap_or("yes"))
+            } else {
+                feature.tag("oneway")
+            }
+        }) {
+            Some("yes" | "1" | "true") => CyclingDirection::Forward,
+            Some("-1" | "reverse") => CyclingDirection::Backward,
+            _ => CyclingDirection::Both,
+        };
+        CyclingAttributes {
+            dedicated: feature.tag("highway") == Some("cycleway")
+                || feature.tag("bicycle") == Some("designated"),
+            left: side("cycleway:left"),
+            right: side("cycleway:right"),
+            direction,
+            surface: feature.tag("surface").map(str::to_string),
+        }
+    }
+
+    /// Check if the way is a cycleway or has cycling infrastructure on
+    /// either side (what [`GeoDesk::query_cycleways`] finds)
+    pub fn has_infrastructure(&self) -> bool {
+        self.dedicated
+            || [self.left, self.right]
+                .into_iter()
+                .flatten()
+                .any(CycleFacility::is_infrastructure)
+    }
+}
+
+impl Feature {
+    /// The cycling infrastructure of a way
+    pub fn cycling(&self) -> CyclingAttributes {
+        CyclingAttributes::of(self)
+    }
+}
+
+impl GeoDesk {
+    /// Query cycleways and the ways with cycling infrastructure (lanes,
+    /// tracks, shared lanes or bus lanes) on either side
+    pub fn query_cycleways(&self, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
+        let query = format!(
+            "w[highway=cycleway], w[bicycle=designated], w[cycleway={0}], \
+             w[cycleway:both={0}], w[cycleway:left={0}], w[cycleway:right={0}]",
+            FACILITY_VALUES
+        );
+        self.query(&query, bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn way(tags: &[(&str, &str)]) -> Feature {
+        Feature {
+            id: 1,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cycling_attributes() {
+        let path = way(&[("highway", "cycleway"), ("surface", "gravel")]).cycling();
+        assert!(path.dedicated && path.has_infrastructure());
+        assert_eq!(path.surface.as_deref(), Some("gravel"));
+        assert_eq!(path.direction, CyclingDirection::Both);
+
+        let street = way(&[
+            ("highway", "residential"),
+            ("cycleway:both", "lane"),
+            ("cycleway:left", "no"),
+        ])
+        .cycling();
+        assert_eq!(street.left, Some(CycleFacility::No));
+        assert_eq!(street.right, Some(CycleFacility::Lane));
+
+        let separate = way(&[("highway", "primary"), ("cycleway", "separate")]).cycling();
+        assert!(!separate.has_infrastructure());
+
+        let roundabout = way(&[("highway", "tertiary"), ("junction", "roundabout")]).cycling();
+        assert_eq!(roundabout.direction, CyclingDirection::Forward);
+        let contraflow = way(&[("oneway", "yes"), ("cycleway", "opposite_lane")]).cycling();
+        assert_eq!(contraflow.direction, CyclingDirection::Both);
+        assert_eq!(contraflow.right, Some(CycleFacility::Lane));
+        let reversed = way(&[("oneway", "yes"), ("oneway:bicycle", "-1")]).cycling();
+        assert_eq!(reversed.direction, CyclingDirection::Backward);
+    }
+}
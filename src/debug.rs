@@ -0,0 +1,133 @@
+// Runtime introspection of a GeoDesk handle, for bug reports and diagnostics
+
+use crate::{ffi, GeoDesk};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Counters for the queries issued through a `GeoDesk` handle
+#[derive(Debug, Default)]
+pub(crate) struct QueryActivity {
+    active: AtomicUsize,
+    executed: AtomicU64,
+}
+
+impl QueryActivity {
+    /// Record the start of a query; it counts as active until the guard drops
+    pub(crate) fn begin(&self) -> ActiveQuery<'_> {
+        self.active.fetch_add(1, Ordering::Relaxed);
+        self.executed.fetch_add(1, Ordering::Relaxed);
+        ActiveQuery { activity: self }
+    }
+}
+
+pub(crate) struct ActiveQuery<'a> {
+    activity: &'a QueryActivity,
+}
+
+impl Drop for ActiveQuery<'_> {
+    fn drop(&mut self) {
+        self.activity.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Snapshot of the runtime state of a `GeoDesk` handle
+///
+/// The `Display` implementation produces a compact multi-line summary
+/// suitable for pasting into bug reports.
+#[derive(Debug, Clone)]
+pub struct DebugState {
+    /// Path of the open GOL file
+    pub path: PathBuf,
+    /// Size of the memory-mapped GOL file in bytes
+    pub mapping_size: u64,
+    /// Bytes of the GOL currently held in the OS page cache, if the platform
+    /// can report it
+    pub resident_bytes: Option<u64>,
+    /// Queries currently executing on this handle
+    pub active_queries: usize,
+    /// Queries started on this handle since it was opened
+    pub queries_executed: u64,
+}
+
+impl DebugState {
+    /// Fraction of the GOL resident in the page cache (0.0 to 1.0)
+    ///
+    /// This is the probability that a random tile access is served from
+    /// memory rather than from disk.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let resident = self.resident_bytes?;
+        if self.mapping_size == 0 {
+            return None;
+        }
+        Some((resident as f64 / self.mapping_size as f64).min(1.0))
+    }
+}
+
+impl fmt::Display for DebugState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "geodesk-rs {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(f, "  file:           {}", self.path.display())?;
+        writeln!(f, "  mapping size:   {} bytes", self.mapping_size)?;
+        match self.cache_hit_rate() {
+            Some(rate) => writeln!(f, "  page cache:     {:.1}% resident", rate * 100.0)?,
+            None => writeln!(f, "  page cache:     unavailable")?,
+        }
+        writeln!(f, "  active queries: {}", self.active_queries)?;
+        write!(f, "  queries run:    {}", self.queries_executed)
+    }
+}
+
+impl GeoDesk {
+    /// Capture the handle's runtime state for debugging
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("world.gol").unwrap();
+    /// eprintln!("{}", geodesk.debug_state());
+    /// ```
+    pub fn debug_state(&self) -> DebugState {
+        let info = ffi::bridge::store_debug_info(&self.store);
+        DebugState {
+            path: PathBuf::from(info.file_path),
+            mapping_size: info.mapping_size,
+            resident_bytes: u64::try_from(info.resident_bytes).ok(),
+            active_queries: self.activity.active.load(Ordering::Relaxed),
+            queries_executed: self.activity.executed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_counters() {
+        let activity = QueryActivity::default();
+        {
+            let _a = activity.begin();
+            let _b = activity.begin();
+            assert_eq!(activity.active.load(Ordering::Relaxed), 2);
+        }
+        assert_eq!(activity.active.load(Ordering::Relaxed), 0);
+        assert_eq!(activity.executed.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_cache_hit_rate() {
+        let mut state = DebugState {
+            path: PathBuf::from("world.gol"),
+            mapping_size: 1000,
+            resident_bytes: Some(250),
+            active_queries: 0,
+            queries_executed: 0,
+        };
+        assert_eq!(state.cache_hit_rate(), Some(0.25));
+        state.resident_bytes = None;
+        assert_eq!(state.cache_hit_rate(), None);
+        assert!(state.to_string().contains("page cache:     unavailable"));
+    }
+}
@@ -0,0 +1,206 @@
+// Counting features per cell of a regular grid, for heatmaps
+
+use crate::error::GeoDeskError;
+use crate::projection::METERS_PER_DEGREE;
+use crate::tags::{parse_number, NumberParsing};
+use crate::zonal::Aggregate;
+use crate::{BoundingBox, GeoDesk};
+use std::ops::ControlFlow;
+
+/// Feature counts (and tag sums) over a grid of cells covering a bounding
+/// box, from [`GeoDesk::density_grid`]
+///
+/// Cells are stored row by row from the south-west. They are square in
+/// meters at the latitude of the box's center, so their height in degrees
+/// is the same everywhere and their width in degrees grows with the
+/// latitude of the center; the last row and column may extend past the
+/// box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityGrid {
+    /// South-west corner of the first cell
+    pub west: f64,
+    pub south: f64,
+    /// Size of a cell in degrees
+    pub cell_width: f64,
+    pub cell_height: f64,
+    pub columns: usize,
+    pub rows: usize,
+    /// Features located in each cell
+    pub counts: Vec<u64>,
+    /// Sum of the tag of [`Aggregate::Tag`] in each cell (all zero for
+    /// [`Aggregate::Count`])
+    pub sums: Vec<f64>,
+}
+
+impl DensityGrid {
+    fn new(bbox: BoundingBox, cell_size_m: f64) -> Result<Self, GeoDeskError> {
+        if !(cell_size_m.is_finite() && cell_size_m > 0.0) {
+            return Err(GeoDeskError::Engine(format!(
+                "Invalid cell size {} m",
+                cell_size_m
+            )));
+        }
+        let center_lat = (bbox.south + bbox.north) / 2.0;
+        let cell_height = cell_size_m / METERS_PER_DEGREE;
+        let cell_width = cell_height / center_lat.to_radians().cos().max(0.01);
+        let cells = |extent: f64, size: f64| ((extent / size).ceil() as usize).max(1);
+        let columns = cells(bbox.east - bbox.west, cell_width);
+        let rows = cells(bbox.north - bbox.south, cell_height);
+        Ok(Self {
+            west: bbox.west,
+            south: bbox.south,
+            cell_width,
+            cell_height,
+            columns,
+            rows,
+            counts: vec![0; columns * rows],
+            sums: vec![0.0; columns * rows],
+        })
+    }
+
+    /// Index of the cell containing a point
+    fn index(&self, lon: f64, lat: f64) -> Option<usize> {
+        let column = ((lon - self.west) / self.cell_width).floor();
+        let row = ((lat - self.south) / self.cell_height).floor();
+        if column < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (column, row) = (column as usize, row as usize);
+        (column < self.columns && row < self.rows).then(|| row * self.columns + column)
+    }
+
+    fn add(&mut self, lon: f64, lat: f64, value: Option<f64>) {
+        if let Some(i) = self.index(lon, lat) {
+            self.counts[i] += 1;
+            self.sums[i] += value.unwrap_or(0.0);
+        }
+    }
+
+    /// Number of features in a cell
+    pub fn count(&self, column: usize, row: usize) -> u64 {
+        self.counts[row * self.columns + column]
+    }
+
+    /// Sum of the tag in a cell
+    pub fn sum(&self, column: usize, row: usize) -> f64 {
+        self.sums[row * self.columns + column]
+    }
+
+    /// Highest count of any cell, to scale a heatmap's colors
+    pub fn max_count(&self) -> u64 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Bounding box of a cell
+    pub fn cell_bounds(&self, column: usize, row: usize) -> BoundingBox {
+        let west = self.west + column as f64 * self.cell_width;
+        let south = self.south + row as f64 * self.cell_height;
+        BoundingBox::new(
+            west,
+            south,
+            west + self.cell_width,
+            south + self.cell_height,
+        )
+    }
+}
+
+impl GeoDesk {
+    /// Count the features matching a GOQL query in each cell of a grid
+    /// with cells of `cell_size_m` meters
+    ///
+    /// Features are counted in the cell containing their location (the
+    /// center of ways and relations), inside the query loop without being
+    /// copied.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let grid = geodesk
+    ///     .density_grid("na[amenity=restaurant]", bboxes::COPENHAGEN, 250.0)
+    ///     .unwrap();
+    /// for row in (0..grid.rows).rev() {
+    ///     let line: String = (0..grid.columns)
+    ///         .map(|column| match grid.count(column, row) {
+    ///             0 => ' ',
+    ///             1..=4 => '.',
+    ///             _ => '#',
+    ///         })
+    ///         .collect();
+    ///     println!("{}", line);
+    /// }
+    /// ```
+    pub fn density_grid(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        cell_size_m: f64,
+    ) -> Result<DensityGrid, GeoDeskError> {
+        self.density_grid_with(goql_query, bbox, cell_size_m, &Aggregate::Count)
+    }
+
+    /// Count the features matching a GOQL query in each cell of a grid,
+    /// and sum up a numeric tag with [`Aggregate::Tag`]
+    ///
+    /// Tag values are parsed tolerantly; features without a number are
+    /// only counted.
+    pub fn density_grid_with(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        cell_size_m: f64,
+        aggregate: &Aggregate,
+    ) -> Result<DensityGrid, GeoDeskError> {
+        let mut grid = DensityGrid::new(bbox, cell_size_m)?;
+        let number = |value: String| parse_number(&value, NumberParsing::Tolerant).ok();
+        let mut error = None;
+        self.for_each(goql_query, bbox, |feature| {
+            let value = match aggregate {
+                Aggregate::Count => None,
+                Aggregate::Tag(key) => match feature.tag(key) {
+                    Ok(value) => value.and_then(number),
+                    Err(e) => {
+                        error = Some(e);
+                        return ControlFlow::Break(());
+                    }
+                },
+            };
+            grid.add(feature.lon(), feature.lat(), value);
+            ControlFlow::Continue(())
+        })?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok(grid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_density_grid() {
+        // 1 km cells at the equator
+        let bbox = BoundingBox::new(0.0, 0.0, 0.025, 0.015);
+        let mut grid = DensityGrid::new(bbox, 1_000.0).unwrap();
+        assert_eq!((grid.columns, grid.rows), (3, 2));
+        grid.add(0.001, 0.001, Some(2.0));
+        grid.add(0.002, 0.002, None);
+        grid.add(0.024, 0.014, Some(5.0));
+        grid.add(0.5, 0.5, Some(1.0)); // Outside
+        assert_eq!(grid.count(0, 0), 2);
+        assert_eq!(grid.sum(0, 0), 2.0);
+        assert_eq!(grid.count(2, 1), 1);
+        assert_eq!(grid.max_count(), 2);
+        assert_eq!(grid.counts.iter().sum::<u64>(), 3);
+        assert!(grid.cell_bounds(2, 1).east >= bbox.east);
+
+        // Cells get wider in degrees away from the equator
+        let north = DensityGrid::new(BoundingBox::new(0.0, 59.9, 0.1, 60.1), 1_000.0).unwrap();
+        assert!((north.cell_width / north.cell_height - 2.0).abs() < 1e-9);
+        assert!(DensityGrid::new(bbox, 0.0).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+// Comparison of the features of two GOL builds
+
+use crate::error::GeoDeskError;
+use crate::provenance::same_content;
+use crate::{BoundingBox, Feature, GeoDesk};
+use std::collections::HashMap;
+
+/// A feature whose tags or geometry differ between two builds
+#[derive(Debug, Clone)]
+pub struct Modified {
+    pub old: Feature,
+    pub new: Feature,
+}
+
+/// A tag that was added, removed or changed, as (key, old value, new value)
+pub type TagChange = (String, Option<String>, Option<String>);
+
+impl Modified {
+    /// Tags that differ between the two versions, sorted by key
+    pub fn tag_changes(&self) -> Vec<TagChange> {
+        let mut changes: Vec<TagChange> = Vec::new();
+        for (key, value) in &self.old.tags {
+            let new_value = self.new.tag(key);
+            if new_value != Some(value.as_str()) {
+                changes.push((
+                    key.clone(),
+                    Some(value.clone()),
+                    new_value.map(str::to_string),
+                ));
+            }
+        }
+        for (key, value) in &self.new.tags {
+            if !self.old.has_tag(key) {
+                changes.push((key.clone(), None, Some(value.clone())));
+            }
+        }
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        changes
+    }
+
+    /// Check if the location or way nodes changed
+    pub fn geometry_changed(&self) -> bool {
+        let (old, new) = (&self.old, &self.new);
+        old.lon != new.lon
+            || old.lat != new.lat
+            || old.nodes.len() != new.nodes.len()
+            || old
+                .nodes
+                .iter()
+                .zip(&new.nodes)
+                .any(|(a, b)| a.id != b.id || a.lon != b.lon || a.lat != b.lat)
+    }
+}
+
+/// Decimal places [`Feature::geometry_hash`] rounds coordinates to, the
+/// precision OSM stores them in
+pub const DEFAULT_HASH_PRECISION: u8 = 7;
+
+impl Feature {
+    /// Hash of the feature's geometry, for detecting geometry changes
+    /// between snapshots without keeping the coordinates
+    ///
+    /// The hash covers the way nodes of ways and the location of other
+    /// features, with coordinates rounded to
+    /// [`DEFAULT_HASH_PRECISION`] decimal places. It is computed with a
+    /// fixed algorithm (64-bit FNV-1a), so it is stable across runs,
+    /// platforms and versions of this crate and can be stored.
+    pub fn geometry_hash(&self) -> u64 {
+        self.geometry_hash_with(DEFAULT_HASH_PRECISION)
+    }
+
+    /// Hash of the feature's geometry with coordinates rounded to
+    /// `decimals` decimal places (at most 9), so that changes below that
+    /// precision don't count
+    ///
+    /// # Example
+    /// ```
+    /// use geodesk_rs::Feature;
+    ///
+    /// let mut cafe = Feature {
+    ///     id: 1,
+    ///     type_name: "node".to_string(),
+    ///     name: String::new(),
+    ///     lon: 12.5683,
+    ///     lat: 55.6761,
+    ///     tags: Vec::new(),
+    ///     nodes: Vec::new(),
+    /// };
+    /// let before = cafe.geometry_hash_with(4);
+    /// cafe.lon += 0.000_01;
+    /// assert_eq!(cafe.geometry_hash_with(4), before);
+    /// assert_ne!(cafe.geometry_hash(), before);
+    /// ```
+    pub fn geometry_hash_with(&self, decimals: u8) -> u64 {
+        let scale = 10f64.powi(i32::from(decimals.min(9)));
+        let mut hash = FNV_OFFSET;
+        let mut add = |coordinate: f64| {
+            for byte in ((coordinate * scale).round() as i64).to_le_bytes() {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+            }
+        };
+        if self.is_way() && !self.nodes.is_empty() {
+            for node in &self.nodes {
+                add(node.lon);
+                add(node.lat);
+            }
+        } else {
+            add(self.lon);
+            add(self.lat);
+        }
+        hash
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Features added, removed and modified between two builds
+#[derive(Debug, Clone, Default)]
+pub struct Diff {
+    /// Features only in the newer build
+    pub added: Vec<Feature>,
+    /// Features only in the older build
+    pub removed: Vec<Feature>,
+    /// Features in both builds whose tags or geometry differ
+    pub modified: Vec<Modified>,
+}
+
+impl Diff {
+    /// Compare two sets of features, matching them by type and ID
+    pub fn between<O, N>(old: O, new: N) -> Self
+    where
+        O: IntoIterator<Item = Feature>,
+        N: IntoIterator<Item = Feature>,
+    {
+        let mut remaining: HashMap<(String, i64), Feature> = old
+            .into_iter()
+            .map(|f| ((f.type_name.clone(), f.id), f))
+            .collect();
+        let mut diff = Self::default();
+        for feature in new {
+            match remaining.remove(&(feature.type_name.clone(), feature.id)) {
+                None => diff.added.push(feature),
+                Some(old) if !same_content(&old, &feature) => {
+                    diff.modified.push(Modified { old, new: feature })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = remaining.into_values().collect();
+        diff.removed
+            .sort_by(|a, b| (&a.type_name, a.id).cmp(&(&b.type_name, b.id)));
+        diff
+    }
+
+    /// Check if the builds are identical for the compared features
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl GeoDesk {
+    /// Compare the features matching a query in this (older) GOL with
+    /// those in a newer build
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::{GeoDesk, BoundingBox};
+    ///
+    /// let last_month = GeoDesk::open("denmark-2024-05.gol").unwrap();
+    /// let this_month = GeoDesk::open("denmark-2024-06.gol").unwrap();
+    /// let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+    /// let diff = last_month
+    ///     .diff(&this_month, "na[amenity=restaurant]", bbox)
+    ///     .unwrap();
+    /// for restaurant in &diff.added {
+    ///     println!("New: {}", restaurant.name);
+    /// }
+    /// ```
+    pub fn diff(
+        &self,
+        other: &GeoDesk,
+        goql_query: &str,
+        bbox: BoundingBox,
+    ) -> Result<Diff, GeoDeskError> {
+        let old = self
+            .query_iter(goql_query, bbox)?
+            .collect::<Result<Vec<_>, _>>()?;
+        // Stream the newer side; only the older one is held in memory
+        let mut error = None;
+        let new = other
+            .query_iter(goql_query, bbox)?
+            .map_while(|f| f.map_err(|e| error = Some(e)).ok());
+        let diff = Diff::between(old, new);
+        match error {
+            Some(e) => Err(e),
+            None => Ok(diff),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: i64, tags: &[(&str, &str)]) -> Feature {
+        Feature {
+            id,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 12.5,
+            lat: 55.7,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_between() {
+        let old = vec![
+            node(1, &[("amenity", "restaurant")]),
+            node(2, &[("amenity", "restaurant"), ("name", "Noma")]),
+            node(3, &[("amenity", "cafe")]),
+        ];
+        let new = vec![
+            node(1, &[("amenity", "restaurant")]),
+            node(2, &[("amenity", "restaurant"), ("name", "Noma 2.0")]),
+            node(4, &[("amenity", "bar")]),
+        ];
+        let diff = Diff::between(old, new);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, 4);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].id, 3);
+        assert_eq!(diff.modified.len(), 1);
+        assert!(!diff.modified[0].geometry_changed());
+        assert_eq!(
+            diff.modified[0].tag_changes(),
+            vec![(
+                "name".to_string(),
+                Some("Noma".to_string()),
+                Some("Noma 2.0".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_geometry_hash() {
+        use crate::Node;
+
+        let mut way = node(5, &[]);
+        way.type_name = "way".to_string();
+        way.nodes = vec![
+            Node {
+                id: 1,
+                lon: 12.5,
+                lat: 55.7,
+            },
+            Node {
+                id: 2,
+                lon: 12.6,
+                lat: 55.8,
+            },
+        ];
+        // The value must never change, as callers store it
+        assert_eq!(way.geometry_hash(), 0xb2ec_48c2_83f8_fa81);
+
+        let mut moved = way.clone();
+        moved.lon = 0.0;
+        moved.tags.push(("highway".to_string(), "path".to_string()));
+        assert_eq!(moved.geometry_hash(), way.geometry_hash());
+        moved.nodes[1].lat += 1e-6;
+        assert_ne!(moved.geometry_hash(), way.geometry_hash());
+        assert_eq!(moved.geometry_hash_with(5), way.geometry_hash_with(5));
+        moved.nodes.reverse();
+        assert_ne!(moved.geometry_hash_with(5), way.geometry_hash_with(5));
+    }
+}
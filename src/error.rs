@@ -0,0 +1,152 @@
+// Error type for GeoDESK operations
+
+use crate::tiles::TileId;
+use std::any::Any;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Errors returned by [`GeoDesk`](crate::GeoDesk) and its results
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GeoDeskError {
+    /// The GOL path is not valid UTF-8
+    InvalidPath(PathBuf),
+    /// An error reported by the GeoDESK engine (bad GOQL, unreadable file, ...)
+    Engine(String),
+    /// The query area includes tiles that are not present in the GOL
+    ///
+    /// Extracts of a larger GOL only contain some of its tiles; querying
+    /// outside of them would silently return incomplete data.
+    TilesMissing { tiles: Vec<TileId> },
+    /// The GOL was written in a format version this build can't read
+    UnsupportedVersion { found: (u16, u16), supported: u16 },
+    /// The GOL was updated while a [`Snapshot`](crate::snapshot::Snapshot)
+    /// was in use
+    RevisionChanged { expected: u32, found: u32 },
+    /// A query matched more features than
+    /// [`QueryOptions::max_results`](crate::options::QueryOptions::max_results)
+    TooManyResults { limit: u64 },
+    /// A query was stopped through its
+    /// [`CancellationToken`](crate::cancel::CancellationToken)
+    Cancelled,
+    /// A query ran longer than
+    /// [`QueryOptions::timeout`](crate::options::QueryOptions::timeout)
+    TimedOut { timeout: Duration },
+    /// A closure called from inside a query panicked; the panic was
+    /// caught at the C++ boundary, which it can't unwind through
+    Panicked(String),
+    /// A coordinate reference system that transformations don't support
+    UnsupportedCrs { epsg: u32 },
+    /// The external GeoDESK `gol` tool, which builds and extracts GOLs,
+    /// is not installed at the given path or on the `PATH`
+    ToolNotFound { tool: PathBuf },
+    /// OSM version, timestamp and changeset were asked for, but the GOL
+    /// was built without them
+    MetadataUnavailable,
+    /// Writing output failed
+    Io(io::Error),
+}
+
+impl fmt::Display for GeoDeskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoDeskError::InvalidPath(path) => write!(f, "Invalid path: {}", path.display()),
+            GeoDeskError::Engine(message) => f.write_str(message),
+            GeoDeskError::TilesMissing { tiles } => {
+                write!(f, "{} tile(s) missing from the GOL", tiles.len())?;
+                if let Some(first) = tiles.first() {
+                    write!(f, " (first: {}/{}/{})", first.z, first.x, first.y)?;
+                }
+                Ok(())
+            }
+            GeoDeskError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Unsupported GOL format version {}.{} (supported: {}.x)",
+                found.0, found.1, supported
+            ),
+            GeoDeskError::RevisionChanged { expected, found } => write!(
+                f,
+                "GOL changed from revision {} to {} during a snapshot",
+                expected, found
+            ),
+            GeoDeskError::TooManyResults { limit } => {
+                write!(f, "Query matched more than {} features", limit)
+            }
+            GeoDeskError::Cancelled => f.write_str("Query cancelled"),
+            GeoDeskError::TimedOut { timeout } => {
+                write!(f, "Query timed out after {:?}", timeout)
+            }
+            GeoDeskError::Panicked(message) => write!(f, "Callback panicked: {}", message),
+            GeoDeskError::UnsupportedCrs { epsg } => {
+                write!(f, "Unsupported coordinate reference system EPSG:{}", epsg)
+            }
+            GeoDeskError::ToolNotFound { tool } => write!(
+                f,
+                "{} not found; install the GeoDESK gol tool or set GEODESK_GOL_TOOL",
+                tool.display()
+            ),
+            GeoDeskError::MetadataUnavailable => {
+                f.write_str("The GOL has no OSM version, timestamp or changeset")
+            }
+            GeoDeskError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl GeoDeskError {
+    /// Short, stable name of the variant, for labelling error metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GeoDeskError::InvalidPath(_) => "invalid_path",
+            GeoDeskError::Engine(_) => "engine",
+            GeoDeskError::TilesMissing { .. } => "tiles_missing",
+            GeoDeskError::UnsupportedVersion { .. } => "unsupported_version",
+            GeoDeskError::RevisionChanged { .. } => "revision_changed",
+            GeoDeskError::TooManyResults { .. } => "too_many_results",
+            GeoDeskError::Cancelled => "cancelled",
+            GeoDeskError::TimedOut { .. } => "timed_out",
+            GeoDeskError::Panicked(_) => "panicked",
+            GeoDeskError::UnsupportedCrs { .. } => "unsupported_crs",
+            GeoDeskError::ToolNotFound { .. } => "tool_not_found",
+            GeoDeskError::MetadataUnavailable => "metadata_unavailable",
+            GeoDeskError::Io(_) => "io",
+        }
+    }
+}
+
+impl GeoDeskError {
+    /// Wrap the payload of a caught panic
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .map_or("unknown panic payload", |m| m)
+                .to_string(),
+        };
+        GeoDeskError::Panicked(message)
+    }
+}
+
+impl std::error::Error for GeoDeskError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GeoDeskError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GeoDeskError {
+    fn from(e: io::Error) -> Self {
+        GeoDeskError::Io(e)
+    }
+}
+
+impl From<cxx::Exception> for GeoDeskError {
+    fn from(e: cxx::Exception) -> Self {
+        GeoDeskError::Engine(e.what().to_string())
+    }
+}
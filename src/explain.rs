@@ -0,0 +1,289 @@
+// Query statistics and plans, for tuning slow queries
+
+use crate::error::GeoDeskError;
+use crate::query::ParseQueryError;
+use crate::GeoDesk;
+use std::fmt;
+use std::time::Duration;
+
+/// Where the time of a query went and how much of the GOL it touched,
+/// from [`QueryResult::stats`](crate::QueryResult::stats)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryStats {
+    /// Tiles of the GOL intersecting the query area, leaving out any
+    /// missing from the file
+    pub tiles_scanned: usize,
+    /// Features the GOQL matcher returned, before any
+    /// [`QueryOptions::tag_matches`](crate::options::QueryOptions::tag_matches)
+    pub features_examined: u64,
+    /// Features that matched, including any that
+    /// [`QueryOptions`](crate::options::QueryOptions) limits left out
+    pub features_matched: u64,
+    /// Checking the query area for missing tiles
+    pub tile_check: Duration,
+    /// Walking the indexes and matching features
+    pub matching: Duration,
+    /// Copying tags and geometry of matches into the result
+    pub copying: Duration,
+}
+
+impl QueryStats {
+    /// Time spent in the query
+    pub fn total(&self) -> Duration {
+        self.tile_check + self.matching + self.copying
+    }
+
+    /// Fraction of the examined features that matched (1.0 if none were
+    /// examined)
+    pub fn selectivity(&self) -> f64 {
+        if self.features_examined == 0 {
+            1.0
+        } else {
+            self.features_matched as f64 / self.features_examined as f64
+        }
+    }
+}
+
+/// How one comma-separated selector of a query is evaluated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorPlan {
+    /// The selector as written
+    pub selector: String,
+    /// Feature types searched, as GOQL type letters (`n`, `w`, `a`, `r`)
+    pub types: String,
+    /// Required keys with a dedicated index in the GOL; only the features
+    /// filed under them are examined
+    pub indexed_keys: Vec<String>,
+    /// Keys tested on every examined feature
+    pub scanned_keys: Vec<String>,
+}
+
+impl SelectorPlan {
+    /// Check if every feature of the selected types in the area is examined
+    pub fn is_full_scan(&self) -> bool {
+        self.indexed_keys.is_empty()
+    }
+}
+
+/// How a GOQL query is evaluated, from [`GeoDesk::explain`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryPlan {
+    pub selectors: Vec<SelectorPlan>,
+}
+
+impl fmt::Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, plan) in self.selectors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}: search {}", plan.selector, type_names(&plan.types))?;
+            if plan.is_full_scan() {
+                write!(f, ", full scan")?;
+            } else {
+                write!(f, " via index on {}", plan.indexed_keys.join(", "))?;
+            }
+            if !plan.scanned_keys.is_empty() {
+                write!(f, ", then test {}", plan.scanned_keys.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn type_names(types: &str) -> String {
+    let names: Vec<&str> = types
+        .chars()
+        .map(|c| match c {
+            'n' => "nodes",
+            'w' => "ways",
+            'a' => "areas",
+            _ => "relations",
+        })
+        .collect();
+    names.join(", ")
+}
+
+/// A key tested by a selector, and whether features must have it to match
+struct KeyTest {
+    key: String,
+    required: bool,
+}
+
+/// Split a query into its selectors, at commas outside of brackets and
+/// quotes
+fn split_selectors(goql: &str) -> Vec<&str> {
+    let mut selectors = Vec::new();
+    let (mut depth, mut quote, mut start) = (0, None, 0);
+    for (i, c) in goql.char_indices() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('[', None) => depth += 1,
+            (']', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                selectors.push(goql[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    selectors.push(goql[start..].trim());
+    selectors
+}
+
+/// The types and key tests of a selector
+///
+/// Values are skipped rather than parsed, so wildcards and regular
+/// expressions are accepted.
+fn scan_selector(selector: &str) -> Result<(String, Vec<KeyTest>), String> {
+    let types_end = selector
+        .find(|c: char| !matches!(c, 'n' | 'w' | 'a' | 'r' | '*'))
+        .unwrap_or(selector.len());
+    let types = &selector[..types_end];
+    let types = if types.is_empty() || types.contains('*') {
+        "nwar".to_string()
+    } else {
+        types.to_string()
+    };
+    let mut tests = Vec::new();
+    let mut rest = selector[types_end..].trim_start();
+    while !rest.is_empty() {
+        let inner = rest
+            .strip_prefix('[')
+            .ok_or_else(|| format!("unexpected '{}'", rest.chars().next().unwrap_or(' ')))?;
+        let end = filter_end(inner).ok_or("unterminated filter")?;
+        let filter = inner[..end].trim();
+        let negated = filter.starts_with('!');
+        let filter = filter.trim_start_matches('!').trim_start();
+        let key_end = filter
+            .find(|c: char| matches!(c, '=' | '!' | '<' | '>' | '~') || c.is_whitespace())
+            .unwrap_or(filter.len());
+        let key = filter[..key_end].trim_matches(|c| c == '"' || c == '\'');
+        if key.is_empty() {
+            return Err("expected a key".to_string());
+        }
+        let op = filter[key_end..].trim_start();
+        tests.push(KeyTest {
+            key: key.to_string(),
+            // [!k], [k!=v] and [k!~v] also match features without the key
+            required: !negated && !op.starts_with('!'),
+        });
+        rest = inner[end + 1..].trim_start();
+    }
+    Ok((types, tests))
+}
+
+/// Position of the `]` closing a filter, skipping quoted values
+fn filter_end(filter: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in filter.char_indices() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (']', None) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Plan a query against the indexed keys of a GOL
+fn plan(goql: &str, indexed: &[String]) -> Result<QueryPlan, ParseQueryError> {
+    let selectors = split_selectors(goql)
+        .into_iter()
+        .map(|selector| {
+            let (types, tests) =
+                scan_selector(selector).map_err(|reason| ParseQueryError::new(goql, reason))?;
+            let (mut indexed_keys, mut scanned_keys) = (Vec::new(), Vec::new());
+            for test in tests {
+                let list = if test.required && indexed.contains(&test.key) {
+                    &mut indexed_keys
+                } else {
+                    &mut scanned_keys
+                };
+                if !list.contains(&test.key) {
+                    list.push(test.key);
+                }
+            }
+            Ok(SelectorPlan {
+                selector: selector.to_string(),
+                types,
+                indexed_keys,
+                scanned_keys,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(QueryPlan { selectors })
+}
+
+impl GeoDesk {
+    /// Describe how a GOQL query will be evaluated
+    ///
+    /// GOLs file the features of each tile under a few indexed keys
+    /// (`building`, `highway`, ...; see
+    /// [`GolMetadata::indexed_keys`](crate::GolMetadata::indexed_keys)).
+    /// A selector that requires one of them only examines the features
+    /// filed under it; any other selector examines every feature of its
+    /// types in the area.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// println!("{}", geodesk.explain("na[amenity=cafe], w[name=\"Strøget\"]").unwrap());
+    /// // na[amenity=cafe]: search nodes, areas via index on amenity
+    /// // w[name="Strøget"]: search ways, full scan, then test name
+    /// ```
+    pub fn explain(&self, goql_query: &str) -> Result<QueryPlan, GeoDeskError> {
+        let indexed = self.metadata()?.indexed_keys;
+        plan(goql_query, &indexed).map_err(|e| GeoDeskError::Engine(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let indexed = vec!["amenity".to_string(), "highway".to_string()];
+        let plan = plan(
+            "na[amenity=cafe][name~\"^C.*\"], w[!highway][name='A, B'], *[building]",
+            &indexed,
+        )
+        .unwrap();
+        assert_eq!(plan.selectors.len(), 3);
+        assert_eq!(plan.selectors[0].types, "na");
+        assert_eq!(plan.selectors[0].indexed_keys, vec!["amenity"]);
+        assert_eq!(plan.selectors[0].scanned_keys, vec!["name"]);
+        assert!(plan.selectors[1].is_full_scan());
+        assert_eq!(plan.selectors[1].scanned_keys, vec!["highway", "name"]);
+        assert_eq!(plan.selectors[2].types, "nwar");
+        assert_eq!(
+            plan.to_string(),
+            "na[amenity=cafe][name~\"^C.*\"]: search nodes, areas via index on amenity, \
+             then test name\n\
+             w[!highway][name='A, B']: search ways, full scan, then test highway, name\n\
+             *[building]: search nodes, ways, areas, relations, full scan, then test building"
+        );
+        assert!(super::plan("n[amenity", &indexed).is_err());
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = QueryStats {
+            features_examined: 200,
+            features_matched: 50,
+            tile_check: Duration::from_millis(1),
+            matching: Duration::from_millis(8),
+            copying: Duration::from_millis(3),
+            ..Default::default()
+        };
+        assert_eq!(stats.total(), Duration::from_millis(12));
+        assert_eq!(stats.selectivity(), 0.25);
+        assert_eq!(QueryStats::default().selectivity(), 1.0);
+    }
+}
@@ -0,0 +1,117 @@
+// CSV output with one row per feature
+
+use super::dialect::Dialect;
+use super::FeatureWriter;
+use crate::Feature;
+use std::io::{self, Write};
+
+/// Streams features as CSV rows
+///
+/// Every row has the columns `id,type,lon,lat,name` followed by one column
+/// per requested tag key (empty if the feature lacks the tag). The order and
+/// number of coordinate columns follow the [`Dialect`].
+///
+/// # Example
+/// ```
+/// use geodesk_rs::export::CsvWriter;
+///
+/// let mut writer = CsvWriter::new(Vec::new(), &["cuisine"]).unwrap();
+/// let csv = writer.finish().unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "id,type,lon,lat,name,cuisine\n");
+/// ```
+pub struct CsvWriter<W: Write> {
+    out: W,
+    tag_columns: Vec<String>,
+    dialect: Dialect,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Create a writer and emit the header row
+    pub fn new<S: AsRef<str>>(out: W, tag_columns: &[S]) -> io::Result<Self> {
+        Self::with_dialect(out, tag_columns, Dialect::default())
+    }
+
+    /// Create a writer using the given coordinate conventions
+    pub fn with_dialect<S: AsRef<str>>(
+        mut out: W,
+        tag_columns: &[S],
+        dialect: Dialect,
+    ) -> io::Result<Self> {
+        let tag_columns: Vec<String> = tag_columns.iter().map(|c| c.as_ref().to_string()).collect();
+        write!(out, "id,type,{},name", dialect.coord_names().join(","))?;
+        for column in &tag_columns {
+            out.write_all(b",")?;
+            write_field(&mut out, column)?;
+        }
+        out.write_all(b"\n")?;
+        Ok(Self {
+            out,
+            tag_columns,
+            dialect,
+        })
+    }
+
+    /// Append a feature as a row
+    pub fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        write!(self.out, "{},{},", feature.id, feature.type_name)?;
+        self.dialect
+            .write_coords(&mut self.out, feature.lon, feature.lat, ",")?;
+        self.out.write_all(b",")?;
+        write_field(&mut self.out, &feature.name)?;
+        for column in &self.tag_columns {
+            self.out.write_all(b",")?;
+            write_field(&mut self.out, feature.tag(column).unwrap_or(""))?;
+        }
+        self.out.write_all(b"\n")
+    }
+
+    /// Flush and return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> FeatureWriter for CsvWriter<W> {
+    fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        CsvWriter::write_feature(self, feature)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        CsvWriter::finish(*self).map(|_| ())
+    }
+}
+
+/// Write a field, quoting it if it contains a delimiter, quote or line break
+fn write_field<W: Write>(out: &mut W, value: &str) -> io::Result<()> {
+    if value.contains([',', '"', '\n', '\r']) {
+        write!(out, "\"{}\"", value.replace('"', "\"\""))
+    } else {
+        out.write_all(value.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_and_quoting() {
+        let feature = Feature {
+            id: 3,
+            type_name: "node".to_string(),
+            name: "Bar \"Zum\", Eck".to_string(),
+            lon: 1.5,
+            lat: -2.0,
+            tags: vec![("cuisine".to_string(), "german".to_string())],
+            nodes: Vec::new(),
+        };
+        let mut writer = CsvWriter::new(Vec::new(), &["cuisine", "phone"]).unwrap();
+        writer.write_feature(&feature).unwrap();
+        let csv = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "id,type,lon,lat,name,cuisine,phone\n3,node,1.5,-2,\"Bar \"\"Zum\"\", Eck\",german,\n"
+        );
+    }
+}
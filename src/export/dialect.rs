@@ -0,0 +1,96 @@
+// Coordinate conventions shared by the text exporters
+
+use std::io::{self, Write};
+
+/// Order of the two horizontal coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AxisOrder {
+    /// Longitude first, as required by GeoJSON (RFC 7946) and WKT
+    #[default]
+    LonLat,
+    /// Latitude first, as used by the EPSG:4326 definition and many
+    /// spreadsheet-oriented tools
+    LatLon,
+}
+
+/// Whether a third coordinate is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZCoordinate {
+    /// Two-dimensional coordinates
+    #[default]
+    Omit,
+    /// Three-dimensional coordinates with a height of 0, for consumers
+    /// that reject 2D input
+    Zero,
+}
+
+/// How the coordinate reference system is declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Crs {
+    /// No declaration (RFC 7946 GeoJSON, plain WKT)
+    #[default]
+    Implicit,
+    /// OGC CRS84 URN, the pre-RFC 7946 GeoJSON convention for lon/lat data
+    Crs84,
+    /// EPSG:4326 (`urn:ogc:def:crs:EPSG::4326` in GeoJSON, `SRID=4326;`
+    /// in WKT)
+    Epsg4326,
+}
+
+/// Coordinate conventions for GeoJSON, WKT and CSV output
+///
+/// Consumers disagree about axis order, dimensionality and how to declare
+/// the CRS; the default follows RFC 7946. Each writer applies the settings
+/// its format can express: CSV has no place for a CRS declaration, and in
+/// WKT the CRS is expressed as an EWKT `SRID` prefix.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::export::dialect::{AxisOrder, Dialect};
+/// use geodesk_rs::export::CsvWriter;
+///
+/// let dialect = Dialect {
+///     axis_order: AxisOrder::LatLon,
+///     ..Dialect::default()
+/// };
+/// let writer = CsvWriter::with_dialect(Vec::new(), &["cuisine"], dialect).unwrap();
+/// let csv = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// assert_eq!(csv, "id,type,lat,lon,name,cuisine\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dialect {
+    pub axis_order: AxisOrder,
+    pub z: ZCoordinate,
+    pub crs: Crs,
+}
+
+impl Dialect {
+    /// Coordinates in output order, separated by `separator`
+    pub(crate) fn write_coords<W: Write>(
+        &self,
+        out: &mut W,
+        lon: f64,
+        lat: f64,
+        separator: &str,
+    ) -> io::Result<()> {
+        let (a, b) = match self.axis_order {
+            AxisOrder::LonLat => (lon, lat),
+            AxisOrder::LatLon => (lat, lon),
+        };
+        write!(out, "{}{}{}", a, separator, b)?;
+        if self.z == ZCoordinate::Zero {
+            write!(out, "{}0", separator)?;
+        }
+        Ok(())
+    }
+
+    /// Names of the coordinate columns in output order
+    pub(crate) fn coord_names(&self) -> &'static [&'static str] {
+        match (self.axis_order, self.z) {
+            (AxisOrder::LonLat, ZCoordinate::Omit) => &["lon", "lat"],
+            (AxisOrder::LatLon, ZCoordinate::Omit) => &["lat", "lon"],
+            (AxisOrder::LonLat, ZCoordinate::Zero) => &["lon", "lat", "z"],
+            (AxisOrder::LatLon, ZCoordinate::Zero) => &["lat", "lon", "z"],
+        }
+    }
+}
@@ -0,0 +1,118 @@
+// FlatGeobuf output
+//
+// Enabled with the `fgb` cargo feature.
+
+use super::FeatureWriter;
+use crate::Feature;
+use flatgeobuf::{ColumnType, FgbWriter, GeometryType};
+use geozero::{ColumnValue, PropertyProcessor};
+use std::io::{self, Write};
+
+/// Streams features into a FlatGeobuf file
+///
+/// Features are spooled to a temporary file while they are added and the
+/// spatially indexed output is written by [`FlatGeobufWriter::finish`], so
+/// memory use does not grow with the number of features.
+///
+/// Each feature has the columns `id`, `type`, `name` and `tags` (a JSON object).
+pub struct FlatGeobufWriter<W: Write> {
+    fgb: FgbWriter<'static>,
+    out: W,
+}
+
+impl<W: Write> FlatGeobufWriter<W> {
+    /// Create a writer for a dataset named `name`
+    pub fn new(out: W, name: &str) -> io::Result<Self> {
+        let mut fgb = FgbWriter::create(name, GeometryType::Unknown).map_err(to_io_error)?;
+        fgb.add_column("id", ColumnType::Long, |_, col| col.nullable = false);
+        fgb.add_column("type", ColumnType::String, |_, col| col.nullable = false);
+        fgb.add_column("name", ColumnType::String, |_, _| {});
+        fgb.add_column("tags", ColumnType::Json, |_, _| {});
+        Ok(Self { fgb, out })
+    }
+
+    /// Append a feature
+    pub fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        let geometry: geo_types::Geometry<f64> = if feature.is_way() && feature.nodes.len() > 1 {
+            geo_types::LineString::from(
+                feature
+                    .nodes
+                    .iter()
+                    .map(|n| (n.lon, n.lat))
+                    .collect::<Vec<_>>(),
+            )
+            .into()
+        } else {
+            geo_types::Point::new(feature.lon, feature.lat).into()
+        };
+
+        let mut tags = Vec::new();
+        tags.push(b'{');
+        for (i, (key, value)) in feature.tags.iter().enumerate() {
+            if i > 0 {
+                tags.push(b',');
+            }
+            super::write_json_string(&mut tags, key)?;
+            tags.push(b':');
+            super::write_json_string(&mut tags, value)?;
+        }
+        tags.push(b'}');
+        let tags = String::from_utf8(tags).expect("tags JSON is valid UTF-8");
+
+        self.fgb
+            .add_feature_geom(geometry, |feat| {
+                feat.property(0, "id", &ColumnValue::Long(feature.id)).ok();
+                feat.property(1, "type", &ColumnValue::String(&feature.type_name))
+                    .ok();
+                if !feature.name.is_empty() {
+                    feat.property(2, "name", &ColumnValue::String(&feature.name))
+                        .ok();
+                }
+                feat.property(3, "tags", &ColumnValue::Json(&tags)).ok();
+            })
+            .map_err(to_io_error)
+    }
+
+    /// Write the indexed dataset and return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.fgb.write(&mut self.out).map_err(to_io_error)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> FeatureWriter for FlatGeobufWriter<W> {
+    fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        FlatGeobufWriter::write_feature(self, feature)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        FlatGeobufWriter::finish(*self).map(|_| ())
+    }
+}
+
+fn to_io_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::other(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writes_flatgeobuf_magic() {
+        let feature = Feature {
+            id: 1,
+            type_name: "node".to_string(),
+            name: "Kiosk".to_string(),
+            lon: 10.0,
+            lat: 50.0,
+            tags: vec![("shop".to_string(), "kiosk".to_string())],
+            nodes: Vec::new(),
+        };
+        let mut writer = FlatGeobufWriter::new(Vec::new(), "test").unwrap();
+        writer.write_feature(&feature).unwrap();
+        let bytes = writer.finish().unwrap();
+        assert_eq!(&bytes[..3], b"fgb");
+    }
+}
@@ -0,0 +1,283 @@
+// GeoJSON (RFC 7946) output
+
+use super::dialect::{Crs, Dialect};
+use super::{write_json_string, FeatureWriter};
+use crate::{Feature, FeatureType, Watermark};
+use std::io::{self, Write};
+
+/// Streams features into a GeoJSON `FeatureCollection`
+///
+/// Features are written as they are passed in, so arbitrarily large results
+/// can be exported without holding the whole document in memory.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::{GeoDesk, BoundingBox};
+/// use geodesk_rs::export::GeoJsonWriter;
+///
+/// let geodesk = GeoDesk::open("world.gol").unwrap();
+/// let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+/// let cafes = geodesk.query("na[amenity=cafe]", bbox).unwrap();
+///
+/// let mut writer = GeoJsonWriter::new(std::io::stdout()).unwrap();
+/// for feature in cafes.to_vec().unwrap() {
+///     writer.write_feature(&feature).unwrap();
+/// }
+/// writer.finish().unwrap();
+/// ```
+pub struct GeoJsonWriter<W: Write> {
+    out: W,
+    count: usize,
+    dialect: Dialect,
+    watermark: Option<Watermark>,
+    truncated: bool,
+}
+
+impl<W: Write> GeoJsonWriter<W> {
+    /// Create a writer and emit the `FeatureCollection` header
+    pub fn new(out: W) -> io::Result<Self> {
+        Self::with_dialect(out, Dialect::default())
+    }
+
+    /// Create a writer using the given coordinate conventions
+    pub fn with_dialect(mut out: W, dialect: Dialect) -> io::Result<Self> {
+        out.write_all(b"{\"type\":\"FeatureCollection\",")?;
+        let crs_name = match dialect.crs {
+            Crs::Implicit => None,
+            Crs::Crs84 => Some("urn:ogc:def:crs:OGC:1.3:CRS84"),
+            Crs::Epsg4326 => Some("urn:ogc:def:crs:EPSG::4326"),
+        };
+        if let Some(name) = crs_name {
+            write!(
+                out,
+                "\"crs\":{{\"type\":\"name\",\"properties\":{{\"name\":\"{}\"}}}},",
+                name
+            )?;
+        }
+        out.write_all(b"\"features\":[")?;
+        Ok(Self {
+            out,
+            count: 0,
+            dialect,
+            watermark: None,
+            truncated: false,
+        })
+    }
+
+    /// Record the data revision in a `watermark` member of the collection
+    /// (`{"revision":3,"timestamp":1700000000}`, the timestamp in seconds
+    /// since the Unix epoch)
+    pub fn with_watermark(mut self, watermark: Watermark) -> Self {
+        self.watermark = Some(watermark);
+        self
+    }
+
+    /// Add `"truncated":true` to the collection if the features written
+    /// are only the first of a larger result
+    pub fn with_truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Append a feature to the collection
+    pub fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        if self.count > 0 {
+            self.out.write_all(b",")?;
+        }
+        write_feature_with(&mut self.out, feature, &self.dialect)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Number of features written so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Close the collection and return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(b"]")?;
+        if let Some(watermark) = self.watermark {
+            write!(
+                self.out,
+                ",\"watermark\":{{\"revision\":{},\"timestamp\":{}}}",
+                watermark.revision,
+                watermark.unix_timestamp()
+            )?;
+        }
+        if self.truncated {
+            self.out.write_all(b",\"truncated\":true")?;
+        }
+        self.out.write_all(b"}")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> FeatureWriter for GeoJsonWriter<W> {
+    fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        GeoJsonWriter::write_feature(self, feature)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        GeoJsonWriter::finish(*self).map(|_| ())
+    }
+}
+
+/// Serialize a slice of features into a GeoJSON `FeatureCollection` string
+pub fn to_geojson(features: &[Feature]) -> String {
+    let mut writer = GeoJsonWriter::new(Vec::new()).expect("writing to a Vec cannot fail");
+    for feature in features {
+        writer
+            .write_feature(feature)
+            .expect("writing to a Vec cannot fail");
+    }
+    let bytes = writer.finish().expect("writing to a Vec cannot fail");
+    String::from_utf8(bytes).expect("GeoJSON output is valid UTF-8")
+}
+
+/// Write a single GeoJSON `Feature` object
+pub fn write_feature<W: Write>(out: &mut W, feature: &Feature) -> io::Result<()> {
+    write_feature_with(out, feature, &Dialect::default())
+}
+
+/// Write a single GeoJSON `Feature` object using the given coordinate conventions
+pub fn write_feature_with<W: Write>(
+    out: &mut W,
+    feature: &Feature,
+    dialect: &Dialect,
+) -> io::Result<()> {
+    let prefix = match feature.feature_type() {
+        FeatureType::Node => "n",
+        FeatureType::Way => "w",
+        FeatureType::Relation => "r",
+    };
+    write!(
+        out,
+        "{{\"type\":\"Feature\",\"id\":\"{}{}\",\"geometry\":",
+        prefix, feature.id
+    )?;
+    write_geometry(out, feature, dialect)?;
+    out.write_all(b",\"properties\":{")?;
+    for (i, (key, value)) in feature.tags.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_json_string(out, key)?;
+        out.write_all(b":")?;
+        write_json_string(out, value)?;
+    }
+    out.write_all(b"}}")
+}
+
+fn write_geometry<W: Write>(out: &mut W, feature: &Feature, dialect: &Dialect) -> io::Result<()> {
+    if feature.is_way() && feature.nodes.len() > 1 {
+        out.write_all(b"{\"type\":\"LineString\",\"coordinates\":[")?;
+        for (i, node) in feature.nodes.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+            out.write_all(b"[")?;
+            dialect.write_coords(out, node.lon, node.lat, ",")?;
+            out.write_all(b"]")?;
+        }
+        out.write_all(b"]}")
+    } else {
+        out.write_all(b"{\"type\":\"Point\",\"coordinates\":[")?;
+        dialect.write_coords(out, feature.lon, feature.lat, ",")?;
+        out.write_all(b"]}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::dialect::{AxisOrder, ZCoordinate};
+    use crate::Node;
+
+    fn cafe() -> Feature {
+        Feature {
+            id: 42,
+            type_name: "node".to_string(),
+            name: "Corner".to_string(),
+            lon: 12.5,
+            lat: 55.75,
+            tags: vec![("amenity".to_string(), "cafe".to_string())],
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_point_feature() {
+        assert_eq!(
+            to_geojson(&[cafe()]),
+            "{\"type\":\"FeatureCollection\",\"features\":[{\"type\":\"Feature\",\"id\":\"n42\",\
+             \"geometry\":{\"type\":\"Point\",\"coordinates\":[12.5,55.75]},\
+             \"properties\":{\"amenity\":\"cafe\"}}]}"
+        );
+    }
+
+    #[test]
+    fn test_dialect() {
+        let dialect = Dialect {
+            axis_order: AxisOrder::LatLon,
+            z: ZCoordinate::Zero,
+            crs: Crs::Crs84,
+        };
+        let mut writer = GeoJsonWriter::with_dialect(Vec::new(), dialect).unwrap();
+        writer.write_feature(&cafe()).unwrap();
+        let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(json.starts_with(
+            "{\"type\":\"FeatureCollection\",\"crs\":{\"type\":\"name\",\
+             \"properties\":{\"name\":\"urn:ogc:def:crs:OGC:1.3:CRS84\"}},\"features\":["
+        ));
+        assert!(json.contains("\"coordinates\":[55.75,12.5,0]"));
+    }
+
+    #[test]
+    fn test_watermark_member() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let watermark = Watermark {
+            revision: 7,
+            timestamp: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        };
+        let writer = GeoJsonWriter::new(Vec::new())
+            .unwrap()
+            .with_watermark(watermark);
+        let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            "{\"type\":\"FeatureCollection\",\"features\":[],\
+             \"watermark\":{\"revision\":7,\"timestamp\":1700000000}}"
+        );
+
+        let writer = GeoJsonWriter::new(Vec::new()).unwrap().with_truncated(true);
+        let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            "{\"type\":\"FeatureCollection\",\"features\":[],\"truncated\":true}"
+        );
+    }
+
+    #[test]
+    fn test_way_is_linestring() {
+        let mut way = cafe();
+        way.type_name = "way".to_string();
+        way.nodes = vec![
+            Node {
+                id: 1,
+                lon: 0.0,
+                lat: 0.0,
+            },
+            Node {
+                id: 2,
+                lon: 1.0,
+                lat: 0.5,
+            },
+        ];
+        let json = to_geojson(&[way]);
+        assert!(json.contains("\"id\":\"w42\""));
+        assert!(json.contains("{\"type\":\"LineString\",\"coordinates\":[[0,0],[1,0.5]]}"));
+    }
+}
@@ -0,0 +1,106 @@
+// Writers for serializing query results into interchange formats
+
+pub mod csv;
+pub mod dialect;
+#[cfg(feature = "fgb")]
+pub mod fgb;
+pub mod geojson;
+pub mod osmchange;
+pub mod sharded;
+pub mod topology;
+pub mod wkb;
+pub mod wkt;
+
+pub use csv::CsvWriter;
+pub use dialect::Dialect;
+#[cfg(feature = "fgb")]
+pub use fgb::FlatGeobufWriter;
+pub use geojson::GeoJsonWriter;
+pub use osmchange::OsmChangeWriter;
+pub use sharded::ShardedWriter;
+pub use topology::Topology;
+
+use crate::Feature;
+use std::io::{self, Write};
+
+/// Common interface of the streaming exporters, for choosing a format at runtime
+pub trait FeatureWriter {
+    /// Append a feature to the output
+    fn write_feature(&mut self, feature: &Feature) -> io::Result<()>;
+
+    /// Complete the output (write trailers, indexes) and flush it
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+/// Write `s` as a quoted JSON string, escaping as required by RFC 8259
+pub(crate) fn write_json_string<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escape = match c {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            _ => None,
+        };
+        if escape.is_none() && c >= ' ' {
+            continue;
+        }
+        out.write_all(&s.as_bytes()[start..i])?;
+        match escape {
+            Some(e) => out.write_all(e.as_bytes())?,
+            None => write!(out, "\\u{:04x}", c as u32)?,
+        }
+        start = i + c.len_utf8();
+    }
+    out.write_all(&s.as_bytes()[start..])?;
+    out.write_all(b"\"")
+}
+
+/// Write `s` with the XML special characters escaped, for use in attribute values
+pub(crate) fn write_xml_escaped<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escape = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            '\'' => "&apos;",
+            '\n' => "&#10;",
+            '\t' => "&#9;",
+            _ => continue,
+        };
+        out.write_all(&s.as_bytes()[start..i])?;
+        out.write_all(escape.as_bytes())?;
+        start = i + c.len_utf8();
+    }
+    out.write_all(&s.as_bytes()[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string_escaping() {
+        let mut out = Vec::new();
+        write_json_string(&mut out, "Caf\u{e9} \"Le\" \\ \n\u{1}").unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\"Caf\u{e9} \\\"Le\\\" \\\\ \\n\\u0001\""
+        );
+    }
+
+    #[test]
+    fn test_xml_escaping() {
+        let mut out = Vec::new();
+        write_xml_escaped(&mut out, "Fish & \"Chips\" <Ltd>").unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Fish &amp; &quot;Chips&quot; &lt;Ltd&gt;"
+        );
+    }
+}
@@ -0,0 +1,359 @@
+// osmChange output, for handing fixes to OSM editors
+
+use super::{write_json_string, write_xml_escaped};
+use crate::Feature;
+use std::io::{self, Write};
+
+/// What an osmChange entry does to the element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeAction {
+    /// A new element (by convention with a negative placeholder ID)
+    Create,
+    /// New tags or geometry for an existing element
+    Modify,
+    /// Removal of an existing element
+    Delete,
+}
+
+impl ChangeAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeAction::Create => "create",
+            ChangeAction::Modify => "modify",
+            ChangeAction::Delete => "delete",
+        }
+    }
+}
+
+/// Output format of [`OsmChangeWriter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChangeFormat {
+    /// osmChange XML (`.osc`), as read by JOSM and the OSM API
+    #[default]
+    Xml,
+    /// The element layout of OSM JSON, with an `action` member per element
+    Json,
+}
+
+/// A feature with the action to apply to it, from [`Feature::to_osm_change`]
+#[derive(Debug, Clone, Copy)]
+pub struct Change<'a> {
+    pub action: ChangeAction,
+    pub feature: &'a Feature,
+    /// Version of the element the change is based on; required to modify
+    /// or delete, so the OSM API can detect conflicting edits
+    pub version: Option<u32>,
+}
+
+impl Feature {
+    /// Describe a change of this feature as an osmChange entry
+    ///
+    /// Edit a clone of the feature (its tags, or the nodes of a way) and
+    /// pass it with [`ChangeAction::Modify`] and the version it was read
+    /// at. Relation members are not part of a [`Feature`], so relations
+    /// can only be deleted.
+    pub fn to_osm_change(&self, action: ChangeAction, version: Option<u32>) -> Change<'_> {
+        Change {
+            action,
+            feature: self,
+            version,
+        }
+    }
+}
+
+impl Change<'_> {
+    /// Check that the change can be written faithfully
+    fn validate(&self) -> io::Result<()> {
+        let feature = self.feature;
+        let invalid = |message: &str| {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} {}: {}", feature.type_name, feature.id, message),
+            ))
+        };
+        if self.action != ChangeAction::Create && self.version.is_none() {
+            return invalid("modifying or deleting requires the element version");
+        }
+        if self.action == ChangeAction::Delete {
+            return Ok(());
+        }
+        if feature.is_relation() {
+            return invalid("relation members are not available, only deletion can be written");
+        }
+        if feature.is_way() && feature.nodes.iter().any(|n| n.id == 0) {
+            return invalid("way nodes have no IDs (GOL built without way node IDs)");
+        }
+        Ok(())
+    }
+
+    fn write_xml<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let feature = self.feature;
+        write!(out, "    <{} id=\"{}\"", feature.type_name, feature.id)?;
+        if let Some(version) = self.version {
+            write!(out, " version=\"{}\"", version)?;
+        }
+        if self.action == ChangeAction::Delete {
+            return writeln!(out, "/>");
+        }
+        if feature.is_node() {
+            write!(out, " lat=\"{}\" lon=\"{}\"", feature.lat, feature.lon)?;
+        }
+        if feature.tags.is_empty() && feature.nodes.is_empty() {
+            return writeln!(out, "/>");
+        }
+        writeln!(out, ">")?;
+        if feature.is_way() {
+            for node in &feature.nodes {
+                writeln!(out, "      <nd ref=\"{}\"/>", node.id)?;
+            }
+        }
+        for (key, value) in &feature.tags {
+            write!(out, "      <tag k=\"")?;
+            write_xml_escaped(out, key)?;
+            write!(out, "\" v=\"")?;
+            write_xml_escaped(out, value)?;
+            writeln!(out, "\"/>")?;
+        }
+        writeln!(out, "    </{}>", feature.type_name)
+    }
+
+    fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let feature = self.feature;
+        write!(
+            out,
+            "{{\"type\":\"{}\",\"action\":\"{}\",\"id\":{}",
+            feature.type_name,
+            self.action.as_str(),
+            feature.id
+        )?;
+        if let Some(version) = self.version {
+            write!(out, ",\"version\":{}", version)?;
+        }
+        if self.action == ChangeAction::Delete {
+            return out.write_all(b"}");
+        }
+        if feature.is_node() {
+            write!(out, ",\"lat\":{},\"lon\":{}", feature.lat, feature.lon)?;
+        }
+        if feature.is_way() {
+            out.write_all(b",\"nodes\":[")?;
+            for (i, node) in feature.nodes.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                write!(out, "{}", node.id)?;
+            }
+            out.write_all(b"]")?;
+        }
+        out.write_all(b",\"tags\":{")?;
+        for (i, (key, value)) in feature.tags.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+            write_json_string(out, key)?;
+            out.write_all(b":")?;
+            write_json_string(out, value)?;
+        }
+        out.write_all(b"}}")
+    }
+}
+
+/// Streams changes into an osmChange document
+///
+/// The crate never writes to a GOL; this only produces files that editors
+/// and upload tools understand, so QA tools can hand over their fixes.
+/// Consecutive changes with the same action share one `<create>`,
+/// `<modify>` or `<delete>` block.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::export::osmchange::{ChangeAction, ChangeFormat, OsmChangeWriter};
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::GeoDesk;
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+/// let cafes = geodesk.query("na[amenity=cafe][cuisine=coffee]", bboxes::COPENHAGEN).unwrap();
+/// let file = std::fs::File::create("fixes.osc").unwrap();
+/// let mut writer = OsmChangeWriter::new(file, ChangeFormat::Xml).unwrap();
+/// for mut cafe in cafes.to_vec().unwrap() {
+///     for (key, value) in &mut cafe.tags {
+///         if key == "cuisine" {
+///             *value = "coffee_shop".to_string();
+///         }
+///     }
+///     // The version would come from the OSM API or the GOL's metadata
+///     let version = Some(1);
+///     writer.write(&cafe.to_osm_change(ChangeAction::Modify, version)).unwrap();
+/// }
+/// writer.finish().unwrap();
+/// ```
+pub struct OsmChangeWriter<W: Write> {
+    out: W,
+    format: ChangeFormat,
+    count: usize,
+    block: Option<ChangeAction>,
+}
+
+impl<W: Write> OsmChangeWriter<W> {
+    /// Create a writer and emit the document header
+    pub fn new(mut out: W, format: ChangeFormat) -> io::Result<Self> {
+        match format {
+            ChangeFormat::Xml => {
+                writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+                writeln!(out, "<osmChange version=\"0.6\" generator=\"geodesk-rs\">")?;
+            }
+            ChangeFormat::Json => {
+                out.write_all(b"{\"version\":\"0.6\",\"generator\":\"geodesk-rs\",\"osmChange\":[")?
+            }
+        }
+        Ok(Self {
+            out,
+            format,
+            count: 0,
+            block: None,
+        })
+    }
+
+    /// Append a change
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] for changes that can't be
+    /// expressed: modifying or deleting without a version, creating or
+    /// modifying a relation, or a way whose nodes have no IDs.
+    pub fn write(&mut self, change: &Change<'_>) -> io::Result<()> {
+        change.validate()?;
+        match self.format {
+            ChangeFormat::Xml => {
+                if self.block != Some(change.action) {
+                    self.close_block()?;
+                    writeln!(self.out, "  <{}>", change.action.as_str())?;
+                    self.block = Some(change.action);
+                }
+                change.write_xml(&mut self.out)?;
+            }
+            ChangeFormat::Json => {
+                if self.count > 0 {
+                    self.out.write_all(b",")?;
+                }
+                change.write_json(&mut self.out)?;
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Number of changes written so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Close the document and return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.format {
+            ChangeFormat::Xml => {
+                self.close_block()?;
+                writeln!(self.out, "</osmChange>")?;
+            }
+            ChangeFormat::Json => self.out.write_all(b"]}")?,
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn close_block(&mut self) -> io::Result<()> {
+        if let Some(action) = self.block.take() {
+            writeln!(self.out, "  </{}>", action.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn feature(type_name: &str, id: i64, nodes: &[i64]) -> Feature {
+        Feature {
+            id,
+            type_name: type_name.to_string(),
+            name: String::new(),
+            lon: 12.5,
+            lat: 55.75,
+            tags: vec![("name".to_string(), "Fish & Chips".to_string())],
+            nodes: nodes
+                .iter()
+                .map(|&id| Node {
+                    id,
+                    lon: 0.0,
+                    lat: 0.0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_osm_change_output() {
+        let node = feature("node", -1, &[]);
+        let way = feature("way", 7, &[1, 2]);
+        let relation = feature("relation", 9, &[]);
+        let changes = [
+            node.to_osm_change(ChangeAction::Create, None),
+            way.to_osm_change(ChangeAction::Modify, Some(3)),
+            relation.to_osm_change(ChangeAction::Delete, Some(5)),
+        ];
+
+        let mut writer = OsmChangeWriter::new(Vec::new(), ChangeFormat::Xml).unwrap();
+        for change in &changes {
+            writer.write(change).unwrap();
+        }
+        let xml = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            xml,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <osmChange version=\"0.6\" generator=\"geodesk-rs\">\n  \
+             <create>\n    \
+             <node id=\"-1\" lat=\"55.75\" lon=\"12.5\">\n      \
+             <tag k=\"name\" v=\"Fish &amp; Chips\"/>\n    \
+             </node>\n  \
+             </create>\n  \
+             <modify>\n    \
+             <way id=\"7\" version=\"3\">\n      \
+             <nd ref=\"1\"/>\n      \
+             <nd ref=\"2\"/>\n      \
+             <tag k=\"name\" v=\"Fish &amp; Chips\"/>\n    \
+             </way>\n  \
+             </modify>\n  \
+             <delete>\n    \
+             <relation id=\"9\" version=\"5\"/>\n  \
+             </delete>\n\
+             </osmChange>\n"
+        );
+
+        let mut writer = OsmChangeWriter::new(Vec::new(), ChangeFormat::Json).unwrap();
+        writer.write(&changes[1]).unwrap();
+        let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            "{\"version\":\"0.6\",\"generator\":\"geodesk-rs\",\"osmChange\":[\
+             {\"type\":\"way\",\"action\":\"modify\",\"id\":7,\"version\":3,\
+             \"nodes\":[1,2],\"tags\":{\"name\":\"Fish & Chips\"}}]}"
+        );
+    }
+
+    #[test]
+    fn test_unrepresentable_changes() {
+        let mut writer = OsmChangeWriter::new(Vec::new(), ChangeFormat::Xml).unwrap();
+        let node = feature("node", 1, &[]);
+        let relation = feature("relation", 9, &[]);
+        let anonymous = feature("way", 7, &[1, 0]);
+        for change in [
+            node.to_osm_change(ChangeAction::Modify, None),
+            relation.to_osm_change(ChangeAction::Modify, Some(2)),
+            anonymous.to_osm_change(ChangeAction::Modify, Some(2)),
+        ] {
+            let error = writer.write(&change).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert_eq!(writer.count(), 0);
+    }
+}
@@ -0,0 +1,227 @@
+// Output split into one file per value of a tag
+
+use super::{FeatureWriter, GeoJsonWriter};
+use crate::error::GeoDeskError;
+use crate::{BoundingBox, Feature, GeoDesk};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Creates the writer for a new shard file
+pub type WriterFactory = Box<dyn FnMut(BufWriter<File>) -> io::Result<Box<dyn FeatureWriter>>>;
+
+/// File name (without extension) of the features lacking the key
+pub const MISSING_SHARD: &str = "_missing";
+
+/// A file written by [`ShardedWriter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shard {
+    pub path: PathBuf,
+    /// Number of features written to the file
+    pub count: usize,
+}
+
+/// Streams features into one file per distinct value of a key
+///
+/// Files are named after the value (e.g. `residential.geojson` when
+/// sharding by `highway`), with characters other than letters, digits,
+/// `-`, `_` and `.` replaced by `_`; features without the key go to
+/// [`MISSING_SHARD`]. A file is opened when its first feature arrives and
+/// stays open until [`ShardedWriter::finish`], so keys with a small set of
+/// values (classes, levels) are the intended use.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::export::ShardedWriter;
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::GeoDesk;
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+/// let mut writer = ShardedWriter::geojson("roads", "highway").unwrap();
+/// for road in geodesk.query_iter("w[highway]", bboxes::COPENHAGEN).unwrap() {
+///     writer.write_feature(&road.unwrap()).unwrap();
+/// }
+/// for shard in writer.finish().unwrap() {
+///     println!("{}: {}", shard.path.display(), shard.count);
+/// }
+/// ```
+pub struct ShardedWriter {
+    dir: PathBuf,
+    key: String,
+    extension: String,
+    make: WriterFactory,
+    shards: BTreeMap<String, (Box<dyn FeatureWriter>, usize)>,
+}
+
+impl ShardedWriter {
+    /// Create a writer that shards into `dir` (created if needed), naming
+    /// files `<value>.<extension>` and writing them with writers from `make`
+    pub fn new<P, F>(dir: P, key: &str, extension: &str, make: F) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        F: FnMut(BufWriter<File>) -> io::Result<Box<dyn FeatureWriter>> + 'static,
+    {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            key: key.to_string(),
+            extension: extension.to_string(),
+            make: Box::new(make),
+            shards: BTreeMap::new(),
+        })
+    }
+
+    /// Create a writer producing one GeoJSON file per value
+    pub fn geojson<P: AsRef<Path>>(dir: P, key: &str) -> io::Result<Self> {
+        Self::new(dir, key, "geojson", |out| {
+            Ok(Box::new(GeoJsonWriter::new(out)?) as Box<dyn FeatureWriter>)
+        })
+    }
+
+    /// Append a feature to the file of its value
+    pub fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        let name = match feature.tag(&self.key) {
+            Some(value) => file_stem(value),
+            None => MISSING_SHARD.to_string(),
+        };
+        if !self.shards.contains_key(&name) {
+            let path = shard_path(&self.dir, &name, &self.extension);
+            let writer = (self.make)(BufWriter::new(File::create(path)?))?;
+            self.shards.insert(name.clone(), (writer, 0));
+        }
+        let (writer, count) = self.shards.get_mut(&name).expect("shard was just inserted");
+        writer.write_feature(feature)?;
+        *count += 1;
+        Ok(())
+    }
+
+    /// Complete all files, returning them in order of their names
+    pub fn finish(self) -> io::Result<Vec<Shard>> {
+        let mut shards = Vec::with_capacity(self.shards.len());
+        for (name, (writer, count)) in self.shards {
+            writer.finish()?;
+            shards.push(Shard {
+                path: shard_path(&self.dir, &name, &self.extension),
+                count,
+            });
+        }
+        Ok(shards)
+    }
+}
+
+impl FeatureWriter for ShardedWriter {
+    fn write_feature(&mut self, feature: &Feature) -> io::Result<()> {
+        ShardedWriter::write_feature(self, feature)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        ShardedWriter::finish(*self).map(|_| ())
+    }
+}
+
+fn shard_path(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    dir.join(format!("{}.{}", name, extension))
+}
+
+/// A file name for a tag value
+fn file_stem(value: &str) -> String {
+    let stem: String = value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // Keep values such as "." or ".." from naming directories
+    if stem.is_empty() || stem.chars().all(|c| c == '.') {
+        format!("_{}", stem)
+    } else {
+        stem
+    }
+}
+
+impl GeoDesk {
+    /// Export the features matching a GOQL query as GeoJSON, one file per
+    /// value of `key`, in a single streaming pass
+    ///
+    /// See [`ShardedWriter`] for how files are named.
+    pub fn export_sharded<P: AsRef<Path>>(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        dir: P,
+        key: &str,
+    ) -> Result<Vec<Shard>, GeoDeskError> {
+        let mut writer = ShardedWriter::geojson(dir, key)?;
+        for feature in self.query_iter(goql_query, bbox)? {
+            writer.write_feature(&feature?)?;
+        }
+        Ok(writer.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn road(id: i64, highway: Option<&str>) -> Feature {
+        Feature {
+            id,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: highway
+                .map(|h| vec![("highway".to_string(), h.to_string())])
+                .unwrap_or_default(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_sharded_output() {
+        let dir = std::env::temp_dir().join(format!("geodesk-shards-{}", std::process::id()));
+        let mut writer = ShardedWriter::geojson(&dir, "highway").unwrap();
+        for (id, highway) in [
+            (1, Some("primary")),
+            (2, Some("residential")),
+            (3, None),
+            (4, Some("primary")),
+            (5, Some("../etc")),
+        ] {
+            writer.write_feature(&road(id, highway)).unwrap();
+        }
+        let shards = writer.finish().unwrap();
+        let names: Vec<(String, usize)> = shards
+            .iter()
+            .map(|s| {
+                let name = s.path.file_name().unwrap().to_string_lossy().to_string();
+                (name, s.count)
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                (".._etc.geojson".to_string(), 1),
+                ("_missing.geojson".to_string(), 1),
+                ("primary.geojson".to_string(), 2),
+                ("residential.geojson".to_string(), 1),
+            ]
+        );
+        let primary = fs::read_to_string(dir.join("primary.geojson")).unwrap();
+        assert!(primary.contains("\"id\":\"n1\"") && primary.contains("\"id\":\"n4\""));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem("motorway_link"), "motorway_link");
+        assert_eq!(file_stem("a/b c"), "a_b_c");
+        assert_eq!(file_stem(".."), "_..");
+        assert_eq!(file_stem(""), "_");
+    }
+}
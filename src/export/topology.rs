@@ -0,0 +1,238 @@
+// Topology-preserving export of ways and their shared nodes
+
+use super::write_xml_escaped;
+use crate::Feature;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// A node in the exported topology
+#[derive(Debug, Clone)]
+pub struct TopoNode {
+    /// OSM node ID, or a negative placeholder for way nodes without an ID
+    pub id: i64,
+    pub lon: f64,
+    pub lat: f64,
+    /// Tags of the node (only populated for nodes that were query results)
+    pub tags: Vec<(String, String)>,
+    /// Number of way positions referencing this node
+    pub way_refs: usize,
+}
+
+/// A way whose geometry is expressed as references into the node table
+#[derive(Debug, Clone)]
+pub struct TopoWay {
+    pub id: i64,
+    pub tags: Vec<(String, String)>,
+    pub node_ids: Vec<i64>,
+}
+
+/// Node/way graph built from query results
+///
+/// Unlike the coordinate-list exporters, every way node is stored once and
+/// ways refer to it by ID, so intersections and other shared nodes survive
+/// the export. Relations are not included.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::{GeoDesk, BoundingBox};
+/// use geodesk_rs::export::Topology;
+///
+/// let geodesk = GeoDesk::open("world.gol").unwrap();
+/// let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+/// let roads = geodesk.query_roads(bbox).unwrap().to_vec().unwrap();
+///
+/// let topology = Topology::from_features(&roads);
+/// println!("{} intersections", topology.shared_nodes().count());
+/// let file = std::fs::File::create("roads.osm").unwrap();
+/// topology.write_osm_xml(std::io::BufWriter::new(file)).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    nodes: Vec<TopoNode>,
+    node_index: HashMap<i64, usize>,
+    placeholder_ids: HashMap<(u64, u64), i64>,
+    ways: Vec<TopoWay>,
+}
+
+impl Topology {
+    /// Build the topology from a set of features
+    pub fn from_features<'a, I>(features: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Feature>,
+    {
+        let mut topology = Self::default();
+        for feature in features {
+            topology.add_feature(feature);
+        }
+        topology
+    }
+
+    /// Add a single feature (relations are ignored)
+    pub fn add_feature(&mut self, feature: &Feature) {
+        if feature.is_node() {
+            let index = self.node_slot(feature.id, feature.lon, feature.lat);
+            self.nodes[index].tags = feature.tags.clone();
+        } else if feature.is_way() {
+            let node_ids = feature
+                .nodes
+                .iter()
+                .map(|n| {
+                    let id = if n.id != 0 {
+                        n.id
+                    } else {
+                        self.placeholder_id(n.lon, n.lat)
+                    };
+                    let index = self.node_slot(id, n.lon, n.lat);
+                    self.nodes[index].way_refs += 1;
+                    id
+                })
+                .collect();
+            self.ways.push(TopoWay {
+                id: feature.id,
+                tags: feature.tags.clone(),
+                node_ids,
+            });
+        }
+    }
+
+    /// All nodes, in the order they were first encountered
+    pub fn nodes(&self) -> &[TopoNode] {
+        &self.nodes
+    }
+
+    /// All ways
+    pub fn ways(&self) -> &[TopoWay] {
+        &self.ways
+    }
+
+    /// Look up a node by ID
+    pub fn node(&self, id: i64) -> Option<&TopoNode> {
+        self.node_index.get(&id).map(|&i| &self.nodes[i])
+    }
+
+    /// Nodes referenced more than once by ways (junctions, closed-ring endpoints)
+    pub fn shared_nodes(&self) -> impl Iterator<Item = &TopoNode> {
+        self.nodes.iter().filter(|n| n.way_refs > 1)
+    }
+
+    /// Write the topology as an OSM XML (API 0.6) document
+    pub fn write_osm_xml<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<osm version=\"0.6\" generator=\"geodesk-rs\">")?;
+        for node in &self.nodes {
+            write!(
+                out,
+                "  <node id=\"{}\" lat=\"{}\" lon=\"{}\"",
+                node.id, node.lat, node.lon
+            )?;
+            if node.tags.is_empty() {
+                writeln!(out, "/>")?;
+            } else {
+                writeln!(out, ">")?;
+                write_tags(&mut out, &node.tags)?;
+                writeln!(out, "  </node>")?;
+            }
+        }
+        for way in &self.ways {
+            writeln!(out, "  <way id=\"{}\">", way.id)?;
+            for id in &way.node_ids {
+                writeln!(out, "    <nd ref=\"{}\"/>", id)?;
+            }
+            write_tags(&mut out, &way.tags)?;
+            writeln!(out, "  </way>")?;
+        }
+        writeln!(out, "</osm>")?;
+        out.flush()
+    }
+
+    fn node_slot(&mut self, id: i64, lon: f64, lat: f64) -> usize {
+        if let Some(&index) = self.node_index.get(&id) {
+            return index;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(TopoNode {
+            id,
+            lon,
+            lat,
+            tags: Vec::new(),
+            way_refs: 0,
+        });
+        self.node_index.insert(id, index);
+        index
+    }
+
+    /// Way nodes without an ID are identified by their exact coordinates
+    fn placeholder_id(&mut self, lon: f64, lat: f64) -> i64 {
+        let next = -(self.placeholder_ids.len() as i64) - 1;
+        *self
+            .placeholder_ids
+            .entry((lon.to_bits(), lat.to_bits()))
+            .or_insert(next)
+    }
+}
+
+fn write_tags<W: Write>(out: &mut W, tags: &[(String, String)]) -> io::Result<()> {
+    for (key, value) in tags {
+        write!(out, "    <tag k=\"")?;
+        write_xml_escaped(out, key)?;
+        write!(out, "\" v=\"")?;
+        write_xml_escaped(out, value)?;
+        writeln!(out, "\"/>")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn way(id: i64, nodes: &[(i64, f64, f64)]) -> Feature {
+        Feature {
+            id,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: vec![("highway".to_string(), "residential".to_string())],
+            nodes: nodes
+                .iter()
+                .map(|&(id, lon, lat)| Node { id, lon, lat })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_shared_nodes_are_deduplicated() {
+        let a = way(1, &[(10, 0.0, 0.0), (11, 1.0, 0.0)]);
+        let b = way(2, &[(11, 1.0, 0.0), (12, 1.0, 1.0)]);
+        let topology = Topology::from_features(&[a, b]);
+
+        assert_eq!(topology.nodes().len(), 3);
+        assert_eq!(topology.ways()[1].node_ids, vec![11, 12]);
+        let shared: Vec<i64> = topology.shared_nodes().map(|n| n.id).collect();
+        assert_eq!(shared, vec![11]);
+    }
+
+    #[test]
+    fn test_nodes_without_ids_share_by_coordinate() {
+        let a = way(1, &[(0, 0.0, 0.0), (0, 1.0, 0.0)]);
+        let b = way(2, &[(0, 1.0, 0.0), (0, 2.0, 0.0)]);
+        let topology = Topology::from_features(&[a, b]);
+
+        assert_eq!(topology.nodes().len(), 3);
+        assert_eq!(topology.ways()[0].node_ids, vec![-1, -2]);
+        assert_eq!(topology.ways()[1].node_ids, vec![-2, -3]);
+    }
+
+    #[test]
+    fn test_osm_xml_output() {
+        let topology = Topology::from_features(&[way(5, &[(1, 0.5, 2.5), (2, 1.0, 3.0)])]);
+        let mut out = Vec::new();
+        topology.write_osm_xml(&mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("<node id=\"1\" lat=\"2.5\" lon=\"0.5\"/>"));
+        assert!(xml.contains("<way id=\"5\">\n    <nd ref=\"1\"/>\n    <nd ref=\"2\"/>"));
+        assert!(xml.contains("<tag k=\"highway\" v=\"residential\"/>"));
+    }
+}
@@ -0,0 +1,79 @@
+// Well-Known Binary (WKB) geometry output, as read by shapely and GEOS
+
+use crate::Feature;
+use std::io::{self, Write};
+
+/// Byte order marker for little-endian (NDR) WKB
+const LITTLE_ENDIAN: u8 = 1;
+
+const WKB_POINT: u32 = 1;
+const WKB_LINESTRING: u32 = 2;
+
+/// Write the geometry of a feature as little-endian WKB
+///
+/// Ways with more than one node become `LineString`s, everything else a
+/// `Point`, as with [`write_wkt`](super::wkt::write_wkt).
+pub fn write_wkb<W: Write>(out: &mut W, feature: &Feature) -> io::Result<()> {
+    out.write_all(&[LITTLE_ENDIAN])?;
+    if feature.is_way() && feature.nodes.len() > 1 {
+        out.write_all(&WKB_LINESTRING.to_le_bytes())?;
+        out.write_all(&(feature.nodes.len() as u32).to_le_bytes())?;
+        for node in &feature.nodes {
+            out.write_all(&node.lon.to_le_bytes())?;
+            out.write_all(&node.lat.to_le_bytes())?;
+        }
+    } else {
+        out.write_all(&WKB_POINT.to_le_bytes())?;
+        out.write_all(&feature.lon.to_le_bytes())?;
+        out.write_all(&feature.lat.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// The geometry of a feature as WKB
+pub fn to_wkb(feature: &Feature) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_wkb(&mut out, feature).expect("writing to a Vec cannot fail");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    #[test]
+    fn test_wkb() {
+        let mut feature = Feature {
+            id: 1,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 1.0,
+            lat: 2.0,
+            tags: Vec::new(),
+            nodes: Vec::new(),
+        };
+        let mut point = vec![1, 1, 0, 0, 0];
+        point.extend(1.0f64.to_le_bytes());
+        point.extend(2.0f64.to_le_bytes());
+        assert_eq!(to_wkb(&feature), point);
+
+        feature.type_name = "way".to_string();
+        feature.nodes = vec![
+            Node {
+                id: 1,
+                lon: 0.0,
+                lat: 1.0,
+            },
+            Node {
+                id: 2,
+                lon: 3.0,
+                lat: 4.0,
+            },
+        ];
+        let wkb = to_wkb(&feature);
+        assert_eq!(wkb.len(), 1 + 4 + 4 + 2 * 16);
+        assert_eq!(wkb[1..9], [2, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(wkb[25..33], 3.0f64.to_le_bytes());
+    }
+}
@@ -0,0 +1,91 @@
+// Well-Known Text (WKT / EWKT) geometry output
+
+use super::dialect::{Crs, Dialect, ZCoordinate};
+use crate::Feature;
+use std::io::{self, Write};
+
+/// Write the geometry of a feature as WKT
+///
+/// Ways with more than one node become `LINESTRING`s, everything else a
+/// `POINT`. With a CRS set in the dialect, an EWKT `SRID=4326;` prefix is
+/// written.
+pub fn write_wkt<W: Write>(out: &mut W, feature: &Feature, dialect: &Dialect) -> io::Result<()> {
+    if dialect.crs != Crs::Implicit {
+        out.write_all(b"SRID=4326;")?;
+    }
+    let z = if dialect.z == ZCoordinate::Zero {
+        " Z"
+    } else {
+        ""
+    };
+    if feature.is_way() && feature.nodes.len() > 1 {
+        write!(out, "LINESTRING{} (", z)?;
+        for (i, node) in feature.nodes.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b", ")?;
+            }
+            dialect.write_coords(out, node.lon, node.lat, " ")?;
+        }
+        out.write_all(b")")
+    } else {
+        write!(out, "POINT{} (", z)?;
+        dialect.write_coords(out, feature.lon, feature.lat, " ")?;
+        out.write_all(b")")
+    }
+}
+
+/// The geometry of a feature as a WKT string
+pub fn to_wkt(feature: &Feature, dialect: &Dialect) -> String {
+    let mut out = Vec::new();
+    write_wkt(&mut out, feature, dialect).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("WKT output is valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::dialect::AxisOrder;
+    use crate::Node;
+
+    #[test]
+    fn test_wkt_dialects() {
+        let mut feature = Feature {
+            id: 1,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 12.5,
+            lat: 55.75,
+            tags: Vec::new(),
+            nodes: Vec::new(),
+        };
+        assert_eq!(to_wkt(&feature, &Dialect::default()), "POINT (12.5 55.75)");
+
+        let dialect = Dialect {
+            axis_order: AxisOrder::LatLon,
+            z: ZCoordinate::Zero,
+            crs: Crs::Epsg4326,
+        };
+        assert_eq!(
+            to_wkt(&feature, &dialect),
+            "SRID=4326;POINT Z (55.75 12.5 0)"
+        );
+
+        feature.type_name = "way".to_string();
+        feature.nodes = vec![
+            Node {
+                id: 1,
+                lon: 0.0,
+                lat: 1.0,
+            },
+            Node {
+                id: 2,
+                lon: 2.0,
+                lat: 3.0,
+            },
+        ];
+        assert_eq!(
+            to_wkt(&feature, &Dialect::default()),
+            "LINESTRING (0 1, 2 3)"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+// Reverse geocoding against the administrative boundaries in a GOL
+
+use crate::error::GeoDeskError;
+use crate::tiles::TileId;
+use crate::{ffi, BoundingBox, Feature, GeoDesk, QueryResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Zoom level of the tiles [`GeoDesk::admin_hierarchy`] caches by
+/// (about 2.4 km across at the equator)
+const CACHE_ZOOM: u8 = 14;
+
+/// Hierarchies shared by every point of a tile; `None` for tiles crossed by
+/// a boundary
+pub(crate) type AdminCache = Mutex<HashMap<TileId, Option<Arc<AdminHierarchy>>>>;
+
+/// The administrative boundaries a location lies in
+#[derive(Debug, Clone, Default)]
+pub struct AdminHierarchy {
+    /// Boundaries from the largest (country) to the smallest
+    pub boundaries: Vec<Feature>,
+}
+
+impl AdminHierarchy {
+    /// The country (`admin_level=2`)
+    pub fn country(&self) -> Option<&Feature> {
+        self.boundaries.iter().find(|b| b.admin_level() == Some(2))
+    }
+
+    /// The largest subdivision of the country (state, region or province,
+    /// `admin_level` 3 to 6)
+    pub fn region(&self) -> Option<&Feature> {
+        self.boundaries
+            .iter()
+            .find(|b| matches!(b.admin_level(), Some(3..=6)))
+    }
+
+    /// The municipality (`admin_level` 7 or 8, the smaller one if both
+    /// exist)
+    pub fn city(&self) -> Option<&Feature> {
+        self.boundaries
+            .iter()
+            .rev()
+            .find(|b| matches!(b.admin_level(), Some(7..=8)))
+    }
+}
+
+impl Feature {
+    /// The `admin_level` of a boundary (2 = country, higher = smaller)
+    pub fn admin_level(&self) -> Option<u8> {
+        self.tag_as_i64("admin_level")
+            .ok()
+            .flatten()
+            .and_then(|level| u8::try_from(level).ok())
+    }
+}
+
+/// Sort boundaries from the largest (lowest `admin_level`) to the smallest;
+/// boundaries without a level go last
+pub(crate) fn sort_by_admin_level(boundaries: &mut [Feature]) {
+    boundaries.sort_by_key(|b| (b.admin_level().unwrap_or(u8::MAX), b.id));
+}
+
+impl GeoDesk {
+    /// Query the features matching a GOQL query whose geometry contains a
+    /// point
+    pub fn query_containing(
+        &self,
+        goql_query: &str,
+        lon: f64,
+        lat: f64,
+    ) -> Result<QueryResult, GeoDeskError> {
+        self.measure(
+            "query_containing",
+            |r: &QueryResult| Some(r.count() as u64),
+            || {
+                let missing_tiles = self.check_tiles(BoundingBox::new(lon, lat, lon, lat))?;
+                let watermark = self.watermark();
+                let _active = self.activity.begin();
+                let result = ffi::bridge::query_containing(&self.store, goql_query, lon, lat)?;
+                Ok(QueryResult::new(result, missing_tiles, watermark))
+            },
+        )
+    }
+
+    /// The administrative boundaries containing a point, from the country
+    /// down to the smallest area
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// for boundary in geodesk.boundaries_containing(12.5683, 55.6761).unwrap() {
+    ///     println!("{:?}: {}", boundary.admin_level(), boundary.name);
+    /// }
+    /// ```
+    pub fn boundaries_containing(&self, lon: f64, lat: f64) -> Result<Vec<Feature>, GeoDeskError> {
+        let mut boundaries = self
+            .query_containing("a[boundary=administrative]", lon, lat)?
+            .to_vec()?;
+        sort_by_admin_level(&mut boundaries);
+        Ok(boundaries)
+    }
+
+    /// The administrative hierarchy (city, region, country) of a feature's
+    /// location
+    ///
+    /// Results are cached per tile: when all corners of the tile around the
+    /// feature lie in the same boundaries, later features in that tile are
+    /// answered from the cache, so enriching many features in one area costs
+    /// few boundary lookups. Tiles crossed by a boundary are resolved per
+    /// feature.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// for cafe in geodesk.query("na[amenity=cafe]", bboxes::COPENHAGEN).unwrap().to_vec().unwrap() {
+    ///     let hierarchy = geodesk.admin_hierarchy(&cafe).unwrap();
+    ///     let city = hierarchy.city().map(|c| c.name.as_str()).unwrap_or("?");
+    ///     println!("{} ({})", cafe.name, city);
+    /// }
+    /// ```
+    pub fn admin_hierarchy(&self, feature: &Feature) -> Result<AdminHierarchy, GeoDeskError> {
+        let tile = TileId::containing(feature.lon, feature.lat, CACHE_ZOOM);
+        let cached = self.admin_cache().get(&tile).cloned();
+        let uniform = match cached {
+            Some(uniform) => uniform,
+            None => {
+                let uniform = self.uniform_hierarchy(tile)?.map(Arc::new);
+                let mut cache = self.admin_cache();
+                let max = self.limits.max_cached_admin_tiles.unwrap_or(usize::MAX);
+                if cache.len() >= max {
+                    cache.clear();
+                }
+                if max > 0 {
+                    cache.insert(tile, uniform.clone());
+                }
+                uniform
+            }
+        };
+        match uniform {
+            Some(hierarchy) => Ok((*hierarchy).clone()),
+            None => Ok(AdminHierarchy {
+                boundaries: self.boundaries_containing(feature.lon, feature.lat)?,
+            }),
+        }
+    }
+
+    /// The hierarchy shared by all corners of a tile, if they agree
+    fn uniform_hierarchy(&self, tile: TileId) -> Result<Option<AdminHierarchy>, GeoDeskError> {
+        let b = tile.bounds();
+        let corners = [
+            (b.west, b.north),
+            (b.east, b.north),
+            (b.west, b.south),
+            (b.east, b.south),
+        ];
+        let mut first: Option<Vec<Feature>> = None;
+        for (lon, lat) in corners {
+            let boundaries = self.boundaries_containing(lon, lat)?;
+            match &first {
+                None => first = Some(boundaries),
+                Some(f) if !same_ids(f, &boundaries) => return Ok(None),
+                Some(_) => {}
+            }
+        }
+        Ok(first.map(|boundaries| AdminHierarchy { boundaries }))
+    }
+
+    fn admin_cache(&self) -> MutexGuard<'_, HashMap<TileId, Option<Arc<AdminHierarchy>>>> {
+        self.admin_cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn same_ids(a: &[Feature], b: &[Feature]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.id == y.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boundary(id: i64, level: Option<&str>) -> Feature {
+        Feature {
+            id,
+            type_name: "relation".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: level
+                .map(|l| vec![("admin_level".to_string(), l.to_string())])
+                .unwrap_or_default(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_sort_by_admin_level() {
+        let mut boundaries = vec![
+            boundary(1, Some("8")),
+            boundary(2, None),
+            boundary(3, Some("2")),
+            boundary(4, Some("4")),
+            boundary(5, Some("ten")),
+        ];
+        sort_by_admin_level(&mut boundaries);
+        let ids: Vec<i64> = boundaries.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![3, 4, 1, 2, 5]);
+    }
+
+    #[test]
+    fn test_hierarchy_levels() {
+        let mut boundaries = vec![
+            boundary(1, Some("8")),
+            boundary(2, Some("2")),
+            boundary(3, Some("4")),
+            boundary(4, Some("7")),
+            boundary(5, Some("10")),
+        ];
+        sort_by_admin_level(&mut boundaries);
+        let hierarchy = AdminHierarchy { boundaries };
+        assert_eq!(hierarchy.country().unwrap().id, 2);
+        assert_eq!(hierarchy.region().unwrap().id, 3);
+        assert_eq!(hierarchy.city().unwrap().id, 1);
+        assert!(AdminHierarchy::default().city().is_none());
+    }
+}
@@ -0,0 +1,254 @@
+// Geometries assembled from several features
+
+use crate::error::GeoDeskError;
+#[cfg(feature = "export")]
+use crate::export::dialect::{Crs, Dialect};
+use crate::{ffi, BoundingBox, GeoDesk, Node};
+#[cfg(feature = "export")]
+use std::io::{self, Write};
+
+/// A node or way member of a relation, with its geometry
+#[derive(Debug, Clone)]
+pub struct MemberGeometry {
+    pub id: i64,
+    /// `"node"` or `"way"`
+    pub type_name: String,
+    /// Role in the parent relation (e.g. `stop`, `platform`, `forward`)
+    pub role: String,
+    pub lon: f64,
+    pub lat: f64,
+    /// Way nodes (empty for nodes)
+    pub nodes: Vec<Node>,
+}
+
+impl MemberGeometry {
+    /// Check if the member is drawn as a line
+    pub fn is_line(&self) -> bool {
+        self.type_name == "way" && self.nodes.len() > 1
+    }
+}
+
+impl From<ffi::bridge::MemberGeometry> for MemberGeometry {
+    fn from(data: ffi::bridge::MemberGeometry) -> Self {
+        Self {
+            id: data.id,
+            type_name: data.type_name,
+            role: data.role,
+            lon: data.lon,
+            lat: data.lat,
+            nodes: data.nodes.into_iter().map(Node::from).collect(),
+        }
+    }
+}
+
+/// The members of a relation as a geometry collection
+///
+/// Members of sub-relations (such as the routes of a route master) are
+/// included, up to two levels deep.
+#[derive(Debug, Clone)]
+pub struct RelationGeometry {
+    pub id: i64,
+    pub members: Vec<MemberGeometry>,
+}
+
+impl From<ffi::bridge::RelationGeometry> for RelationGeometry {
+    fn from(data: ffi::bridge::RelationGeometry) -> Self {
+        Self {
+            id: data.id,
+            members: data.members.into_iter().map(MemberGeometry::from).collect(),
+        }
+    }
+}
+
+impl RelationGeometry {
+    /// Write the members as a WKT `GEOMETRYCOLLECTION`
+    #[cfg(feature = "export")]
+    pub fn write_wkt<W: Write>(&self, out: &mut W, dialect: &Dialect) -> io::Result<()> {
+        if dialect.crs != Crs::Implicit {
+            out.write_all(b"SRID=4326;")?;
+        }
+        out.write_all(b"GEOMETRYCOLLECTION (")?;
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b", ")?;
+            }
+            if member.is_line() {
+                out.write_all(b"LINESTRING (")?;
+                for (j, node) in member.nodes.iter().enumerate() {
+                    if j > 0 {
+                        out.write_all(b", ")?;
+                    }
+                    dialect.write_coords(out, node.lon, node.lat, " ")?;
+                }
+            } else {
+                out.write_all(b"POINT (")?;
+                dialect.write_coords(out, member.lon, member.lat, " ")?;
+            }
+            out.write_all(b")")?;
+        }
+        out.write_all(b")")
+    }
+
+    /// The members as a WKT `GEOMETRYCOLLECTION` string
+    #[cfg(feature = "export")]
+    pub fn to_wkt(&self, dialect: &Dialect) -> String {
+        let mut out = Vec::new();
+        self.write_wkt(&mut out, dialect)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("WKT output is valid UTF-8")
+    }
+
+    /// Write the members as a GeoJSON `GeometryCollection` object
+    #[cfg(feature = "export")]
+    pub fn write_geojson<W: Write>(&self, out: &mut W, dialect: &Dialect) -> io::Result<()> {
+        out.write_all(b"{\"type\":\"GeometryCollection\",\"geometries\":[")?;
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+            if member.is_line() {
+                out.write_all(b"{\"type\":\"LineString\",\"coordinates\":[")?;
+                for (j, node) in member.nodes.iter().enumerate() {
+                    if j > 0 {
+                        out.write_all(b",")?;
+                    }
+                    out.write_all(b"[")?;
+                    dialect.write_coords(out, node.lon, node.lat, ",")?;
+                    out.write_all(b"]")?;
+                }
+                out.write_all(b"]}")?;
+            } else {
+                out.write_all(b"{\"type\":\"Point\",\"coordinates\":[")?;
+                dialect.write_coords(out, member.lon, member.lat, ",")?;
+                out.write_all(b"]}")?;
+            }
+        }
+        out.write_all(b"]}")
+    }
+
+    /// Convert to a `geo-types` geometry collection
+    #[cfg(feature = "geo")]
+    pub fn to_geometry_collection(&self) -> geo_types::GeometryCollection<f64> {
+        self.members
+            .iter()
+            .map(|member| -> geo_types::Geometry<f64> {
+                if member.is_line() {
+                    geo_types::LineString::from(
+                        member
+                            .nodes
+                            .iter()
+                            .map(|n| (n.lon, n.lat))
+                            .collect::<Vec<_>>(),
+                    )
+                    .into()
+                } else {
+                    geo_types::Point::new(member.lon, member.lat).into()
+                }
+            })
+            .collect()
+    }
+}
+
+impl GeoDesk {
+    /// Member geometries of the relations matching a GOQL query
+    ///
+    /// Each relation comes back with the points and lines of all its
+    /// members in a single call, so drawing a bus line doesn't require a
+    /// query per member. Features other than relations are skipped.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// for route in geodesk
+    ///     .relation_geometries("r[type=route][route=bus]", bboxes::COPENHAGEN)
+    ///     .unwrap()
+    /// {
+    ///     let stops = route.members.iter().filter(|m| m.role == "stop").count();
+    ///     println!("route {}: {} stops", route.id, stops);
+    /// }
+    /// ```
+    pub fn relation_geometries(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+    ) -> Result<Vec<RelationGeometry>, GeoDeskError> {
+        self.measure(
+            "relation_geometries",
+            |r: &Vec<RelationGeometry>| Some(r.len() as u64),
+            || {
+                self.check_tiles(bbox)?;
+                let _active = self.activity.begin();
+                let relations = ffi::bridge::query_relation_geometries(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                )?;
+                Ok(relations.into_iter().map(RelationGeometry::from).collect())
+            },
+        )
+    }
+}
+
+#[cfg(all(test, feature = "export"))]
+mod tests {
+    use super::*;
+
+    fn route() -> RelationGeometry {
+        RelationGeometry {
+            id: 7,
+            members: vec![
+                MemberGeometry {
+                    id: 1,
+                    type_name: "node".to_string(),
+                    role: "stop".to_string(),
+                    lon: 12.5,
+                    lat: 55.5,
+                    nodes: Vec::new(),
+                },
+                MemberGeometry {
+                    id: 2,
+                    type_name: "way".to_string(),
+                    role: String::new(),
+                    lon: 12.55,
+                    lat: 55.55,
+                    nodes: vec![
+                        Node {
+                            id: 3,
+                            lon: 12.5,
+                            lat: 55.5,
+                        },
+                        Node {
+                            id: 4,
+                            lon: 12.6,
+                            lat: 55.6,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_geometry_collection_output() {
+        let route = route();
+        assert_eq!(
+            route.to_wkt(&Dialect::default()),
+            "GEOMETRYCOLLECTION (POINT (12.5 55.5), LINESTRING (12.5 55.5, 12.6 55.6))"
+        );
+
+        let mut out = Vec::new();
+        route.write_geojson(&mut out, &Dialect::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"type\":\"GeometryCollection\",\"geometries\":[\
+             {\"type\":\"Point\",\"coordinates\":[12.5,55.5]},\
+             {\"type\":\"LineString\",\"coordinates\":[[12.5,55.5],[12.6,55.6]]}]}"
+        );
+    }
+}
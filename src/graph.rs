@@ -0,0 +1,691 @@
+// Routable graphs extracted from road networks
+
+use crate::error::GeoDeskError;
+use crate::geometry::RelationGeometry;
+use crate::roads::HighwayClass;
+use crate::{BoundingBox, Feature, GeoDesk};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// OSM ID of a graph node
+pub type NodeId = i64;
+
+pub use crate::projection::haversine;
+
+/// A junction or shape point of the network
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphNode {
+    pub id: NodeId,
+    pub lon: f64,
+    pub lat: f64,
+}
+
+/// Weight of a directed edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeWeight {
+    /// The way the edge is a segment of
+    pub way_id: i64,
+    /// Length in meters
+    pub length: f64,
+    /// The way's `maxspeed` in km/h, if tagged
+    pub max_speed: Option<f64>,
+    /// The way's road class, if its `highway` tag is a kind of road
+    pub class: Option<HighwayClass>,
+}
+
+/// How [`RoadGraph::shortest_path`] weighs edges
+#[derive(Debug, Clone, Copy)]
+pub enum CostModel {
+    /// Length in meters
+    Distance,
+    /// Travel time in seconds at the `maxspeed` of each way, or at
+    /// `default_speed` (km/h) where it is missing
+    TravelTime { default_speed: f64 },
+    /// A custom cost per edge, which must not be negative
+    Custom(fn(&EdgeWeight) -> f64),
+}
+
+impl CostModel {
+    /// Cost of traversing an edge
+    pub fn cost(&self, weight: &EdgeWeight) -> f64 {
+        match self {
+            CostModel::Distance => weight.length,
+            CostModel::TravelTime { default_speed } => {
+                weight.length / (weight.max_speed.unwrap_or(*default_speed) / 3.6)
+            }
+            CostModel::Custom(cost) => cost(weight),
+        }
+    }
+}
+
+/// A route found by [`RoadGraph::shortest_path`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    /// Total cost under the [`CostModel`] used
+    pub cost: f64,
+    /// Total length in meters
+    pub length: f64,
+    /// OSM IDs of the nodes passed, including both ends
+    pub nodes: Vec<NodeId>,
+    /// Indexes of the edges taken, in [`RoadGraph::edges`]
+    pub edges: Vec<usize>,
+}
+
+// An edge waiting in the search queue, ordered by lowest priority first
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    priority: f64,
+    edge: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then_with(|| other.edge.cmp(&self.edge))
+    }
+}
+
+/// A directed segment between two consecutive nodes of a way
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    /// Index of the start node in [`RoadGraph::nodes`]
+    pub from: usize,
+    /// Index of the end node in [`RoadGraph::nodes`]
+    pub to: usize,
+    pub weight: EdgeWeight,
+}
+
+/// The element a turn restriction turns at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Via {
+    Node(NodeId),
+    /// A sequence of ways (restrictions spanning several junctions)
+    Ways(Vec<i64>),
+}
+
+/// A `type=restriction` relation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnRestriction {
+    pub id: i64,
+    /// Value of the `restriction` tag, e.g. `no_left_turn` or `only_straight_on`
+    pub kind: String,
+    pub from_way: i64,
+    pub via: Via,
+    pub to_way: i64,
+}
+
+impl TurnRestriction {
+    /// Build a restriction from a relation's tags and members, or `None`
+    /// if it lacks a `restriction` tag or its from/via/to members
+    pub fn from_relation(relation: &Feature, members: &RelationGeometry) -> Option<Self> {
+        let kind = relation.tag("restriction")?.to_string();
+        let role = |role: &'static str| members.members.iter().filter(move |m| m.role == role);
+        let from_way = role("from").find(|m| m.type_name == "way")?.id;
+        let to_way = role("to").find(|m| m.type_name == "way")?.id;
+        let via = match role("via").next()? {
+            m if m.type_name == "node" => Via::Node(m.id),
+            _ => Via::Ways(role("via").map(|m| m.id).collect()),
+        };
+        Some(Self {
+            id: relation.id,
+            kind,
+            from_way,
+            via,
+            to_way,
+        })
+    }
+
+    /// Check if the restriction forbids a turn (`no_*`)
+    pub fn is_prohibitive(&self) -> bool {
+        self.kind.starts_with("no_")
+    }
+
+    /// Check if the restriction makes a turn the only one allowed (`only_*`)
+    pub fn is_mandatory(&self) -> bool {
+        self.kind.starts_with("only_")
+    }
+}
+
+/// Directed road graph built from ways
+///
+/// Every segment between consecutive way nodes becomes an edge; two-way
+/// roads get an edge in each direction, `oneway=yes` roads only a forward
+/// edge and `oneway=-1` roads only a backward edge. Roundabouts and
+/// motorways (including `motorway_link`) are one-way unless tagged
+/// otherwise, such as `oneway=no`. Ways share a node where they share an
+/// OSM node ID.
+///
+/// Turn restrictions added with [`RoadGraph::add_restriction`] are checked
+/// by [`RoadGraph::is_turn_allowed`]; restrictions with a via node are
+/// enforced, those with via ways are kept but not evaluated.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::GeoDesk;
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+/// let graph = geodesk.road_graph("w[highway]", bboxes::COPENHAGEN).unwrap();
+/// println!("{} nodes, {} edges", graph.nodes().len(), graph.edges().len());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RoadGraph {
+    nodes: Vec<GraphNode>,
+    node_index: HashMap<NodeId, usize>,
+    edges: Vec<Edge>,
+    outgoing: Vec<Vec<usize>>,
+    restrictions: Vec<TurnRestriction>,
+    // Restrictions with a via node, by (from way, via node)
+    turn_index: HashMap<(i64, NodeId), Vec<usize>>,
+}
+
+impl RoadGraph {
+    /// Build the graph from a set of features (anything but ways is ignored)
+    pub fn from_features<'a, I>(features: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Feature>,
+    {
+        let mut graph = Self::default();
+        for feature in features {
+            graph.add_way(feature);
+        }
+        graph
+    }
+
+    /// Add the segments of a way
+    pub fn add_way(&mut self, way: &Feature) {
+        if !way.is_way() || way.nodes.len() < 2 {
+            return;
+        }
+        let max_speed = way.maxspeed().ok().flatten().filter(|&speed| speed > 0.0);
+        let class = way.highway_class();
+        let implied_oneway = way.tag("junction") == Some("roundabout")
+            || matches!(way.tag("highway"), Some("motorway" | "motorway_link"));
+        let oneway = way
+            .tag("oneway")
+            .or(if implied_oneway { Some("yes") } else { None });
+        let (forward, backward) = match oneway {
+            Some("yes" | "1" | "true") => (true, false),
+            Some("-1" | "reverse") => (false, true),
+            _ => (true, true),
+        };
+        let indexes: Vec<usize> = way
+            .nodes
+            .iter()
+            .map(|n| self.node_slot(n.id, n.lon, n.lat))
+            .collect();
+        for pair in indexes.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let weight = EdgeWeight {
+                way_id: way.id,
+                length: haversine(
+                    self.nodes[a].lon,
+                    self.nodes[a].lat,
+                    self.nodes[b].lon,
+                    self.nodes[b].lat,
+                ),
+                max_speed,
+                class,
+            };
+            if forward {
+                self.push_edge(a, b, weight);
+            }
+            if backward {
+                self.push_edge(b, a, weight);
+            }
+        }
+    }
+
+    /// Add a turn restriction
+    pub fn add_restriction(&mut self, restriction: TurnRestriction) {
+        if let Via::Node(node) = restriction.via {
+            self.turn_index
+                .entry((restriction.from_way, node))
+                .or_default()
+                .push(self.restrictions.len());
+        }
+        self.restrictions.push(restriction);
+    }
+
+    /// All turn restrictions
+    pub fn restrictions(&self) -> &[TurnRestriction] {
+        &self.restrictions
+    }
+
+    /// Check if the turn from one edge onto another is allowed
+    ///
+    /// Returns `false` if the edges don't meet (the first must end where
+    /// the second starts). Continuing along the same way is always allowed
+    /// unless an `only_*` restriction says otherwise.
+    pub fn is_turn_allowed(&self, from_edge: usize, to_edge: usize) -> bool {
+        let (from, to) = (&self.edges[from_edge], &self.edges[to_edge]);
+        if from.to != to.from {
+            return false;
+        }
+        let node = self.nodes[from.to].id;
+        let Some(indexes) = self.turn_index.get(&(from.weight.way_id, node)) else {
+            return true;
+        };
+        indexes.iter().all(|&i| {
+            let restriction = &self.restrictions[i];
+            let onto = restriction.to_way == to.weight.way_id;
+            if restriction.is_prohibitive() {
+                !onto
+            } else if restriction.is_mandatory() {
+                onto
+            } else {
+                true
+            }
+        })
+    }
+
+    /// All nodes
+    pub fn nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    /// All edges
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Index of a node in [`RoadGraph::nodes`]
+    pub fn node_index(&self, id: NodeId) -> Option<usize> {
+        self.node_index.get(&id).copied()
+    }
+
+    /// Edges leaving the node at `index`
+    pub fn outgoing(&self, index: usize) -> impl Iterator<Item = &Edge> {
+        self.outgoing
+            .get(index)
+            .into_iter()
+            .flatten()
+            .map(|&e| &self.edges[e])
+    }
+
+    /// Find the cheapest path between two nodes
+    ///
+    /// Uses A* with a straight-line estimate for the built-in cost models
+    /// (plain Dijkstra for [`CostModel::Custom`]). The search runs over
+    /// edges rather than nodes, so turn restrictions are honored. Returns
+    /// `None` if either node isn't in the graph or `to` can't be reached.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::graph::CostModel;
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let graph = geodesk.road_graph("w[highway]", bboxes::COPENHAGEN).unwrap();
+    /// let model = CostModel::TravelTime { default_speed: 50.0 };
+    /// if let Some(path) = graph.shortest_path(26_659_084, 29_188_592, model) {
+    ///     println!("{:.0} s over {:.0} m", path.cost, path.length);
+    /// }
+    /// ```
+    pub fn shortest_path(&self, from: NodeId, to: NodeId, model: CostModel) -> Option<Path> {
+        let (start, target) = (self.node_index(from)?, self.node_index(to)?);
+        if start == target {
+            return Some(Path {
+                cost: 0.0,
+                length: 0.0,
+                nodes: vec![from],
+                edges: Vec::new(),
+            });
+        }
+
+        // Cost per meter of straight-line distance that no edge undercuts
+        let min_rate = match model {
+            CostModel::Distance => 1.0,
+            CostModel::TravelTime { default_speed } => {
+                let fastest = self
+                    .edges
+                    .iter()
+                    .filter_map(|e| e.weight.max_speed)
+                    .fold(default_speed, f64::max);
+                3.6 / fastest
+            }
+            CostModel::Custom(_) => 0.0,
+        };
+        let goal = &self.nodes[target];
+        let estimate = |edge: usize| {
+            let node = &self.nodes[self.edges[edge].to];
+            min_rate * haversine(node.lon, node.lat, goal.lon, goal.lat)
+        };
+
+        let mut best = vec![f64::INFINITY; self.edges.len()];
+        let mut previous = vec![usize::MAX; self.edges.len()];
+        let mut queue = BinaryHeap::new();
+        for &edge in &self.outgoing[start] {
+            best[edge] = model.cost(&self.edges[edge].weight);
+            queue.push(Candidate {
+                priority: best[edge] + estimate(edge),
+                edge,
+            });
+        }
+        while let Some(Candidate { priority, edge }) = queue.pop() {
+            if priority > best[edge] + estimate(edge) {
+                continue;
+            }
+            if self.edges[edge].to == target {
+                return Some(self.trace(edge, best[edge], &previous));
+            }
+            for &next in &self.outgoing[self.edges[edge].to] {
+                if !self.is_turn_allowed(edge, next) {
+                    continue;
+                }
+                let cost = best[edge] + model.cost(&self.edges[next].weight);
+                if cost < best[next] {
+                    best[next] = cost;
+                    previous[next] = edge;
+                    queue.push(Candidate {
+                        priority: cost + estimate(next),
+                        edge: next,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Convert to a `petgraph` graph whose node weights are OSM node IDs
+    ///
+    /// Node and edge indexes match those of this graph, so results of
+    /// `petgraph` algorithms map back via [`RoadGraph::nodes`].
+    #[cfg(feature = "petgraph")]
+    pub fn to_petgraph(&self) -> petgraph::graph::DiGraph<NodeId, EdgeWeight> {
+        use petgraph::graph::{DiGraph, NodeIndex};
+
+        let mut graph = DiGraph::with_capacity(self.nodes.len(), self.edges.len());
+        for node in &self.nodes {
+            graph.add_node(node.id);
+        }
+        for edge in &self.edges {
+            graph.add_edge(
+                NodeIndex::new(edge.from),
+                NodeIndex::new(edge.to),
+                edge.weight,
+            );
+        }
+        graph
+    }
+
+    fn trace(&self, last: usize, cost: f64, previous: &[usize]) -> Path {
+        let mut edges = vec![last];
+        while let Some(&edge) = edges.last().map(|&e| &previous[e]) {
+            if edge == usize::MAX {
+                break;
+            }
+            edges.push(edge);
+        }
+        edges.reverse();
+        let mut nodes = vec![self.nodes[self.edges[edges[0]].from].id];
+        nodes.extend(edges.iter().map(|&e| self.nodes[self.edges[e].to].id));
+        Path {
+            cost,
+            length: edges.iter().map(|&e| self.edges[e].weight.length).sum(),
+            nodes,
+            edges,
+        }
+    }
+
+    fn node_slot(&mut self, id: NodeId, lon: f64, lat: f64) -> usize {
+        if let Some(&index) = self.node_index.get(&id) {
+            return index;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(GraphNode { id, lon, lat });
+        self.outgoing.push(Vec::new());
+        self.node_index.insert(id, index);
+        index
+    }
+
+    fn push_edge(&mut self, from: usize, to: usize, weight: EdgeWeight) {
+        self.outgoing[from].push(self.edges.len());
+        self.edges.push(Edge { from, to, weight });
+    }
+}
+
+impl GeoDesk {
+    /// Build a road graph from the ways matching a GOQL query, with the
+    /// turn restrictions in the bounding box
+    pub fn road_graph(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+    ) -> Result<RoadGraph, GeoDeskError> {
+        let mut graph = RoadGraph::default();
+        for feature in self.query_iter(goql_query, bbox)? {
+            graph.add_way(&feature?);
+        }
+
+        let query = "r[type=restriction][restriction]";
+        let members: HashMap<i64, RelationGeometry> = self
+            .relation_geometries(query, bbox)?
+            .into_iter()
+            .map(|r| (r.id, r))
+            .collect();
+        for relation in self.query(query, bbox)?.to_vec()? {
+            let restriction = members
+                .get(&relation.id)
+                .and_then(|m| TurnRestriction::from_relation(&relation, m));
+            if let Some(restriction) = restriction {
+                graph.add_restriction(restriction);
+            }
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn way(id: i64, oneway: Option<&str>, nodes: &[(i64, f64, f64)]) -> Feature {
+        let mut tags = vec![("highway".to_string(), "residential".to_string())];
+        if let Some(value) = oneway {
+            tags.push(("oneway".to_string(), value.to_string()));
+        }
+        Feature {
+            id,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags,
+            nodes: nodes
+                .iter()
+                .map(|&(id, lon, lat)| Node { id, lon, lat })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_graph_from_ways() {
+        let a = way(1, None, &[(10, 12.0, 55.0), (11, 12.001, 55.0)]);
+        let b = way(2, Some("yes"), &[(11, 12.001, 55.0), (12, 12.001, 55.001)]);
+        let c = way(3, Some("-1"), &[(12, 12.001, 55.001), (13, 12.0, 55.001)]);
+        let graph = RoadGraph::from_features(&[a, b, c]);
+
+        assert_eq!(graph.nodes().len(), 4);
+        assert_eq!(graph.edges().len(), 4);
+        assert!(graph
+            .edges()
+            .iter()
+            .all(|e| e.weight.class == Some(HighwayClass::Residential)));
+        let junction = graph.node_index(11).unwrap();
+        let targets: Vec<NodeId> = graph
+            .outgoing(junction)
+            .map(|e| graph.nodes()[e.to].id)
+            .collect();
+        assert_eq!(targets, vec![10, 12]);
+        // The reversed oneway only leads from 13 to 12
+        assert_eq!(graph.outgoing(graph.node_index(12).unwrap()).count(), 0);
+
+        let length = graph.edges()[0].weight.length;
+        assert!((length - 63.8).abs() < 0.5, "{}", length);
+    }
+
+    #[test]
+    fn test_implied_oneways() {
+        let nodes = [(10, 12.0, 55.0), (11, 12.001, 55.0)];
+        let edges = |tags: &[(&str, &str)]| {
+            let mut road = way(1, None, &nodes);
+            road.tags = tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            RoadGraph::from_features([&road]).edges().len()
+        };
+        assert_eq!(
+            edges(&[("highway", "primary"), ("junction", "roundabout")]),
+            1
+        );
+        assert_eq!(edges(&[("highway", "motorway")]), 1);
+        assert_eq!(edges(&[("highway", "motorway_link")]), 1);
+        assert_eq!(edges(&[("highway", "motorway_link"), ("oneway", "no")]), 2);
+        assert_eq!(edges(&[("highway", "trunk")]), 2);
+    }
+
+    #[test]
+    fn test_turn_restrictions() {
+        use crate::geometry::MemberGeometry;
+
+        // A cross junction at node 1: way 10 from the west, 11 to the
+        // north, 12 to the east
+        let west = way(10, None, &[(2, 11.999, 55.0), (1, 12.0, 55.0)]);
+        let north = way(11, None, &[(1, 12.0, 55.0), (3, 12.0, 55.001)]);
+        let east = way(12, None, &[(1, 12.0, 55.0), (4, 12.001, 55.0)]);
+        let mut graph = RoadGraph::from_features(&[west, north, east]);
+
+        let member = |id, type_name: &str, role: &str| MemberGeometry {
+            id,
+            type_name: type_name.to_string(),
+            role: role.to_string(),
+            lon: 0.0,
+            lat: 0.0,
+            nodes: Vec::new(),
+        };
+        let mut relation = way(100, None, &[]);
+        relation.type_name = "relation".to_string();
+        relation.tags = vec![
+            ("type".to_string(), "restriction".to_string()),
+            ("restriction".to_string(), "no_left_turn".to_string()),
+        ];
+        let members = RelationGeometry {
+            id: 100,
+            members: vec![
+                member(10, "way", "from"),
+                member(1, "node", "via"),
+                member(11, "way", "to"),
+            ],
+        };
+        let restriction = TurnRestriction::from_relation(&relation, &members).unwrap();
+        assert_eq!(restriction.via, Via::Node(1));
+        graph.add_restriction(restriction);
+
+        let edge = |from: NodeId, to: NodeId| {
+            let (a, b) = (
+                graph.node_index(from).unwrap(),
+                graph.node_index(to).unwrap(),
+            );
+            graph
+                .edges()
+                .iter()
+                .position(|e| e.from == a && e.to == b)
+                .unwrap()
+        };
+        assert!(!graph.is_turn_allowed(edge(2, 1), edge(1, 3)));
+        assert!(graph.is_turn_allowed(edge(2, 1), edge(1, 4)));
+        assert!(graph.is_turn_allowed(edge(4, 1), edge(1, 3)));
+        assert!(!graph.is_turn_allowed(edge(2, 1), edge(3, 1)));
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        // A square 1-2-3-4: the northern route 1-2-3 is a fast road, the
+        // southern route 1-4-3 a shorter 30 km/h street
+        let mut fast = way(
+            1,
+            None,
+            &[(1, 12.0, 55.0), (2, 12.0, 55.002), (3, 12.002, 55.002)],
+        );
+        fast.tags.push(("maxspeed".to_string(), "80".to_string()));
+        let mut slow = way(
+            2,
+            None,
+            &[(1, 12.0, 55.0), (4, 12.0015, 55.0), (3, 12.002, 55.002)],
+        );
+        slow.tags.push(("maxspeed".to_string(), "30".to_string()));
+        let mut graph = RoadGraph::from_features(&[fast, slow]);
+
+        let path = graph.shortest_path(1, 3, CostModel::Distance).unwrap();
+        assert_eq!(path.nodes, vec![1, 4, 3]);
+        assert!((path.cost - path.length).abs() < 1e-9);
+
+        let model = CostModel::TravelTime {
+            default_speed: 50.0,
+        };
+        let path = graph.shortest_path(1, 3, model).unwrap();
+        assert_eq!(path.nodes, vec![1, 2, 3]);
+        assert!((path.cost - path.length / (80.0 / 3.6)).abs() < 1e-9);
+
+        // From 4 to 2 the way round via 1 is shorter, until the turn from
+        // way 2 onto way 1 at node 1 is forbidden
+        let path = graph.shortest_path(4, 2, CostModel::Distance).unwrap();
+        assert_eq!(path.nodes, vec![4, 1, 2]);
+        graph.add_restriction(TurnRestriction {
+            id: 100,
+            kind: "no_right_turn".to_string(),
+            from_way: 2,
+            via: Via::Node(1),
+            to_way: 1,
+        });
+        let path = graph.shortest_path(4, 2, CostModel::Distance).unwrap();
+        assert_eq!(path.nodes, vec![4, 3, 2]);
+
+        assert_eq!(
+            graph
+                .shortest_path(2, 2, CostModel::Distance)
+                .unwrap()
+                .nodes,
+            vec![2]
+        );
+        assert!(graph.shortest_path(1, 99, CostModel::Distance).is_none());
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_to_petgraph() {
+        use petgraph::graph::NodeIndex;
+
+        let graph = RoadGraph::from_features(&[way(
+            1,
+            None,
+            &[(10, 12.0, 55.0), (11, 12.001, 55.0), (12, 12.002, 55.0)],
+        )]);
+        let pg = graph.to_petgraph();
+        assert_eq!(pg.node_count(), 3);
+        assert_eq!(pg.edge_count(), 4);
+        let (start, end) = (NodeIndex::new(0), NodeIndex::new(2));
+        let costs = petgraph::algo::dijkstra(&pg, start, Some(end), |e| e.weight().length);
+        assert!((costs[&end] - 2.0 * graph.edges()[0].weight.length).abs() < 1e-6);
+    }
+}
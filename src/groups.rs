@@ -0,0 +1,177 @@
+// Grouping and counting features by their tags
+
+use crate::error::GeoDeskError;
+use crate::{BoundingBox, FeatureType, GeoDesk, TypeCounts};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+/// Feature counts and tag keys of an area, from [`GeoDesk::summary`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub counts: TypeCounts,
+    /// Tag keys with the number of features using them, most used first
+    /// (ties by key)
+    pub keys: Vec<(String, u64)>,
+}
+
+impl Summary {
+    /// The `n` most used tag keys
+    pub fn top_keys(&self, n: usize) -> &[(String, u64)] {
+        &self.keys[..n.min(self.keys.len())]
+    }
+}
+
+// Sort key counts with the most used first
+fn ranked(keys: HashMap<String, u64>) -> Vec<(String, u64)> {
+    let mut keys: Vec<_> = keys.into_iter().collect();
+    keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    keys
+}
+
+impl GeoDesk {
+    /// Count the features matching a GOQL query per value of a tag
+    ///
+    /// The tag is read inside the query loop, so features are never
+    /// copied. Features without the tag are not counted; add `[key]` to
+    /// the query to skip them early.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let cuisines = geodesk
+    ///     .count_by_tag("na[amenity=restaurant][cuisine]", bboxes::COPENHAGEN, "cuisine")
+    ///     .unwrap();
+    /// let mut cuisines: Vec<_> = cuisines.into_iter().collect();
+    /// cuisines.sort_by(|a, b| b.1.cmp(&a.1));
+    /// for (cuisine, count) in cuisines.iter().take(10) {
+    ///     println!("{}: {}", cuisine, count);
+    /// }
+    /// ```
+    pub fn count_by_tag(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        key: &str,
+    ) -> Result<HashMap<String, u64>, GeoDeskError> {
+        let mut counts = HashMap::new();
+        let mut error = None;
+        self.for_each(goql_query, bbox, |feature| match feature.tag(key) {
+            Ok(Some(value)) => {
+                *counts.entry(value).or_insert(0) += 1;
+                ControlFlow::Continue(())
+            }
+            Ok(None) => ControlFlow::Continue(()),
+            Err(e) => {
+                error = Some(e);
+                ControlFlow::Break(())
+            }
+        })?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok(counts),
+        }
+    }
+
+    /// The distinct values of a tag among the features matching a GOQL
+    /// query, sorted
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::{BoundingBox, GeoDesk};
+    ///
+    /// let geodesk = GeoDesk::open("canada.gol").unwrap();
+    /// let montreal = BoundingBox::new(-73.98, 45.41, -73.47, 45.70);
+    /// // Choices for a shop filter
+    /// for shop in geodesk.distinct_values("na[shop]", montreal, "shop").unwrap() {
+    ///     println!("{}", shop);
+    /// }
+    /// ```
+    pub fn distinct_values(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        key: &str,
+    ) -> Result<Vec<String>, GeoDeskError> {
+        let mut values: Vec<String> = self
+            .count_by_tag(goql_query, bbox, key)?
+            .into_keys()
+            .collect();
+        values.sort();
+        Ok(values)
+    }
+
+    /// Count the nodes, ways and relations in an area and the tag keys
+    /// they use, in a single pass
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let summary = geodesk.summary(bboxes::COPENHAGEN).unwrap();
+    /// println!("{} features", summary.counts.total());
+    /// for (key, count) in summary.top_keys(20) {
+    ///     println!("{}: {}", key, count);
+    /// }
+    /// ```
+    pub fn summary(&self, bbox: BoundingBox) -> Result<Summary, GeoDeskError> {
+        let mut counts = TypeCounts::default();
+        let mut keys: HashMap<String, u64> = HashMap::new();
+        let mut error = None;
+        self.for_each("*", bbox, |feature| {
+            match feature.feature_type() {
+                FeatureType::Node => counts.nodes += 1,
+                FeatureType::Way => counts.ways += 1,
+                FeatureType::Relation => counts.relations += 1,
+            }
+            for tag in feature.tags() {
+                match tag {
+                    Ok((key, _)) => match keys.get_mut(key) {
+                        Some(count) => *count += 1,
+                        None => {
+                            keys.insert(key.to_string(), 1);
+                        }
+                    },
+                    Err(e) => {
+                        error = Some(e);
+                        return ControlFlow::Break(());
+                    }
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok(Summary {
+                counts,
+                keys: ranked(keys),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranked_keys() {
+        let keys = HashMap::from([
+            ("name".to_string(), 3),
+            ("building".to_string(), 5),
+            ("amenity".to_string(), 3),
+        ]);
+        let summary = Summary {
+            counts: TypeCounts::default(),
+            keys: ranked(keys),
+        };
+        let names: Vec<&str> = summary.keys.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(names, ["building", "amenity", "name"]);
+        assert_eq!(summary.top_keys(1), &[("building".to_string(), 5)]);
+        assert_eq!(summary.top_keys(10).len(), 3);
+    }
+}
@@ -0,0 +1,100 @@
+// Evaluation of the opening_hours tag
+
+use crate::Feature;
+use chrono::NaiveDateTime;
+use std::fmt;
+
+/// A parsed `opening_hours` value
+///
+/// Times are local wall-clock times at the feature's location; converting
+/// from UTC is up to the caller, who knows (or can look up) the time zone.
+/// Rules depending on public holidays or sunrise are evaluated without a
+/// location, so `PH` never matches and `sunrise`/`sunset` use default times.
+#[derive(Clone)]
+pub struct OpeningHours {
+    raw: String,
+    inner: opening_hours::OpeningHours,
+}
+
+impl OpeningHours {
+    /// Parse an `opening_hours` expression, returning `None` if it is invalid
+    pub fn parse(raw: &str) -> Option<Self> {
+        Some(Self {
+            raw: raw.to_string(),
+            inner: raw.parse().ok()?,
+        })
+    }
+
+    /// Check if the feature is open at a local time
+    ///
+    /// Returns `false` for times the expression marks as unknown or closed.
+    pub fn is_open_at(&self, time: NaiveDateTime) -> bool {
+        self.inner.is_open(time)
+    }
+
+    /// Check if the state at a local time is unknown (rules such as
+    /// `Su unknown "by appointment"`)
+    pub fn is_unknown_at(&self, time: NaiveDateTime) -> bool {
+        self.inner.is_unknown(time)
+    }
+
+    /// The next local time at which the state changes, if any
+    pub fn next_change(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.inner.next_change(time)
+    }
+
+    /// The expression as written in the tag
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl fmt::Debug for OpeningHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OpeningHours").field(&self.raw).finish()
+    }
+}
+
+impl Feature {
+    /// The parsed `opening_hours` tag, or `None` if it is absent or invalid
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::{GeoDesk, BoundingBox};
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+    /// let now = chrono::Local::now().naive_local();
+    /// let cafes = geodesk.query("na[amenity=cafe][opening_hours]", bbox).unwrap();
+    /// for cafe in cafes.to_vec().unwrap() {
+    ///     if cafe.opening_hours().is_some_and(|h| h.is_open_at(now)) {
+    ///         println!("{} is open", cafe.name);
+    ///     }
+    /// }
+    /// ```
+    pub fn opening_hours(&self) -> Option<OpeningHours> {
+        OpeningHours::parse(self.tag("opening_hours")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_is_open_at() {
+        let hours = OpeningHours::parse("Mo-Fr 08:00-18:00; Sa 10:00-14:00").unwrap();
+        assert!(hours.is_open_at(at("2024-06-03 09:30"))); // Monday
+        assert!(!hours.is_open_at(at("2024-06-03 19:00")));
+        assert!(!hours.is_open_at(at("2024-06-09 12:00"))); // Sunday
+        assert_eq!(
+            hours.next_change(at("2024-06-08 11:00")),
+            Some(at("2024-06-08 14:00"))
+        );
+        assert!(OpeningHours::parse("whenever").is_none());
+    }
+}
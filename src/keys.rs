@@ -0,0 +1,355 @@
+// Typed constants for common OSM tag keys and values
+//
+// Compiled from the most frequently used keys and values on taginfo. Using
+// these instead of string literals turns a misspelled key into a compile
+// error rather than a silently empty query.
+
+use crate::query::{Comparison, TagFilter};
+
+/// An OSM tag key usable to build [`TagFilter`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key(&'static str);
+
+impl Key {
+    /// Define a key that has no predefined constant
+    pub const fn new(key: &'static str) -> Self {
+        Self(key)
+    }
+
+    /// The key as it appears in OSM data
+    pub const fn as_str(&self) -> &'static str {
+        self.0
+    }
+
+    /// Match features that have this tag
+    pub fn exists(&self) -> TagFilter {
+        TagFilter::Exists(self.0.to_string())
+    }
+
+    /// Match features that don't have this tag
+    pub fn missing(&self) -> TagFilter {
+        TagFilter::Missing(self.0.to_string())
+    }
+
+    /// Match features whose value equals `value`
+    pub fn eq(&self, value: &str) -> TagFilter {
+        TagFilter::Equals(self.0.to_string(), vec![value.to_string()])
+    }
+
+    /// Match features whose value is not `value`
+    pub fn ne(&self, value: &str) -> TagFilter {
+        TagFilter::NotEquals(self.0.to_string(), vec![value.to_string()])
+    }
+
+    /// Match features whose value is any of `values`
+    pub fn one_of(&self, values: &[&str]) -> TagFilter {
+        TagFilter::Equals(
+            self.0.to_string(),
+            values.iter().map(|v| v.to_string()).collect(),
+        )
+    }
+
+    /// Match features whose numeric value is less than `n`
+    pub fn lt(&self, n: f64) -> TagFilter {
+        TagFilter::Compare(self.0.to_string(), Comparison::Less, n)
+    }
+
+    /// Match features whose numeric value is at most `n`
+    pub fn le(&self, n: f64) -> TagFilter {
+        TagFilter::Compare(self.0.to_string(), Comparison::LessOrEqual, n)
+    }
+
+    /// Match features whose numeric value is greater than `n`
+    pub fn gt(&self, n: f64) -> TagFilter {
+        TagFilter::Compare(self.0.to_string(), Comparison::Greater, n)
+    }
+
+    /// Match features whose numeric value is at least `n`
+    pub fn ge(&self, n: f64) -> TagFilter {
+        TagFilter::Compare(self.0.to_string(), Comparison::GreaterOrEqual, n)
+    }
+}
+
+impl AsRef<str> for Key {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+/// Common tag keys, named after the key (`:` replaced by `_`)
+#[allow(non_upper_case_globals)]
+pub mod tag {
+    use super::Key;
+
+    pub const access: Key = Key::new("access");
+    pub const addr_city: Key = Key::new("addr:city");
+    pub const addr_country: Key = Key::new("addr:country");
+    pub const addr_housenumber: Key = Key::new("addr:housenumber");
+    pub const addr_postcode: Key = Key::new("addr:postcode");
+    pub const addr_street: Key = Key::new("addr:street");
+    pub const admin_level: Key = Key::new("admin_level");
+    pub const aeroway: Key = Key::new("aeroway");
+    pub const amenity: Key = Key::new("amenity");
+    pub const barrier: Key = Key::new("barrier");
+    pub const bicycle: Key = Key::new("bicycle");
+    pub const boundary: Key = Key::new("boundary");
+    pub const brand: Key = Key::new("brand");
+    pub const building: Key = Key::new("building");
+    pub const building_levels: Key = Key::new("building:levels");
+    pub const capacity: Key = Key::new("capacity");
+    pub const craft: Key = Key::new("craft");
+    pub const cuisine: Key = Key::new("cuisine");
+    pub const cycleway: Key = Key::new("cycleway");
+    pub const denomination: Key = Key::new("denomination");
+    pub const emergency: Key = Key::new("emergency");
+    pub const entrance: Key = Key::new("entrance");
+    pub const foot: Key = Key::new("foot");
+    pub const height: Key = Key::new("height");
+    pub const highway: Key = Key::new("highway");
+    pub const historic: Key = Key::new("historic");
+    pub const landuse: Key = Key::new("landuse");
+    pub const lanes: Key = Key::new("lanes");
+    pub const layer: Key = Key::new("layer");
+    pub const leisure: Key = Key::new("leisure");
+    pub const man_made: Key = Key::new("man_made");
+    pub const maxspeed: Key = Key::new("maxspeed");
+    pub const name: Key = Key::new("name");
+    pub const natural: Key = Key::new("natural");
+    pub const office: Key = Key::new("office");
+    pub const oneway: Key = Key::new("oneway");
+    pub const opening_hours: Key = Key::new("opening_hours");
+    pub const operator: Key = Key::new("operator");
+    pub const phone: Key = Key::new("phone");
+    pub const place: Key = Key::new("place");
+    pub const population: Key = Key::new("population");
+    pub const power: Key = Key::new("power");
+    pub const public_transport: Key = Key::new("public_transport");
+    pub const railway: Key = Key::new("railway");
+    pub const r#ref: Key = Key::new("ref");
+    pub const religion: Key = Key::new("religion");
+    pub const route: Key = Key::new("route");
+    pub const service: Key = Key::new("service");
+    pub const shop: Key = Key::new("shop");
+    pub const sport: Key = Key::new("sport");
+    pub const surface: Key = Key::new("surface");
+    pub const tourism: Key = Key::new("tourism");
+    pub const r#type: Key = Key::new("type");
+    pub const wheelchair: Key = Key::new("wheelchair");
+    pub const website: Key = Key::new("website");
+    pub const width: Key = Key::new("width");
+}
+
+/// Common values of the `amenity` key
+pub mod amenity {
+    pub const BAR: &str = "bar";
+    pub const BENCH: &str = "bench";
+    pub const BICYCLE_PARKING: &str = "bicycle_parking";
+    pub const BUS_STATION: &str = "bus_station";
+    pub const CAFE: &str = "cafe";
+    pub const CAR_PARKING: &str = "car_parking";
+    pub const CHARGING_STATION: &str = "charging_station";
+    pub const CINEMA: &str = "cinema";
+    pub const CLINIC: &str = "clinic";
+    pub const DENTIST: &str = "dentist";
+    pub const DOCTORS: &str = "doctors";
+    pub const DRINKING_WATER: &str = "drinking_water";
+    pub const FAST_FOOD: &str = "fast_food";
+    pub const FIRE_STATION: &str = "fire_station";
+    pub const FUEL: &str = "fuel";
+    pub const HOSPITAL: &str = "hospital";
+    pub const ICE_CREAM: &str = "ice_cream";
+    pub const KINDERGARTEN: &str = "kindergarten";
+    pub const LIBRARY: &str = "library";
+    pub const MARKETPLACE: &str = "marketplace";
+    pub const PARKING: &str = "parking";
+    pub const PHARMACY: &str = "pharmacy";
+    pub const PLACE_OF_WORSHIP: &str = "place_of_worship";
+    pub const POLICE: &str = "police";
+    pub const POST_BOX: &str = "post_box";
+    pub const POST_OFFICE: &str = "post_office";
+    pub const PUB: &str = "pub";
+    pub const RECYCLING: &str = "recycling";
+    pub const RESTAURANT: &str = "restaurant";
+    pub const SCHOOL: &str = "school";
+    pub const SHELTER: &str = "shelter";
+    pub const THEATRE: &str = "theatre";
+    pub const TOILETS: &str = "toilets";
+    pub const TOWNHALL: &str = "townhall";
+    pub const UNIVERSITY: &str = "university";
+    pub const WASTE_BASKET: &str = "waste_basket";
+}
+
+/// Common values of the `highway` key
+pub mod highway {
+    pub const BUS_STOP: &str = "bus_stop";
+    pub const CROSSING: &str = "crossing";
+    pub const CYCLEWAY: &str = "cycleway";
+    pub const FOOTWAY: &str = "footway";
+    pub const LIVING_STREET: &str = "living_street";
+    pub const MOTORWAY: &str = "motorway";
+    pub const MOTORWAY_LINK: &str = "motorway_link";
+    pub const PATH: &str = "path";
+    pub const PEDESTRIAN: &str = "pedestrian";
+    pub const PRIMARY: &str = "primary";
+    pub const PRIMARY_LINK: &str = "primary_link";
+    pub const RESIDENTIAL: &str = "residential";
+    pub const SECONDARY: &str = "secondary";
+    pub const SECONDARY_LINK: &str = "secondary_link";
+    pub const SERVICE: &str = "service";
+    pub const STEPS: &str = "steps";
+    pub const TERTIARY: &str = "tertiary";
+    pub const TERTIARY_LINK: &str = "tertiary_link";
+    pub const TRAFFIC_SIGNALS: &str = "traffic_signals";
+    pub const TRACK: &str = "track";
+    pub const TRUNK: &str = "trunk";
+    pub const TRUNK_LINK: &str = "trunk_link";
+    pub const UNCLASSIFIED: &str = "unclassified";
+}
+
+/// Common values of the `shop` key
+pub mod shop {
+    pub const BAKERY: &str = "bakery";
+    pub const BEAUTY: &str = "beauty";
+    pub const BICYCLE: &str = "bicycle";
+    pub const BOOKS: &str = "books";
+    pub const BUTCHER: &str = "butcher";
+    pub const CAR: &str = "car";
+    pub const CAR_REPAIR: &str = "car_repair";
+    pub const CLOTHES: &str = "clothes";
+    pub const CONVENIENCE: &str = "convenience";
+    pub const DEPARTMENT_STORE: &str = "department_store";
+    pub const ELECTRONICS: &str = "electronics";
+    pub const FLORIST: &str = "florist";
+    pub const FURNITURE: &str = "furniture";
+    pub const GREENGROCER: &str = "greengrocer";
+    pub const HAIRDRESSER: &str = "hairdresser";
+    pub const HARDWARE: &str = "hardware";
+    pub const KIOSK: &str = "kiosk";
+    pub const MALL: &str = "mall";
+    pub const MOBILE_PHONE: &str = "mobile_phone";
+    pub const OPTICIAN: &str = "optician";
+    pub const SHOES: &str = "shoes";
+    pub const SUPERMARKET: &str = "supermarket";
+}
+
+/// Common values of the `building` key
+pub mod building {
+    pub const APARTMENTS: &str = "apartments";
+    pub const COMMERCIAL: &str = "commercial";
+    pub const DETACHED: &str = "detached";
+    pub const GARAGE: &str = "garage";
+    pub const HOUSE: &str = "house";
+    pub const INDUSTRIAL: &str = "industrial";
+    pub const RESIDENTIAL: &str = "residential";
+    pub const RETAIL: &str = "retail";
+    pub const SCHOOL: &str = "school";
+    pub const TERRACE: &str = "terrace";
+    pub const YES: &str = "yes";
+}
+
+/// Common values of the `leisure` key
+pub mod leisure {
+    pub const FITNESS_CENTRE: &str = "fitness_centre";
+    pub const GARDEN: &str = "garden";
+    pub const NATURE_RESERVE: &str = "nature_reserve";
+    pub const PARK: &str = "park";
+    pub const PITCH: &str = "pitch";
+    pub const PLAYGROUND: &str = "playground";
+    pub const SPORTS_CENTRE: &str = "sports_centre";
+    pub const STADIUM: &str = "stadium";
+    pub const SWIMMING_POOL: &str = "swimming_pool";
+}
+
+/// Common values of the `tourism` key
+pub mod tourism {
+    pub const ATTRACTION: &str = "attraction";
+    pub const CAMP_SITE: &str = "camp_site";
+    pub const GUEST_HOUSE: &str = "guest_house";
+    pub const HOSTEL: &str = "hostel";
+    pub const HOTEL: &str = "hotel";
+    pub const INFORMATION: &str = "information";
+    pub const MOTEL: &str = "motel";
+    pub const MUSEUM: &str = "museum";
+    pub const PICNIC_SITE: &str = "picnic_site";
+    pub const VIEWPOINT: &str = "viewpoint";
+}
+
+/// Common values of the `landuse` key
+pub mod landuse {
+    pub const COMMERCIAL: &str = "commercial";
+    pub const FARMLAND: &str = "farmland";
+    pub const FOREST: &str = "forest";
+    pub const GRASS: &str = "grass";
+    pub const INDUSTRIAL: &str = "industrial";
+    pub const MEADOW: &str = "meadow";
+    pub const RESIDENTIAL: &str = "residential";
+    pub const RETAIL: &str = "retail";
+}
+
+/// Common values of the `natural` key
+pub mod natural {
+    pub const BEACH: &str = "beach";
+    pub const COASTLINE: &str = "coastline";
+    pub const PEAK: &str = "peak";
+    pub const SCRUB: &str = "scrub";
+    pub const TREE: &str = "tree";
+    pub const WATER: &str = "water";
+    pub const WETLAND: &str = "wetland";
+    pub const WOOD: &str = "wood";
+}
+
+/// Common values of the `railway` key
+pub mod railway {
+    pub const PLATFORM: &str = "platform";
+    pub const RAIL: &str = "rail";
+    pub const STATION: &str = "station";
+    pub const SUBWAY: &str = "subway";
+    pub const TRAM: &str = "tram";
+    pub const TRAM_STOP: &str = "tram_stop";
+}
+
+/// Common values of the `public_transport` key
+pub mod public_transport {
+    pub const PLATFORM: &str = "platform";
+    pub const STATION: &str = "station";
+    pub const STOP_AREA: &str = "stop_area";
+    pub const STOP_POSITION: &str = "stop_position";
+}
+
+/// Common values of the `boundary` key
+pub mod boundary {
+    pub const ADMINISTRATIVE: &str = "administrative";
+    pub const NATIONAL_PARK: &str = "national_park";
+    pub const POSTAL_CODE: &str = "postal_code";
+    pub const PROTECTED_AREA: &str = "protected_area";
+}
+
+/// Common values of the `place` key
+pub mod place {
+    pub const CITY: &str = "city";
+    pub const COUNTRY: &str = "country";
+    pub const HAMLET: &str = "hamlet";
+    pub const ISLAND: &str = "island";
+    pub const LOCALITY: &str = "locality";
+    pub const NEIGHBOURHOOD: &str = "neighbourhood";
+    pub const STATE: &str = "state";
+    pub const SUBURB: &str = "suburb";
+    pub const TOWN: &str = "town";
+    pub const VILLAGE: &str = "village";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_filters() {
+        assert_eq!(tag::addr_street.as_str(), "addr:street");
+        assert_eq!(
+            tag::highway.eq(highway::PRIMARY).to_string(),
+            "[highway=primary]"
+        );
+        assert_eq!(tag::building.missing().to_string(), "[!building]");
+        assert_eq!(tag::population.gt(1e6).to_string(), "[population>1000000]");
+    }
+}
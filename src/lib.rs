@@ -1,6 +1,11 @@
 // Rust library for GeoDESK C++ bindings using cxx.rs
 
 pub mod ffi {
+    use crate::cancel::{query_can_stop, query_should_stop, QueryControl};
+    use crate::refs::{
+        filter_feature, query_matches_tags, visit_feature, FeatureFilter, FeatureVisitor,
+    };
+
     #[cxx::bridge(namespace = "geodesk_bridge")]
     pub mod bridge {
         // Node data for way geometry
@@ -12,7 +17,7 @@ pub mod ffi {
         }
 
         // Shared structs between Rust and C++
-        #[derive(Debug, Clone)]
+        #[derive(Debug, Clone, Default)]
         pub struct FeatureData {
             pub id: i64,
             pub type_name: String,
@@ -21,7 +26,138 @@ pub mod ffi {
             pub lat: f64,
             pub tag_keys: Vec<String>,
             pub tag_values: Vec<String>,
-            pub nodes: Vec<NodeData>,  // Way nodes (empty for non-ways)
+            pub nodes: Vec<NodeData>, // Way nodes (empty for non-ways)
+        }
+
+        // Header information of a GOL file
+        #[derive(Debug, Clone, Default)]
+        pub struct GolMetadata {
+            pub west: f64,
+            pub south: f64,
+            pub east: f64,
+            pub north: f64,
+            pub revision: u32,
+            pub revision_timestamp: i64, // Milliseconds since the Unix epoch
+            pub format_version: u32,
+            pub tile_count: u32,
+            pub indexed_keys: Vec<String>,
+            pub osm_metadata: bool, // Features carry version, timestamp and changeset
+        }
+
+        // OSM editing metadata of a feature
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct OsmMetadata {
+            pub version: u32,
+            pub timestamp: i64, // Milliseconds since the Unix epoch
+            pub changeset: u64,
+        }
+
+        // Runtime state of an open store, for diagnostics
+        #[derive(Debug, Clone, Default)]
+        pub struct StoreDebugInfo {
+            pub file_path: String,
+            pub mapping_size: u64,
+            pub resident_bytes: i64, // -1 if the platform can't report residency
+        }
+
+        // A tile of the GOL's tile index
+        #[derive(Debug, Clone, Copy)]
+        pub struct TileData {
+            pub zoom: u8,
+            pub column: u32,
+            pub row: u32,
+            pub present: bool, // False for tiles left out of an extract
+            pub page: u32,     // First page of the tile's data in the file
+        }
+
+        // Number of features of each type in a result
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct TypeCounts {
+            pub nodes: u64,
+            pub ways: u64,
+            pub relations: u64,
+        }
+
+        // Bounding box of a single feature
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct FeatureBounds {
+            pub id: i64,
+            pub west: f64,
+            pub south: f64,
+            pub east: f64,
+            pub north: f64,
+        }
+
+        // Point or line geometry of a relation member
+        #[derive(Debug, Clone, Default)]
+        pub struct MemberGeometry {
+            pub id: i64,
+            pub type_name: String,
+            pub role: String,
+            pub lon: f64,
+            pub lat: f64,
+            pub nodes: Vec<NodeData>, // Way nodes (empty for nodes)
+        }
+
+        // A relation with the geometries of its members
+        #[derive(Debug, Clone, Default)]
+        pub struct RelationGeometry {
+            pub id: i64,
+            pub members: Vec<MemberGeometry>,
+        }
+
+        // Settings applied when opening a store
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct StoreOptions {
+            pub access: u8, // 0 = normal, 1 = random, 2 = sequential
+            pub prefetch: bool,
+            pub strict_utf8: bool, // Fail on invalid UTF-8 instead of replacing it
+        }
+
+        // Identity of a feature in a result
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct FeatureKey {
+            pub type_code: u8, // 0 = node, 1 = way, 2 = relation
+            pub id: i64,
+        }
+
+        // Per-query limits (0 = unlimited)
+        #[derive(Debug, Clone, Default)]
+        pub struct QuerySettings {
+            pub max_way_nodes: u64,
+            pub max_relation_members: u64,
+            pub truncate_oversized: bool, // Keep oversized ways, cut to the limit
+            pub max_results: u64,         // Stop collecting after this many features
+            pub collect_stats: bool,      // Record QueryStatsData
+            pub web_mercator: bool,       // Coordinates in EPSG:3857 meters
+            pub simplify_tolerance: f64,  // Douglas-Peucker tolerance in meters, 0 for off
+            pub match_tags: bool,         // Ask query_matches_tags about each feature
+        }
+
+        // What a Rust predicate decided about a feature
+        enum FilterVerdict {
+            Skip,
+            Keep,
+            Stop, // The predicate panicked
+        }
+
+        // Counts and timings of a query with QuerySettings::collect_stats
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct QueryStatsData {
+            pub tiles: u64,          // Tiles present in the query area
+            pub examined: u64,       // Features the GOQL matcher returned
+            pub matched: u64,        // Of those, the ones that passed tag patterns
+            pub matching_nanos: u64, // Finding and matching features
+            pub copying_nanos: u64,  // Copying tags and geometry into the result
+        }
+
+        // A feature that exceeded a size limit of QuerySettings
+        #[derive(Debug, Clone, Default)]
+        pub struct OversizedFeature {
+            pub id: i64,
+            pub type_name: String,
+            pub size: u64, // Nodes of a way, members of a relation
+            pub truncated: bool,
         }
 
         // Opaque C++ types
@@ -30,9 +166,16 @@ pub mod ffi {
 
             type FeatureStore;
             type FeatureResult;
+            type FeatureCursor;
+            type FeatureList;
+            type FeatureHandle;
+            type TagCursor;
 
             // Factory functions
-            fn create_feature_store(gol_path: &str) -> Result<UniquePtr<FeatureStore>>;
+            fn create_feature_store(
+                gol_path: &str,
+                options: &StoreOptions,
+            ) -> Result<UniquePtr<FeatureStore>>;
 
             fn query_amenities_in_bbox(
                 store: &FeatureStore,
@@ -52,18 +195,342 @@ pub mod ffi {
                 north: f64,
             ) -> Result<UniquePtr<FeatureResult>>;
 
+            #[allow(clippy::too_many_arguments)]
+            fn query_with_settings(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+                settings: &QuerySettings,
+                control: &QueryControl,
+            ) -> Result<UniquePtr<FeatureResult>>;
+
+            fn query_counted(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+                limit: usize,
+            ) -> Result<UniquePtr<FeatureResult>>;
+
+            fn query_cursor(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+            ) -> Result<UniquePtr<FeatureCursor>>;
+
+            fn cursor_next(cursor: Pin<&mut FeatureCursor>, out: &mut FeatureData) -> Result<bool>;
+
+            fn store_metadata(store: &FeatureStore) -> Result<GolMetadata>;
+
+            fn store_debug_info(store: &FeatureStore) -> StoreDebugInfo;
+
+            fn store_revision(store: &FeatureStore) -> u32;
+
+            fn store_revision_timestamp(store: &FeatureStore) -> i64;
+
+            fn store_release_memory(store: &FeatureStore) -> bool;
+
+            fn create_memory_file(data: &[u8]) -> Result<i32>;
+
+            fn store_tiles(
+                store: &FeatureStore,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+            ) -> Result<Vec<TileData>>;
+
+            fn count_by_type(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+            ) -> Result<TypeCounts>;
+
+            fn query_containing(
+                store: &FeatureStore,
+                goql_query: &str,
+                lon: f64,
+                lat: f64,
+            ) -> Result<UniquePtr<FeatureResult>>;
+
+            fn query_relation_geometries(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+            ) -> Result<Vec<RelationGeometry>>;
+
+            // Direct members of relations, including relation members
+            // (without coordinates), and no way nodes
+            fn query_relation_members(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+            ) -> Result<Vec<RelationGeometry>>;
+
+            fn query_bounds(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+            ) -> Result<Vec<FeatureBounds>>;
+
+            fn query_handles(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+                control: &QueryControl,
+            ) -> Result<UniquePtr<FeatureList>>;
+
+            // Returns true if the control stopped the query
+            #[allow(clippy::too_many_arguments)]
+            fn query_for_each(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+                visitor: &mut FeatureVisitor,
+                control: &QueryControl,
+            ) -> Result<bool>;
+
+            #[allow(clippy::too_many_arguments)]
+            fn query_filtered(
+                store: &FeatureStore,
+                goql_query: &str,
+                west: f64,
+                south: f64,
+                east: f64,
+                north: f64,
+                filter: &mut FeatureFilter,
+                control: &QueryControl,
+            ) -> Result<UniquePtr<FeatureResult>>;
+
+            fn list_len(list: &FeatureList) -> usize;
+
+            fn list_stopped(list: &FeatureList) -> bool;
+
+            fn list_get(list: &FeatureList, index: usize) -> Result<&FeatureHandle>;
+
+            fn handle_id(handle: &FeatureHandle) -> i64;
+
+            fn handle_type(handle: &FeatureHandle) -> u8; // 0 = node, 1 = way, 2 = relation
+
+            fn handle_lon(handle: &FeatureHandle) -> f64;
+
+            fn handle_lat(handle: &FeatureHandle) -> f64;
+
+            fn handle_bounds(handle: &FeatureHandle) -> FeatureBounds;
+
+            fn handle_name(handle: &FeatureHandle) -> Result<String>;
+
+            // Returns false if the GOL doesn't store OSM metadata
+            fn handle_osm_metadata(handle: &FeatureHandle, out: &mut OsmMetadata) -> bool;
+
+            // Look up a single tag; returns false if the feature lacks it
+            fn feature_tag(handle: &FeatureHandle, key: &str, value: &mut String) -> Result<bool>;
+
+            fn handle_hydrate(
+                handle: &FeatureHandle,
+                tags: bool,
+                geometry: bool,
+                out: &mut FeatureData,
+            ) -> Result<()>;
+
+            fn handle_members(handle: &FeatureHandle) -> Result<Vec<MemberGeometry>>;
+
+            // Tags of a feature, read one at a time from its tag table
+            fn handle_tags(handle: &FeatureHandle) -> UniquePtr<TagCursor>;
+
+            fn tag_cursor_next(cursor: Pin<&mut TagCursor>) -> bool;
+
+            // Keys are strings in the GOL, so they outlive the cursor
+            fn tag_cursor_key<'a>(cursor: &TagCursor, handle: &'a FeatureHandle)
+                -> Result<&'a str>;
+
+            fn tag_cursor_value(cursor: &TagCursor) -> Result<String>;
+
             fn result_count(result: &FeatureResult) -> usize;
 
+            fn result_counts_by_type(result: &FeatureResult) -> TypeCounts;
+
+            fn result_oversized(result: &FeatureResult) -> Vec<OversizedFeature>;
+
+            // Check if collecting stopped at QuerySettings::max_results
+            fn result_truncated(result: &FeatureResult) -> bool;
+
+            // Check if the query was stopped by its QueryControl
+            fn result_stopped(result: &FeatureResult) -> bool;
+
+            // Stats of a query with QuerySettings::collect_stats (zero otherwise)
+            fn result_stats(result: &FeatureResult) -> QueryStatsData;
+
             fn result_to_vec(result: &FeatureResult) -> Result<UniquePtr<CxxVector<FeatureData>>>;
+
+            fn result_range(
+                result: &FeatureResult,
+                start: usize,
+                count: usize,
+            ) -> Result<UniquePtr<CxxVector<FeatureData>>>;
+
+            // Types and ids of the features, in order
+            fn result_keys(result: &FeatureResult) -> Vec<FeatureKey>;
+
+            // Bounds of the features and their nodes (west > east if empty)
+            fn result_bounds(result: &FeatureResult) -> FeatureBounds;
+
+            // Convex hull of the features' nodes, counter-clockwise; ids
+            // are those of way nodes, 0 for feature locations
+            fn result_convex_hull(result: &FeatureResult) -> Vec<NodeData>;
+
+            // The features at the given indices, in that order
+            fn result_select(
+                result: &FeatureResult,
+                indices: &[usize],
+            ) -> Result<UniquePtr<CxxVector<FeatureData>>>;
+        }
+
+        // Rust callbacks invoked from C++ iteration loops
+        extern "Rust" {
+            type FeatureVisitor<'a>;
+
+            // Returns false to stop the iteration
+            fn visit_feature(visitor: &mut FeatureVisitor, handle: &FeatureHandle) -> bool;
+
+            type FeatureFilter<'a>;
+
+            // Decides whether a feature is copied into the result
+            fn filter_feature(filter: &mut FeatureFilter, handle: &FeatureHandle) -> FilterVerdict;
+
+            type QueryControl;
+
+            // Returns true once the query is cancelled or past its deadline
+            fn query_should_stop(control: &QueryControl) -> bool;
+
+            // Returns false if the query has neither a token nor a deadline
+            fn query_can_stop(control: &QueryControl) -> bool;
+
+            // Returns false if the feature fails a tag pattern of the query
+            fn query_matches_tags(control: &QueryControl, handle: &FeatureHandle) -> bool;
         }
     }
 }
 
+// GeoDESK feature stores are safe to query from multiple threads, and
+// query results are owned copies that no longer reference the store
+unsafe impl Send for ffi::bridge::FeatureStore {}
+unsafe impl Sync for ffi::bridge::FeatureStore {}
+unsafe impl Send for ffi::bridge::FeatureResult {}
+unsafe impl Sync for ffi::bridge::FeatureResult {}
+
+pub mod address;
+pub mod boundary;
+pub mod builder;
+pub mod cache;
+pub mod cancel;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod categories;
+pub mod combine;
+#[cfg(feature = "crs")]
+pub mod crs;
+pub mod cycling;
+pub mod debug;
+pub mod density;
+pub mod diff;
+pub mod error;
+pub mod explain;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod geocode;
+pub mod geometry;
+#[cfg(feature = "routing")]
+pub mod graph;
+pub mod groups;
+#[cfg(feature = "opening-hours")]
+pub mod hours;
+pub mod keys;
+pub mod metadata;
+pub mod metrics;
+#[cfg(feature = "mvt")]
+pub mod mvt;
+#[cfg(feature = "transliterate")]
+pub mod names;
+pub mod options;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "places")]
+pub mod places;
+pub mod projection;
+pub mod provenance;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query;
+pub mod refs;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod roads;
+#[cfg(feature = "routing")]
+pub mod routes;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod set;
+pub mod snap;
+pub mod snapshot;
+pub mod source;
+pub mod tags;
+#[cfg(feature = "test-data")]
+pub mod test_data;
+pub mod testing;
+pub mod tiled;
+pub mod tiles;
+#[cfg(feature = "routing")]
+pub mod transit;
+pub mod translate;
+pub mod updates;
+pub mod validity;
+pub mod verify;
+pub mod zonal;
+
+pub use debug::DebugState;
+pub use error::GeoDeskError;
+pub use metadata::{GolMetadata, Watermark};
+pub use options::OpenOptions;
+pub use set::GeoDeskSet;
+pub use tiles::{MissingTiles, TileCoverage};
+
 use cxx::UniquePtr;
+use explain::QueryStats;
+use std::fmt;
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+use tiles::TileId;
 
 /// Represents a bounding box in WGS84 coordinates
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct BoundingBox {
     pub west: f64,
     pub south: f64,
@@ -91,6 +558,42 @@ impl BoundingBox {
             north: lat + radius_deg,
         }
     }
+
+    /// Create a bounding box reaching `meters` from a point in every
+    /// direction
+    pub fn around(lon: f64, lat: f64, meters: f64) -> Self {
+        let dlat = meters / projection::METERS_PER_DEGREE;
+        let dlon = dlat / lat.to_radians().cos().max(0.01);
+        Self::new(lon - dlon, lat - dlat, lon + dlon, lat + dlat)
+    }
+
+    /// Check if two bounding boxes overlap (touching edges count)
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.west <= other.east
+            && other.west <= self.east
+            && self.south <= other.north
+            && other.south <= self.north
+    }
+}
+
+impl FromStr for BoundingBox {
+    type Err = String;
+
+    /// Parse a bounding box from `"west,south,east,north"`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coords = s
+            .split(',')
+            .map(|c| c.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid bounding box '{}': {}", s, e))?;
+        match coords[..] {
+            [west, south, east, north] => Ok(Self::new(west, south, east, north)),
+            _ => Err(format!(
+                "Invalid bounding box '{}': expected west,south,east,north",
+                s
+            )),
+        }
+    }
 }
 
 /// A node in a way's geometry
@@ -111,6 +614,56 @@ impl From<ffi::bridge::NodeData> for Node {
     }
 }
 
+/// Type of an OSM feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureType {
+    Node,
+    Way,
+    Relation,
+}
+
+impl FeatureType {
+    /// `"node"`, `"way"` or `"relation"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FeatureType::Node => "node",
+            FeatureType::Way => "way",
+            FeatureType::Relation => "relation",
+        }
+    }
+}
+
+impl From<&str> for FeatureType {
+    /// Parse a type name as found in [`Feature::type_name`]; the bridge only
+    /// produces the three names, so anything else is taken as a relation
+    fn from(name: &str) -> Self {
+        match name {
+            "node" => FeatureType::Node,
+            "way" => FeatureType::Way,
+            _ => FeatureType::Relation,
+        }
+    }
+}
+
+impl fmt::Display for FeatureType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ffi::bridge::FeatureKey {
+    pub(crate) fn feature_type(&self) -> FeatureType {
+        match self.type_code {
+            0 => FeatureType::Node,
+            1 => FeatureType::Way,
+            _ => FeatureType::Relation,
+        }
+    }
+}
+
+/// Keys of ways that are lines even when closed, unless tagged `area=yes`
+const LINEAR_KEYS: [&str; 4] = ["highway", "barrier", "railway", "waterway"];
+
 /// A feature from OpenStreetMap data
 #[derive(Debug, Clone)]
 pub struct Feature {
@@ -120,16 +673,12 @@ pub struct Feature {
     pub lon: f64,
     pub lat: f64,
     pub tags: Vec<(String, String)>,
-    pub nodes: Vec<Node>,  // Way nodes (empty for non-ways)
+    pub nodes: Vec<Node>, // Way nodes (empty for non-ways)
 }
 
 impl From<ffi::bridge::FeatureData> for Feature {
     fn from(data: ffi::bridge::FeatureData) -> Self {
-        let tags = data
-            .tag_keys
-            .into_iter()
-            .zip(data.tag_values.into_iter())
-            .collect();
+        let tags = data.tag_keys.into_iter().zip(data.tag_values).collect();
 
         // Convert NodeData to Node
         let nodes: Vec<Node> = data.nodes.into_iter().map(|n| n.into()).collect();
@@ -160,30 +709,99 @@ impl Feature {
         self.tags.iter().any(|(k, _)| k == key)
     }
 
+    /// The feature's type
+    pub fn feature_type(&self) -> FeatureType {
+        FeatureType::from(self.type_name.as_str())
+    }
+
     /// Check if this feature is a way
     pub fn is_way(&self) -> bool {
-        self.type_name == "way"
+        self.feature_type() == FeatureType::Way
     }
 
     /// Check if this feature is a node
     pub fn is_node(&self) -> bool {
-        self.type_name == "node"
+        self.feature_type() == FeatureType::Node
     }
 
     /// Check if this feature is a relation
     pub fn is_relation(&self) -> bool {
-        self.type_name == "relation"
+        self.feature_type() == FeatureType::Relation
+    }
+
+    /// Check if this feature is an area: a closed way not tagged as a line
+    /// (roads, barriers, railways, waterways, unless `area=yes`), or a
+    /// multipolygon or boundary relation
+    ///
+    /// Ways need their nodes for this, so it is always `false` for ways
+    /// read without geometry.
+    pub fn is_area(&self) -> bool {
+        match self.feature_type() {
+            FeatureType::Node => false,
+            FeatureType::Way => {
+                let closed = self.nodes.len() >= 4
+                    && self.nodes.first().map(|n| n.id) == self.nodes.last().map(|n| n.id);
+                closed
+                    && match self.tag("area") {
+                        Some("yes") => true,
+                        Some("no") => false,
+                        _ => !LINEAR_KEYS.iter().any(|key| self.has_tag(key)),
+                    }
+            }
+            FeatureType::Relation => {
+                matches!(self.tag("type"), Some("multipolygon" | "boundary"))
+            }
+        }
+    }
+}
+
+/// Number of nodes, ways and relations matched by a query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeCounts {
+    pub nodes: u64,
+    pub ways: u64,
+    pub relations: u64,
+}
+
+impl TypeCounts {
+    /// Number of features of all types
+    pub fn total(&self) -> u64 {
+        self.nodes + self.ways + self.relations
+    }
+}
+
+impl From<ffi::bridge::TypeCounts> for TypeCounts {
+    fn from(data: ffi::bridge::TypeCounts) -> Self {
+        Self {
+            nodes: data.nodes,
+            ways: data.ways,
+            relations: data.relations,
+        }
     }
 }
 
 /// Result of a GeoDESK query
 pub struct QueryResult {
     result: UniquePtr<ffi::bridge::FeatureResult>,
+    missing_tiles: Vec<TileId>,
+    watermark: Watermark,
+    stats: Option<QueryStats>,
+    max_batch: Option<usize>,
 }
 
 impl QueryResult {
-    fn new(result: UniquePtr<ffi::bridge::FeatureResult>) -> Self {
-        Self { result }
+    fn new(
+        result: UniquePtr<ffi::bridge::FeatureResult>,
+        missing_tiles: Vec<TileId>,
+        watermark: Watermark,
+    ) -> Self {
+        Self {
+            result,
+            missing_tiles,
+            watermark,
+            stats: None,
+            max_batch: None,
+        }
     }
 
     /// Get the number of features in the result
@@ -191,30 +809,362 @@ impl QueryResult {
         ffi::bridge::result_count(&self.result)
     }
 
+    /// Number of nodes, ways and relations in the result
+    ///
+    /// The counts are kept while the result is collected, so this doesn't
+    /// convert any features.
+    pub fn counts_by_type(&self) -> TypeCounts {
+        ffi::bridge::result_counts_by_type(&self.result).into()
+    }
+
     /// Convert result to a vector of features
-    pub fn to_vec(&self) -> Result<Vec<Feature>, Box<dyn std::error::Error>> {
+    pub fn to_vec(&self) -> Result<Vec<Feature>, GeoDeskError> {
         let cpp_vec = ffi::bridge::result_to_vec(&self.result)?;
         let features: Vec<Feature> = cpp_vec.iter().map(|f| f.clone().into()).collect();
         Ok(features)
     }
 
+    /// The feature at `index`, converting only that one (`None` past the
+    /// end)
+    pub fn get(&self, index: usize) -> Result<Option<Feature>, GeoDeskError> {
+        let features = ffi::bridge::result_range(&self.result, index, 1)?;
+        Ok(features.iter().next().map(|f| f.clone().into()))
+    }
+
+    /// Iterate over the features, converting each as it is reached
+    ///
+    /// The iterator knows its length and skips features without converting
+    /// them, so sampling or paging through a large result stays cheap. A
+    /// feature that fails to convert is yielded as an error.
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "test-data")] {
+    /// use geodesk_rs::test_data::TestGol;
+    /// use geodesk_rs::testing::bboxes;
+    ///
+    /// let Some(geodesk) = TestGol::try_open() else { return };
+    /// let buildings = geodesk.query("a[building]", bboxes::MONACO).unwrap();
+    /// // The third page of 50
+    /// for building in buildings.iter().skip(100).take(50) {
+    ///     println!("{}", building.unwrap().id);
+    /// }
+    /// // Every 100th building
+    /// let sample: Vec<_> = buildings.iter().step_by(100).collect();
+    /// assert_eq!(sample.len(), buildings.count().div_ceil(100));
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            result: self,
+            next: 0,
+            end: self.count(),
+        }
+    }
+
+    /// Convert the result in batches of `size` features
+    ///
+    /// Only one batch is converted at a time, so exporting a large result
+    /// in batches (e.g. into Arrow record batches) keeps memory use bounded
+    /// by the batch size rather than the result size. The last batch may
+    /// be shorter. In [`LowMemoryMode`](options::LowMemoryMode), batches are
+    /// capped at its batch size.
+    ///
+    /// # Panics
+    /// If `size` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "test-data")] {
+    /// use geodesk_rs::test_data::TestGol;
+    /// use geodesk_rs::testing::bboxes;
+    ///
+    /// let Some(geodesk) = TestGol::try_open() else { return };
+    /// let buildings = geodesk.query("a[building]", bboxes::MONACO).unwrap();
+    /// let mut total = 0;
+    /// for batch in buildings.chunks(1_000) {
+    ///     let batch = batch.unwrap();
+    ///     total += batch.len();
+    /// }
+    /// assert_eq!(total, buildings.count());
+    /// # }
+    /// ```
+    pub fn chunks(&self, size: usize) -> Chunks<'_> {
+        assert!(size > 0, "chunk size must be non-zero");
+        Chunks {
+            result: self,
+            next: 0,
+            size: self.max_batch.map_or(size, |max| size.min(max.max(1))),
+        }
+    }
+
     /// Check if the result is empty
     pub fn is_empty(&self) -> bool {
         self.count() == 0
     }
+
+    /// Tiles of the query area that are missing from the GOL
+    ///
+    /// Only populated with [`MissingTiles::Flag`]; a non-empty list means
+    /// the result may be incomplete.
+    pub fn missing_tiles(&self) -> &[TileId] {
+        &self.missing_tiles
+    }
+
+    /// The data revision the result was read from
+    pub fn watermark(&self) -> Watermark {
+        self.watermark
+    }
+
+    /// Counts and timings of the query, if it ran with
+    /// [`QueryOptions::collect_stats`](options::QueryOptions::collect_stats)
+    pub fn stats(&self) -> Option<&QueryStats> {
+        self.stats.as_ref()
+    }
+
+    /// Check if the query matched more features than
+    /// [`QueryOptions::max_results`](options::QueryOptions::max_results)
+    /// and only the first ones were collected
+    pub fn is_truncated(&self) -> bool {
+        ffi::bridge::result_truncated(&self.result)
+    }
+
+    /// Features that exceeded a limit of [`GeoDesk::query_with`], whether
+    /// they were skipped or truncated
+    pub fn oversized(&self) -> Vec<options::OversizedFeature> {
+        ffi::bridge::result_oversized(&self.result)
+            .into_iter()
+            .map(options::OversizedFeature::from)
+            .collect()
+    }
+
+    /// Bounding box of the features and their nodes (`None` if empty)
+    ///
+    /// The bounds are kept while the result is collected, so this doesn't
+    /// convert any features. Use it to zoom a map to the result. It is in
+    /// degrees, also for results in [`projection::CoordSys::WebMercator`].
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let b = ffi::bridge::result_bounds(&self.result);
+        (b.west <= b.east).then(|| BoundingBox::new(b.west, b.south, b.east, b.north))
+    }
+
+    /// Convex hull of the features' nodes, and of the locations of nodes
+    /// and relations, as a counter-clockwise ring of (lon, lat) pairs that
+    /// doesn't repeat its first point
+    ///
+    /// Computed without converting any features, and in degrees like
+    /// [`QueryResult::bounds`]. A result with fewer than three distinct
+    /// points returns just those.
+    pub fn convex_hull(&self) -> Vec<(f64, f64)> {
+        ffi::bridge::result_convex_hull(&self.result)
+            .into_iter()
+            .map(|point| (point.lon, point.lat))
+            .collect()
+    }
+}
+
+/// Batches of the features of a [`QueryResult`], from [`QueryResult::chunks`]
+pub struct Chunks<'a> {
+    result: &'a QueryResult,
+    next: usize,
+    size: usize,
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Result<Vec<Feature>, GeoDeskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.result.count() {
+            return None;
+        }
+        let start = self.next;
+        self.next = start.saturating_add(self.size);
+        let batch = ffi::bridge::result_range(&self.result.result, start, self.size)
+            .map(|features| features.iter().map(|f| f.clone().into()).collect());
+        if batch.is_err() {
+            self.next = usize::MAX;
+        }
+        Some(batch.map_err(GeoDeskError::from))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self
+            .result
+            .count()
+            .saturating_sub(self.next)
+            .div_ceil(self.size);
+        (remaining, Some(remaining))
+    }
+}
+
+/// Iterator over the features of a [`QueryResult`], from
+/// [`QueryResult::iter`]
+pub struct Iter<'a> {
+    result: &'a QueryResult,
+    next: usize,
+    end: usize,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Result<Feature, GeoDeskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+        self.next += 1;
+        self.result.get(self.next - 1).transpose()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.next = self.next.saturating_add(n).min(self.end);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        self.result.get(self.end).transpose()
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a QueryResult {
+    type Item = Result<Feature, GeoDeskError>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl ExactSizeIterator for Chunks<'_> {}
+
+/// Features from [`GeoDesk::features`], collected or streamed
+pub enum Features {
+    Collected(std::vec::IntoIter<Feature>),
+    Streamed(FeatureIter),
+}
+
+impl Iterator for Features {
+    type Item = Result<Feature, GeoDeskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Features::Collected(features) => features.next().map(Ok),
+            Features::Streamed(features) => features.next(),
+        }
+    }
+}
+
+/// Streaming iterator over the features matched by a query
+///
+/// Features are converted one at a time as the iterator advances, so memory
+/// use stays flat regardless of how many features the query matches.
+pub struct FeatureIter {
+    cursor: UniquePtr<ffi::bridge::FeatureCursor>,
+    done: bool,
+    missing_tiles: Vec<TileId>,
+}
+
+impl FeatureIter {
+    /// Tiles of the query area that are missing from the GOL
+    /// (see [`QueryResult::missing_tiles`])
+    pub fn missing_tiles(&self) -> &[TileId] {
+        &self.missing_tiles
+    }
+}
+
+impl Iterator for FeatureIter {
+    type Item = Result<Feature, GeoDeskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut data = ffi::bridge::FeatureData::default();
+        match ffi::bridge::cursor_next(self.cursor.pin_mut(), &mut data) {
+            Ok(true) => Some(Ok(data.into())),
+            Ok(false) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
 }
 
 /// Main interface to GeoDESK GOL files
 pub struct GeoDesk {
     store: UniquePtr<ffi::bridge::FeatureStore>,
+    // Dropped after the store, which maps the file through it
+    source: Option<options::FileSource>,
+    #[cfg(feature = "remote")]
+    remote: Option<remote::RemoteSource>,
+    activity: debug::QueryActivity,
+    missing_tiles: MissingTiles,
+    complete: OnceLock<bool>,
+    threads: usize,
+    warnings: Vec<String>,
+    // Held for reading by snapshots and for writing while applying updates
+    update_lock: RwLock<()>,
+    admin_cache: geocode::AdminCache,
+    limits: options::Limits,
+    metrics: Option<Arc<dyn metrics::Metrics>>,
 }
 
 impl GeoDesk {
-    /// Open a GOL file
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
-        let path_str = path.as_ref().to_str().ok_or("Invalid path")?;
-        let store = ffi::bridge::create_feature_store(path_str)?;
-        Ok(Self { store })
+    /// Open a GOL file with default options
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, GeoDeskError> {
+        OpenOptions::new().open(path)
+    }
+
+    /// Open a GOL from a file that is already open, with default options
+    /// (see [`OpenOptions::open_file`])
+    pub fn open_mmap(file: std::fs::File) -> Result<Self, GeoDeskError> {
+        OpenOptions::new().open_file(file)
+    }
+
+    /// Open a GOL held in memory, with default options (see
+    /// [`OpenOptions::open_bytes`])
+    pub fn open_bytes(data: &[u8]) -> Result<Self, GeoDeskError> {
+        OpenOptions::new().open_bytes(data)
+    }
+
+    /// Set how queries treat tiles that are missing from the GOL
+    ///
+    /// By default, a query whose bounding box includes missing tiles fails
+    /// with [`GeoDeskError::TilesMissing`] rather than returning partial data.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::{GeoDesk, BoundingBox, MissingTiles};
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol")
+    ///     .unwrap()
+    ///     .with_missing_tiles(MissingTiles::Flag);
+    /// let bbox = BoundingBox::new(12.0, 54.0, 15.0, 56.0);
+    /// let result = geodesk.query("na[amenity]", bbox).unwrap();
+    /// if !result.missing_tiles().is_empty() {
+    ///     eprintln!("warning: result is incomplete");
+    /// }
+    /// ```
+    pub fn with_missing_tiles(mut self, policy: MissingTiles) -> Self {
+        self.missing_tiles = policy;
+        self
     }
 
     /// Query amenities of a specific type within a bounding box
@@ -224,28 +1174,39 @@ impl GeoDesk {
     /// * `bbox` - The bounding box to search within
     ///
     /// # Example
-    /// ```no_run
-    /// use geodesk_rs::{GeoDesk, BoundingBox};
+    /// ```
+    /// # #[cfg(feature = "test-data")] {
+    /// use geodesk_rs::test_data::TestGol;
+    /// use geodesk_rs::testing::bboxes;
     ///
-    /// let geodesk = GeoDesk::open("world.gol").unwrap();
-    /// let bbox = BoundingBox::new(-73.9, 45.4, -73.5, 45.7); // Montreal
-    /// let restaurants = geodesk.query_amenities("restaurant", bbox).unwrap();
-    /// println!("Found {} restaurants", restaurants.count());
+    /// let Some(geodesk) = TestGol::try_open() else { return };
+    /// let restaurants = geodesk.query_amenities("restaurant", bboxes::MONACO).unwrap();
+    /// assert!(restaurants.count() > 0);
+    /// # }
     /// ```
     pub fn query_amenities(
         &self,
         amenity_type: &str,
         bbox: BoundingBox,
-    ) -> Result<QueryResult, Box<dyn std::error::Error>> {
-        let result = ffi::bridge::query_amenities_in_bbox(
-            &self.store,
-            amenity_type,
-            bbox.west,
-            bbox.south,
-            bbox.east,
-            bbox.north,
-        )?;
-        Ok(QueryResult::new(result))
+    ) -> Result<QueryResult, GeoDeskError> {
+        self.measure(
+            "query_amenities",
+            |r: &QueryResult| Some(r.count() as u64),
+            || {
+                let missing_tiles = self.check_tiles(bbox)?;
+                let watermark = self.watermark();
+                let _active = self.activity.begin();
+                let result = ffi::bridge::query_amenities_in_bbox(
+                    &self.store,
+                    amenity_type,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                )?;
+                Ok(QueryResult::new(result, missing_tiles, watermark))
+            },
+        )
     }
 
     /// Query features using GOQL (Geographic Object Query Language)
@@ -255,72 +1216,252 @@ impl GeoDesk {
     /// * `bbox` - The bounding box to search within
     ///
     /// # Example
-    /// ```no_run
-    /// use geodesk_rs::{GeoDesk, BoundingBox};
+    /// ```
+    /// # #[cfg(feature = "test-data")] {
+    /// use geodesk_rs::test_data::TestGol;
+    /// use geodesk_rs::testing::bboxes;
     ///
-    /// let geodesk = GeoDesk::open("world.gol").unwrap();
-    /// let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73); // Copenhagen
-    /// let roads = geodesk.query("w[highway]", bbox).unwrap();
+    /// let Some(geodesk) = TestGol::try_open() else { return };
+    /// let roads = geodesk.query("w[highway]", bboxes::MONACO).unwrap();
     /// println!("Found {} roads", roads.count());
+    /// assert!(roads.to_vec().unwrap().iter().all(|road| road.tag("highway").is_some()));
+    /// # }
+    /// ```
+    pub fn query(&self, goql_query: &str, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
+        self.measure(
+            "query",
+            |r: &QueryResult| Some(r.count() as u64),
+            || {
+                let missing_tiles = self.check_tiles(bbox)?;
+                let watermark = self.watermark();
+                let _active = self.activity.begin();
+                let result = ffi::bridge::query_with_goql(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                )?;
+                let mut result = QueryResult::new(result, missing_tiles, watermark);
+                result.max_batch = self.limits.low_memory.map(|mode| mode.batch_size);
+                Ok(result)
+            },
+        )
+    }
+
+    /// Count all features matching a GOQL query and collect only the first
+    /// `limit` of them, in one pass
+    ///
+    /// Returns the total number of matches and the collected features, the
+    /// shape a paginated listing needs. With [`MissingTiles::Flag`], use
+    /// [`GeoDesk::tile_coverage_in`] to learn whether the count is complete.
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "test-data")] {
+    /// use geodesk_rs::test_data::TestGol;
+    /// use geodesk_rs::testing::bboxes;
+    ///
+    /// let Some(geodesk) = TestGol::try_open() else { return };
+    /// let (total, first) = geodesk
+    ///     .query_counted("na[amenity=cafe]", bboxes::MONACO, 20)
+    ///     .unwrap();
+    /// println!("{} results, showing {}", total, first.len());
+    /// assert_eq!(first.len() as u64, total.min(20));
+    /// # }
     /// ```
-    pub fn query(
+    pub fn query_counted(
         &self,
         goql_query: &str,
         bbox: BoundingBox,
-    ) -> Result<QueryResult, Box<dyn std::error::Error>> {
-        let result = ffi::bridge::query_with_goql(
-            &self.store,
-            goql_query,
-            bbox.west,
-            bbox.south,
-            bbox.east,
-            bbox.north,
-        )?;
-        Ok(QueryResult::new(result))
+        limit: usize,
+    ) -> Result<(u64, Vec<Feature>), GeoDeskError> {
+        self.measure(
+            "query_counted",
+            |(_, features): &(u64, Vec<Feature>)| Some(features.len() as u64),
+            || {
+                self.check_tiles(bbox)?;
+                let watermark = self.watermark();
+                let _active = self.activity.begin();
+                let result = ffi::bridge::query_counted(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                    limit,
+                )?;
+                let result = QueryResult::new(result, Vec::new(), watermark);
+                Ok((result.counts_by_type().total(), result.to_vec()?))
+            },
+        )
     }
 
-    /// Query all amenities within a bounding box (any type)
-    pub fn query_all_amenities(
+    /// Stream the features matching a GOQL query without collecting them first
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "test-data")] {
+    /// use geodesk_rs::test_data::TestGol;
+    /// use geodesk_rs::testing::bboxes;
+    ///
+    /// let Some(geodesk) = TestGol::try_open() else { return };
+    /// for building in geodesk.query_iter("a[building]", bboxes::MONACO).unwrap() {
+    ///     let building = building.unwrap();
+    ///     println!("{}", building.id);
+    /// }
+    /// # }
+    /// ```
+    pub fn query_iter(
         &self,
+        goql_query: &str,
         bbox: BoundingBox,
-    ) -> Result<QueryResult, Box<dyn std::error::Error>> {
-        self.query("na[amenity]", bbox)
+    ) -> Result<FeatureIter, GeoDeskError> {
+        self.measure(
+            "query_iter",
+            |_| None,
+            || {
+                let missing_tiles = self.check_tiles(bbox)?;
+                let _active = self.activity.begin();
+                let cursor = ffi::bridge::query_cursor(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                )?;
+                Ok(FeatureIter {
+                    cursor,
+                    done: false,
+                    missing_tiles,
+                })
+            },
+        )
     }
 
-    /// Query restaurants within a bounding box
-    pub fn query_restaurants(
+    /// Iterate over the features matching a GOQL query, collecting them
+    /// first unless the GOL was opened in
+    /// [`LowMemoryMode`](options::LowMemoryMode)
+    ///
+    /// Collecting is the faster way to read a result; streaming keeps only
+    /// one feature in memory. Code that runs on both servers and small
+    /// devices can use this and let the open options decide.
+    pub fn features(&self, goql_query: &str, bbox: BoundingBox) -> Result<Features, GeoDeskError> {
+        if self.limits.low_memory.is_some() {
+            Ok(Features::Streamed(self.query_iter(goql_query, bbox)?))
+        } else {
+            Ok(Features::Collected(
+                self.query(goql_query, bbox)?.to_vec()?.into_iter(),
+            ))
+        }
+    }
+
+    /// Count the nodes, ways and relations matching a GOQL query without
+    /// collecting them
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "test-data")] {
+    /// use geodesk_rs::test_data::TestGol;
+    /// use geodesk_rs::testing::bboxes;
+    ///
+    /// let Some(geodesk) = TestGol::try_open() else { return };
+    /// let counts = geodesk.count_by_type("*[amenity=parking]", bboxes::MONACO).unwrap();
+    /// println!("{} nodes, {} areas as ways", counts.nodes, counts.ways);
+    /// let parking = geodesk.query("*[amenity=parking]", bboxes::MONACO).unwrap();
+    /// assert_eq!(counts.total(), parking.count() as u64);
+    /// # }
+    /// ```
+    pub fn count_by_type(
         &self,
+        goql_query: &str,
         bbox: BoundingBox,
-    ) -> Result<QueryResult, Box<dyn std::error::Error>> {
-        self.query_amenities("restaurant", bbox)
+    ) -> Result<TypeCounts, GeoDeskError> {
+        self.measure(
+            "count_by_type",
+            |_| None,
+            || {
+                self.check_tiles(bbox)?;
+                let _active = self.activity.begin();
+                let counts = ffi::bridge::count_by_type(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                )?;
+                Ok(counts.into())
+            },
+        )
     }
 
-    /// Query cafes within a bounding box
-    pub fn query_cafes(
+    /// Bounding box of all features matching a GOQL query, computed
+    /// without collecting them (`None` if nothing matches)
+    pub fn extent(
         &self,
+        goql_query: &str,
         bbox: BoundingBox,
-    ) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    ) -> Result<Option<BoundingBox>, GeoDeskError> {
+        self.measure(
+            "extent",
+            |_| None,
+            || {
+                self.check_tiles(bbox)?;
+                let _active = self.activity.begin();
+                let bounds = ffi::bridge::query_bounds(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                )?;
+                Ok(bounds
+                    .iter()
+                    .map(|b| BoundingBox::new(b.west, b.south, b.east, b.north))
+                    .reduce(|a, b| {
+                        BoundingBox::new(
+                            a.west.min(b.west),
+                            a.south.min(b.south),
+                            a.east.max(b.east),
+                            a.north.max(b.north),
+                        )
+                    }))
+            },
+        )
+    }
+
+    /// Query all amenities within a bounding box (any type)
+    pub fn query_all_amenities(&self, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
+        self.query("na[amenity]", bbox)
+    }
+
+    /// Query restaurants within a bounding box
+    pub fn query_restaurants(&self, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
+        self.query_amenities("restaurant", bbox)
+    }
+
+    /// Query cafes within a bounding box
+    pub fn query_cafes(&self, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
         self.query_amenities("cafe", bbox)
     }
 
     /// Query bars and pubs within a bounding box
-    pub fn query_bars(&self, bbox: BoundingBox) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    pub fn query_bars(&self, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
         self.query("na[amenity=bar,pub]", bbox)
     }
 
     /// Query bus stops within a bounding box
-    pub fn query_bus_stops(
-        &self,
-        bbox: BoundingBox,
-    ) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    pub fn query_bus_stops(&self, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
         self.query("na[highway=bus_stop]", bbox)
     }
 
     /// Query roads within a bounding box
-    pub fn query_roads(
-        &self,
-        bbox: BoundingBox,
-    ) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    pub fn query_roads(&self, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
         self.query("w[highway]", bbox)
     }
 }
@@ -346,4 +1487,71 @@ mod tests {
         assert_eq!(bbox.east, 1.0);
         assert_eq!(bbox.north, 1.0);
     }
+
+    #[test]
+    fn test_bounding_box_from_str() {
+        let bbox: BoundingBox = "12.45, 55.61,12.65,55.73".parse().unwrap();
+        assert_eq!(bbox.west, 12.45);
+        assert_eq!(bbox.north, 55.73);
+        assert!("12.45,55.61,12.65".parse::<BoundingBox>().is_err());
+        assert!("a,b,c,d".parse::<BoundingBox>().is_err());
+    }
+
+    #[test]
+    fn test_bounding_box_intersects() {
+        let bbox = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+        assert!(bbox.intersects(&BoundingBox::new(5.0, 5.0, 15.0, 15.0)));
+        assert!(bbox.intersects(&BoundingBox::new(10.0, 0.0, 20.0, 10.0)));
+        assert!(!bbox.intersects(&BoundingBox::new(11.0, 0.0, 20.0, 10.0)));
+    }
+
+    #[test]
+    fn test_feature_type_and_area() {
+        assert_eq!(FeatureType::from("way"), FeatureType::Way);
+        assert_eq!(FeatureType::from("relation").to_string(), "relation");
+        let node = |id| Node {
+            id,
+            lon: 0.0,
+            lat: 0.0,
+        };
+        let mut way = Feature {
+            id: 1,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: vec![("building".to_string(), "yes".to_string())],
+            nodes: vec![node(1), node(2), node(3), node(1)],
+        };
+        assert_eq!(way.feature_type(), FeatureType::Way);
+        assert!(way.is_area());
+        way.tags = vec![("highway".to_string(), "pedestrian".to_string())];
+        assert!(!way.is_area());
+        way.tags.push(("area".to_string(), "yes".to_string()));
+        assert!(way.is_area());
+        way.nodes.pop();
+        assert!(!way.is_area());
+    }
+
+    #[cfg(feature = "test-data")]
+    #[test]
+    fn test_queries_agree() {
+        let Some(geodesk) = test_data::TestGol::try_open() else {
+            return;
+        };
+        let bbox = testing::bboxes::MONACO;
+        let cafes = geodesk.query("na[amenity=cafe]", bbox).unwrap();
+        let features = cafes.to_vec().unwrap();
+        assert!(!features.is_empty());
+        assert_eq!(features.len(), cafes.count());
+        assert!(features.iter().all(|f| f.tag("amenity") == Some("cafe")));
+
+        let counts = geodesk.count_by_type("na[amenity=cafe]", bbox).unwrap();
+        assert_eq!(counts.total(), features.len() as u64);
+        let streamed = geodesk.query_iter("na[amenity=cafe]", bbox).unwrap();
+        assert_eq!(streamed.count(), features.len());
+        let (total, first) = geodesk.query_counted("na[amenity=cafe]", bbox, 3).unwrap();
+        assert_eq!(total, counts.total());
+        assert_eq!(first.len(), features.len().min(3));
+    }
 }
@@ -0,0 +1,184 @@
+// Information about an open GOL file
+
+use crate::{ffi, BoundingBox, GeoDesk, GeoDeskError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Header information and coverage of a GOL file
+#[derive(Debug, Clone)]
+pub struct GolMetadata {
+    /// Union of the bounds of all tiles present in the file
+    ///
+    /// For a planet file this is the whole world; for a regional extract it
+    /// is the area in which queries can return results.
+    pub bounds: BoundingBox,
+    /// Revision number of the data (incremented by each update)
+    pub revision: u32,
+    /// Time of the most recent OSM edit included in the data
+    pub revision_timestamp: SystemTime,
+    /// GOL format version as `major << 16 | minor`
+    pub format_version: u32,
+    /// Number of tiles present in the file
+    pub tile_count: u32,
+    /// Keys with dedicated indexes (queries on these are fastest)
+    pub indexed_keys: Vec<String>,
+    /// Whether features carry their OSM version, timestamp and changeset
+    /// (see [`FeatureRef::version`](crate::refs::FeatureRef::version))
+    pub osm_metadata: bool,
+}
+
+/// The data revision a result was read from
+///
+/// Attached to query results and exports so that caches can tell how
+/// stale their data is, and so that results read from different revisions
+/// aren't merged unknowingly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Watermark {
+    /// Revision number of the data (see [`GolMetadata::revision`])
+    pub revision: u32,
+    /// Time of the most recent OSM edit included in the data
+    pub timestamp: SystemTime,
+}
+
+impl Watermark {
+    /// Check that another result was read from the same revision
+    ///
+    /// Returns [`GeoDeskError::RevisionChanged`] if it wasn't.
+    pub fn ensure_same(&self, other: &Watermark) -> Result<(), GeoDeskError> {
+        if self == other {
+            Ok(())
+        } else {
+            Err(GeoDeskError::RevisionChanged {
+                expected: self.revision,
+                found: other.revision,
+            })
+        }
+    }
+
+    /// Seconds since the Unix epoch of the timestamp
+    pub fn unix_timestamp(&self) -> u64 {
+        self.timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+pub(crate) fn timestamp_from_millis(millis: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis.max(0) as u64)
+}
+
+impl GolMetadata {
+    /// The watermark of the data
+    pub fn watermark(&self) -> Watermark {
+        Watermark {
+            revision: self.revision,
+            timestamp: self.revision_timestamp,
+        }
+    }
+
+    /// GOL format version as a (major, minor) pair
+    pub fn format_version(&self) -> (u16, u16) {
+        (
+            (self.format_version >> 16) as u16,
+            self.format_version as u16,
+        )
+    }
+
+    /// Time elapsed since the data's revision timestamp
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.revision_timestamp)
+            .unwrap_or_default()
+    }
+
+    /// Check if `bbox` lies entirely within the file's coverage
+    pub fn covers(&self, bbox: &BoundingBox) -> bool {
+        bbox.west >= self.bounds.west
+            && bbox.south >= self.bounds.south
+            && bbox.east <= self.bounds.east
+            && bbox.north <= self.bounds.north
+    }
+}
+
+impl From<ffi::bridge::GolMetadata> for GolMetadata {
+    fn from(data: ffi::bridge::GolMetadata) -> Self {
+        GolMetadata {
+            bounds: BoundingBox::new(data.west, data.south, data.east, data.north),
+            revision: data.revision,
+            revision_timestamp: timestamp_from_millis(data.revision_timestamp),
+            format_version: data.format_version,
+            tile_count: data.tile_count,
+            indexed_keys: data.indexed_keys,
+            osm_metadata: data.osm_metadata,
+        }
+    }
+}
+
+impl GeoDesk {
+    /// Read the GOL's header information and tile coverage
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::{GeoDesk, BoundingBox};
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let meta = geodesk.metadata().unwrap();
+    /// println!("Data is {} days old", meta.age().as_secs() / 86400);
+    /// assert!(meta.covers(&BoundingBox::new(12.45, 55.61, 12.65, 55.73)));
+    /// ```
+    pub fn metadata(&self) -> Result<GolMetadata, GeoDeskError> {
+        Ok(ffi::bridge::store_metadata(&self.store)?.into())
+    }
+
+    /// The current revision of the data, as attached to query results
+    pub fn watermark(&self) -> Watermark {
+        Watermark {
+            revision: ffi::bridge::store_revision(&self.store),
+            timestamp: timestamp_from_millis(ffi::bridge::store_revision_timestamp(&self.store)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bridge_data() {
+        let meta: GolMetadata = ffi::bridge::GolMetadata {
+            west: 8.0,
+            south: 54.5,
+            east: 15.2,
+            north: 57.8,
+            revision: 3,
+            revision_timestamp: 1_700_000_000_000,
+            format_version: (2 << 16) | 1,
+            tile_count: 120,
+            indexed_keys: vec!["amenity".to_string()],
+            osm_metadata: false,
+        }
+        .into();
+        assert_eq!(meta.format_version(), (2, 1));
+        assert_eq!(
+            meta.revision_timestamp,
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        assert!(meta.covers(&BoundingBox::new(12.45, 55.61, 12.65, 55.73)));
+        assert!(!meta.covers(&BoundingBox::new(-73.9, 45.4, -73.5, 45.7)));
+
+        let watermark = meta.watermark();
+        assert_eq!(watermark.unix_timestamp(), 1_700_000_000);
+        assert!(watermark.ensure_same(&watermark).is_ok());
+        let newer = Watermark {
+            revision: 4,
+            ..watermark
+        };
+        assert!(matches!(
+            watermark.ensure_same(&newer),
+            Err(GeoDeskError::RevisionChanged {
+                expected: 3,
+                found: 4
+            })
+        ));
+    }
+}
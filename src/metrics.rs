@@ -0,0 +1,204 @@
+// Hooks for exporting query metrics (Prometheus, StatsD, ...)
+
+use crate::error::GeoDeskError;
+use crate::GeoDesk;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Receiver of query metrics, set with [`GeoDesk::with_metrics`]
+///
+/// Every query method reports to it once per call, under the method's name
+/// (`"query"`, `"count_by_type"`, ...). Methods are called on the querying
+/// thread, so implementations should only update counters.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::metrics::Metrics;
+/// use geodesk_rs::GeoDesk;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// struct Log;
+///
+/// impl Metrics for Log {
+///     fn query_executed(&self, operation: &'static str, latency: Duration) {
+///         eprintln!("{} took {:?}", operation, latency);
+///     }
+/// }
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap().with_metrics(Arc::new(Log));
+/// ```
+pub trait Metrics: Send + Sync {
+    /// A query finished, successfully or not
+    fn query_executed(&self, operation: &'static str, latency: Duration);
+
+    /// A query returned `count` features (not called by queries that
+    /// stream or only count)
+    fn features_returned(&self, operation: &'static str, count: u64) {
+        let _ = (operation, count);
+    }
+
+    /// A query failed; `kind` is [`GeoDeskError::kind`]
+    fn query_failed(&self, operation: &'static str, kind: &'static str) {
+        let _ = (operation, kind);
+    }
+}
+
+/// Upper bounds of the latency buckets of [`BasicMetrics`]
+pub const LATENCY_BUCKETS: [Duration; 8] = [
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+];
+
+/// Error kinds counted separately by [`BasicMetrics`]
+const ERROR_KINDS: [&str; 13] = [
+    "invalid_path",
+    "engine",
+    "tiles_missing",
+    "unsupported_version",
+    "revision_changed",
+    "too_many_results",
+    "cancelled",
+    "timed_out",
+    "panicked",
+    "unsupported_crs",
+    "tool_not_found",
+    "metadata_unavailable",
+    "io",
+];
+
+/// Ready-made [`Metrics`] with atomic counters and a latency histogram,
+/// for reading from a metrics endpoint
+#[derive(Debug, Default)]
+pub struct BasicMetrics {
+    queries: AtomicU64,
+    features: AtomicU64,
+    latency_nanos: AtomicU64,
+    // One count per bucket, plus one for slower queries
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    errors: [AtomicU64; ERROR_KINDS.len()],
+}
+
+impl BasicMetrics {
+    /// Queries executed
+    pub fn queries(&self) -> u64 {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    /// Features returned by collecting queries
+    pub fn features(&self) -> u64 {
+        self.features.load(Ordering::Relaxed)
+    }
+
+    /// Time spent in all queries
+    pub fn total_latency(&self) -> Duration {
+        Duration::from_nanos(self.latency_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Cumulative histogram: for each of [`LATENCY_BUCKETS`], the number of
+    /// queries that took at most that long, followed by the count of all
+    /// queries (as Prometheus expects)
+    pub fn latency_histogram(&self) -> Vec<u64> {
+        let mut total = 0;
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                total += bucket.load(Ordering::Relaxed);
+                total
+            })
+            .collect()
+    }
+
+    /// Failed queries by error kind, leaving out kinds that didn't occur
+    pub fn errors(&self) -> Vec<(&'static str, u64)> {
+        ERROR_KINDS
+            .iter()
+            .zip(&self.errors)
+            .map(|(&kind, count)| (kind, count.load(Ordering::Relaxed)))
+            .filter(|&(_, count)| count > 0)
+            .collect()
+    }
+}
+
+impl Metrics for BasicMetrics {
+    fn query_executed(&self, _operation: &'static str, latency: Duration) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        self.latency_nanos.fetch_add(nanos, Ordering::Relaxed);
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| latency <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn features_returned(&self, _operation: &'static str, count: u64) {
+        self.features.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn query_failed(&self, _operation: &'static str, kind: &'static str) {
+        if let Some(i) = ERROR_KINDS.iter().position(|&k| k == kind) {
+            self.errors[i].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl GeoDesk {
+    /// Report query metrics to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Run a query, reporting it to the metrics receiver if there is one;
+    /// `count` gives the number of features returned, if known
+    pub(crate) fn measure<T>(
+        &self,
+        operation: &'static str,
+        count: impl FnOnce(&T) -> Option<u64>,
+        query: impl FnOnce() -> Result<T, GeoDeskError>,
+    ) -> Result<T, GeoDeskError> {
+        let Some(metrics) = &self.metrics else {
+            return query();
+        };
+        let started = Instant::now();
+        let result = query();
+        metrics.query_executed(operation, started.elapsed());
+        match &result {
+            Ok(value) => {
+                if let Some(count) = count(value) {
+                    metrics.features_returned(operation, count);
+                }
+            }
+            Err(e) => metrics.query_failed(operation, e.kind()),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_metrics() {
+        let metrics = BasicMetrics::default();
+        metrics.query_executed("query", Duration::from_micros(500));
+        metrics.query_executed("query", Duration::from_millis(20));
+        metrics.query_executed("query", Duration::from_secs(10));
+        metrics.features_returned("query", 42);
+        metrics.query_failed("query", GeoDeskError::Cancelled.kind());
+
+        assert_eq!(metrics.queries(), 3);
+        assert_eq!(metrics.features(), 42);
+        assert_eq!(metrics.latency_histogram(), vec![1, 1, 1, 2, 2, 2, 2, 2, 3]);
+        assert_eq!(metrics.errors(), vec![("cancelled", 1)]);
+    }
+}
@@ -0,0 +1,228 @@
+// Mapbox Vector Tile (MVT 2.1) encoding of query results
+
+use crate::Feature;
+use std::collections::HashMap;
+
+pub use crate::tiles::TileId;
+
+/// Default tile extent (resolution of the tile-local integer grid)
+pub const DEFAULT_EXTENT: u32 = 4096;
+
+const GEOM_POINT: u32 = 1;
+const GEOM_LINESTRING: u32 = 2;
+
+const CMD_MOVE_TO: u32 = 1;
+const CMD_LINE_TO: u32 = 2;
+
+/// Builds a single-layer vector tile from features
+///
+/// Nodes and relations are encoded as points, ways as linestrings. All tags
+/// are written as string-valued properties.
+pub struct TileEncoder {
+    tile: TileId,
+    extent: u32,
+    layer_name: String,
+    keys: Vec<String>,
+    key_index: HashMap<String, u32>,
+    values: Vec<String>,
+    value_index: HashMap<String, u32>,
+    features: Vec<Vec<u8>>,
+}
+
+impl TileEncoder {
+    /// Create an encoder for a tile with a single named layer
+    pub fn new(tile: TileId, layer_name: &str) -> Self {
+        Self {
+            tile,
+            extent: DEFAULT_EXTENT,
+            layer_name: layer_name.to_string(),
+            keys: Vec::new(),
+            key_index: HashMap::new(),
+            values: Vec::new(),
+            value_index: HashMap::new(),
+            features: Vec::new(),
+        }
+    }
+
+    /// Set the tile extent (default 4096)
+    pub fn with_extent(mut self, extent: u32) -> Self {
+        self.extent = extent;
+        self
+    }
+
+    /// Number of features added so far
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Check if no features have been added
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Add a feature to the layer
+    pub fn add_feature(&mut self, feature: &Feature) {
+        let (geom_type, geometry) = self.encode_geometry(feature);
+        if geometry.is_empty() {
+            return;
+        }
+
+        let mut tags = Vec::with_capacity(feature.tags.len() * 2);
+        for (key, value) in &feature.tags {
+            tags.push(intern(&mut self.keys, &mut self.key_index, key));
+            tags.push(intern(&mut self.values, &mut self.value_index, value));
+        }
+
+        let mut buf = Vec::new();
+        if feature.id >= 0 {
+            write_varint_field(&mut buf, 1, feature.id as u64);
+        }
+        write_packed(&mut buf, 2, &tags);
+        write_varint_field(&mut buf, 3, u64::from(geom_type));
+        write_packed(&mut buf, 4, &geometry);
+        self.features.push(buf);
+    }
+
+    /// Encode the tile as MVT protobuf bytes
+    pub fn encode(self) -> Vec<u8> {
+        let mut layer = Vec::new();
+        write_varint_field(&mut layer, 15, 2);
+        write_bytes_field(&mut layer, 1, self.layer_name.as_bytes());
+        for feature in &self.features {
+            write_bytes_field(&mut layer, 2, feature);
+        }
+        for key in &self.keys {
+            write_bytes_field(&mut layer, 3, key.as_bytes());
+        }
+        for value in &self.values {
+            let mut encoded = Vec::new();
+            write_bytes_field(&mut encoded, 1, value.as_bytes());
+            write_bytes_field(&mut layer, 4, &encoded);
+        }
+        write_varint_field(&mut layer, 5, u64::from(self.extent));
+
+        let mut tile = Vec::new();
+        write_bytes_field(&mut tile, 3, &layer);
+        tile
+    }
+
+    fn encode_geometry(&self, feature: &Feature) -> (u32, Vec<u32>) {
+        let mut points: Vec<(i32, i32)> = if feature.is_way() && feature.nodes.len() > 1 {
+            feature
+                .nodes
+                .iter()
+                .map(|n| self.tile.project(n.lon, n.lat, self.extent))
+                .collect()
+        } else {
+            vec![self.tile.project(feature.lon, feature.lat, self.extent)]
+        };
+        points.dedup();
+
+        let mut geometry = Vec::new();
+        let mut cursor = (0, 0);
+        let mut push = |geometry: &mut Vec<u32>, (x, y): (i32, i32)| {
+            geometry.push(zigzag(x - cursor.0));
+            geometry.push(zigzag(y - cursor.1));
+            cursor = (x, y);
+        };
+
+        if points.len() == 1 {
+            geometry.push(command(CMD_MOVE_TO, 1));
+            push(&mut geometry, points[0]);
+            (GEOM_POINT, geometry)
+        } else {
+            geometry.push(command(CMD_MOVE_TO, 1));
+            push(&mut geometry, points[0]);
+            geometry.push(command(CMD_LINE_TO, points.len() as u32 - 1));
+            for &p in &points[1..] {
+                push(&mut geometry, p);
+            }
+            (GEOM_LINESTRING, geometry)
+        }
+    }
+}
+
+fn intern(table: &mut Vec<String>, index: &mut HashMap<String, u32>, s: &str) -> u32 {
+    if let Some(&i) = index.get(s) {
+        return i;
+    }
+    let i = table.len() as u32;
+    table.push(s.to_string());
+    index.insert(s.to_string(), i);
+    i
+}
+
+fn command(id: u32, count: u32) -> u32 {
+    (id & 0x7) | (count << 3)
+}
+
+fn zigzag(n: i32) -> u32 {
+    ((n << 1) ^ (n >> 31)) as u32
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn write_varint_field(buf: &mut Vec<u8>, field: u32, v: u64) {
+    write_varint(buf, u64::from(field << 3));
+    write_varint(buf, v);
+}
+
+fn write_bytes_field(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_varint(buf, u64::from((field << 3) | 2));
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_packed(buf: &mut Vec<u8>, field: u32, values: &[u32]) {
+    if values.is_empty() {
+        return;
+    }
+    let mut packed = Vec::with_capacity(values.len());
+    for &v in values {
+        write_varint(&mut packed, u64::from(v));
+    }
+    write_bytes_field(buf, field, &packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zigzag_and_commands() {
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+        assert_eq!(command(CMD_MOVE_TO, 1), 9);
+        assert_eq!(command(CMD_LINE_TO, 3), 26);
+    }
+
+    #[test]
+    fn test_encode_point_layer() {
+        let tile = TileId::new(0, 0, 0).unwrap();
+        let mut encoder = TileEncoder::new(tile, "pois");
+        encoder.add_feature(&Feature {
+            id: 7,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: vec![("amenity".to_string(), "cafe".to_string())],
+            nodes: Vec::new(),
+        });
+        assert_eq!(encoder.len(), 1);
+
+        let bytes = encoder.encode();
+        // Tile.layers (field 3, length-delimited), then Layer.version = 2
+        assert_eq!(bytes[0], 0x1a);
+        assert_eq!(&bytes[2..4], &[0x78, 0x02]);
+        assert!(bytes.windows(4).any(|w| w == b"pois"));
+        assert!(bytes.windows(7).any(|w| w == b"amenity"));
+    }
+}
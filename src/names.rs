@@ -0,0 +1,90 @@
+// Latin-script rendering of feature names
+
+use crate::Feature;
+
+impl Feature {
+    /// The feature's name in Latin script
+    ///
+    /// Names already written in Latin script are returned as-is. Otherwise a
+    /// Latin-script name from the tags is preferred (`name:<lang>-Latn`,
+    /// then `int_name`), and only if there is none is `name` transliterated.
+    /// Translations such as `name:en` are not used, since they may be a
+    /// different name rather than a rendering of the local one.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::{GeoDesk, BoundingBox};
+    ///
+    /// let geodesk = GeoDesk::open("japan.gol").unwrap();
+    /// let bbox = BoundingBox::new(139.69, 35.68, 139.71, 35.70);
+    /// for station in geodesk.query("n[railway=station]", bbox).unwrap().to_vec().unwrap() {
+    ///     println!("{:?}", station.name_latin()); // e.g. Some("Shinjuku")
+    /// }
+    /// ```
+    pub fn name_latin(&self) -> Option<String> {
+        if self.name.is_empty() {
+            return None;
+        }
+        if is_latin(&self.name) {
+            return Some(self.name.clone());
+        }
+        let tagged = self
+            .tags
+            .iter()
+            .find(|(k, v)| k.starts_with("name:") && k.ends_with("-Latn") && !v.is_empty())
+            .map(|(_, v)| v.as_str())
+            .or_else(|| self.tag("int_name").filter(|v| is_latin(v)));
+        Some(match tagged {
+            Some(name) => name.to_string(),
+            None => any_ascii::any_ascii(&self.name),
+        })
+    }
+}
+
+/// Check if every letter is from the Latin script
+fn is_latin(s: &str) -> bool {
+    s.chars().filter(|c| c.is_alphabetic()).all(|c| {
+        matches!(c,
+            '\u{0000}'..='\u{024F}'   // Basic Latin to Latin Extended-B
+            | '\u{1E00}'..='\u{1EFF}' // Latin Extended Additional
+            | '\u{2C60}'..='\u{2C7F}' // Latin Extended-C
+            | '\u{A720}'..='\u{A7FF}' // Latin Extended-D
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str, tags: &[(&str, &str)]) -> Feature {
+        Feature {
+            id: 1,
+            type_name: "node".to_string(),
+            name: name.to_string(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_name_latin() {
+        assert_eq!(
+            named("København", &[]).name_latin().as_deref(),
+            Some("København")
+        );
+        assert_eq!(
+            named("新宿", &[("name:ja-Latn", "Shinjuku")])
+                .name_latin()
+                .as_deref(),
+            Some("Shinjuku")
+        );
+        assert_eq!(named("Москва", &[]).name_latin().as_deref(), Some("Moskva"));
+        assert_eq!(named("", &[]).name_latin(), None);
+    }
+}
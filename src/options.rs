@@ -0,0 +1,851 @@
+// Options for opening a GOL file
+
+use crate::cancel::{CancellationToken, QueryControl};
+use crate::error::GeoDeskError;
+use crate::explain::QueryStats;
+use crate::projection::CoordSys;
+use crate::refs::FeatureRef;
+use crate::tiles::MissingTiles;
+use crate::{debug, ffi, BoundingBox, GeoDesk, QueryResult};
+use regex::{Regex, RegexBuilder};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// Major GOL format version this crate's GeoDESK build reads
+pub const SUPPORTED_FORMAT_MAJOR: u16 = 2;
+
+/// Expected pattern of access to the GOL, passed to the OS as a read-ahead hint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccessPattern {
+    /// Let the OS decide
+    #[default]
+    Normal,
+    /// Scattered lookups (small bounding boxes, tile serving); disables read-ahead
+    Random,
+    /// Scans of large areas; enables aggressive read-ahead
+    Sequential,
+}
+
+/// What to do when the GOL's format version differs from the supported one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionCheck {
+    /// Fail with [`GeoDeskError::UnsupportedVersion`]
+    Strict,
+    /// Open anyway and record a message in [`GeoDesk::warnings`]
+    #[default]
+    Warn,
+}
+
+/// How text that is not valid UTF-8 is read from the GOL
+///
+/// OSM data is meant to be UTF-8, but some extracts carry names and tag
+/// values with broken encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Policy {
+    /// Replace invalid sequences with U+FFFD (`�`)
+    #[default]
+    Lossy,
+    /// Fail the query with a [`GeoDeskError::Engine`] naming the feature
+    Strict,
+}
+
+/// Caps on the caches of a store; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Limits {
+    pub(crate) max_cached_admin_tiles: Option<usize>,
+    pub(crate) low_memory: Option<LowMemoryMode>,
+}
+
+/// Settings for devices with little memory (Raspberry Pi and other
+/// embedded deployments reading regional GOLs)
+///
+/// Selected with [`OpenOptions::low_memory`], which switches the store to:
+/// - random access without prefetching, so the OS doesn't read ahead
+/// - no cache of administrative areas ([`GeoDesk::admin_hierarchy`])
+/// - a single thread for parallel operations
+/// - streaming in [`GeoDesk::features`]
+/// - ID-and-handle results in [`GeoDesk::zonal_stats`], which reads only
+///   the location and the aggregated tag of each match
+/// - batches of at most `batch_size` features from [`QueryResult::chunks`]
+///
+/// [`GeoDesk::features`] still yields whole features, as that is what it
+/// returns; list matches with [`GeoDesk::query_refs`] to hold only their
+/// ids and handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowMemoryMode {
+    /// Largest batch [`QueryResult::chunks`] converts at once
+    pub batch_size: usize,
+}
+
+impl Default for LowMemoryMode {
+    fn default() -> Self {
+        Self { batch_size: 1_000 }
+    }
+}
+
+/// Settings for opening a GOL file
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::{GeoDesk, OpenOptions};
+/// use geodesk_rs::options::{AccessPattern, VersionCheck};
+///
+/// let options = OpenOptions::new()
+///     .access(AccessPattern::Random)
+///     .prefetch(true)
+///     .version_check(VersionCheck::Strict);
+/// let geodesk = GeoDesk::open_with("planet.gol", options).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OpenOptions {
+    access: AccessPattern,
+    prefetch: bool,
+    threads: usize,
+    version_check: VersionCheck,
+    utf8: Utf8Policy,
+    pub(crate) missing_tiles: MissingTiles,
+    limits: Limits,
+    #[cfg(feature = "remote")]
+    pub(crate) tile_cache: Option<PathBuf>,
+}
+
+impl OpenOptions {
+    /// Default options, equivalent to [`GeoDesk::open`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hint how the file will be read
+    pub fn access(mut self, access: AccessPattern) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Ask the OS to start loading the whole file into the page cache
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Number of threads operations that run queries in parallel plan
+    /// for (0, the default, uses one per available core); with 1 they run
+    /// on the calling thread
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Fail or warn when the GOL format version is not supported
+    pub fn version_check(mut self, check: VersionCheck) -> Self {
+        self.version_check = check;
+        self
+    }
+
+    /// How names, tag keys and values, and member roles that are not
+    /// valid UTF-8 are read
+    pub fn utf8(mut self, policy: Utf8Policy) -> Self {
+        self.utf8 = policy;
+        self
+    }
+
+    /// How queries treat tiles that are missing from the GOL
+    pub fn missing_tiles(mut self, policy: MissingTiles) -> Self {
+        self.missing_tiles = policy;
+        self
+    }
+
+    /// Limit the number of tiles whose administrative areas are cached by
+    /// [`GeoDesk::admin_hierarchy`]; the cache is emptied when full, and 0
+    /// disables it
+    ///
+    /// This is the only cache of the crate. GeoDESK reads tiles from the
+    /// memory-mapped GOL, leaving them to the OS page cache, and has no
+    /// limit of its own to set (see [`GeoDesk::release_memory`]).
+    pub fn max_cached_admin_tiles(mut self, tiles: usize) -> Self {
+        self.limits.max_cached_admin_tiles = Some(tiles);
+        self
+    }
+
+    /// Keep the tiles of GOLs opened from remote storage in this directory
+    /// instead of `geodesk-remote` in the temporary directory (see
+    /// [`OpenOptions::open_url`])
+    #[cfg(feature = "remote")]
+    pub fn tile_cache<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.tile_cache = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Degrade gracefully on devices with little memory (see
+    /// [`LowMemoryMode`])
+    ///
+    /// This sets the access pattern, prefetching, threads and admin cache
+    /// limit; builder calls made afterwards override them.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::options::LowMemoryMode;
+    /// use geodesk_rs::OpenOptions;
+    ///
+    /// let geodesk = OpenOptions::new()
+    ///     .low_memory(LowMemoryMode::default())
+    ///     .open("bavaria.gol")
+    ///     .unwrap();
+    /// ```
+    pub fn low_memory(mut self, mode: LowMemoryMode) -> Self {
+        self.access = AccessPattern::Random;
+        self.prefetch = false;
+        self.threads = 1;
+        self.limits = Limits {
+            max_cached_admin_tiles: Some(0),
+            low_memory: Some(mode),
+        };
+        self
+    }
+
+    /// Open a GOL file with these options
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<GeoDesk, GeoDeskError> {
+        let path = path.as_ref();
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| GeoDeskError::InvalidPath(path.to_path_buf()))?;
+        let store = ffi::bridge::create_feature_store(path_str, &self.to_bridge())?;
+        let mut geodesk = GeoDesk {
+            store,
+            source: None,
+            #[cfg(feature = "remote")]
+            remote: None,
+            activity: debug::QueryActivity::default(),
+            missing_tiles: self.missing_tiles,
+            complete: OnceLock::new(),
+            threads: self.threads,
+            warnings: Vec::new(),
+            update_lock: RwLock::new(()),
+            admin_cache: Default::default(),
+            limits: self.limits,
+            metrics: None,
+        };
+
+        let found = geodesk.metadata()?.format_version();
+        if found.0 != SUPPORTED_FORMAT_MAJOR {
+            match self.version_check {
+                VersionCheck::Strict => {
+                    return Err(GeoDeskError::UnsupportedVersion {
+                        found,
+                        supported: SUPPORTED_FORMAT_MAJOR,
+                    })
+                }
+                VersionCheck::Warn => geodesk.warnings.push(format!(
+                    "GOL format {}.{} may not be readable (supported: {}.x)",
+                    found.0, found.1, SUPPORTED_FORMAT_MAJOR
+                )),
+            }
+        }
+        Ok(geodesk)
+    }
+
+    /// Open a GOL from a file that is already open, such as one received
+    /// as a handle from another process or unpacked into an anonymous file
+    ///
+    /// The file stays open for as long as the returned [`GeoDesk`]. The
+    /// engine only opens GOLs by path, so it is reached through a symbolic
+    /// link to the descriptor in the temporary directory, removed again on
+    /// drop (Unix only).
+    pub fn open_file(&self, file: File) -> Result<GeoDesk, GeoDeskError> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            // Through the process id rather than /proc/self, so the gol
+            // tool run by GeoDesk::extract can follow the link too
+            let target = if cfg!(target_os = "linux") {
+                format!("/proc/{}/fd/{}", std::process::id(), file.as_raw_fd())
+            } else {
+                format!("/dev/fd/{}", file.as_raw_fd())
+            };
+            let link = std::env::temp_dir().join(format!(
+                "geodesk-{}-{}.gol",
+                std::process::id(),
+                SOURCES.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::os::unix::fs::symlink(target, &link)?;
+            let source = FileSource { _file: file, link };
+            let mut geodesk = self.open(&source.link)?;
+            geodesk.source = Some(source);
+            Ok(geodesk)
+        }
+        #[cfg(not(unix))]
+        {
+            drop(file);
+            Err(GeoDeskError::Engine(
+                "Opening a GOL from a file handle is only supported on Unix".to_string(),
+            ))
+        }
+    }
+
+    /// Open a GOL held in memory, such as one unpacked from an archive or
+    /// received over the network, without writing it to disk
+    ///
+    /// The data is copied into an anonymous in-memory file, which the
+    /// engine maps like any other GOL (Linux only).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::OpenOptions;
+    ///
+    /// let gol: Vec<u8> = std::fs::read("monaco.gol").unwrap();
+    /// let geodesk = OpenOptions::new().open_bytes(&gol).unwrap();
+    /// ```
+    pub fn open_bytes(&self, data: &[u8]) -> Result<GeoDesk, GeoDeskError> {
+        let fd = ffi::bridge::create_memory_file(data)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::FromRawFd;
+
+            // SAFETY: the descriptor was just created and nothing else owns it
+            let file = unsafe { File::from_raw_fd(fd) };
+            self.open_file(file)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = fd;
+            unreachable!("in-memory files are only created on Linux")
+        }
+    }
+
+    fn to_bridge(&self) -> ffi::bridge::StoreOptions {
+        ffi::bridge::StoreOptions {
+            access: match self.access {
+                AccessPattern::Normal => 0,
+                AccessPattern::Random => 1,
+                AccessPattern::Sequential => 2,
+            },
+            prefetch: self.prefetch,
+            strict_utf8: self.utf8 == Utf8Policy::Strict,
+        }
+    }
+}
+
+/// Files opened by [`OpenOptions::open_file`], to keep their links apart
+static SOURCES: AtomicUsize = AtomicUsize::new(0);
+
+/// The file behind a GOL opened from a handle, with the link the engine
+/// opened it through
+pub(crate) struct FileSource {
+    _file: File,
+    link: PathBuf,
+}
+
+impl Drop for FileSource {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.link);
+    }
+}
+
+/// What a query does with features over a size limit of [`QueryOptions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OversizedPolicy {
+    /// Leave the feature out of the result
+    #[default]
+    Skip,
+    /// Keep the feature; ways are cut to the node limit, relations are kept
+    /// as they are (their members are never copied into the result)
+    Truncate,
+}
+
+/// What a query does when it matches more than
+/// [`QueryOptions::max_results`] features
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaxResultsPolicy {
+    /// Fail with [`GeoDeskError::TooManyResults`]
+    #[default]
+    Fail,
+    /// Return the first features, with [`QueryResult::is_truncated`] set
+    Truncate,
+}
+
+/// Per-query guards against pathological features, overly broad queries
+/// and queries that run too long
+///
+/// A handful of features in the planet (coastline and boundary relations
+/// with hundreds of thousands of members, ways with over 100k nodes) can
+/// stall a conversion or an export on their own. Features over a limit are
+/// reported by [`QueryResult::oversized`] whichever the policy.
+///
+/// Services taking GOQL from users should also cap the number of results:
+/// collecting stops as soon as the cap is passed, so a query for `*` over a
+/// continent fails quickly instead of exhausting memory. A timeout or a
+/// [`CancellationToken`] stops a query that is no longer wanted; both are
+/// checked between matching features, every few hundred matches.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::options::{OversizedPolicy, QueryOptions};
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::GeoDesk;
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+/// let options = QueryOptions::new()
+///     .max_way_nodes(100_000)
+///     .max_relation_members(50_000)
+///     .oversized(OversizedPolicy::Truncate)
+///     .max_results(1_000_000);
+/// let result = geodesk
+///     .query_with("w[natural=coastline]", bboxes::COPENHAGEN, &options)
+///     .unwrap();
+/// for feature in result.oversized() {
+///     eprintln!("{} {}: {} nodes", feature.type_name, feature.id, feature.size);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    max_way_nodes: Option<u64>,
+    max_relation_members: Option<u64>,
+    oversized: OversizedPolicy,
+    max_results: Option<u64>,
+    on_max_results: MaxResultsPolicy,
+    timeout: Option<Duration>,
+    cancel: Option<CancellationToken>,
+    collect_stats: bool,
+    coordinate_system: CoordSys,
+    tag_patterns: Vec<(String, String)>,
+    simplify: Option<f64>,
+}
+
+impl QueryOptions {
+    /// No limits, equivalent to [`GeoDesk::query`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of nodes of a way (0 disables the limit)
+    pub fn max_way_nodes(mut self, nodes: u64) -> Self {
+        self.max_way_nodes = Some(nodes);
+        self
+    }
+
+    /// Limit the number of members of a relation (0 disables the limit)
+    pub fn max_relation_members(mut self, members: u64) -> Self {
+        self.max_relation_members = Some(members);
+        self
+    }
+
+    /// Skip or truncate features over a limit
+    pub fn oversized(mut self, policy: OversizedPolicy) -> Self {
+        self.oversized = policy;
+        self
+    }
+
+    /// Limit the number of features a query may collect (0 disables the
+    /// limit)
+    pub fn max_results(mut self, features: u64) -> Self {
+        self.max_results = Some(features);
+        self
+    }
+
+    /// Fail or truncate when a query matches more than
+    /// [`max_results`](Self::max_results) features
+    pub fn on_max_results(mut self, policy: MaxResultsPolicy) -> Self {
+        self.on_max_results = policy;
+        self
+    }
+
+    /// Fail with [`GeoDeskError::TimedOut`] once the query has run this long
+    ///
+    /// With a timeout or a cancellation token, the query area is scanned
+    /// in cells of a zoom-8 tile and checked before each, so sparse scans
+    /// stop promptly too.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail with [`GeoDeskError::Cancelled`] once `token` is cancelled
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Record [`QueryStats`] on the result
+    ///
+    /// Counts and timings are taken while the query runs, at the cost of
+    /// reading the clock around each copy.
+    pub fn collect_stats(mut self, collect: bool) -> Self {
+        self.collect_stats = collect;
+        self
+    }
+
+    /// Return coordinates in this system
+    ///
+    /// With [`CoordSys::WebMercator`], the `lon`/`lat` of each feature and
+    /// its nodes hold EPSG:3857 x/y in meters, projected while the query
+    /// copies them. [`QueryResult::bounds`] and
+    /// [`QueryResult::convex_hull`] stay in degrees.
+    pub fn coordinate_system(mut self, coordinate_system: CoordSys) -> Self {
+        self.coordinate_system = coordinate_system;
+        self
+    }
+
+    /// Only return features with a `key` tag whose value matches `regex`
+    ///
+    /// Patterns are checked in the query loop, so features that don't
+    /// match are never copied; combined with GOQL, this covers matches
+    /// GOQL can't express. All patterns must match. The syntax is that of
+    /// the [`regex`](https://docs.rs/regex) crate, which matches in linear
+    /// time; inline flags such as `(?i)` for case-insensitive matching are
+    /// supported, and others fail. An invalid or overly large pattern
+    /// fails the query with [`GeoDeskError::Engine`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::options::QueryOptions;
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let options = QueryOptions::new().tag_matches("name", "(?i)pizz");
+    /// let pizzerias = geodesk
+    ///     .query_with("na[amenity=restaurant]", bboxes::COPENHAGEN, &options)
+    ///     .unwrap();
+    /// ```
+    pub fn tag_matches(mut self, key: &str, regex: &str) -> Self {
+        self.tag_patterns.push((key.to_string(), regex.to_string()));
+        self
+    }
+
+    /// Simplify way geometries with the Douglas-Peucker algorithm,
+    /// dropping nodes within `tolerance_m` meters of the simplified line
+    ///
+    /// Ways are simplified while the query copies them, so the nodes
+    /// dropped never cross to Rust; the end nodes are always kept, so
+    /// closed ways stay closed. Dropped nodes are gone from the result,
+    /// so don't simplify queries whose nodes are matched by id (as for
+    /// routing). Apply it to rendering at low zoom levels, where a
+    /// tolerance of about a pixel's size is invisible.
+    pub fn simplify(mut self, tolerance_m: f64) -> Self {
+        self.simplify = Some(tolerance_m);
+        self
+    }
+
+    pub(crate) fn to_bridge(&self) -> ffi::bridge::QuerySettings {
+        ffi::bridge::QuerySettings {
+            max_way_nodes: self.max_way_nodes.unwrap_or(0),
+            max_relation_members: self.max_relation_members.unwrap_or(0),
+            truncate_oversized: self.oversized == OversizedPolicy::Truncate,
+            max_results: self.max_results.unwrap_or(0),
+            collect_stats: self.collect_stats,
+            web_mercator: self.coordinate_system == CoordSys::WebMercator,
+            simplify_tolerance: self.simplify.unwrap_or(0.0),
+            match_tags: !self.tag_patterns.is_empty(),
+        }
+    }
+
+    /// The cancellation token and deadline of a query starting now
+    pub(crate) fn control(&self) -> QueryControl {
+        QueryControl::new(
+            self.cancel.clone(),
+            self.timeout.map(|timeout| Instant::now() + timeout),
+        )
+    }
+
+    /// Why `control` stopped a query
+    pub(crate) fn stopped_error(&self, control: &QueryControl) -> GeoDeskError {
+        match self.timeout {
+            Some(timeout) if !control.is_cancelled() => GeoDeskError::TimedOut { timeout },
+            _ => GeoDeskError::Cancelled,
+        }
+    }
+
+    fn compile_patterns(&self) -> Result<Vec<TagPattern>, GeoDeskError> {
+        self.tag_patterns
+            .iter()
+            .map(|(key, pattern)| TagPattern::new(key, pattern))
+            .collect()
+    }
+}
+
+/// Largest compiled size of a tag pattern, so that patterns taken from
+/// requests can't make a query build a huge automaton
+const PATTERN_SIZE_LIMIT: usize = 1 << 20;
+/// Deepest nesting of groups and repetitions in a tag pattern
+const PATTERN_NEST_LIMIT: u32 = 32;
+
+/// A compiled pattern of [`QueryOptions::tag_matches`]
+pub(crate) struct TagPattern {
+    key: String,
+    regex: Regex,
+}
+
+impl TagPattern {
+    fn new(key: &str, pattern: &str) -> Result<Self, GeoDeskError> {
+        let regex = RegexBuilder::new(pattern)
+            .size_limit(PATTERN_SIZE_LIMIT)
+            .nest_limit(PATTERN_NEST_LIMIT)
+            .build()
+            .map_err(|e| GeoDeskError::Engine(format!("Invalid pattern for tag {}: {}", key, e)))?;
+        Ok(Self {
+            key: key.to_string(),
+            regex,
+        })
+    }
+
+    /// Check if the feature has the tag, with a value matching the pattern
+    pub(crate) fn matches(&self, feature: &FeatureRef<'_>) -> bool {
+        matches!(feature.tag(&self.key), Ok(Some(value)) if self.regex.is_match(&value))
+    }
+}
+
+/// A feature that exceeded a limit of [`QueryOptions`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OversizedFeature {
+    pub id: i64,
+    /// `"way"` or `"relation"`
+    pub type_name: String,
+    /// Number of nodes of a way, or members of a relation
+    pub size: u64,
+    /// Whether the feature was kept in the result (cut to the limit)
+    /// rather than skipped
+    pub truncated: bool,
+}
+
+impl From<ffi::bridge::OversizedFeature> for OversizedFeature {
+    fn from(data: ffi::bridge::OversizedFeature) -> Self {
+        Self {
+            id: data.id,
+            type_name: data.type_name,
+            size: data.size,
+            truncated: data.truncated,
+        }
+    }
+}
+
+impl GeoDesk {
+    /// Open a GOL file with the given options
+    pub fn open_with<P: AsRef<Path>>(path: P, options: OpenOptions) -> Result<Self, GeoDeskError> {
+        options.open(path)
+    }
+
+    /// Query features using GOQL, with limits on the size and number of
+    /// the features collected (see [`QueryOptions`])
+    pub fn query_with(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        options: &QueryOptions,
+    ) -> Result<QueryResult, GeoDeskError> {
+        self.measure(
+            "query_with",
+            |r: &QueryResult| Some(r.count() as u64),
+            || {
+                let started = Instant::now();
+                let missing_tiles = self.check_tiles(bbox)?;
+                let tile_check = started.elapsed();
+                let watermark = self.watermark();
+                let control = options.control().with_patterns(options.compile_patterns()?);
+                let _active = self.activity.begin();
+                let result = ffi::bridge::query_with_settings(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                    &options.to_bridge(),
+                    &control,
+                )?;
+                if ffi::bridge::result_stopped(&result) {
+                    return Err(options.stopped_error(&control));
+                }
+                if options.on_max_results == MaxResultsPolicy::Fail
+                    && ffi::bridge::result_truncated(&result)
+                {
+                    return Err(GeoDeskError::TooManyResults {
+                        limit: options.max_results.unwrap_or(0),
+                    });
+                }
+                let stats = if options.collect_stats {
+                    let data = ffi::bridge::result_stats(&result);
+                    Some(QueryStats {
+                        tiles_scanned: data.tiles as usize,
+                        features_examined: data.examined,
+                        features_matched: data.matched,
+                        tile_check,
+                        matching: Duration::from_nanos(data.matching_nanos),
+                        copying: Duration::from_nanos(data.copying_nanos),
+                    })
+                } else {
+                    None
+                };
+                let mut result = QueryResult::new(result, missing_tiles, watermark);
+                result.stats = stats;
+                result.max_batch = self.limits.low_memory.map(|mode| mode.batch_size);
+                Ok(result)
+            },
+        )
+    }
+
+    /// The low-memory settings the GOL was opened with, if any
+    pub fn low_memory(&self) -> Option<LowMemoryMode> {
+        self.limits.low_memory
+    }
+
+    /// Number of threads parallel operations use
+    pub fn threads(&self) -> usize {
+        if self.threads > 0 {
+            self.threads
+        } else {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        }
+    }
+
+    /// Problems found while opening the file that did not prevent it
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Ask the OS to drop the GOL's pages from the page cache
+    ///
+    /// Returns `false` where this is not supported (only Linux is). Pages
+    /// that GeoDESK's memory mapping still references stay resident, so
+    /// this frees memory between bursts of queries, such as after
+    /// streaming a large area with [`GeoDesk::query_iter`], rather than
+    /// bounding what a query uses.
+    pub fn release_memory(&self) -> bool {
+        ffi::bridge::store_release_memory(&self.store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_maps_to_bridge_options() {
+        let options = OpenOptions::new()
+            .access(AccessPattern::Sequential)
+            .prefetch(true)
+            .threads(4)
+            .utf8(Utf8Policy::Strict);
+        let bridge = options.to_bridge();
+        assert_eq!(bridge.access, 2);
+        assert!(bridge.prefetch);
+        assert!(bridge.strict_utf8);
+        assert_eq!(options.threads, 4);
+        assert_eq!(OpenOptions::new().to_bridge().access, 0);
+        assert!(!OpenOptions::new().to_bridge().strict_utf8);
+    }
+
+    #[test]
+    fn test_query_options_map_to_bridge_settings() {
+        let settings = QueryOptions::new()
+            .max_way_nodes(100_000)
+            .max_relation_members(50_000)
+            .oversized(OversizedPolicy::Truncate)
+            .max_results(1_000)
+            .coordinate_system(CoordSys::WebMercator)
+            .tag_matches("name", "(?i)^pizz")
+            .simplify(2.5)
+            .to_bridge();
+        assert_eq!(settings.max_way_nodes, 100_000);
+        assert_eq!(settings.max_relation_members, 50_000);
+        assert!(settings.truncate_oversized);
+        assert_eq!(settings.max_results, 1_000);
+        assert!(settings.web_mercator);
+        assert!(settings.match_tags);
+        assert_eq!(settings.simplify_tolerance, 2.5);
+
+        let unlimited = QueryOptions::new().to_bridge();
+        assert_eq!(unlimited.max_way_nodes, 0);
+        assert_eq!(unlimited.max_relation_members, 0);
+        assert!(!unlimited.truncate_oversized);
+        assert_eq!(unlimited.max_results, 0);
+        assert!(!unlimited.web_mercator);
+        assert!(!unlimited.match_tags);
+        assert_eq!(unlimited.simplify_tolerance, 0.0);
+    }
+
+    #[test]
+    fn test_tag_patterns() {
+        let pizza = TagPattern::new("name", "(?i)^pizz").unwrap();
+        assert!(pizza.regex.is_match("Pizzeria Luca"));
+        assert!(!pizza.regex.is_match("Bar Pizza"));
+
+        let options = QueryOptions::new()
+            .tag_matches("name", "(?i)pizz")
+            .tag_matches("cuisine", "^(pizza|italian)$");
+        assert_eq!(options.compile_patterns().unwrap().len(), 2);
+        // Unsupported flags, bad syntax and runaway sizes are rejected
+        for pattern in [
+            "(?z)pizz",
+            "(pizz",
+            "(?<!pi)zz",
+            "(((((a{1000}){1000}){1000})))",
+        ] {
+            let result = QueryOptions::new()
+                .tag_matches("name", pattern)
+                .compile_patterns();
+            assert!(
+                matches!(result, Err(GeoDeskError::Engine(_))),
+                "{}",
+                pattern
+            );
+        }
+        let nested = format!("{}a{}", "(".repeat(100), ")".repeat(100));
+        assert!(TagPattern::new("name", &nested).is_err());
+    }
+
+    #[cfg(feature = "test-data")]
+    #[test]
+    fn test_web_mercator_bounds() {
+        let Some(geodesk) = crate::test_data::TestGol::try_open() else {
+            return;
+        };
+        let monaco = crate::testing::bboxes::MONACO;
+        let options = QueryOptions::new().coordinate_system(CoordSys::WebMercator);
+        let result = geodesk.query_with("na[amenity]", monaco, &options).unwrap();
+        let bounds = result.bounds().expect("amenities in Monaco");
+        // Degrees near Monaco, not meters (x would be some 820,000)
+        assert!((7.0..8.0).contains(&bounds.west) && (7.0..8.0).contains(&bounds.east));
+        assert!((43.0..44.0).contains(&bounds.south) && (43.0..44.0).contains(&bounds.north));
+        for (lon, lat) in result.convex_hull() {
+            assert!((7.0..8.0).contains(&lon) && (43.0..44.0).contains(&lat));
+        }
+        let first = result.get(0).unwrap().unwrap();
+        assert!(first.lon > 800_000.0);
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = OpenOptions::new().max_cached_admin_tiles(256);
+        assert_eq!(options.limits.max_cached_admin_tiles, Some(256));
+        assert!(OpenOptions::new().limits.max_cached_admin_tiles.is_none());
+    }
+
+    #[test]
+    fn test_low_memory_mode() {
+        let options = OpenOptions::new()
+            .prefetch(true)
+            .low_memory(LowMemoryMode::default())
+            .threads(2);
+        let bridge = options.to_bridge();
+        assert_eq!(bridge.access, 1);
+        assert!(!bridge.prefetch);
+        assert_eq!(options.threads, 2);
+        assert_eq!(options.limits.max_cached_admin_tiles, Some(0));
+        assert_eq!(options.limits.low_memory.map(|m| m.batch_size), Some(1_000));
+    }
+
+    #[test]
+    fn test_unsupported_version_message() {
+        let error = GeoDeskError::UnsupportedVersion {
+            found: (3, 0),
+            supported: 2,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Unsupported GOL format version 3.0 (supported: 2.x)"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+// Queries split across threads
+
+use crate::error::GeoDeskError;
+use crate::provenance::Identity;
+use crate::tiled::grid;
+use crate::{ffi, BoundingBox, Feature, GeoDesk, QueryResult};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Mutex, PoisonError};
+
+/// Cells per thread, so threads that finish early can take on more work
+const CELLS_PER_THREAD: usize = 4;
+
+impl GeoDesk {
+    /// Query a large area on several threads
+    ///
+    /// The bounding box is split into a grid of cells, sized for
+    /// [`GeoDesk::threads`] threads, that are queried in parallel on the
+    /// current rayon pool: the global one, or a pool of your own when
+    /// called inside [`rayon::ThreadPool::install`]. With a single thread
+    /// the cells are queried on the calling thread. Features spanning
+    /// several cells are returned once, by type and id; each is converted
+    /// only by the first cell that finds it. The order of the features
+    /// differs from that of [`GeoDesk::query`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("planet.gol").unwrap();
+    /// let pool = rayon::ThreadPoolBuilder::new().num_threads(8).build().unwrap();
+    /// let buildings = pool
+    ///     .install(|| geodesk.query_par("a[building]", bboxes::WORLD))
+    ///     .unwrap();
+    /// println!("{} buildings", buildings.len());
+    /// ```
+    pub fn query_par(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+    ) -> Result<Vec<Feature>, GeoDeskError> {
+        self.query_par_with(goql_query, bbox, Identity::default())
+    }
+
+    /// [`GeoDesk::query_par`], returning features spanning several cells
+    /// once by another [`Identity`]
+    ///
+    /// Deduplicating by [`Identity::Geometry`] converts every cell's
+    /// features first, to compare them.
+    pub fn query_par_with(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        identity: Identity,
+    ) -> Result<Vec<Feature>, GeoDeskError> {
+        self.measure(
+            "query_par",
+            |r: &Vec<Feature>| Some(r.len() as u64),
+            || {
+                self.check_tiles(bbox)?;
+                let watermark = self.watermark();
+                let threads = self.threads();
+                let side = ((threads * CELLS_PER_THREAD) as f64).sqrt().ceil() as usize;
+                let cells = grid(bbox, side, side);
+                let seen = Mutex::new(HashSet::new());
+                let query_cell = |cell: BoundingBox| -> Result<Vec<Feature>, GeoDeskError> {
+                    let _active = self.activity.begin();
+                    let result = ffi::bridge::query_with_goql(
+                        &self.store,
+                        goql_query,
+                        cell.west,
+                        cell.south,
+                        cell.east,
+                        cell.north,
+                    )?;
+                    let keys: Option<Vec<_>> = ffi::bridge::result_keys(&result)
+                        .iter()
+                        .map(|key| identity.key_of(key))
+                        .collect();
+                    let Some(keys) = keys else {
+                        let features = QueryResult::new(result, Vec::new(), watermark).to_vec()?;
+                        let mut seen = seen.lock().unwrap_or_else(PoisonError::into_inner);
+                        return Ok(features
+                            .into_iter()
+                            .filter(|f| seen.insert(identity.key(f)))
+                            .collect());
+                    };
+                    // Claim the features no other cell has returned, and
+                    // convert only those
+                    let indices: Vec<usize> = {
+                        let mut seen = seen.lock().unwrap_or_else(PoisonError::into_inner);
+                        (0..keys.len()).filter(|&i| seen.insert(keys[i])).collect()
+                    };
+                    if indices.is_empty() {
+                        return Ok(Vec::new());
+                    }
+                    let selected = ffi::bridge::result_select(&result, &indices)?;
+                    Ok(selected.iter().map(|f| Feature::from(f.clone())).collect())
+                };
+                let results: Vec<Vec<Feature>> = if threads == 1 {
+                    cells
+                        .into_iter()
+                        .map(query_cell)
+                        .collect::<Result<_, _>>()?
+                } else {
+                    cells
+                        .into_par_iter()
+                        .map(query_cell)
+                        .collect::<Result<_, _>>()?
+                };
+                Ok(results.into_iter().flatten().collect())
+            },
+        )
+    }
+}
+
+#[cfg(all(test, feature = "test-data"))]
+mod tests {
+    #[test]
+    fn test_query_par_matches_query() {
+        use crate::testing::bboxes;
+        let Some(geodesk) = crate::test_data::TestGol::try_open() else {
+            return;
+        };
+        let roads = geodesk.query("w[highway]", bboxes::MONACO).unwrap();
+        let parallel = geodesk.query_par("w[highway]", bboxes::MONACO).unwrap();
+        assert_eq!(parallel.len(), roads.count());
+    }
+}
@@ -0,0 +1,482 @@
+// Offline places API: name search and nearby points of interest over HTTP
+//
+// Enabled with the `places` cargo feature.
+
+pub use crate::categories::Category;
+use crate::error::GeoDeskError;
+use crate::export::write_json_string;
+use crate::projection::haversine;
+use crate::{BoundingBox, Feature, GeoDesk};
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// The categories served by `/nearby` and reported by `/search`, in the
+/// order they are matched
+pub const CATEGORIES: &[Category] = Category::ALL;
+
+/// Lowercase words of a name or search term
+fn words(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// In-memory index finding places by the beginnings of the words of their
+/// names
+///
+/// # Example
+/// ```
+/// use geodesk_rs::places::NameIndex;
+/// use geodesk_rs::Feature;
+///
+/// let place = |id, name: &str| Feature {
+///     id,
+///     type_name: "node".to_string(),
+///     name: name.to_string(),
+///     lon: 12.57,
+///     lat: 55.68,
+///     tags: Vec::new(),
+///     nodes: Vec::new(),
+/// };
+/// let index = NameIndex::new(vec![place(1, "Café Norden"), place(2, "Nordisk Film")]);
+/// let found: Vec<i64> = index.search("nord", 10).iter().map(|f| f.id).collect();
+/// assert_eq!(found, vec![2, 1]);
+/// assert_eq!(index.search("café nor", 10)[0].id, 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct NameIndex {
+    places: Vec<Feature>,
+    // Each word of each name with the index of its place, sorted
+    words: Vec<(String, usize)>,
+}
+
+impl NameIndex {
+    /// Index the names of places; places without a name are left out
+    pub fn new(places: Vec<Feature>) -> Self {
+        let places: Vec<Feature> = places.into_iter().filter(|p| !p.name.is_empty()).collect();
+        let mut index_words: Vec<(String, usize)> = places
+            .iter()
+            .enumerate()
+            .flat_map(|(i, place)| words(&place.name).into_iter().map(move |w| (w, i)))
+            .collect();
+        index_words.sort();
+        index_words.dedup();
+        Self {
+            places,
+            words: index_words,
+        }
+    }
+
+    /// Number of places indexed
+    pub fn len(&self) -> usize {
+        self.places.len()
+    }
+
+    /// Check if no places are indexed
+    pub fn is_empty(&self) -> bool {
+        self.places.is_empty()
+    }
+
+    /// Places with a name word starting with each word of `query`
+    ///
+    /// Names equal to the query come first, then names starting with it,
+    /// then shorter names before longer ones.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Feature> {
+        let terms = words(query);
+        let Some(first) = terms.first() else {
+            return Vec::new();
+        };
+        let start = self.words.partition_point(|(w, _)| w < first);
+        let mut candidates: Vec<usize> = self.words[start..]
+            .iter()
+            .take_while(|(w, _)| w.starts_with(first.as_str()))
+            .map(|&(_, i)| i)
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let query = terms.join(" ");
+        let mut matches: Vec<(u8, usize, &Feature)> = candidates
+            .into_iter()
+            .filter_map(|i| {
+                let place = &self.places[i];
+                let name_words = words(&place.name);
+                let all = terms[1..]
+                    .iter()
+                    .all(|t| name_words.iter().any(|w| w.starts_with(t.as_str())));
+                if !all {
+                    return None;
+                }
+                let name = name_words.join(" ");
+                let rank = if name == query {
+                    0
+                } else if name.starts_with(&query) {
+                    1
+                } else {
+                    2
+                };
+                Some((rank, place.name.len(), place))
+            })
+            .collect();
+        matches.sort_by_key(|&(rank, len, place)| (rank, len, place.id));
+        matches.truncate(limit);
+        matches.into_iter().map(|(_, _, place)| place).collect()
+    }
+}
+
+/// Configuration of the places API
+#[derive(Debug, Clone)]
+pub struct PlacesConfig {
+    /// Address to listen on
+    pub addr: SocketAddr,
+    /// GOQL selecting the places that can be found by name
+    pub search_query: String,
+    /// Most places a response lists
+    pub max_results: usize,
+    /// Largest radius `/nearby` searches, in meters
+    pub max_radius: f64,
+}
+
+impl Default for PlacesConfig {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            search_query: "na[amenity][name], na[shop][name], na[tourism][name], \
+                           na[leisure][name], n[place][name]"
+                .to_string(),
+            max_results: 50,
+            max_radius: 10_000.0,
+        }
+    }
+}
+
+struct PlacesState {
+    geodesk: Arc<GeoDesk>,
+    index: NameIndex,
+    config: PlacesConfig,
+}
+
+/// Find places within `radius` meters of a point, nearest first
+///
+/// The search starts small and widens until `limit` places are found or
+/// the radius is reached, so dense city centers don't read more of the GOL
+/// than needed.
+pub fn nearby(
+    geodesk: &GeoDesk,
+    goql_query: &str,
+    lon: f64,
+    lat: f64,
+    radius: f64,
+    limit: usize,
+) -> Result<Vec<(Feature, f64)>, GeoDeskError> {
+    let mut search = radius.min(250.0);
+    loop {
+        let bbox = BoundingBox::around(lon, lat, search);
+        let mut found: Vec<(Feature, f64)> = geodesk
+            .query(goql_query, bbox)?
+            .to_vec()?
+            .into_iter()
+            .map(|f| {
+                let distance = haversine(lon, lat, f.lon, f.lat);
+                (f, distance)
+            })
+            .filter(|(_, distance)| *distance <= search)
+            .collect();
+        if found.len() >= limit || search >= radius {
+            found.sort_by(|a, b| a.1.total_cmp(&b.1));
+            found.truncate(limit);
+            return Ok(found);
+        }
+        search = (search * 2.0).min(radius);
+    }
+}
+
+/// Build the places index and the router serving `/search`, `/nearby`
+/// and `/categories`
+///
+/// - `GET /search?q=...[&lat=..&lon=..][&limit=n]` finds places by name,
+///   nearest first if a location is given
+/// - `GET /nearby?lat=..&lon=..&category=food[&radius=m][&limit=n]` lists
+///   the places of a category around a point, nearest first
+/// - `GET /categories` lists the category names
+///
+/// Responses are JSON objects with a `places` array of `id`, `type`,
+/// `name`, `category`, `lon`, `lat` and, for located requests, `distance`
+/// in meters. Indexing reads every place of `search_query` in the GOL, so
+/// this takes a while for large files.
+pub fn router(geodesk: Arc<GeoDesk>, config: PlacesConfig) -> Result<Router, GeoDeskError> {
+    let bounds = geodesk.metadata()?.bounds;
+    let mut places = Vec::new();
+    for place in geodesk.features(&config.search_query, bounds)? {
+        let mut place = place?;
+        // Only the location of areas is used
+        place.nodes = Vec::new();
+        places.push(place);
+    }
+    let state = Arc::new(PlacesState {
+        geodesk,
+        index: NameIndex::new(places),
+        config,
+    });
+    Ok(Router::new()
+        .route("/search", get(search_handler))
+        .route("/nearby", get(nearby_handler))
+        .route("/categories", get(categories_handler))
+        .with_state(state))
+}
+
+/// Index a GOL and serve the places API on [`PlacesConfig::addr`] until the
+/// process is stopped
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::places::{serve, PlacesConfig};
+/// use geodesk_rs::GeoDesk;
+/// use std::sync::Arc;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let gol = Arc::new(GeoDesk::open("denmark.gol").unwrap());
+/// serve(gol, PlacesConfig::default()).await
+/// # }
+/// ```
+pub async fn serve(gol: Arc<GeoDesk>, config: PlacesConfig) -> io::Result<()> {
+    let addr = config.addr;
+    let router = tokio::task::spawn_blocking(move || router(gol, config))
+        .await
+        .map_err(io::Error::other)?
+        .map_err(io::Error::other)?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router).await
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, message.into()).into_response()
+}
+
+/// Parse an optional numeric parameter
+fn param<T: std::str::FromStr>(
+    params: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>, String> {
+    params
+        .get(name)
+        .map(|v| v.parse())
+        .transpose()
+        .map_err(|_| format!("Invalid {} parameter", name))
+}
+
+fn write_places<W: Write>(out: &mut W, places: &[(&Feature, Option<f64>)]) -> io::Result<()> {
+    out.write_all(b"{\"places\":[")?;
+    for (i, (place, distance)) in places.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write!(
+            out,
+            "{{\"id\":{},\"type\":\"{}\",\"name\":",
+            place.id, place.type_name
+        )?;
+        write_json_string(out, &place.name)?;
+        out.write_all(b",\"category\":")?;
+        match Category::of(place) {
+            Some(category) => write_json_string(out, category.name())?,
+            None => out.write_all(b"null")?,
+        }
+        write!(out, ",\"lon\":{},\"lat\":{}", place.lon, place.lat)?;
+        if let Some(distance) = distance {
+            write!(out, ",\"distance\":{:.1}", distance)?;
+        }
+        out.write_all(b"}")?;
+    }
+    out.write_all(b"]}")
+}
+
+fn json(places: &[(&Feature, Option<f64>)]) -> Response {
+    let mut out = Vec::new();
+    write_places(&mut out, places).expect("writing to a Vec cannot fail");
+    ([(header::CONTENT_TYPE, "application/json")], out).into_response()
+}
+
+/// The `lon` and `lat` parameters, if both are given
+fn location(params: &HashMap<String, String>) -> Result<Option<(f64, f64)>, String> {
+    match (param(params, "lon")?, param(params, "lat")?) {
+        (Some(lon), Some(lat)) => Ok(Some((lon, lat))),
+        (None, None) => Ok(None),
+        _ => Err("Expected both lon and lat".to_string()),
+    }
+}
+
+async fn search_handler(
+    State(state): State<Arc<PlacesState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(q) = params.get("q") else {
+        return error(StatusCode::BAD_REQUEST, "Missing q parameter");
+    };
+    let (limit, location) =
+        match param::<usize>(&params, "limit").and_then(|limit| Ok((limit, location(&params)?))) {
+            Ok(parsed) => parsed,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e),
+        };
+    let limit = limit
+        .unwrap_or(state.config.max_results)
+        .min(state.config.max_results);
+
+    let places: Vec<(&Feature, Option<f64>)> = match location {
+        Some((lon, lat)) => {
+            // Rank all matches by distance rather than by name
+            let mut found: Vec<(&Feature, Option<f64>)> = state
+                .index
+                .search(q, usize::MAX)
+                .into_iter()
+                .map(|f| (f, Some(haversine(lon, lat, f.lon, f.lat))))
+                .collect();
+            found.sort_by(|a, b| a.1.unwrap_or(0.0).total_cmp(&b.1.unwrap_or(0.0)));
+            found.truncate(limit);
+            found
+        }
+        None => state
+            .index
+            .search(q, limit)
+            .into_iter()
+            .map(|f| (f, None))
+            .collect(),
+    };
+    json(&places)
+}
+
+async fn nearby_handler(
+    State(state): State<Arc<PlacesState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(category) = params.get("category").map(|c| Category::by_name(c)) else {
+        return error(StatusCode::BAD_REQUEST, "Missing category parameter");
+    };
+    let Some(category) = category else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Unknown category (see /categories)",
+        );
+    };
+    let (lon, lat) = match location(&params) {
+        Ok(Some(location)) => location,
+        Ok(None) => return error(StatusCode::BAD_REQUEST, "Missing lon and lat parameters"),
+        Err(e) => return error(StatusCode::BAD_REQUEST, e),
+    };
+    let (radius, limit) = match param::<f64>(&params, "radius")
+        .and_then(|radius| Ok((radius, param::<usize>(&params, "limit")?)))
+    {
+        Ok(parsed) => parsed,
+        Err(e) => return error(StatusCode::BAD_REQUEST, e),
+    };
+    let radius = radius
+        .unwrap_or(1_000.0)
+        .clamp(0.0, state.config.max_radius);
+    let limit = limit
+        .unwrap_or(state.config.max_results)
+        .min(state.config.max_results);
+
+    let goql = category.goql();
+    let geodesk = state.geodesk.clone();
+    let found =
+        tokio::task::spawn_blocking(move || nearby(&geodesk, &goql, lon, lat, radius, limit)).await;
+    match found {
+        Ok(Ok(found)) => {
+            let places: Vec<(&Feature, Option<f64>)> =
+                found.iter().map(|(f, d)| (f, Some(*d))).collect();
+            json(&places)
+        }
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn categories_handler() -> Response {
+    let mut out = Vec::new();
+    out.push(b'[');
+    for (i, category) in CATEGORIES.iter().enumerate() {
+        if i > 0 {
+            out.push(b',');
+        }
+        write_json_string(&mut out, category.name()).expect("writing to a Vec cannot fail");
+    }
+    out.push(b']');
+    ([(header::CONTENT_TYPE, "application/json")], out).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(id: i64, name: &str, tags: &[(&str, &str)]) -> Feature {
+        Feature {
+            id,
+            type_name: "node".to_string(),
+            name: name.to_string(),
+            lon: 12.57,
+            lat: 55.68,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_categories() {
+        let cafe = place(1, "Kaffe", &[("amenity", "cafe")]);
+        let bakery = place(2, "Brød", &[("shop", "bakery")]);
+        let bench = place(3, "", &[("amenity", "bench")]);
+        assert_eq!(Category::of(&cafe).map(|c| c.name()), Some("food"));
+        assert_eq!(Category::of(&bakery).map(|c| c.name()), Some("shopping"));
+        assert_eq!(Category::of(&bench), None);
+        assert_eq!(Category::by_name("Shopping").unwrap().goql(), "na[shop]");
+        assert_eq!(
+            Category::by_name("money").unwrap().goql(),
+            "na[amenity=bank,atm,bureau_de_change]"
+        );
+        assert_eq!(
+            Category::by_name("transport").unwrap().goql(),
+            "na[amenity=fuel,parking,bicycle_rental,car_rental], \
+             na[railway=station,halt], na[highway=bus_stop]"
+        );
+    }
+
+    #[test]
+    fn test_name_index() {
+        let index = NameIndex::new(vec![
+            place(1, "Central Station Café", &[]),
+            place(2, "Central", &[]),
+            place(3, "Station Hotel", &[]),
+            place(4, "", &[]),
+            place(5, "Centralværkstedet", &[]),
+        ]);
+        assert_eq!(index.len(), 4);
+        let ids =
+            |query: &str| -> Vec<i64> { index.search(query, 10).iter().map(|f| f.id).collect() };
+        assert_eq!(ids("central"), vec![2, 5, 1]);
+        assert_eq!(ids("STATION"), vec![3, 1]);
+        assert_eq!(ids("central sta"), vec![1]);
+        assert_eq!(ids("cafe"), Vec::<i64>::new());
+        assert!(ids("  ").is_empty());
+        assert_eq!(index.search("central", 1).len(), 1);
+
+        let mut json = Vec::new();
+        let found = index.search("hotel", 1);
+        write_places(&mut json, &[(found[0], Some(12.34))]).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"places\":[{\"id\":3,\"type\":\"node\",\"name\":\"Station Hotel\",\
+             \"category\":null,\"lon\":12.57,\"lat\":55.68,\"distance\":12.3}]}"
+        );
+    }
+}
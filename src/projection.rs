@@ -0,0 +1,166 @@
+// Conversions between WGS-84 coordinates, GeoDESK's projection and
+// Web Mercator
+
+use crate::{Feature, Node};
+use std::f64::consts::PI;
+
+/// Width of GeoDESK's Mercator plane in imps (its integer units), spanning
+/// the full range of an `i32`
+pub const MAP_WIDTH: f64 = 4_294_967_294.999_9;
+
+/// Latitudes beyond this are clamped, as the Mercator plane is square
+pub const MAX_LAT: f64 = 85.051_128_779_8;
+
+/// Radius of the sphere Web Mercator (EPSG:3857) projects from, in meters
+pub const WEB_MERCATOR_RADIUS: f64 = 6_378_137.0;
+
+/// Mean Earth radius used for distances, in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Meters per degree of latitude
+pub const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Great-circle distance between two WGS84 coordinates, in meters
+pub fn haversine(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dphi = phi2 - phi1;
+    let dlambda = (lon2 - lon1).to_radians();
+    let a = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Coordinate system of the features a query returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordSys {
+    /// Longitude and latitude in degrees (EPSG:4326)
+    #[default]
+    Wgs84,
+    /// Web Mercator x and y in meters (EPSG:3857), as used by tile renderers
+    WebMercator,
+}
+
+/// Project a longitude and latitude to GeoDESK's imp coordinates
+pub fn to_imps(lon: f64, lat: f64) -> (i32, i32) {
+    let lat = lat.clamp(-MAX_LAT, MAX_LAT);
+    let x = (MAP_WIDTH * lon / 360.0).round();
+    let y = ((lat + 90.0) * PI / 360.0).tan().ln() * MAP_WIDTH / (2.0 * PI);
+    (x as i32, y.round() as i32)
+}
+
+/// Longitude and latitude of GeoDESK imp coordinates
+pub fn from_imps(x: i32, y: i32) -> (f64, f64) {
+    let lon = x as f64 * 360.0 / MAP_WIDTH;
+    let lat = (2.0 * (y as f64 * 2.0 * PI / MAP_WIDTH).exp().atan() - PI / 2.0).to_degrees();
+    (lon, lat)
+}
+
+/// Project a longitude and latitude to Web Mercator x and y in meters
+pub fn to_web_mercator(lon: f64, lat: f64) -> (f64, f64) {
+    let lat = lat.clamp(-MAX_LAT, MAX_LAT);
+    let x = lon.to_radians() * WEB_MERCATOR_RADIUS;
+    let y = (PI / 4.0 + lat.to_radians() / 2.0).tan().ln() * WEB_MERCATOR_RADIUS;
+    (x, y)
+}
+
+/// Longitude and latitude of Web Mercator x and y in meters
+pub fn from_web_mercator(x: f64, y: f64) -> (f64, f64) {
+    let lon = (x / WEB_MERCATOR_RADIUS).to_degrees();
+    let lat = (2.0 * (y / WEB_MERCATOR_RADIUS).exp().atan() - PI / 2.0).to_degrees();
+    (lon, lat)
+}
+
+impl Feature {
+    /// The feature's location in GeoDESK's native imp coordinates
+    ///
+    /// The GOL stores coordinates as these integers, and `lon`/`lat` are
+    /// converted from them, so projecting back is exact. Compare features
+    /// by `xy()` rather than by floating-point degrees, and pass it on
+    /// unchanged to tools that work in imps.
+    pub fn xy(&self) -> (i32, i32) {
+        to_imps(self.lon, self.lat)
+    }
+
+    /// A copy of the feature with its location and nodes in Web Mercator
+    ///
+    /// `lon` and `lat` of the copy hold x and y in meters. To have a query
+    /// return features projected already, use
+    /// [`QueryOptions::coordinate_system`](crate::options::QueryOptions::coordinate_system).
+    pub fn to_mercator(&self) -> Feature {
+        let (x, y) = to_web_mercator(self.lon, self.lat);
+        Feature {
+            lon: x,
+            lat: y,
+            nodes: self
+                .nodes
+                .iter()
+                .map(|node| {
+                    let (x, y) = to_web_mercator(node.lon, node.lat);
+                    Node {
+                        id: node.id,
+                        lon: x,
+                        lat: y,
+                    }
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imp_round_trip() {
+        assert_eq!(to_imps(0.0, 0.0), (0, 0));
+        assert_eq!(to_imps(180.0, 0.0).0, i32::MAX);
+        assert_eq!(to_imps(-180.0, 0.0).0, -i32::MAX);
+        assert_eq!(to_imps(0.0, 90.0), to_imps(0.0, MAX_LAT));
+        for (x, y) in [(1_000, -1_000), (150_123_456, 1_020_304_050), (-7, 7)] {
+            let (lon, lat) = from_imps(x, y);
+            assert_eq!(to_imps(lon, lat), (x, y));
+        }
+    }
+
+    #[test]
+    fn test_haversine() {
+        assert_eq!(haversine(12.57, 55.68, 12.57, 55.68), 0.0);
+        let degree = haversine(0.0, 0.0, 0.0, 1.0);
+        assert!((degree - METERS_PER_DEGREE).abs() < 200.0);
+        // Copenhagen to Aarhus is about 157 km
+        let d = haversine(12.5683, 55.6761, 10.2039, 56.1629);
+        assert!((d - 157_000.0).abs() < 2_000.0);
+    }
+
+    #[test]
+    fn test_web_mercator() {
+        let (x, y) = to_web_mercator(180.0, MAX_LAT);
+        assert!((x - 20_037_508.34).abs() < 0.01);
+        assert!((y - 20_037_508.34).abs() < 0.01);
+        let (x, y) = to_web_mercator(12.57, 55.68);
+        let (lon, lat) = from_web_mercator(x, y);
+        assert!((lon - 12.57).abs() < 1e-9 && (lat - 55.68).abs() < 1e-9);
+
+        let feature = Feature {
+            id: 1,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: 12.57,
+            lat: 55.68,
+            tags: vec![("highway".to_string(), "primary".to_string())],
+            nodes: vec![Node {
+                id: 2,
+                lon: 0.0,
+                lat: 0.0,
+            }],
+        };
+        let projected = feature.to_mercator();
+        assert_eq!(
+            (projected.lon, projected.lat),
+            to_web_mercator(12.57, 55.68)
+        );
+        assert!(projected.nodes[0].lon.abs() < 1e-6 && projected.nodes[0].lat.abs() < 1e-6);
+        assert_eq!(projected.tags, feature.tags);
+    }
+}
@@ -0,0 +1,327 @@
+// Provenance of features merged from several stores
+
+use crate::tiles::TileId;
+use crate::{ffi, Feature, FeatureType};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The store a feature was read from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Source {
+    /// Position of the store in its set (lower means higher priority)
+    pub index: usize,
+    pub path: Arc<Path>,
+}
+
+impl Source {
+    pub fn new(index: usize, path: impl Into<PathBuf>) -> Self {
+        Self {
+            index,
+            path: Arc::from(path.into()),
+        }
+    }
+}
+
+/// A feature annotated with the store it came from
+#[derive(Debug, Clone)]
+pub struct SourcedFeature {
+    pub feature: Feature,
+    /// The store whose copy was kept
+    pub source: Source,
+    /// Other stores that returned an identical copy
+    pub also_in: Vec<Source>,
+}
+
+/// Differing copies of one feature returned by overlapping stores
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub id: i64,
+    pub type_name: String,
+    /// Every distinct copy, in the order the stores were merged
+    pub versions: Vec<(Source, Feature)>,
+}
+
+/// Which copy to keep when stores disagree about a feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Keep the copy from the store with the lowest index
+    #[default]
+    FirstSource,
+    /// Keep the copy from the store with the highest index
+    LastSource,
+    /// Keep the copy with the most tags, then the lowest index
+    MostTags,
+}
+
+impl ConflictPolicy {
+    /// Whether the copy from `new_source` should replace the one kept so far
+    fn prefers(
+        &self,
+        new: &Feature,
+        new_source: &Source,
+        old: &Feature,
+        old_source: &Source,
+    ) -> bool {
+        match self {
+            ConflictPolicy::FirstSource => new_source.index < old_source.index,
+            ConflictPolicy::LastSource => new_source.index > old_source.index,
+            ConflictPolicy::MostTags => {
+                (new.tags.len(), std::cmp::Reverse(new_source.index))
+                    > (old.tags.len(), std::cmp::Reverse(old_source.index))
+            }
+        }
+    }
+}
+
+/// What makes two features copies of the same feature when merging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Identity {
+    /// OSM ID alone, so a node and a way with the same number are one
+    /// feature (for inputs that renumber features into a shared ID space)
+    Id,
+    /// Feature type and OSM ID
+    #[default]
+    TypeAndId,
+    /// Geometry, by [`Feature::geometry_hash`] (way nodes, or the location
+    /// of other features, at OSM's precision), regardless of type and ID,
+    /// for matching features that were re-imported under new IDs
+    Geometry,
+}
+
+/// Key under which features are deduplicated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum IdentityKey {
+    Id(i64),
+    TypeAndId(FeatureType, i64),
+    Geometry(u64),
+}
+
+impl Identity {
+    pub(crate) fn key(&self, feature: &Feature) -> IdentityKey {
+        match self {
+            Identity::Id => IdentityKey::Id(feature.id),
+            Identity::TypeAndId => IdentityKey::TypeAndId(feature.feature_type(), feature.id),
+            Identity::Geometry => IdentityKey::Geometry(feature.geometry_hash()),
+        }
+    }
+
+    /// Key of a feature from its type and id alone, without converting
+    /// it; `None` for [`Identity::Geometry`]
+    pub(crate) fn key_of(&self, key: &ffi::bridge::FeatureKey) -> Option<IdentityKey> {
+        match self {
+            Identity::Id => Some(IdentityKey::Id(key.id)),
+            Identity::TypeAndId => Some(IdentityKey::TypeAndId(key.feature_type(), key.id)),
+            Identity::Geometry => None,
+        }
+    }
+}
+
+/// Features merged from several stores, with duplicates removed
+#[derive(Debug, Clone, Default)]
+pub struct Merged {
+    pub features: Vec<SourcedFeature>,
+    /// Features that differed between stores (resolved by the policy)
+    pub conflicts: Vec<Conflict>,
+    /// Tiles of the query area missing from every store that was queried
+    /// (only populated with [`MissingTiles::Flag`](crate::MissingTiles::Flag))
+    pub missing_tiles: Vec<TileId>,
+}
+
+impl Merged {
+    /// Drop the provenance and keep only the features
+    pub fn into_features(self) -> Vec<Feature> {
+        self.features.into_iter().map(|f| f.feature).collect()
+    }
+}
+
+/// Merges query results from several stores, deduplicating by an
+/// [`Identity`] (feature type and ID by default) and recording where each
+/// copy came from
+///
+/// # Example
+/// ```
+/// use geodesk_rs::provenance::{ConflictPolicy, Merger, Source};
+///
+/// let mut merger = Merger::new(ConflictPolicy::FirstSource);
+/// merger.add(&Source::new(0, "europe.gol"), Vec::new());
+/// merger.add(&Source::new(1, "asia.gol"), Vec::new());
+/// let merged = merger.finish();
+/// assert!(merged.conflicts.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Merger {
+    policy: ConflictPolicy,
+    identity: Identity,
+    features: Vec<SourcedFeature>,
+    index: HashMap<IdentityKey, usize>,
+    conflicts: HashMap<usize, Vec<(Source, Feature)>>,
+}
+
+impl Merger {
+    pub fn new(policy: ConflictPolicy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    /// Set what makes two features the same
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Add the features returned by one store
+    pub fn add<I>(&mut self, source: &Source, features: I)
+    where
+        I: IntoIterator<Item = Feature>,
+    {
+        for feature in features {
+            self.add_feature(source, feature);
+        }
+    }
+
+    /// Add a single feature returned by `source`
+    pub fn add_feature(&mut self, source: &Source, feature: Feature) {
+        let key = self.identity.key(&feature);
+        let Some(&slot) = self.index.get(&key) else {
+            self.index.insert(key, self.features.len());
+            self.features.push(SourcedFeature {
+                feature,
+                source: source.clone(),
+                also_in: Vec::new(),
+            });
+            return;
+        };
+
+        let kept = &mut self.features[slot];
+        if same_content(&kept.feature, &feature) {
+            kept.also_in.push(source.clone());
+            return;
+        }
+
+        let versions = self
+            .conflicts
+            .entry(slot)
+            .or_insert_with(|| vec![(kept.source.clone(), kept.feature.clone())]);
+        if !versions.iter().any(|(_, f)| same_content(f, &feature)) {
+            versions.push((source.clone(), feature.clone()));
+        }
+        if self
+            .policy
+            .prefers(&feature, source, &kept.feature, &kept.source)
+        {
+            kept.also_in.clear();
+            kept.feature = feature;
+            kept.source = source.clone();
+        }
+    }
+
+    /// Finish merging; features keep the order in which they were first seen
+    pub fn finish(self) -> Merged {
+        let mut conflicts: Vec<(usize, Vec<(Source, Feature)>)> =
+            self.conflicts.into_iter().collect();
+        conflicts.sort_by_key(|(slot, _)| *slot);
+        let conflicts = conflicts
+            .into_iter()
+            .map(|(slot, versions)| Conflict {
+                id: self.features[slot].feature.id,
+                type_name: self.features[slot].feature.type_name.clone(),
+                versions,
+            })
+            .collect();
+        Merged {
+            features: self.features,
+            conflicts,
+            missing_tiles: Vec::new(),
+        }
+    }
+}
+
+/// Whether two copies of a feature have the same tags and geometry
+pub(crate) fn same_content(a: &Feature, b: &Feature) -> bool {
+    a.lon == b.lon
+        && a.lat == b.lat
+        && a.tags == b.tags
+        && a.nodes.len() == b.nodes.len()
+        && a.nodes
+            .iter()
+            .zip(&b.nodes)
+            .all(|(m, n)| m.id == n.id && m.lon == n.lon && m.lat == n.lat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: i64, tags: &[(&str, &str)]) -> Feature {
+        Feature {
+            id,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 10.0,
+            lat: 50.0,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_identical_copies_are_merged() {
+        let (a, b) = (Source::new(0, "a.gol"), Source::new(1, "b.gol"));
+        let mut merger = Merger::new(ConflictPolicy::FirstSource);
+        merger.add(&a, vec![node(1, &[("amenity", "cafe")]), node(2, &[])]);
+        merger.add(&b, vec![node(1, &[("amenity", "cafe")])]);
+        let merged = merger.finish();
+
+        assert_eq!(merged.features.len(), 2);
+        assert_eq!(merged.features[0].source, a);
+        assert_eq!(merged.features[0].also_in, vec![b]);
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_conflicts_are_resolved_by_policy() {
+        let (a, b) = (Source::new(0, "a.gol"), Source::new(1, "b.gol"));
+        let old = node(1, &[("amenity", "cafe")]);
+        let new = node(1, &[("amenity", "cafe"), ("name", "Kaffe")]);
+
+        for (policy, winner) in [
+            (ConflictPolicy::FirstSource, &a),
+            (ConflictPolicy::LastSource, &b),
+            (ConflictPolicy::MostTags, &b),
+        ] {
+            let mut merger = Merger::new(policy);
+            merger.add(&a, vec![old.clone()]);
+            merger.add(&b, vec![new.clone()]);
+            let merged = merger.finish();
+            assert_eq!(&merged.features[0].source, winner);
+            assert_eq!(merged.conflicts.len(), 1);
+            assert_eq!(merged.conflicts[0].versions.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_identity_strategies() {
+        let (a, b) = (Source::new(0, "a.gol"), Source::new(1, "b.gol"));
+        let mut way = node(1, &[]);
+        way.type_name = "way".to_string();
+        let renumbered = node(99, &[]);
+
+        let count = |identity| {
+            let mut merger = Merger::new(ConflictPolicy::FirstSource).with_identity(identity);
+            merger.add(&a, vec![node(1, &[])]);
+            merger.add(&b, vec![way.clone(), renumbered.clone()]);
+            merger.finish().features.len()
+        };
+        assert_eq!(count(Identity::TypeAndId), 3);
+        assert_eq!(count(Identity::Id), 2);
+        // The node and the way share a location in this test, and the
+        // renumbered node is an exact copy
+        assert_eq!(count(Identity::Geometry), 1);
+    }
+}
@@ -0,0 +1,123 @@
+// Python module over GeoDesk, built with maturin (`maturin build --release`
+// enables the features listed in pyproject.toml)
+//
+// Queries run without holding the GIL; features come back as dicts, and
+// geometries as WKB for `shapely.from_wkb`.
+
+use crate::error::GeoDeskError;
+use crate::export::wkb::to_wkb;
+use crate::testing::bboxes;
+use crate::{BoundingBox, Feature, GeoDesk};
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+impl From<GeoDeskError> for PyErr {
+    fn from(e: GeoDeskError) -> Self {
+        match e {
+            GeoDeskError::Io(e) => PyIOError::new_err(e.to_string()),
+            GeoDeskError::InvalidPath(_) => PyValueError::new_err(e.to_string()),
+            e => PyRuntimeError::new_err(e.to_string()),
+        }
+    }
+}
+
+type Bbox = (f64, f64, f64, f64);
+
+fn bounding_box(bbox: Option<Bbox>) -> BoundingBox {
+    bbox.map_or(bboxes::WORLD, |(west, south, east, north)| {
+        BoundingBox::new(west, south, east, north)
+    })
+}
+
+fn feature_dict<'py>(py: Python<'py>, feature: &Feature) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("id", feature.id)?;
+    dict.set_item("type", &feature.type_name)?;
+    dict.set_item("name", &feature.name)?;
+    dict.set_item("lon", feature.lon)?;
+    dict.set_item("lat", feature.lat)?;
+    let tags = PyDict::new(py);
+    for (key, value) in &feature.tags {
+        tags.set_item(key, value)?;
+    }
+    dict.set_item("tags", tags)?;
+    Ok(dict)
+}
+
+/// A GOL file opened from Python: `GeoDesk("denmark.gol")`
+#[pyclass(name = "GeoDesk", module = "geodesk_rs", frozen)]
+pub struct PyGeoDesk {
+    geodesk: GeoDesk,
+}
+
+#[pymethods]
+impl PyGeoDesk {
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        Ok(Self {
+            geodesk: GeoDesk::open(path)?,
+        })
+    }
+
+    /// Features matching a GOQL query in a `(west, south, east, north)`
+    /// bounding box, or everywhere
+    #[pyo3(signature = (goql, bbox = None))]
+    fn query(&self, py: Python<'_>, goql: &str, bbox: Option<Bbox>) -> PyResult<PyQueryResult> {
+        let bbox = bounding_box(bbox);
+        let features = py.allow_threads(|| self.geodesk.query(goql, bbox)?.to_vec())?;
+        Ok(PyQueryResult { features })
+    }
+
+    /// Number of features matching a GOQL query
+    #[pyo3(signature = (goql, bbox = None))]
+    fn count(&self, py: Python<'_>, goql: &str, bbox: Option<Bbox>) -> PyResult<usize> {
+        let bbox = bounding_box(bbox);
+        Ok(py.allow_threads(|| self.geodesk.query(goql, bbox).map(|r| r.count()))?)
+    }
+}
+
+/// Features returned by `GeoDesk.query`
+#[pyclass(name = "QueryResult", module = "geodesk_rs", frozen)]
+pub struct PyQueryResult {
+    features: Vec<Feature>,
+}
+
+#[pymethods]
+impl PyQueryResult {
+    fn __len__(&self) -> usize {
+        self.features.len()
+    }
+
+    /// The features as dicts with `id`, `type`, `name`, `lon`, `lat` and
+    /// `tags`, plus their geometry as WKB under `wkb` if asked for
+    #[pyo3(signature = (wkb = false))]
+    fn to_dicts<'py>(&self, py: Python<'py>, wkb: bool) -> PyResult<Bound<'py, PyList>> {
+        let list = PyList::empty(py);
+        for feature in &self.features {
+            let dict = feature_dict(py, feature)?;
+            if wkb {
+                dict.set_item("wkb", PyBytes::new(py, &to_wkb(feature)))?;
+            }
+            list.append(dict)?;
+        }
+        Ok(list)
+    }
+
+    /// The geometries of the features as WKB, in the order of `to_dicts`;
+    /// `shapely.from_wkb(result.to_wkb())` turns them into shapes
+    fn to_wkb<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let geometries = self
+            .features
+            .iter()
+            .map(|feature| PyBytes::new(py, &to_wkb(feature)));
+        PyList::new(py, geometries)
+    }
+}
+
+#[pymodule]
+fn geodesk_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGeoDesk>()?;
+    m.add_class::<PyQueryResult>()?;
+    Ok(())
+}
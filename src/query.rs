@@ -0,0 +1,839 @@
+// Builder for GOQL query strings
+
+use crate::error::GeoDeskError;
+use crate::projection::haversine;
+use crate::testing::bboxes;
+use crate::zonal::Polygon;
+use crate::{BoundingBox, Feature, FeatureType, GeoDesk};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// A single tag condition in a GOQL selector, e.g. `[amenity=restaurant]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagFilter {
+    /// `[key]` - the tag is present
+    Exists(String),
+    /// `[!key]` - the tag is absent
+    Missing(String),
+    /// `[key=a,b,...]` - the value is one of the given values
+    Equals(String, Vec<String>),
+    /// `[key!=a,b,...]` - the value is none of the given values
+    NotEquals(String, Vec<String>),
+    /// `[key<op>number]` - numeric comparison
+    Compare(String, Comparison, f64),
+}
+
+/// Numeric comparison operator for [`TagFilter::Compare`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+impl fmt::Display for TagFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagFilter::Exists(key) => write!(f, "[{}]", quote(key)),
+            TagFilter::Missing(key) => write!(f, "[!{}]", quote(key)),
+            TagFilter::Equals(key, values) => {
+                write!(f, "[{}={}]", quote(key), join_values(values))
+            }
+            TagFilter::NotEquals(key, values) => {
+                write!(f, "[{}!={}]", quote(key), join_values(values))
+            }
+            TagFilter::Compare(key, op, n) => write!(f, "[{}{}{}]", quote(key), op.as_str(), n),
+        }
+    }
+}
+
+/// A GOQL query assembled from a type selector and tag filters
+///
+/// # Example
+/// ```
+/// use geodesk_rs::query::Query;
+/// use geodesk_rs::keys::{amenity, tag};
+///
+/// let query = Query::new("na")
+///     .find(tag::amenity.eq(amenity::RESTAURANT))
+///     .find(tag::cuisine.exists());
+/// assert_eq!(query.to_string(), "na[amenity=restaurant][cuisine]");
+/// ```
+///
+/// Giving the query an area to search ([`within`](Query::within),
+/// [`inside`](Query::inside), [`near`](Query::near) or
+/// [`everywhere`](Query::everywhere)) makes it runnable with
+/// [`run`](Query::run). The area and the order of the results are part of
+/// the type, so combinations that make no sense don't compile: a query
+/// has one area, and only queries near a point can be sorted by distance.
+///
+/// ```compile_fail
+/// use geodesk_rs::query::Query;
+/// use geodesk_rs::testing::bboxes;
+///
+/// // Nearest to what?
+/// let query = Query::new("na").within(bboxes::BERLIN).nearest_first();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<S = Unscoped, O = Unordered> {
+    types: String,
+    filters: Vec<TagFilter>,
+    scope: S,
+    order: O,
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Area a [`Query`] searches, set by [`Query::within`], [`Query::inside`],
+/// [`Query::near`] or [`Query::everywhere`]
+pub trait Scope: sealed::Sealed {
+    /// Bounding box to query the GOL with
+    fn bbox(&self) -> BoundingBox;
+
+    /// Check if a feature from the bounding box lies in the area
+    fn contains(&self, _feature: &Feature) -> bool {
+        true
+    }
+}
+
+/// Order of the results of a [`Query`]
+pub trait Order: sealed::Sealed {
+    /// Sort the results
+    fn arrange(&self, _features: &mut [Feature]) {}
+}
+
+/// No area chosen yet; the query can only be rendered as GOQL
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Unscoped;
+
+/// The whole GOL
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Everywhere;
+
+/// Features intersecting a bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Within(pub BoundingBox);
+
+/// Features whose location lies inside a polygon
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inside(pub Polygon);
+
+/// Features located within a radius in meters of a point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Near {
+    pub lon: f64,
+    pub lat: f64,
+    pub radius: f64,
+}
+
+/// Results in the order the GOL returns them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Unordered;
+
+/// Results sorted by distance from the point of a [`Near`] query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByDistance {
+    lon: f64,
+    lat: f64,
+}
+
+impl sealed::Sealed for Everywhere {}
+impl sealed::Sealed for Within {}
+impl sealed::Sealed for Inside {}
+impl sealed::Sealed for Near {}
+impl sealed::Sealed for Unordered {}
+impl sealed::Sealed for ByDistance {}
+
+impl Scope for Everywhere {
+    fn bbox(&self) -> BoundingBox {
+        bboxes::WORLD
+    }
+}
+
+impl Scope for Within {
+    fn bbox(&self) -> BoundingBox {
+        self.0
+    }
+}
+
+impl Scope for Inside {
+    fn bbox(&self) -> BoundingBox {
+        self.0.bounds()
+    }
+
+    fn contains(&self, feature: &Feature) -> bool {
+        self.0.contains(feature.lon, feature.lat)
+    }
+}
+
+impl Scope for Near {
+    fn bbox(&self) -> BoundingBox {
+        BoundingBox::around(self.lon, self.lat, self.radius)
+    }
+
+    fn contains(&self, feature: &Feature) -> bool {
+        haversine(self.lon, self.lat, feature.lon, feature.lat) <= self.radius
+    }
+}
+
+impl Order for Unordered {}
+
+impl Order for ByDistance {
+    fn arrange(&self, features: &mut [Feature]) {
+        features.sort_by(|a, b| {
+            let a = haversine(self.lon, self.lat, a.lon, a.lat);
+            let b = haversine(self.lon, self.lat, b.lon, b.lat);
+            a.total_cmp(&b)
+        });
+    }
+}
+
+impl Query {
+    /// Start a query for the given feature types (`n`, `w`, `a`, `r` or `*`)
+    pub fn new(types: &str) -> Self {
+        Self {
+            types: if types.is_empty() {
+                "*".to_string()
+            } else {
+                types.to_string()
+            },
+            filters: Vec::new(),
+            scope: Unscoped,
+            order: Unordered,
+        }
+    }
+
+    /// Start a query matching features of any type
+    pub fn any() -> Self {
+        Self::new("*")
+    }
+
+    /// Search the features intersecting a bounding box
+    pub fn within(self, bbox: BoundingBox) -> Query<Within> {
+        self.scoped(Within(bbox))
+    }
+
+    /// Search the features located inside a polygon
+    pub fn inside(self, polygon: Polygon) -> Query<Inside> {
+        self.scoped(Inside(polygon))
+    }
+
+    /// Search the features located within `radius` meters of a point
+    pub fn near(self, lon: f64, lat: f64, radius: f64) -> Query<Near> {
+        self.scoped(Near { lon, lat, radius })
+    }
+
+    /// Search the whole GOL
+    pub fn everywhere(self) -> Query<Everywhere> {
+        self.scoped(Everywhere)
+    }
+
+    fn scoped<S: Scope>(self, scope: S) -> Query<S> {
+        Query {
+            types: self.types,
+            filters: self.filters,
+            scope,
+            order: Unordered,
+        }
+    }
+}
+
+impl Query<Near> {
+    /// Sort the results by distance from the point, nearest first
+    pub fn nearest_first(self) -> Query<Near, ByDistance> {
+        let order = ByDistance {
+            lon: self.scope.lon,
+            lat: self.scope.lat,
+        };
+        Query {
+            types: self.types,
+            filters: self.filters,
+            scope: self.scope,
+            order,
+        }
+    }
+}
+
+impl<S: Scope, O: Order> Query<S, O> {
+    /// Run the query on a GOL
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::keys::{amenity, tag};
+    /// use geodesk_rs::query::Query;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let cafes = Query::new("na")
+    ///     .find(tag::amenity.eq(amenity::CAFE))
+    ///     .near(12.5683, 55.6761, 500.0)
+    ///     .nearest_first()
+    ///     .run(&geodesk)
+    ///     .unwrap();
+    /// ```
+    pub fn run(&self, geodesk: &GeoDesk) -> Result<Vec<Feature>, GeoDeskError> {
+        let mut features = geodesk
+            .query(&self.to_goql(), self.scope.bbox())?
+            .to_vec()?;
+        features.retain(|feature| self.scope.contains(feature));
+        self.order.arrange(&mut features);
+        Ok(features)
+    }
+
+    /// The area the query searches
+    pub fn scope(&self) -> &S {
+        &self.scope
+    }
+}
+
+impl<S, O> Query<S, O> {
+    /// Add a tag filter; all filters must match
+    pub fn find(mut self, filter: TagFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// The feature type selector
+    pub fn types(&self) -> &str {
+        &self.types
+    }
+
+    /// The tag filters
+    pub fn filters(&self) -> &[TagFilter] {
+        &self.filters
+    }
+
+    /// Render the query as a GOQL string
+    pub fn to_goql(&self) -> String {
+        self.to_string()
+    }
+
+    /// Check if a feature at hand matches the query, as GeoDESK would
+    ///
+    /// Ways that are areas (see [`Feature::is_area`]) match `a` rather
+    /// than `w`. Numeric comparisons fail for values that aren't plain
+    /// numbers.
+    pub fn matches(&self, feature: &Feature) -> bool {
+        let t = self.types.as_str();
+        let type_matches = t.contains('*')
+            || match feature.feature_type() {
+                FeatureType::Node => t.contains('n'),
+                FeatureType::Way if feature.is_area() => t.contains('a'),
+                FeatureType::Way => t.contains('w'),
+                FeatureType::Relation => t.contains('r') || (t.contains('a') && feature.is_area()),
+            };
+        type_matches && self.filters.iter().all(|filter| filter.matches(feature))
+    }
+}
+
+impl TagFilter {
+    fn matches(&self, feature: &Feature) -> bool {
+        match self {
+            TagFilter::Exists(key) => feature.has_tag(key),
+            TagFilter::Missing(key) => !feature.has_tag(key),
+            TagFilter::Equals(key, values) => feature
+                .tag(key)
+                .is_some_and(|v| values.iter().any(|value| value == v)),
+            TagFilter::NotEquals(key, values) => feature
+                .tag(key)
+                .is_none_or(|v| values.iter().all(|value| value != v)),
+            TagFilter::Compare(key, op, n) => {
+                let value = feature.tag(key).and_then(|v| v.trim().parse::<f64>().ok());
+                value.is_some_and(|v| match op {
+                    Comparison::Less => v < *n,
+                    Comparison::LessOrEqual => v <= *n,
+                    Comparison::Greater => v > *n,
+                    Comparison::GreaterOrEqual => v >= *n,
+                })
+            }
+        }
+    }
+}
+
+impl<S, O> fmt::Display for Query<S, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.types)?;
+        for filter in &self.filters {
+            write!(f, "{}", filter)?;
+        }
+        Ok(())
+    }
+}
+
+/// A GOQL string with user-supplied values bound into it
+///
+/// Values are substituted for `{}` placeholders in a template and quoted
+/// as needed, so a value such as `x],[*` can't end its filter or add
+/// selectors; it is only ever compared as a whole. Placeholders are only
+/// allowed inside filters (`[...]`), as keys or values; write `{{` and
+/// `}}` for literal braces.
+///
+/// Derefs to `str`, so it can be passed to any query method.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::query::GoqlQuery;
+///
+/// let name = "Joe's Diner";
+/// let query = GoqlQuery::with_params("na[amenity={}][name={}]", &["restaurant", name]).unwrap();
+/// assert_eq!(&*query, "na[amenity=restaurant][name='Joe\\'s Diner']");
+///
+/// let injected = GoqlQuery::with_params("na[amenity={}]", &["cafe],*[shop"]).unwrap();
+/// assert_eq!(&*injected, "na[amenity='cafe],*[shop']");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GoqlQuery(String);
+
+impl GoqlQuery {
+    /// Bind `params` to the `{}` placeholders of `template`, in order
+    ///
+    /// Fails if the number of placeholders and parameters differ, or if a
+    /// placeholder is outside a filter.
+    pub fn with_params<S: AsRef<str>>(
+        template: &str,
+        params: &[S],
+    ) -> Result<Self, ParseQueryError> {
+        let error = |reason: String| ParseQueryError::new(template, reason);
+        let mut goql = String::with_capacity(template.len());
+        let mut params_left = params.iter();
+        let mut chars = template.chars().peekable();
+        let mut in_filter = false;
+        let mut in_quotes: Option<char> = None;
+        while let Some(c) = chars.next() {
+            if let Some(quote_char) = in_quotes {
+                goql.push(c);
+                if c == '\\' {
+                    goql.extend(chars.next());
+                } else if c == quote_char {
+                    in_quotes = None;
+                }
+                continue;
+            }
+            match c {
+                '\'' | '"' => in_quotes = Some(c),
+                '[' => in_filter = true,
+                ']' => in_filter = false,
+                '{' if chars.next_if_eq(&'}').is_some() => {
+                    if !in_filter {
+                        return Err(error(
+                            "placeholders are only allowed inside [...]".to_string(),
+                        ));
+                    }
+                    let value = params_left.next().ok_or_else(|| {
+                        error(format!(
+                            "more placeholders than the {} parameter(s)",
+                            params.len()
+                        ))
+                    })?;
+                    goql.push_str(&quote(value.as_ref()));
+                    continue;
+                }
+                '{' | '}' if chars.next_if_eq(&c).is_some() => {}
+                '{' | '}' => return Err(error(format!("unmatched '{}'", c))),
+                _ => {}
+            }
+            goql.push(c);
+        }
+        if params_left.next().is_some() {
+            return Err(error(format!(
+                "fewer placeholders than the {} parameter(s)",
+                params.len()
+            )));
+        }
+        Ok(GoqlQuery(goql))
+    }
+
+    /// The GOQL string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for GoqlQuery {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for GoqlQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Error for a query string that can't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseQueryError {
+    pub query: String,
+    pub reason: String,
+}
+
+impl ParseQueryError {
+    pub(crate) fn new(query: &str, reason: impl Into<String>) -> Self {
+        Self {
+            query: query.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Can't parse '{}': {}", self.query, self.reason)
+    }
+}
+
+impl std::error::Error for ParseQueryError {}
+
+/// Parse a GOQL string with a single selector, e.g. `na[amenity=cafe][!name]`
+///
+/// Only what [`TagFilter`] can represent is accepted: several
+/// comma-separated selectors, wildcards and regular expressions are errors.
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(goql: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| ParseQueryError::new(goql, reason);
+        let mut chars = goql.trim().chars().peekable();
+        let mut types = String::new();
+        while let Some(&c) = chars.peek() {
+            if !matches!(c, 'n' | 'w' | 'a' | 'r' | '*') {
+                break;
+            }
+            types.push(c);
+            chars.next();
+        }
+        let mut query = Query::new(&types);
+        while let Some(c) = chars.next() {
+            match c {
+                '[' => query
+                    .filters
+                    .push(parse_filter(&mut chars).map_err(|r| error(&r))?),
+                ',' => return Err(error("only a single selector is supported")),
+                c if c.is_whitespace() => {}
+                c => return Err(error(&format!("unexpected '{}'", c))),
+            }
+        }
+        Ok(query)
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn parse_filter(chars: &mut Chars) -> Result<TagFilter, String> {
+    skip_spaces(chars);
+    let negated = chars.next_if_eq(&'!').is_some();
+    let key = parse_token(chars)?;
+    skip_spaces(chars);
+    if negated {
+        expect(chars, ']')?;
+        return Ok(TagFilter::Missing(key));
+    }
+    let op: String =
+        std::iter::from_fn(|| chars.next_if(|c| matches!(c, '=' | '!' | '<' | '>' | '~')))
+            .collect();
+    let filter = match op.as_str() {
+        "" => TagFilter::Exists(key),
+        "=" | "!=" => {
+            let mut values = vec![parse_token(chars)?];
+            skip_spaces(chars);
+            while chars.next_if_eq(&',').is_some() {
+                values.push(parse_token(chars)?);
+                skip_spaces(chars);
+            }
+            if op == "=" {
+                TagFilter::Equals(key, values)
+            } else {
+                TagFilter::NotEquals(key, values)
+            }
+        }
+        "<" | "<=" | ">" | ">=" => {
+            let value = parse_token(chars)?;
+            let number = value
+                .parse::<f64>()
+                .map_err(|_| format!("'{}' is not a number", value))?;
+            let comparison = match op.as_str() {
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessOrEqual,
+                ">" => Comparison::Greater,
+                _ => Comparison::GreaterOrEqual,
+            };
+            TagFilter::Compare(key, comparison, number)
+        }
+        "~" | "!~" => return Err("regular expressions are not supported".to_string()),
+        other => return Err(format!("unknown operator '{}'", other)),
+    };
+    skip_spaces(chars);
+    expect(chars, ']')?;
+    Ok(filter)
+}
+
+fn parse_token(chars: &mut Chars) -> Result<String, String> {
+    skip_spaces(chars);
+    let mut token = String::new();
+    match chars.peek() {
+        Some(&quote @ ('\'' | '"')) => {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('\\') => token.extend(chars.next()),
+                    Some(c) if c == quote => return Ok(token),
+                    Some(c) => token.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+        }
+        _ => {
+            while let Some(c) = chars.next_if(|&c| {
+                !matches!(c, ',' | ']' | '=' | '!' | '<' | '>' | '~') && !c.is_whitespace()
+            }) {
+                token.push(c);
+            }
+        }
+    }
+    if token.is_empty() {
+        Err("expected a key or value".to_string())
+    } else if token.contains('*') {
+        Err("wildcards are not supported".to_string())
+    } else {
+        Ok(token)
+    }
+}
+
+fn skip_spaces(chars: &mut Chars) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn expect(chars: &mut Chars, expected: char) -> Result<(), String> {
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+        None => Err(format!("expected '{}'", expected)),
+    }
+}
+
+fn join_values(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| quote(v))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Quote a key or value unless it consists only of characters GOQL accepts bare
+pub(crate) fn quote(s: &str) -> String {
+    let bare = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'));
+    if bare {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_rendering() {
+        let key = || "highway".to_string();
+        assert_eq!(TagFilter::Exists(key()).to_string(), "[highway]");
+        assert_eq!(TagFilter::Missing(key()).to_string(), "[!highway]");
+        assert_eq!(
+            TagFilter::NotEquals(key(), vec!["footway".into(), "path".into()]).to_string(),
+            "[highway!=footway,path]"
+        );
+        assert_eq!(
+            TagFilter::Compare("lanes".into(), Comparison::GreaterOrEqual, 2.0).to_string(),
+            "[lanes>=2]"
+        );
+    }
+
+    #[test]
+    fn test_values_are_quoted_when_needed() {
+        let filter = TagFilter::Equals("name".into(), vec!["Joe's Diner".into()]);
+        assert_eq!(filter.to_string(), "[name='Joe\\'s Diner']");
+        assert_eq!(Query::new("").to_string(), "*");
+    }
+
+    #[test]
+    fn test_parse_goql() {
+        let query: Query = "na[amenity=cafe,restaurant][!name]['addr:street'][lanes>=2]"
+            .parse()
+            .unwrap();
+        assert_eq!(query.types(), "na");
+        assert_eq!(
+            query.filters(),
+            &[
+                TagFilter::Equals("amenity".into(), vec!["cafe".into(), "restaurant".into()]),
+                TagFilter::Missing("name".into()),
+                TagFilter::Exists("addr:street".into()),
+                TagFilter::Compare("lanes".into(), Comparison::GreaterOrEqual, 2.0),
+            ]
+        );
+        let quoted: Query = "[name='Joe\\'s Diner']".parse().unwrap();
+        assert_eq!(quoted.to_string(), "*[name='Joe\\'s Diner']");
+        assert!("n[amenity], w[highway]".parse::<Query>().is_err());
+        assert!("n[name~'^A']".parse::<Query>().is_err());
+        assert!("n[amenity".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let feature = |type_name: &str, tags: &[(&str, &str)]| Feature {
+            id: 1,
+            type_name: type_name.to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: Vec::new(),
+        };
+        let query: Query = "nw[highway!=footway][lanes>=2][!access]".parse().unwrap();
+        assert!(query.matches(&feature("way", &[("highway", "primary"), ("lanes", "2")])));
+        assert!(!query.matches(&feature("way", &[("highway", "footway"), ("lanes", "2")])));
+        assert!(!query.matches(&feature("way", &[("lanes", "two")])));
+        assert!(!query.matches(&feature("relation", &[("lanes", "3")])));
+        let areas: Query = "a[landuse]".parse().unwrap();
+        let multipolygon = feature(
+            "relation",
+            &[("type", "multipolygon"), ("landuse", "forest")],
+        );
+        assert!(areas.matches(&multipolygon));
+        assert!(!areas.matches(&feature("node", &[("landuse", "forest")])));
+    }
+
+    #[test]
+    fn test_goql_params() {
+        let hostile = "x'],w[highway";
+        let query = GoqlQuery::with_params("na[{}={}][!name]", &["cuisine", hostile]).unwrap();
+        let parsed: Query = query.parse().unwrap();
+        assert_eq!(
+            parsed.filters()[0],
+            TagFilter::Equals("cuisine".into(), vec![hostile.into()])
+        );
+        assert_eq!(parsed.filters().len(), 2);
+
+        let quoted = GoqlQuery::with_params("n['{}'][a={}]", &["b"]).unwrap();
+        assert_eq!(quoted.as_str(), "n['{}'][a=b]");
+        assert!(GoqlQuery::with_params("n[a={}]", &["b", "c"]).is_err());
+        assert!(GoqlQuery::with_params("n[a={}][b={}]", &["c"]).is_err());
+        assert!(GoqlQuery::with_params("{}[a]", &["n"]).is_err());
+        assert!(GoqlQuery::with_params("n[a={]", &[] as &[&str]).is_err());
+    }
+
+    #[test]
+    fn test_scopes() {
+        let point = |id, lon, lat| Feature {
+            id,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon,
+            lat,
+            tags: Vec::new(),
+            nodes: Vec::new(),
+        };
+        let query = Query::new("n").near(0.0, 0.0, 1000.0).nearest_first();
+        let bbox = query.scope().bbox();
+        assert!((bbox.north - 1000.0 / crate::projection::METERS_PER_DEGREE).abs() < 1e-9);
+
+        let mut features = vec![
+            point(1, 0.005, 0.0),
+            point(2, 0.0, 0.001),
+            point(3, 0.02, 0.0),
+        ];
+        features.retain(|f| query.scope().contains(f));
+        query.order.arrange(&mut features);
+        assert_eq!(features.iter().map(|f| f.id).collect::<Vec<_>>(), [2, 1]);
+
+        let square = Polygon::new(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        let inside = Query::any().inside(square);
+        assert!(inside.scope().contains(&point(1, 0.5, 0.5)));
+        assert!(!inside.scope().contains(&point(2, 1.5, 0.5)));
+        assert_eq!(inside.to_goql(), "*");
+    }
+
+    mod properties {
+        use super::*;
+        use crate::testing::bboxes;
+        use crate::translate::{to_overpass, to_sql, SqlSchema};
+        use proptest::prelude::*;
+
+        /// Strings that look like GOQL often enough to get past the first
+        /// character, with the punctuation the parser branches on
+        fn goql_like() -> impl Strategy<Value = String> {
+            prop_oneof![
+                any::<String>(),
+                "[nwar*]{0,4}(\\[[!a-z:_]{0,6}([=!~<>]{1,2}[a-z0-9.,'\\\\ ]{0,8})?\\]?){0,3}",
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn parsing_never_panics(goql in goql_like()) {
+                let _ = goql.parse::<Query>();
+                let _ = to_overpass(&goql, bboxes::BERLIN);
+                let _ = to_sql(&goql, &SqlSchema::default());
+                let _ = Query::from_overpass(&goql);
+            }
+
+            #[test]
+            fn rendering_round_trips(goql in goql_like()) {
+                if let Ok(query) = goql.parse::<Query>() {
+                    prop_assert_eq!(query.to_string().parse::<Query>(), Ok(query));
+                }
+            }
+        }
+
+        /// Queries on the test GOL: malformed input must come back as an
+        /// error, not crash the engine
+        #[cfg(feature = "test-data")]
+        #[test]
+        fn bridge_rejects_malformed_input() {
+            let Some(geodesk) = crate::test_data::TestGol::try_open() else {
+                return;
+            };
+            let coordinate = prop_oneof![
+                -200.0..200.0f64,
+                Just(f64::NAN),
+                Just(f64::INFINITY),
+                Just(f64::NEG_INFINITY),
+            ];
+            let bbox = (
+                coordinate.clone(),
+                coordinate.clone(),
+                coordinate.clone(),
+                coordinate,
+            );
+            proptest!(|(goql in goql_like(), (w, s, e, n) in bbox)| {
+                let bbox = crate::BoundingBox::new(w, s, e, n);
+                if let Ok(result) = geodesk.query(&goql, bbox) {
+                    let _ = result.to_vec();
+                }
+                let _ = geodesk.count_by_type(&goql, bbox);
+            });
+        }
+    }
+}
@@ -0,0 +1,665 @@
+// Lightweight handles to features, with details fetched on demand
+
+use crate::cancel::QueryControl;
+use crate::error::GeoDeskError;
+use crate::geometry::MemberGeometry;
+use crate::metadata::timestamp_from_millis;
+use crate::options::QueryOptions;
+use crate::tiles::TileId;
+use crate::{ffi, BoundingBox, Feature, FeatureType, GeoDesk, QueryResult};
+use cxx::UniquePtr;
+use std::marker::PhantomData;
+use std::ops::{BitOr, ControlFlow};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::SystemTime;
+
+/// Which details [`FeatureRef::hydrate`] fetches, combined with `|`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Detail(u8);
+
+impl Detail {
+    /// Only the id, type, name and location
+    pub const NONE: Detail = Detail(0);
+    /// All tags
+    pub const TAGS: Detail = Detail(1);
+    /// Way nodes
+    pub const GEOMETRY: Detail = Detail(2);
+    /// Members of relations, with their geometries
+    pub const MEMBERS: Detail = Detail(4);
+    /// Everything
+    pub const ALL: Detail = Detail(7);
+
+    /// Check if all details in `other` are included
+    pub fn contains(self, other: Detail) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Detail {
+    type Output = Detail;
+
+    fn bitor(self, other: Detail) -> Detail {
+        Detail(self.0 | other.0)
+    }
+}
+
+/// A feature with the details requested from [`FeatureRef::hydrate`]
+#[derive(Debug, Clone)]
+pub struct Hydrated {
+    /// The feature; `tags` and `nodes` are empty unless requested
+    pub feature: Feature,
+    /// Relation members (empty unless requested, and for nodes and ways)
+    pub members: Vec<MemberGeometry>,
+}
+
+/// Features matched by [`GeoDesk::query_refs`], held as handles into the GOL
+///
+/// Nothing but a reference is stored per feature, so a listing of many
+/// features costs little memory; tags and geometry are read from the GOL
+/// only for the features that are [hydrated](FeatureRef::hydrate).
+pub struct FeatureRefs<'a> {
+    list: UniquePtr<ffi::bridge::FeatureList>,
+    missing_tiles: Vec<TileId>,
+    _geodesk: PhantomData<&'a GeoDesk>,
+}
+
+impl FeatureRefs<'_> {
+    pub(crate) fn new(
+        list: UniquePtr<ffi::bridge::FeatureList>,
+        missing_tiles: Vec<TileId>,
+    ) -> Self {
+        Self {
+            list,
+            missing_tiles,
+            _geodesk: PhantomData,
+        }
+    }
+
+    /// Number of features
+    pub fn len(&self) -> usize {
+        ffi::bridge::list_len(&self.list)
+    }
+
+    /// Check if no features matched
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The feature at `index`
+    pub fn get(&self, index: usize) -> Option<FeatureRef<'_>> {
+        if index >= self.len() {
+            return None;
+        }
+        ffi::bridge::list_get(&self.list, index)
+            .ok()
+            .map(|handle| FeatureRef { handle })
+    }
+
+    /// Iterate over the features
+    pub fn iter(&self) -> impl Iterator<Item = FeatureRef<'_>> {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+
+    /// Tiles of the query area that are missing from the GOL
+    /// (see [`crate::QueryResult::missing_tiles`])
+    pub fn missing_tiles(&self) -> &[TileId] {
+        &self.missing_tiles
+    }
+}
+
+/// A feature in an open GOL, read lazily
+#[derive(Clone, Copy)]
+pub struct FeatureRef<'a> {
+    handle: &'a ffi::bridge::FeatureHandle,
+}
+
+impl<'a> FeatureRef<'a> {
+    /// OSM ID
+    pub fn id(&self) -> i64 {
+        ffi::bridge::handle_id(self.handle)
+    }
+
+    /// The feature's type
+    pub fn feature_type(&self) -> FeatureType {
+        match ffi::bridge::handle_type(self.handle) {
+            0 => FeatureType::Node,
+            1 => FeatureType::Way,
+            _ => FeatureType::Relation,
+        }
+    }
+
+    /// `"node"`, `"way"` or `"relation"`
+    pub fn type_name(&self) -> &'static str {
+        self.feature_type().as_str()
+    }
+
+    /// Longitude of the feature's location (the center for ways and
+    /// relations)
+    pub fn lon(&self) -> f64 {
+        ffi::bridge::handle_lon(self.handle)
+    }
+
+    /// Latitude of the feature's location
+    pub fn lat(&self) -> f64 {
+        ffi::bridge::handle_lat(self.handle)
+    }
+
+    /// Bounding box of the feature's geometry
+    pub fn bounds(&self) -> BoundingBox {
+        let b = ffi::bridge::handle_bounds(self.handle);
+        BoundingBox::new(b.west, b.south, b.east, b.north)
+    }
+
+    /// Version of the feature, counting its edits from 1
+    ///
+    /// Fails with [`GeoDeskError::MetadataUnavailable`] if the GOL was
+    /// built without OSM metadata (see
+    /// [`GolMetadata::osm_metadata`](crate::metadata::GolMetadata::osm_metadata));
+    /// GOLs written by the current `gol build` never include it.
+    pub fn version(&self) -> Result<u32, GeoDeskError> {
+        Ok(self.osm_metadata()?.version)
+    }
+
+    /// Time of the feature's last edit (see [`FeatureRef::version`] for
+    /// when it is unavailable)
+    pub fn timestamp(&self) -> Result<SystemTime, GeoDeskError> {
+        Ok(timestamp_from_millis(self.osm_metadata()?.timestamp))
+    }
+
+    /// Changeset of the feature's last edit (see [`FeatureRef::version`]
+    /// for when it is unavailable)
+    pub fn changeset(&self) -> Result<u64, GeoDeskError> {
+        Ok(self.osm_metadata()?.changeset)
+    }
+
+    fn osm_metadata(&self) -> Result<ffi::bridge::OsmMetadata, GeoDeskError> {
+        let mut metadata = ffi::bridge::OsmMetadata::default();
+        if ffi::bridge::handle_osm_metadata(self.handle, &mut metadata) {
+            Ok(metadata)
+        } else {
+            Err(GeoDeskError::MetadataUnavailable)
+        }
+    }
+
+    /// The `name` tag, or an empty string
+    ///
+    /// Fails only for names that are not valid UTF-8 in a GOL opened with
+    /// [`Utf8Policy::Strict`](crate::options::Utf8Policy::Strict).
+    pub fn name(&self) -> Result<String, GeoDeskError> {
+        Ok(ffi::bridge::handle_name(self.handle)?)
+    }
+
+    /// The value of a tag, read from the GOL without copying the others
+    pub fn tag(&self, key: &str) -> Result<Option<String>, GeoDeskError> {
+        let mut value = String::new();
+        Ok(ffi::bridge::feature_tag(self.handle, key, &mut value)?.then_some(value))
+    }
+
+    /// Iterate over the tags, reading them from the GOL one at a time
+    ///
+    /// Keys are borrowed from the GOL's string table rather than copied,
+    /// so scanning the tags of many features for a few keys allocates
+    /// only for the values that are read.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let shops = geodesk.query_refs("na[shop]", bboxes::COPENHAGEN).unwrap();
+    /// for shop in shops.iter() {
+    ///     for tag in shop.tags() {
+    ///         let (key, value) = tag.unwrap();
+    ///         if key.starts_with("payment:") {
+    ///             println!("{} {}={}", shop.id(), key, value);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn tags(&self) -> Tags<'a> {
+        Tags {
+            cursor: ffi::bridge::handle_tags(self.handle),
+            handle: self.handle,
+        }
+    }
+
+    /// Read the requested details from the GOL
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::refs::Detail;
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let parks = geodesk.query_refs("a[leisure=park]", bboxes::COPENHAGEN).unwrap();
+    /// for park in parks.iter() {
+    ///     println!("{} {}", park.id(), park.name().unwrap());
+    /// }
+    /// // The user picked the first park
+    /// if let Some(park) = parks.get(0) {
+    ///     let detail = park.hydrate(Detail::TAGS | Detail::GEOMETRY).unwrap();
+    ///     println!("{:?}", detail.feature.tags);
+    /// }
+    /// ```
+    pub fn hydrate(&self, detail: Detail) -> Result<Hydrated, GeoDeskError> {
+        let mut data = ffi::bridge::FeatureData::default();
+        ffi::bridge::handle_hydrate(
+            self.handle,
+            detail.contains(Detail::TAGS),
+            detail.contains(Detail::GEOMETRY),
+            &mut data,
+        )?;
+        let members = if detail.contains(Detail::MEMBERS) {
+            ffi::bridge::handle_members(self.handle)?
+                .into_iter()
+                .map(MemberGeometry::from)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(Hydrated {
+            feature: data.into(),
+            members,
+        })
+    }
+
+    /// Read the feature with its tags and geometry
+    pub fn to_feature(&self) -> Result<Feature, GeoDeskError> {
+        Ok(self.hydrate(Detail::TAGS | Detail::GEOMETRY)?.feature)
+    }
+}
+
+/// Tags of a [`FeatureRef`], read lazily (see [`FeatureRef::tags`])
+///
+/// Yields an error only for tags that are not valid UTF-8: always for
+/// keys, and for values in a GOL opened with
+/// [`Utf8Policy::Strict`](crate::options::Utf8Policy::Strict).
+pub struct Tags<'a> {
+    cursor: UniquePtr<ffi::bridge::TagCursor>,
+    handle: &'a ffi::bridge::FeatureHandle,
+}
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = Result<(&'a str, String), GeoDeskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !ffi::bridge::tag_cursor_next(self.cursor.pin_mut()) {
+            return None;
+        }
+        let key = match ffi::bridge::tag_cursor_key(&self.cursor, self.handle) {
+            Ok(key) => key,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(
+            ffi::bridge::tag_cursor_value(&self.cursor)
+                .map(|value| (key, value))
+                .map_err(GeoDeskError::from),
+        )
+    }
+}
+
+impl std::fmt::Debug for FeatureRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeatureRef")
+            .field("type", &self.type_name())
+            .field("id", &self.id())
+            .finish()
+    }
+}
+
+/// The closure of [`GeoDesk::for_each`], as passed through the bridge
+pub struct FeatureVisitor<'a> {
+    f: &'a mut dyn FnMut(FeatureRef<'_>) -> ControlFlow<()>,
+    // A panic of the closure, caught before it could unwind into C++
+    panic: Option<GeoDeskError>,
+}
+
+/// Called from the C++ query loop for each feature; returns `false` to stop
+pub(crate) fn visit_feature(
+    visitor: &mut FeatureVisitor<'_>,
+    handle: &ffi::bridge::FeatureHandle,
+) -> bool {
+    // Unwinding out of an extern "Rust" function aborts the process, so
+    // stop the query instead and report the panic once back in Rust
+    let f = &mut visitor.f;
+    match panic::catch_unwind(AssertUnwindSafe(|| f(FeatureRef { handle }))) {
+        Ok(flow) => flow.is_continue(),
+        Err(payload) => {
+            visitor.panic = Some(GeoDeskError::from_panic(payload));
+            false
+        }
+    }
+}
+
+/// Called from the C++ query loop of [`GeoDesk::query_with`] for each
+/// feature, before it is copied; returns `true` if it matches every tag
+/// pattern of the query
+pub(crate) fn query_matches_tags(
+    control: &QueryControl,
+    handle: &ffi::bridge::FeatureHandle,
+) -> bool {
+    let feature = FeatureRef { handle };
+    control.patterns().iter().all(|p| p.matches(&feature))
+}
+
+/// The predicate of [`GeoDesk::query_filtered`], as passed through the bridge
+pub struct FeatureFilter<'a> {
+    f: &'a mut dyn FnMut(&FeatureRef<'_>) -> bool,
+    panic: Option<GeoDeskError>,
+}
+
+/// Called from the C++ query loop for each feature, before it is copied
+pub(crate) fn filter_feature(
+    filter: &mut FeatureFilter<'_>,
+    handle: &ffi::bridge::FeatureHandle,
+) -> ffi::bridge::FilterVerdict {
+    let f = &mut filter.f;
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&FeatureRef { handle }))) {
+        Ok(true) => ffi::bridge::FilterVerdict::Keep,
+        Ok(false) => ffi::bridge::FilterVerdict::Skip,
+        Err(payload) => {
+            filter.panic = Some(GeoDeskError::from_panic(payload));
+            ffi::bridge::FilterVerdict::Stop
+        }
+    }
+}
+
+impl GeoDesk {
+    /// Pass each feature matching a GOQL query to a closure, as it is found
+    ///
+    /// The closure runs inside the query loop, so nothing is collected or
+    /// copied up front, and returning [`ControlFlow::Break`] ends the query
+    /// early. The [`FeatureRef`] is only valid during the call; hydrate it
+    /// or copy what is needed to keep it. If the closure panics, the query
+    /// stops and fails with [`GeoDeskError::Panicked`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    /// use std::ops::ControlFlow;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let mut names = Vec::new();
+    /// geodesk
+    ///     .for_each("na[amenity=cafe][name]", bboxes::COPENHAGEN, |cafe| {
+    ///         names.push(cafe.name().unwrap());
+    ///         if names.len() == 10 {
+    ///             ControlFlow::Break(())
+    ///         } else {
+    ///             ControlFlow::Continue(())
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn for_each<F>(&self, goql_query: &str, bbox: BoundingBox, f: F) -> Result<(), GeoDeskError>
+    where
+        F: FnMut(FeatureRef<'_>) -> ControlFlow<()>,
+    {
+        self.for_each_with(goql_query, bbox, &QueryOptions::new(), f)
+    }
+
+    /// [`GeoDesk::for_each`], failing with [`GeoDeskError::TimedOut`] or
+    /// [`GeoDeskError::Cancelled`] when the timeout or cancellation token
+    /// of `options` stops it
+    ///
+    /// The query area is checked in cells of a zoom-8 tile, so even a scan
+    /// that rarely finds a match stops promptly; a feature spanning cells
+    /// is still visited once. Other settings of `options` don't apply.
+    pub fn for_each_with<F>(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        options: &QueryOptions,
+        mut f: F,
+    ) -> Result<(), GeoDeskError>
+    where
+        F: FnMut(FeatureRef<'_>) -> ControlFlow<()>,
+    {
+        self.measure(
+            "for_each",
+            |_| None,
+            || {
+                self.check_tiles(bbox)?;
+                let _active = self.activity.begin();
+                let mut visitor = FeatureVisitor {
+                    f: &mut f,
+                    panic: None,
+                };
+                let control = options.control();
+                let stopped = ffi::bridge::query_for_each(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                    &mut visitor,
+                    &control,
+                )?;
+                match visitor.panic {
+                    Some(e) => Err(e),
+                    None if stopped => Err(options.stopped_error(&control)),
+                    None => Ok(()),
+                }
+            },
+        )
+    }
+
+    /// Query features using GOQL, keeping only those a predicate accepts
+    ///
+    /// The predicate runs inside the query loop and sees each match as a
+    /// [`FeatureRef`], so rejected features are never copied. Use it for
+    /// conditions GOQL can't express, reading only the tags they need
+    /// with [`FeatureRef::tag`]. If the predicate panics, the query stops
+    /// and fails with [`GeoDeskError::Panicked`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// // Buildings with more than ten levels
+    /// let towers = geodesk
+    ///     .query_filtered("a[building]", bboxes::COPENHAGEN, |building| {
+    ///         building
+    ///             .tag("building:levels")
+    ///             .ok()
+    ///             .flatten()
+    ///             .and_then(|levels| levels.parse::<u32>().ok())
+    ///             .is_some_and(|levels| levels > 10)
+    ///     })
+    ///     .unwrap();
+    /// println!("{} towers", towers.count());
+    /// ```
+    pub fn query_filtered<F>(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        f: F,
+    ) -> Result<QueryResult, GeoDeskError>
+    where
+        F: FnMut(&FeatureRef<'_>) -> bool,
+    {
+        self.query_filtered_with(goql_query, bbox, &QueryOptions::new(), f)
+    }
+
+    /// [`GeoDesk::query_filtered`], stopped by the timeout or cancellation
+    /// token of `options` like [`GeoDesk::for_each_with`]
+    pub fn query_filtered_with<F>(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        options: &QueryOptions,
+        mut f: F,
+    ) -> Result<QueryResult, GeoDeskError>
+    where
+        F: FnMut(&FeatureRef<'_>) -> bool,
+    {
+        self.measure(
+            "query_filtered",
+            |r: &QueryResult| Some(r.count() as u64),
+            || {
+                let missing_tiles = self.check_tiles(bbox)?;
+                let watermark = self.watermark();
+                let _active = self.activity.begin();
+                let mut filter = FeatureFilter {
+                    f: &mut f,
+                    panic: None,
+                };
+                let control = options.control();
+                let result = ffi::bridge::query_filtered(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                    &mut filter,
+                    &control,
+                )?;
+                match filter.panic {
+                    Some(e) => Err(e),
+                    None if ffi::bridge::result_stopped(&result) => {
+                        Err(options.stopped_error(&control))
+                    }
+                    None => Ok(QueryResult::new(result, missing_tiles, watermark)),
+                }
+            },
+        )
+    }
+
+    /// Query features as lightweight handles
+    ///
+    /// Listing the result only reads ids, names and locations; use
+    /// [`FeatureRef::hydrate`] to fetch tags, geometry or members of the
+    /// features that need them.
+    pub fn query_refs(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+    ) -> Result<FeatureRefs<'_>, GeoDeskError> {
+        self.query_refs_with(goql_query, bbox, &QueryOptions::new())
+    }
+
+    /// [`GeoDesk::query_refs`], stopped by the timeout or cancellation
+    /// token of `options` like [`GeoDesk::for_each_with`]
+    pub fn query_refs_with(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        options: &QueryOptions,
+    ) -> Result<FeatureRefs<'_>, GeoDeskError> {
+        self.measure(
+            "query_refs",
+            |r: &FeatureRefs<'_>| Some(r.len() as u64),
+            || {
+                let missing_tiles = self.check_tiles(bbox)?;
+                let _active = self.activity.begin();
+                let control = options.control();
+                let list = ffi::bridge::query_handles(
+                    &self.store,
+                    goql_query,
+                    bbox.west,
+                    bbox.south,
+                    bbox.east,
+                    bbox.north,
+                    &control,
+                )?;
+                if ffi::bridge::list_stopped(&list) {
+                    return Err(options.stopped_error(&control));
+                }
+                Ok(FeatureRefs::new(list, missing_tiles))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detail_flags() {
+        let detail = Detail::TAGS | Detail::MEMBERS;
+        assert!(detail.contains(Detail::TAGS));
+        assert!(!detail.contains(Detail::GEOMETRY));
+        assert!(Detail::ALL.contains(detail));
+        assert!(Detail::TAGS.contains(Detail::NONE));
+    }
+
+    #[test]
+    fn test_panic_to_error() {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let formatted = panic::catch_unwind(|| panic!("feature {}", 7)).unwrap_err();
+        let literal = panic::catch_unwind(|| panic!("no tags")).unwrap_err();
+        panic::set_hook(hook);
+
+        let error = GeoDeskError::from_panic(formatted);
+        assert_eq!(error.kind(), "panicked");
+        assert_eq!(error.to_string(), "Callback panicked: feature 7");
+        assert!(matches!(
+            GeoDeskError::from_panic(literal),
+            GeoDeskError::Panicked(m) if m == "no tags"
+        ));
+    }
+
+    #[cfg(feature = "test-data")]
+    #[test]
+    fn test_osm_metadata_unavailable() {
+        let Some(geodesk) = crate::test_data::TestGol::try_open() else {
+            return;
+        };
+        assert!(!geodesk.metadata().unwrap().osm_metadata);
+        let refs = geodesk
+            .query_refs("n", crate::testing::bboxes::MONACO)
+            .unwrap();
+        let node = refs.iter().next().expect("nodes in Monaco");
+        assert!(matches!(
+            node.version(),
+            Err(GeoDeskError::MetadataUnavailable)
+        ));
+        assert!(node.timestamp().is_err());
+        assert!(node.changeset().is_err());
+    }
+
+    #[cfg(feature = "test-data")]
+    #[test]
+    fn test_cancelled_scans() {
+        let Some(geodesk) = crate::test_data::TestGol::try_open() else {
+            return;
+        };
+        let token = crate::cancel::CancellationToken::new();
+        token.cancel();
+        let options = QueryOptions::new().cancellation(token);
+        let monaco = crate::testing::bboxes::MONACO;
+        let mut visited = 0;
+        let result = geodesk.for_each_with("*", monaco, &options, |_| {
+            visited += 1;
+            ControlFlow::Continue(())
+        });
+        assert!(matches!(result, Err(GeoDeskError::Cancelled)));
+        assert_eq!(visited, 0);
+        assert!(matches!(
+            geodesk.query_refs_with("*", monaco, &options),
+            Err(GeoDeskError::Cancelled)
+        ));
+        assert!(matches!(
+            geodesk.query_filtered_with("*", monaco, &options, |_| true),
+            Err(GeoDeskError::Cancelled)
+        ));
+
+        // Features spanning cells of a stoppable scan are visited once
+        let running = QueryOptions::new().cancellation(Default::default());
+        let world = crate::testing::bboxes::WORLD;
+        let plain = geodesk.query_refs("w[highway]", world).unwrap().len();
+        let checked = geodesk
+            .query_refs_with("w[highway]", world, &running)
+            .unwrap();
+        assert_eq!(checked.len(), plain);
+    }
+}
@@ -0,0 +1,485 @@
+// GOLs read from remote storage, fetching tiles with range requests as
+// queries need them
+
+use crate::error::GeoDeskError;
+use crate::testing::bboxes;
+use crate::verify::PAGE_SIZE;
+use crate::{BoundingBox, GeoDesk, OpenOptions};
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions as FileOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Bytes fetched from the start of the file before its tile index is read
+const INITIAL_FETCH: u64 = 1 << 20;
+
+/// Times the start of the file is extended until it reaches the first tile
+const MAX_INDEX_FETCHES: usize = 8;
+
+/// Storage that GOLs can be read from in byte ranges, such as an HTTP
+/// server or an object store (see [`OpenOptions::open_object_store`])
+pub trait RangeStore: Send + Sync {
+    /// Size of the object in bytes, and a version (such as its ETag) that
+    /// changes whenever its content does
+    fn head(&self, key: &str) -> Result<(u64, String), GeoDeskError>;
+
+    /// Read a range of bytes of the object
+    fn get_range(&self, key: &str, range: Range<u64>) -> Result<Vec<u8>, GeoDeskError>;
+
+    /// Name of the object that sets it apart from objects of other stores
+    /// sharing the tile cache, such as its URL
+    fn location(&self, key: &str) -> String {
+        key.to_string()
+    }
+}
+
+/// Objects served over HTTP(S), keyed by URL
+pub struct Http;
+
+fn http_error(url: &str, e: impl std::fmt::Display) -> GeoDeskError {
+    GeoDeskError::Engine(format!("Fetching {} failed: {}", url, e))
+}
+
+impl RangeStore for Http {
+    fn head(&self, url: &str) -> Result<(u64, String), GeoDeskError> {
+        let response = ureq::head(url).call().map_err(|e| http_error(url, e))?;
+        let size = response
+            .header("Content-Length")
+            .and_then(|len| len.parse().ok())
+            .ok_or_else(|| http_error(url, "no Content-Length"))?;
+        let version = response
+            .header("ETag")
+            .or_else(|| response.header("Last-Modified"))
+            .unwrap_or_default()
+            .to_string();
+        Ok((size, version))
+    }
+
+    fn get_range(&self, url: &str, range: Range<u64>) -> Result<Vec<u8>, GeoDeskError> {
+        let response = ureq::get(url)
+            .set("Range", &format!("bytes={}-{}", range.start, range.end - 1))
+            .call()
+            .map_err(|e| http_error(url, e))?;
+        if response.status() != 206 {
+            return Err(http_error(url, "the server doesn't support range requests"));
+        }
+        let mut data = Vec::with_capacity((range.end - range.start) as usize);
+        response
+            .into_reader()
+            .take(range.end - range.start)
+            .read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// Runtime the requests of object stores run on; `RangeStore` calls block
+/// on it, so they must not be made from async code (wrap them in
+/// `tokio::task::spawn_blocking`)
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("geodesk-object-store")
+            .enable_all()
+            .build()
+            .expect("the object store runtime could not be started")
+    })
+}
+
+fn object_store_error(store: &dyn ObjectStore, key: &str, e: object_store::Error) -> GeoDeskError {
+    GeoDeskError::Engine(format!("Fetching {}/{} failed: {}", store, key, e))
+}
+
+/// Objects of an [`object_store`] store (S3, GCS, Azure, local files or
+/// memory), keyed by path
+impl RangeStore for Arc<dyn ObjectStore> {
+    fn head(&self, key: &str) -> Result<(u64, String), GeoDeskError> {
+        let meta = runtime()
+            .block_on(self.as_ref().head(&ObjectPath::from(key)))
+            .map_err(|e| object_store_error(self.as_ref(), key, e))?;
+        let version = meta
+            .e_tag
+            .or(meta.version)
+            .unwrap_or_else(|| meta.last_modified.to_rfc3339());
+        Ok((meta.size, version))
+    }
+
+    fn get_range(&self, key: &str, range: Range<u64>) -> Result<Vec<u8>, GeoDeskError> {
+        let bytes = runtime()
+            .block_on(self.as_ref().get_range(&ObjectPath::from(key), range))
+            .map_err(|e| object_store_error(self.as_ref(), key, e))?;
+        Ok(bytes.to_vec())
+    }
+
+    fn location(&self, key: &str) -> String {
+        format!("{}/{}", self, key)
+    }
+}
+
+/// Local copy of a remote GOL: a sparse file of the remote file's size,
+/// filled in tile by tile, with a state file listing what was fetched
+pub(crate) struct RemoteSource {
+    store: Box<dyn RangeStore>,
+    key: String,
+    file: File,
+    size: u64,
+    state_path: PathBuf,
+    /// First pages of the tiles in file order, to tell where each ends
+    pages: Vec<u32>,
+    /// Pages of the tiles fetched so far; held while fetching, so
+    /// concurrent queries don't download the same tiles
+    fetched: Mutex<HashSet<u32>>,
+}
+
+/// Fetch a range into the local copy
+fn fetch_range(
+    store: &dyn RangeStore,
+    key: &str,
+    file: &File,
+    range: Range<u64>,
+) -> Result<(), GeoDeskError> {
+    let data = store.get_range(key, range.clone())?;
+    if data.len() as u64 != range.end - range.start {
+        return Err(GeoDeskError::Engine(format!(
+            "Fetching {} failed: expected {} bytes, got {}",
+            store.location(key),
+            range.end - range.start,
+            data.len()
+        )));
+    }
+    let mut file = file;
+    file.seek(SeekFrom::Start(range.start))?;
+    file.write_all(&data)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Stable name of the local copy of a URL (64-bit FNV-1a)
+fn cache_name(url: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in url.bytes() {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// What was fetched into a local copy: `(length of the fetched start of
+/// the file, tile pages)`, or `None` if the copy is of another version
+fn read_state(path: &Path, header: &str) -> Option<(u64, HashSet<u32>)> {
+    let state = fs::read_to_string(path).ok()?;
+    let mut lines = state.lines();
+    if lines.next()? != header {
+        return None;
+    }
+    let mut prefix = 0;
+    let mut pages = HashSet::new();
+    for line in lines {
+        match line.split_once(' ') {
+            Some(("prefix", len)) => prefix = len.parse().ok()?,
+            Some(("page", page)) => {
+                pages.insert(page.parse().ok()?);
+            }
+            _ => return None,
+        }
+    }
+    Some((prefix, pages))
+}
+
+fn append_state(path: &Path, lines: &str) -> Result<(), GeoDeskError> {
+    let mut state = FileOptions::new().append(true).create(true).open(path)?;
+    state.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+impl RemoteSource {
+    /// Fetch the tiles intersecting a bounding box that aren't local yet;
+    /// returns how many were fetched
+    pub(crate) fn fetch(
+        &self,
+        geodesk: &GeoDesk,
+        bbox: BoundingBox,
+    ) -> Result<usize, GeoDeskError> {
+        let mut needed: Vec<u32> = geodesk
+            .tiles(bbox)?
+            .into_iter()
+            .filter(|tile| tile.present)
+            .map(|tile| tile.page)
+            .collect();
+        let mut fetched = self.fetched.lock().unwrap_or_else(|e| e.into_inner());
+        needed.retain(|page| !fetched.contains(page));
+        if needed.is_empty() {
+            return Ok(0);
+        }
+        needed.sort_unstable();
+        needed.dedup();
+        // Runs of tiles that follow each other in the file are fetched in
+        // one request
+        let mut count = 0;
+        let mut i = 0;
+        while i < needed.len() {
+            let start = u64::from(needed[i]) * PAGE_SIZE;
+            let mut end = self.tile_end(needed[i]);
+            let mut j = i + 1;
+            while j < needed.len() && u64::from(needed[j]) * PAGE_SIZE == end {
+                end = self.tile_end(needed[j]);
+                j += 1;
+            }
+            fetch_range(&*self.store, &self.key, &self.file, start..end)?;
+            let run = &needed[i..j];
+            let lines: String = run.iter().map(|page| format!("page {}\n", page)).collect();
+            append_state(&self.state_path, &lines)?;
+            fetched.extend(run);
+            count += run.len();
+            i = j;
+        }
+        Ok(count)
+    }
+
+    /// End of the tile starting at `page`: the start of the next tile, or
+    /// the end of the file
+    fn tile_end(&self, page: u32) -> u64 {
+        match self.pages.binary_search(&page) {
+            Ok(i) if i + 1 < self.pages.len() => u64::from(self.pages[i + 1]) * PAGE_SIZE,
+            _ => self.size,
+        }
+    }
+}
+
+impl OpenOptions {
+    /// Open a GOL served over HTTP(S), fetching its tiles with range
+    /// requests as queries need them
+    ///
+    /// The tiles are kept in a local copy in `cache_dir` (or else the tile
+    /// cache, see [`OpenOptions::tile_cache`]) that later opens of the same
+    /// URL reuse; the copy starts over when the remote file changes. The
+    /// server must support range requests and report the file's size.
+    ///
+    /// Tiles are fetched for the bounding box of each query. Reading the
+    /// members or parents of relations can reach into tiles outside it;
+    /// fetch those first with [`GeoDesk::prefetch`].
+    pub fn open_url(&self, url: &str, cache_dir: Option<&Path>) -> Result<GeoDesk, GeoDeskError> {
+        self.open_remote(Http, url, cache_dir)
+    }
+
+    /// Open a GOL kept in an object store such as S3, GCS or Azure Blob
+    /// Storage, fetching its tiles as queries need them (see
+    /// [`OpenOptions::open_url`])
+    ///
+    /// Any [`RangeStore`] works; `Arc<dyn ObjectStore>` is one, so stores
+    /// of the `object_store` crate can be passed as they are. Enable the
+    /// crate's `aws`, `gcp` or `azure` feature for the cloud stores.
+    ///
+    /// # Example
+    /// ```
+    /// use geodesk_rs::OpenOptions;
+    /// use object_store::memory::InMemory;
+    /// use object_store::ObjectStore;
+    /// use std::sync::Arc;
+    ///
+    /// // An S3 bucket would be `AmazonS3Builder::from_env().with_bucket_name("maps").build()`
+    /// let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    /// let missing = OpenOptions::new()
+    ///     .tile_cache(std::env::temp_dir().join("geodesk-doctest"))
+    ///     .open_object_store(store, "gol/planet.gol");
+    /// assert!(missing.is_err());
+    /// ```
+    pub fn open_object_store<S: RangeStore + 'static>(
+        &self,
+        store: S,
+        key: &str,
+    ) -> Result<GeoDesk, GeoDeskError> {
+        self.open_remote(store, key, None)
+    }
+
+    fn open_remote<S: RangeStore + 'static>(
+        &self,
+        store: S,
+        key: &str,
+        cache_dir: Option<&Path>,
+    ) -> Result<GeoDesk, GeoDeskError> {
+        let (size, version) = store.head(key)?;
+        let dir = cache_dir.or(self.tile_cache.as_deref()).map_or_else(
+            || std::env::temp_dir().join("geodesk-remote"),
+            Path::to_path_buf,
+        );
+        fs::create_dir_all(&dir)?;
+        let name = cache_name(&store.location(key));
+        let path = dir.join(format!("{}.gol", name));
+        let state_path = dir.join(format!("{}.tiles", name));
+        let header = format!("{} {}", size, version);
+
+        let (mut prefix, fetched) = match read_state(&state_path, &header) {
+            Some(state) if path.is_file() => state,
+            _ => {
+                let file = File::create(&path)?;
+                file.set_len(size)?;
+                fs::write(&state_path, format!("{}\n", header))?;
+                (0, HashSet::new())
+            }
+        };
+        let file = FileOptions::new().read(true).write(true).open(&path)?;
+
+        // Fetch the start of the file until it reaches the first tile, so
+        // the header and the whole tile index are local
+        let mut wanted = INITIAL_FETCH.min(size);
+        for _ in 0..MAX_INDEX_FETCHES {
+            if wanted > prefix {
+                fetch_range(&store, key, &file, prefix..wanted)?;
+                prefix = wanted;
+                append_state(&state_path, &format!("prefix {}\n", prefix))?;
+            }
+            let mut geodesk = self.open(&path)?;
+            let mut pages: Vec<u32> = geodesk
+                .tiles(bboxes::WORLD)?
+                .into_iter()
+                .filter(|tile| tile.present)
+                .map(|tile| tile.page)
+                .collect();
+            pages.sort_unstable();
+            pages.dedup();
+            let first = pages
+                .first()
+                .map_or(size, |&page| u64::from(page) * PAGE_SIZE);
+            if first <= prefix {
+                geodesk.remote = Some(RemoteSource {
+                    store: Box::new(store),
+                    key: key.to_string(),
+                    file,
+                    size,
+                    state_path,
+                    pages,
+                    fetched: Mutex::new(fetched),
+                });
+                return Ok(geodesk);
+            }
+            wanted = first.min(size);
+        }
+        Err(GeoDeskError::Engine(format!(
+            "Fetching {} failed: the tile index could not be read",
+            store.location(key)
+        )))
+    }
+}
+
+impl GeoDesk {
+    /// Open a GOL served over HTTP(S) with default options, caching its
+    /// tiles in the temporary directory (see [`OpenOptions::open_url`])
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let planet = GeoDesk::open_url("https://example.com/planet.gol").unwrap();
+    /// // Only the tiles around Copenhagen are downloaded
+    /// let cafes = planet.query("na[amenity=cafe]", bboxes::COPENHAGEN).unwrap();
+    /// ```
+    pub fn open_url(url: &str) -> Result<Self, GeoDeskError> {
+        OpenOptions::new().open_url(url, None)
+    }
+
+    /// Open a GOL kept in an object store with default options (see
+    /// [`OpenOptions::open_object_store`])
+    pub fn open_object_store<S: RangeStore + 'static>(
+        store: S,
+        key: &str,
+    ) -> Result<Self, GeoDeskError> {
+        OpenOptions::new().open_object_store(store, key)
+    }
+
+    /// Fetch the tiles of a remote GOL intersecting a bounding box ahead
+    /// of queries; returns how many were fetched (0 for local GOLs)
+    pub fn prefetch(&self, bbox: BoundingBox) -> Result<usize, GeoDeskError> {
+        match &self.remote {
+            Some(remote) => remote.fetch(self, bbox),
+            None => Ok(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("geodesk-remote-{}.tiles", std::process::id()));
+        fs::write(&path, "8192 \"abc\"\n").unwrap();
+        append_state(&path, "prefix 4096\npage 3\npage 4\n").unwrap();
+        let (prefix, pages) = read_state(&path, "8192 \"abc\"").unwrap();
+        assert_eq!(prefix, 4096);
+        assert_eq!(pages, HashSet::from([3, 4]));
+        // A changed remote file invalidates the copy
+        assert!(read_state(&path, "8192 \"def\"").is_none());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(cache_name("https://example.com/planet.gol").len(), 16);
+        assert_ne!(cache_name("a"), cache_name("b"));
+    }
+
+    struct Memory(Vec<u8>);
+
+    impl RangeStore for Memory {
+        fn head(&self, _key: &str) -> Result<(u64, String), GeoDeskError> {
+            Ok((self.0.len() as u64, String::new()))
+        }
+
+        fn get_range(&self, _key: &str, range: Range<u64>) -> Result<Vec<u8>, GeoDeskError> {
+            let end = (range.end as usize).min(self.0.len());
+            Ok(self.0[range.start as usize..end].to_vec())
+        }
+    }
+
+    #[test]
+    fn test_fetch_range() {
+        let path = std::env::temp_dir().join(format!("geodesk-remote-{}.gol", std::process::id()));
+        let file = FileOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.set_len(16).unwrap();
+        let store = Memory((0..16).collect());
+        fetch_range(&store, "planet.gol", &file, 4..8).unwrap();
+        let data = fs::read(&path).unwrap();
+        assert_eq!(data[..4], [0; 4]);
+        assert_eq!(data[4..8], [4, 5, 6, 7]);
+        assert_eq!(data[8..], [0; 8]);
+        // A short response must not be taken for the whole range
+        assert!(fetch_range(&store, "planet.gol", &file, 12..20).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_object_store() {
+        use object_store::memory::InMemory;
+
+        let memory = InMemory::new();
+        runtime()
+            .block_on(memory.put(
+                &ObjectPath::from("gol/planet.gol"),
+                (0..16u8).collect::<Vec<_>>().into(),
+            ))
+            .unwrap();
+        let store: Arc<dyn ObjectStore> = Arc::new(memory);
+        let (size, version) = RangeStore::head(&store, "gol/planet.gol").unwrap();
+        assert_eq!(size, 16);
+        assert!(!version.is_empty());
+        assert_eq!(
+            RangeStore::get_range(&store, "gol/planet.gol", 4..8).unwrap(),
+            [4, 5, 6, 7]
+        );
+        assert!(RangeStore::head(&store, "gol/missing.gol").is_err());
+        assert!(store
+            .location("gol/planet.gol")
+            .ends_with("/gol/planet.gol"));
+    }
+}
@@ -0,0 +1,179 @@
+// Road classes from the `highway` tag, ranked by importance
+
+use crate::error::GeoDeskError;
+use crate::{BoundingBox, Feature, GeoDesk, QueryResult};
+use std::fmt;
+use std::str::FromStr;
+
+/// Importance of a road, from the `highway` tag
+///
+/// Classes compare by importance, so `Motorway > Trunk > ... > Path`.
+/// `_link` roads belong to the class of the road they connect to, living
+/// streets count as residential, and footways, cycleways, bridleways,
+/// steps and pedestrian streets as paths.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::roads::HighwayClass;
+///
+/// let class: HighwayClass = "primary_link".parse().unwrap();
+/// assert_eq!(class, HighwayClass::Primary);
+/// assert!(class >= HighwayClass::Secondary);
+/// assert!(HighwayClass::Track < HighwayClass::Service);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HighwayClass {
+    // Declared from least to most important, for the derived ordering
+    Path,
+    Track,
+    Service,
+    Residential,
+    Unclassified,
+    Tertiary,
+    Secondary,
+    Primary,
+    Trunk,
+    Motorway,
+}
+
+impl HighwayClass {
+    /// Every class, most important first
+    pub const ALL: [HighwayClass; 10] = [
+        HighwayClass::Motorway,
+        HighwayClass::Trunk,
+        HighwayClass::Primary,
+        HighwayClass::Secondary,
+        HighwayClass::Tertiary,
+        HighwayClass::Unclassified,
+        HighwayClass::Residential,
+        HighwayClass::Service,
+        HighwayClass::Track,
+        HighwayClass::Path,
+    ];
+
+    /// Lowercase name, the `highway` value of the class's main roads
+    pub fn name(self) -> &'static str {
+        match self {
+            HighwayClass::Motorway => "motorway",
+            HighwayClass::Trunk => "trunk",
+            HighwayClass::Primary => "primary",
+            HighwayClass::Secondary => "secondary",
+            HighwayClass::Tertiary => "tertiary",
+            HighwayClass::Unclassified => "unclassified",
+            HighwayClass::Residential => "residential",
+            HighwayClass::Service => "service",
+            HighwayClass::Track => "track",
+            HighwayClass::Path => "path",
+        }
+    }
+
+    /// The `highway` values of the class
+    pub fn values(self) -> &'static [&'static str] {
+        match self {
+            HighwayClass::Motorway => &["motorway", "motorway_link"],
+            HighwayClass::Trunk => &["trunk", "trunk_link"],
+            HighwayClass::Primary => &["primary", "primary_link"],
+            HighwayClass::Secondary => &["secondary", "secondary_link"],
+            HighwayClass::Tertiary => &["tertiary", "tertiary_link"],
+            HighwayClass::Unclassified => &["unclassified"],
+            HighwayClass::Residential => &["residential", "living_street"],
+            HighwayClass::Service => &["service"],
+            HighwayClass::Track => &["track"],
+            HighwayClass::Path => &[
+                "path",
+                "footway",
+                "cycleway",
+                "bridleway",
+                "steps",
+                "pedestrian",
+            ],
+        }
+    }
+
+    /// The class of a `highway` value, if it is a kind of road
+    pub fn from_value(value: &str) -> Option<HighwayClass> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.values().contains(&value))
+    }
+
+    /// The class of a way's `highway` tag
+    pub fn of(feature: &Feature) -> Option<HighwayClass> {
+        feature.tag("highway").and_then(Self::from_value)
+    }
+
+    /// GOQL query selecting the ways of this class and more important ones
+    pub fn goql_at_least(self) -> String {
+        let values: Vec<&str> = Self::ALL
+            .into_iter()
+            .filter(|&class| class >= self)
+            .flat_map(|class| class.values().iter().copied())
+            .collect();
+        format!("w[highway={}]", values.join(","))
+    }
+}
+
+impl fmt::Display for HighwayClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HighwayClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_value(s).ok_or_else(|| format!("Not a road class: {}", s))
+    }
+}
+
+impl Feature {
+    /// The road class of the feature's `highway` tag, if it is a road
+    pub fn highway_class(&self) -> Option<HighwayClass> {
+        HighwayClass::of(self)
+    }
+}
+
+impl GeoDesk {
+    /// Query the roads of a class and the more important ones, such as
+    /// everything from secondary roads up for a low-zoom map
+    pub fn query_roads_at_least(
+        &self,
+        bbox: BoundingBox,
+        class: HighwayClass,
+    ) -> Result<QueryResult, GeoDeskError> {
+        self.query(&class.goql_at_least(), bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highway_class() {
+        assert!(HighwayClass::Motorway > HighwayClass::Trunk);
+        assert!(HighwayClass::Residential > HighwayClass::Service);
+        assert_eq!(
+            HighwayClass::ALL.iter().max(),
+            Some(&HighwayClass::Motorway)
+        );
+        assert_eq!(HighwayClass::ALL.iter().min(), Some(&HighwayClass::Path));
+        assert!(HighwayClass::ALL.windows(2).all(|w| w[0] > w[1]));
+
+        assert_eq!(
+            HighwayClass::from_value("living_street"),
+            Some(HighwayClass::Residential)
+        );
+        assert_eq!(HighwayClass::from_value("steps"), Some(HighwayClass::Path));
+        assert_eq!(HighwayClass::from_value("bus_stop"), None);
+        for class in HighwayClass::ALL {
+            assert_eq!(class.to_string().parse(), Ok(class));
+        }
+
+        assert_eq!(
+            HighwayClass::Trunk.goql_at_least(),
+            "w[highway=motorway,motorway_link,trunk,trunk_link]"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+// Route relations resolved into ordered ways and stops
+
+use crate::error::GeoDeskError;
+use crate::geometry::{MemberGeometry, RelationGeometry};
+use crate::query::quote;
+use crate::{BoundingBox, Feature, GeoDesk, Node};
+use std::collections::HashMap;
+
+/// Whether a stop member is where the vehicle halts or where passengers wait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopRole {
+    /// `stop`, `stop_entry_only` or `stop_exit_only`: the stop position on
+    /// the road or track
+    Stop,
+    /// `platform`, `platform_entry_only` or `platform_exit_only`
+    Platform,
+}
+
+impl StopRole {
+    /// The stop role of a member role (PTv2), if it is one
+    pub fn from_role(role: &str) -> Option<Self> {
+        if role.starts_with("stop") {
+            Some(StopRole::Stop)
+        } else if role.starts_with("platform") {
+            Some(StopRole::Platform)
+        } else {
+            None
+        }
+    }
+}
+
+/// A stop or platform of a route, in the order the route serves them
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteStop {
+    pub id: i64,
+    /// `"node"` or `"way"` (platforms are often mapped as areas)
+    pub type_name: String,
+    pub role: StopRole,
+    /// Passengers may only board here (`stop_entry_only` and
+    /// `platform_entry_only`)
+    pub entry_only: bool,
+    /// Passengers may only alight here (`stop_exit_only` and
+    /// `platform_exit_only`)
+    pub exit_only: bool,
+    pub lon: f64,
+    pub lat: f64,
+}
+
+/// A way of a route, oriented in the direction of travel
+#[derive(Debug, Clone)]
+pub struct RouteWay {
+    pub id: i64,
+    /// Member role (empty, `forward`, `backward`, ...)
+    pub role: String,
+    /// Nodes in the direction of travel
+    pub nodes: Vec<Node>,
+    /// Whether the nodes were reversed relative to the way
+    pub reversed: bool,
+}
+
+/// A `type=route` relation
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// The relation, with its tags (`route`, `ref`, `name`, `from`, `to`, ...)
+    pub relation: Feature,
+    pub ways: Vec<RouteWay>,
+    pub stops: Vec<RouteStop>,
+}
+
+impl Route {
+    /// Assemble a route from its relation and member geometries
+    ///
+    /// Ways are kept in member order and flipped where needed so that each
+    /// one starts where the previous one ends.
+    pub fn new(relation: Feature, members: &RelationGeometry) -> Self {
+        let mut stops = Vec::new();
+        let mut ways = Vec::new();
+        for member in &members.members {
+            if let Some(role) = StopRole::from_role(&member.role) {
+                stops.push(RouteStop {
+                    id: member.id,
+                    type_name: member.type_name.clone(),
+                    role,
+                    entry_only: member.role.ends_with("_entry_only"),
+                    exit_only: member.role.ends_with("_exit_only"),
+                    lon: member.lon,
+                    lat: member.lat,
+                });
+            } else if member.is_line() {
+                ways.push(member);
+            }
+        }
+        Self {
+            relation,
+            ways: orient(&ways),
+            stops,
+        }
+    }
+
+    /// Value of the `ref` tag (the line number)
+    pub fn reference(&self) -> Option<&str> {
+        self.relation.tag("ref")
+    }
+
+    /// Check if every way starts where the previous one ends
+    pub fn is_continuous(&self) -> bool {
+        self.ways
+            .windows(2)
+            .all(|pair| connects(&pair[0].nodes, &pair[1].nodes))
+    }
+
+    /// All nodes along the route, without repeating the node shared by
+    /// consecutive ways
+    pub fn path(&self) -> Vec<Node> {
+        let mut path: Vec<Node> = Vec::new();
+        for way in &self.ways {
+            let skip = match (path.last(), way.nodes.first()) {
+                (Some(last), Some(first)) => usize::from(same_node(last, first)),
+                _ => 0,
+            };
+            path.extend(way.nodes.iter().skip(skip));
+        }
+        path
+    }
+}
+
+fn same_node(a: &Node, b: &Node) -> bool {
+    if a.id != 0 && b.id != 0 {
+        a.id == b.id
+    } else {
+        a.lon == b.lon && a.lat == b.lat
+    }
+}
+
+fn connects(a: &[Node], b: &[Node]) -> bool {
+    matches!((a.last(), b.first()), (Some(x), Some(y)) if same_node(x, y))
+}
+
+/// Orient the ways so that each continues from the previous one; the first
+/// way is oriented towards the second
+fn orient(members: &[&MemberGeometry]) -> Vec<RouteWay> {
+    let mut ways: Vec<RouteWay> = Vec::with_capacity(members.len());
+    for (i, member) in members.iter().enumerate() {
+        let nodes = &member.nodes;
+        let reversed = match ways.last() {
+            Some(previous) => {
+                let end = previous.nodes.last();
+                !end.is_some_and(|e| same_node(e, &nodes[0]))
+                    && end.is_some_and(|e| same_node(e, &nodes[nodes.len() - 1]))
+            }
+            None => members.get(i + 1).is_some_and(|next| {
+                let first = &nodes[0];
+                let ends = [&next.nodes[0], &next.nodes[next.nodes.len() - 1]];
+                ends.iter().any(|e| same_node(first, e))
+                    && !ends.iter().any(|e| same_node(&nodes[nodes.len() - 1], e))
+            }),
+        };
+        let mut nodes = nodes.clone();
+        if reversed {
+            nodes.reverse();
+        }
+        ways.push(RouteWay {
+            id: member.id,
+            role: member.role.clone(),
+            nodes,
+            reversed,
+        });
+    }
+    ways
+}
+
+impl GeoDesk {
+    /// Route relations of a type (`bus`, `tram`, `bicycle`, `hiking`, ...)
+    /// in a bounding box, resolved into ordered ways and stops
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// for route in geodesk.routes(bboxes::COPENHAGEN, "bus").unwrap() {
+    ///     println!(
+    ///         "{}: {} ways, {} stops",
+    ///         route.reference().unwrap_or("?"),
+    ///         route.ways.len(),
+    ///         route.stops.len()
+    ///     );
+    /// }
+    /// ```
+    pub fn routes(&self, bbox: BoundingBox, route_type: &str) -> Result<Vec<Route>, GeoDeskError> {
+        let query = format!("r[type=route][route={}]", quote(route_type));
+        let mut members: HashMap<i64, RelationGeometry> = self
+            .relation_geometries(&query, bbox)?
+            .into_iter()
+            .map(|r| (r.id, r))
+            .collect();
+        Ok(self
+            .query(&query, bbox)?
+            .to_vec()?
+            .into_iter()
+            .filter_map(|relation| {
+                let geometry = members.remove(&relation.id)?;
+                Some(Route::new(relation, &geometry))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(id: i64, type_name: &str, role: &str, nodes: &[i64]) -> MemberGeometry {
+        MemberGeometry {
+            id,
+            type_name: type_name.to_string(),
+            role: role.to_string(),
+            lon: id as f64,
+            lat: 0.0,
+            nodes: nodes
+                .iter()
+                .map(|&id| Node {
+                    id,
+                    lon: id as f64,
+                    lat: 0.0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_route_assembly() {
+        let relation = Feature {
+            id: 1,
+            type_name: "relation".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: vec![("ref".to_string(), "5C".to_string())],
+            nodes: Vec::new(),
+        };
+        // The first and third ways are mapped against the direction of travel
+        let members = RelationGeometry {
+            id: 1,
+            members: vec![
+                member(100, "node", "stop", &[]),
+                member(101, "way", "platform", &[7, 8, 9, 7]),
+                member(10, "way", "", &[2, 1]),
+                member(11, "way", "", &[2, 3, 4]),
+                member(12, "way", "", &[5, 4]),
+                member(102, "node", "stop_exit_only", &[]),
+            ],
+        };
+        let route = Route::new(relation, &members);
+
+        assert_eq!(route.reference(), Some("5C"));
+        let roles: Vec<StopRole> = route.stops.iter().map(|s| s.role).collect();
+        assert_eq!(
+            roles,
+            vec![StopRole::Stop, StopRole::Platform, StopRole::Stop]
+        );
+        assert!(route.stops[2].exit_only && !route.stops[2].entry_only);
+        let reversed: Vec<bool> = route.ways.iter().map(|w| w.reversed).collect();
+        assert_eq!(reversed, vec![true, false, true]);
+        assert!(route.is_continuous());
+        let path: Vec<i64> = route.path().iter().map(|n| n.id).collect();
+        assert_eq!(path, vec![1, 2, 3, 4, 5]);
+    }
+}
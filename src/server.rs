@@ -0,0 +1,678 @@
+// HTTP server exposing a shared GeoDesk over vector tile and query endpoints
+//
+// Enabled with the `server` cargo feature.
+
+use crate::cancel::CancellationToken;
+use crate::error::GeoDeskError;
+use crate::export::{write_json_string, GeoJsonWriter};
+use crate::mvt::{TileEncoder, TileId};
+use crate::options::{MaxResultsPolicy, QueryOptions};
+use crate::{BoundingBox, FeatureType, GeoDesk};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, Semaphore};
+
+/// Configuration for the embedded HTTP server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Maximum number of GeoDESK queries executing at the same time
+    pub max_concurrent_queries: usize,
+    /// GOQL query used to populate tiles when the request has no `goql` parameter
+    pub tile_query: String,
+    /// Name of the layer written into vector tiles
+    pub layer_name: String,
+    /// Most features returned by `/query`; larger results are cut off and
+    /// marked `"truncated":true`. `None` returns every match.
+    pub max_results: Option<u64>,
+    /// Lowest zoom level for which tiles are served
+    pub min_zoom: u8,
+    /// Highest zoom level for which tiles are served
+    pub max_zoom: u8,
+    /// Update notifications served at `/changes`; `None` disables the
+    /// endpoint
+    pub changes: Option<Arc<ChangeFeed>>,
+    /// How often [`serve`] checks whether the GOL was updated by another
+    /// process, when `changes` is set
+    pub change_poll_interval: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_queries: 8,
+            tile_query: "*".to_string(),
+            layer_name: "features".to_string(),
+            max_results: Some(10_000),
+            min_zoom: 12,
+            max_zoom: 20,
+            changes: None,
+            change_poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+struct AppState {
+    geodesk: Arc<GeoDesk>,
+    config: ServerConfig,
+    permits: Semaphore,
+}
+
+/// Notices a [`ChangeFeed`] keeps for clients that fall behind
+const MAX_NOTICES: usize = 256;
+
+/// Longest time a `/changes` request waits for an update
+const MAX_CHANGES_WAIT: Duration = Duration::from_secs(60);
+
+/// The tiles touched by one update of the GOL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeNotice {
+    /// Revision of the GOL after the update
+    pub revision: u32,
+    /// Tiles whose features changed; empty if not known, in which case
+    /// clients should refresh everything
+    pub tiles: Vec<TileId>,
+}
+
+/// Recent updates of the GOL, which `/changes` requests wait on
+///
+/// Whatever applies updates to the GOL publishes the tiles it changed;
+/// [`serve`] also checks the GOL's revision periodically and publishes
+/// updates made by other processes (without tiles, as these are not known).
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::server::{serve, ChangeFeed, ServerConfig};
+/// use geodesk_rs::tiles::TileId;
+/// use geodesk_rs::GeoDesk;
+/// use std::sync::Arc;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let geodesk = Arc::new(GeoDesk::open("denmark.gol").unwrap());
+/// let changes = Arc::new(ChangeFeed::new());
+/// let config = ServerConfig {
+///     changes: Some(changes.clone()),
+///     ..Default::default()
+/// };
+/// // After applying an update that touched one tile:
+/// changes.publish(geodesk.watermark().revision, vec![TileId::new(12, 2190, 1281).unwrap()]);
+/// serve(geodesk, "127.0.0.1:8080".parse().unwrap(), config).await
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ChangeFeed {
+    state: Mutex<FeedState>,
+    published: Notify,
+}
+
+#[derive(Debug, Default)]
+struct FeedState {
+    notices: VecDeque<ChangeNotice>,
+    // Revision of the newest notice dropped to stay within MAX_NOTICES
+    dropped: Option<u32>,
+}
+
+impl ChangeFeed {
+    /// An empty feed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Announce an update and wake the waiting `/changes` requests
+    ///
+    /// Notices for a revision at or below the latest one are ignored.
+    pub fn publish(&self, revision: u32, tiles: Vec<TileId>) {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.notices.back().is_some_and(|n| n.revision >= revision) {
+                return;
+            }
+            if state.notices.len() == MAX_NOTICES {
+                state.dropped = state.notices.pop_front().map(|n| n.revision);
+            }
+            state.notices.push_back(ChangeNotice { revision, tiles });
+        }
+        self.published.notify_waiters();
+    }
+
+    /// Revision of the latest update published
+    pub fn latest_revision(&self) -> Option<u32> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.notices.back().map(|n| n.revision)
+    }
+
+    /// The updates after `revision`
+    ///
+    /// If some of them were dropped, returns a single notice for the
+    /// latest revision without tiles instead, so the client refreshes
+    /// everything.
+    pub fn since(&self, revision: u32) -> Vec<ChangeNotice> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.dropped.is_some_and(|dropped| dropped > revision) {
+            return state
+                .notices
+                .back()
+                .map(|latest| ChangeNotice {
+                    revision: latest.revision,
+                    tiles: Vec::new(),
+                })
+                .into_iter()
+                .collect();
+        }
+        state
+            .notices
+            .iter()
+            .filter(|n| n.revision > revision)
+            .cloned()
+            .collect()
+    }
+
+    /// Wait up to `timeout` for updates after `revision`
+    async fn wait_since(&self, revision: u32, timeout: Duration) -> Vec<ChangeNotice> {
+        // Created before checking, so a notice published in between still
+        // wakes it
+        let published = self.published.notified();
+        let changes = self.since(revision);
+        if !changes.is_empty() {
+            return changes;
+        }
+        let _ = tokio::time::timeout(timeout, published).await;
+        self.since(revision)
+    }
+}
+
+/// Most frequent values listed per key by `/stats`; the rest are summed up
+/// as `other`
+const MAX_HISTOGRAM_VALUES: usize = 100;
+
+/// Build the router serving `/tiles/{z}/{x}/{y}.mvt`, `/query`, `/stats`
+/// and `/changes`
+///
+/// Tiles accept an optional `goql` query parameter. `/query` requires `goql`
+/// and `bbox=west,south,east,north` and responds with a GeoJSON
+/// `FeatureCollection` of at most [`ServerConfig::max_results`] features,
+/// with `"truncated":true` if more matched. Queries of clients that
+/// disconnect are cancelled.
+///
+/// `/stats` takes the same parameters as `/query` plus an optional
+/// `keys=k1,k2`, and responds with summary numbers instead of features:
+///
+/// ```json
+/// {"count":3,"nodes":2,"ways":1,"relations":0,
+///  "extent":[12.5,55.6,12.6,55.7],
+///  "tags":{"cuisine":{"values":{"coffee_shop":2},"other":0,"missing":1}}}
+/// ```
+///
+/// Counts and extent are computed without copying features; tag
+/// histograms read only the tags of the matches.
+///
+/// With [`ServerConfig::changes`] set, `/changes?since=<revision>` waits
+/// (up to `timeout` seconds, 30 by default) for updates of the GOL after
+/// the given revision and lists the tiles they changed, so web maps can
+/// refresh only those. Without `since`, it returns the current revision
+/// right away:
+///
+/// ```json
+/// {"revision":42,"layer":"features","changes":[{"revision":42,"tiles":[[12,2190,1281]]}]}
+/// ```
+pub fn router(geodesk: Arc<GeoDesk>, config: ServerConfig) -> Router {
+    let state = Arc::new(AppState {
+        geodesk,
+        permits: Semaphore::new(config.max_concurrent_queries.max(1)),
+        config,
+    });
+    Router::new()
+        .route("/tiles/{z}/{x}/{tile}", get(tile_handler))
+        .route("/query", get(query_handler))
+        .route("/stats", get(stats_handler))
+        .route("/changes", get(changes_handler))
+        .with_state(state)
+}
+
+/// Serve the router on `addr` until the process is stopped
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::GeoDesk;
+/// use geodesk_rs::server::{serve, ServerConfig};
+/// use std::sync::Arc;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let geodesk = Arc::new(GeoDesk::open("world.gol").unwrap());
+/// serve(geodesk, "127.0.0.1:8080".parse().unwrap(), ServerConfig::default()).await
+/// # }
+/// ```
+pub async fn serve(
+    geodesk: Arc<GeoDesk>,
+    addr: SocketAddr,
+    config: ServerConfig,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let watcher = config.changes.clone().map(|feed| {
+        tokio::spawn(watch_revision(
+            geodesk.clone(),
+            feed,
+            config.change_poll_interval,
+        ))
+    });
+    let result = axum::serve(listener, router(geodesk, config)).await;
+    if let Some(watcher) = watcher {
+        watcher.abort();
+    }
+    result
+}
+
+/// Publish updates of the GOL made by other processes
+async fn watch_revision(geodesk: Arc<GeoDesk>, feed: Arc<ChangeFeed>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval.max(Duration::from_millis(100)));
+    let mut last = geodesk.watermark().revision;
+    loop {
+        ticks.tick().await;
+        let revision = geodesk.watermark().revision;
+        if revision != last {
+            feed.publish(revision, Vec::new());
+            last = revision;
+        }
+    }
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, message.into()).into_response()
+}
+
+/// Cancels a token when dropped
+///
+/// Axum drops the handler future when the client disconnects; holding one
+/// of these in the handler stops the query running on its behalf.
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Options for a query that stops when the request is abandoned
+fn cancel_on_drop() -> (QueryOptions, CancelOnDrop) {
+    let token = CancellationToken::new();
+    (
+        QueryOptions::new().cancellation(token.clone()),
+        CancelOnDrop(token),
+    )
+}
+
+/// Run a query on the blocking thread pool, bounded by the concurrency limit
+async fn run_query<T, F>(state: &Arc<AppState>, f: F) -> Result<T, Response>
+where
+    T: Send + 'static,
+    F: FnOnce(&GeoDesk) -> Result<T, String> + Send + 'static,
+{
+    let _permit = state
+        .permits
+        .acquire()
+        .await
+        .map_err(|_| error(StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down"))?;
+    let geodesk = state.geodesk.clone();
+    tokio::task::spawn_blocking(move || f(&geodesk))
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e))
+}
+
+async fn tile_handler(
+    State(state): State<Arc<AppState>>,
+    Path((z, x, tile)): Path<(u8, u32, String)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(y) = tile.strip_suffix(".mvt").and_then(|y| y.parse().ok()) else {
+        return error(StatusCode::NOT_FOUND, "Expected /tiles/{z}/{x}/{y}.mvt");
+    };
+    let Some(tile_id) = TileId::new(z, x, y) else {
+        return error(StatusCode::NOT_FOUND, "Tile coordinates out of range");
+    };
+    if z < state.config.min_zoom || z > state.config.max_zoom {
+        return error(StatusCode::NOT_FOUND, "Zoom level not served");
+    }
+
+    let goql = params
+        .get("goql")
+        .cloned()
+        .unwrap_or_else(|| state.config.tile_query.clone());
+    let layer_name = state.config.layer_name.clone();
+    let (options, _guard) = cancel_on_drop();
+    let result = run_query(&state, move |geodesk| {
+        let features = geodesk
+            .query_with(&goql, tile_id.bounds(), &options)
+            .and_then(|r| r.to_vec())
+            .map_err(|e| e.to_string())?;
+        let mut encoder = TileEncoder::new(tile_id, &layer_name);
+        for feature in &features {
+            encoder.add_feature(feature);
+        }
+        Ok(encoder.encode())
+    })
+    .await;
+
+    match result {
+        Ok(bytes) => (
+            [(header::CONTENT_TYPE, "application/vnd.mapbox-vector-tile")],
+            bytes,
+        )
+            .into_response(),
+        Err(response) => response,
+    }
+}
+
+async fn query_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(goql) = params.get("goql").cloned() else {
+        return error(StatusCode::BAD_REQUEST, "Missing goql parameter");
+    };
+    let bbox: BoundingBox = match params.get("bbox").map(|b| b.parse()) {
+        Some(Ok(bbox)) => bbox,
+        Some(Err(e)) => return error(StatusCode::BAD_REQUEST, e),
+        None => return error(StatusCode::BAD_REQUEST, "Missing bbox parameter"),
+    };
+
+    let (mut options, _guard) = cancel_on_drop();
+    if let Some(limit) = state.config.max_results {
+        options = options
+            .max_results(limit)
+            .on_max_results(MaxResultsPolicy::Truncate);
+    }
+    let result = run_query(&state, move |geodesk| {
+        query_geojson(geodesk, &goql, bbox, &options).map_err(|e| e.to_string())
+    })
+    .await;
+
+    match result {
+        Ok(json) => ([(header::CONTENT_TYPE, "application/geo+json")], json).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// The features matching a query, as the GeoJSON of `/query`
+fn query_geojson(
+    geodesk: &GeoDesk,
+    goql: &str,
+    bbox: BoundingBox,
+    options: &QueryOptions,
+) -> Result<String, GeoDeskError> {
+    let result = geodesk.query_with(goql, bbox, options)?;
+    let features = result.to_vec()?;
+    let mut writer = GeoJsonWriter::new(Vec::new())?
+        .with_watermark(result.watermark())
+        .with_truncated(result.is_truncated());
+    for feature in &features {
+        writer.write_feature(feature)?;
+    }
+    let json = writer.finish()?;
+    Ok(String::from_utf8(json).expect("GeoJSON output is valid UTF-8"))
+}
+
+/// Summary of the features matching a query, as the JSON of `/stats`
+///
+/// One pass over the matches, reading only the requested keys of each.
+fn layer_stats(
+    geodesk: &GeoDesk,
+    goql: &str,
+    bbox: BoundingBox,
+    keys: &[String],
+    options: &QueryOptions,
+) -> Result<Vec<u8>, GeoDeskError> {
+    let (mut nodes, mut ways, mut relations) = (0u64, 0u64, 0u64);
+    let mut extent: Option<BoundingBox> = None;
+    let mut histograms = vec![HashMap::<String, u64>::new(); keys.len()];
+    let mut missing = vec![0u64; keys.len()];
+    let mut failed = None;
+    geodesk.for_each_with(goql, bbox, options, |feature| {
+        match feature.feature_type() {
+            FeatureType::Node => nodes += 1,
+            FeatureType::Way => ways += 1,
+            FeatureType::Relation => relations += 1,
+        }
+        let b = feature.bounds();
+        let e = extent.get_or_insert(b);
+        e.west = e.west.min(b.west);
+        e.south = e.south.min(b.south);
+        e.east = e.east.max(b.east);
+        e.north = e.north.max(b.north);
+        for (i, key) in keys.iter().enumerate() {
+            match feature.tag(key) {
+                Ok(Some(value)) => *histograms[i].entry(value).or_default() += 1,
+                Ok(None) => missing[i] += 1,
+                Err(e) => {
+                    failed = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    })?;
+    if let Some(e) = failed {
+        return Err(e);
+    }
+
+    let mut out = Vec::new();
+    write!(
+        out,
+        "{{\"count\":{},\"nodes\":{},\"ways\":{},\"relations\":{},\"extent\":",
+        nodes + ways + relations,
+        nodes,
+        ways,
+        relations
+    )?;
+    match extent {
+        Some(b) => write!(out, "[{},{},{},{}]", b.west, b.south, b.east, b.north)?,
+        None => out.write_all(b"null")?,
+    }
+    out.write_all(b",\"tags\":{")?;
+    for (i, (key, histogram)) in keys.iter().zip(histograms).enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        let mut values: Vec<(String, u64)> = histogram.into_iter().collect();
+        values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let other: u64 = values.iter().skip(MAX_HISTOGRAM_VALUES).map(|v| v.1).sum();
+        values.truncate(MAX_HISTOGRAM_VALUES);
+
+        write_json_string(&mut out, key)?;
+        out.write_all(b":{\"values\":{")?;
+        for (j, (value, count)) in values.iter().enumerate() {
+            if j > 0 {
+                out.write_all(b",")?;
+            }
+            write_json_string(&mut out, value)?;
+            write!(out, ":{}", count)?;
+        }
+        write!(out, "}},\"other\":{},\"missing\":{}}}", other, missing[i])?;
+    }
+    out.write_all(b"}}")?;
+    Ok(out)
+}
+
+async fn stats_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(goql) = params.get("goql").cloned() else {
+        return error(StatusCode::BAD_REQUEST, "Missing goql parameter");
+    };
+    let bbox: BoundingBox = match params.get("bbox").map(|b| b.parse()) {
+        Some(Ok(bbox)) => bbox,
+        Some(Err(e)) => return error(StatusCode::BAD_REQUEST, e),
+        None => return error(StatusCode::BAD_REQUEST, "Missing bbox parameter"),
+    };
+    let keys: Vec<String> = params
+        .get("keys")
+        .map(|keys| {
+            keys.split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (options, _guard) = cancel_on_drop();
+    let result = run_query(&state, move |geodesk| {
+        layer_stats(geodesk, &goql, bbox, &keys, &options).map_err(|e| e.to_string())
+    })
+    .await;
+
+    match result {
+        Ok(json) => ([(header::CONTENT_TYPE, "application/json")], json).into_response(),
+        Err(response) => response,
+    }
+}
+
+async fn changes_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(feed) = state.config.changes.clone() else {
+        return error(
+            StatusCode::NOT_FOUND,
+            "Change notifications are not enabled",
+        );
+    };
+    let since = match params.get("since").map(|s| s.parse::<u32>()) {
+        Some(Ok(since)) => Some(since),
+        Some(Err(_)) => return error(StatusCode::BAD_REQUEST, "Invalid since parameter"),
+        None => None,
+    };
+    let timeout = match params.get("timeout").map(|s| s.parse::<u64>()) {
+        Some(Ok(secs)) => Duration::from_secs(secs).min(MAX_CHANGES_WAIT),
+        Some(Err(_)) => return error(StatusCode::BAD_REQUEST, "Invalid timeout parameter"),
+        None => Duration::from_secs(30),
+    };
+
+    let changes = match since {
+        Some(since) => feed.wait_since(since, timeout).await,
+        None => Vec::new(),
+    };
+    let revision = changes
+        .last()
+        .map(|n| n.revision)
+        .or(since)
+        .unwrap_or_else(|| state.geodesk.watermark().revision);
+
+    let mut json = Vec::new();
+    write_changes(&mut json, revision, &state.config.layer_name, &changes)
+        .expect("writing to a Vec cannot fail");
+    ([(header::CONTENT_TYPE, "application/json")], json).into_response()
+}
+
+fn write_changes<W: Write>(
+    out: &mut W,
+    revision: u32,
+    layer: &str,
+    changes: &[ChangeNotice],
+) -> std::io::Result<()> {
+    write!(out, "{{\"revision\":{},\"layer\":", revision)?;
+    write_json_string(out, layer)?;
+    out.write_all(b",\"changes\":[")?;
+    for (i, notice) in changes.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write!(out, "{{\"revision\":{},\"tiles\":[", notice.revision)?;
+        for (j, tile) in notice.tiles.iter().enumerate() {
+            if j > 0 {
+                out.write_all(b",")?;
+            }
+            write!(out, "[{},{},{}]", tile.z, tile.x, tile.y)?;
+        }
+        out.write_all(b"]}")?;
+    }
+    out.write_all(b"]}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_feed() {
+        let feed = ChangeFeed::new();
+        let tile = TileId::new(12, 2190, 1281).unwrap();
+        feed.publish(5, vec![tile]);
+        feed.publish(4, Vec::new()); // Older than the latest, ignored
+        feed.publish(7, Vec::new());
+        assert_eq!(feed.latest_revision(), Some(7));
+        assert_eq!(feed.since(4).len(), 2);
+        assert_eq!(feed.since(5)[0].revision, 7);
+        assert!(feed.since(7).is_empty());
+
+        let mut json = Vec::new();
+        write_changes(&mut json, 7, "roads", &feed.since(4)).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"revision\":7,\"layer\":\"roads\",\"changes\":[\
+             {\"revision\":5,\"tiles\":[[12,2190,1281]]},{\"revision\":7,\"tiles\":[]}]}"
+        );
+
+        for revision in 8..8 + MAX_NOTICES as u32 {
+            feed.publish(revision, vec![tile]);
+        }
+        // Notices after revision 6 were dropped, so the client refreshes all
+        let reset = feed.since(6);
+        assert_eq!(reset.len(), 1);
+        assert!(reset[0].tiles.is_empty());
+        assert_eq!(feed.since(8).len(), MAX_NOTICES - 1);
+    }
+
+    #[cfg(feature = "test-data")]
+    #[test]
+    fn test_query_geojson_truncated() {
+        let Some(geodesk) = crate::test_data::TestGol::try_open() else {
+            return;
+        };
+        let monaco = crate::testing::bboxes::MONACO;
+        let all = query_geojson(&geodesk, "na[amenity]", monaco, &QueryOptions::new()).unwrap();
+        assert!(!all.contains("\"truncated\""));
+
+        let options = QueryOptions::new()
+            .max_results(1)
+            .on_max_results(MaxResultsPolicy::Truncate);
+        let first = query_geojson(&geodesk, "na[amenity]", monaco, &options).unwrap();
+        assert!(first.ends_with(",\"truncated\":true}"));
+        assert_eq!(first.matches("\"type\":\"Feature\"").count(), 1);
+    }
+
+    #[cfg(feature = "test-data")]
+    #[test]
+    fn test_layer_stats() {
+        let Some(geodesk) = crate::test_data::TestGol::try_open() else {
+            return;
+        };
+        let monaco = crate::testing::bboxes::MONACO;
+        let keys = vec!["amenity".to_string()];
+        let json =
+            layer_stats(&geodesk, "na[amenity]", monaco, &keys, &QueryOptions::new()).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        let counts = geodesk.count_by_type("na[amenity]", monaco).unwrap();
+        assert!(json.starts_with(&format!("{{\"count\":{},", counts.total())));
+        let b = geodesk.extent("na[amenity]", monaco).unwrap().unwrap();
+        assert!(json.contains(&format!("[{},{},{},{}]", b.west, b.south, b.east, b.north)));
+        assert!(json.contains("\"missing\":0}"));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = QueryOptions::new().cancellation(token);
+        assert!(matches!(
+            layer_stats(&geodesk, "na[amenity]", monaco, &keys, &cancelled),
+            Err(GeoDeskError::Cancelled)
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+// Several GOL files queried as one logical store
+
+use crate::error::GeoDeskError;
+use crate::provenance::{ConflictPolicy, Identity, Merged, Merger, Source};
+use crate::tiles::{MissingTiles, TileId};
+use crate::{BoundingBox, GeoDesk, OpenOptions};
+use std::collections::HashSet;
+use std::path::Path;
+
+struct Member {
+    geodesk: GeoDesk,
+    source: Source,
+    bounds: BoundingBox,
+}
+
+/// A set of GOL files, such as per-continent extracts, queried together
+///
+/// Queries are sent to every store whose coverage intersects the bounding
+/// box. Features found in more than one store are returned once, annotated
+/// with the store their copy came from (see [`crate::provenance`]).
+///
+/// Tiles missing from one store are not reported as long as another queried
+/// store has them, so overlapping extracts can fill each other's gaps.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::{BoundingBox, GeoDeskSet};
+///
+/// let set = GeoDeskSet::open(&["europe.gol", "asia.gol"]).unwrap();
+/// let bbox = BoundingBox::new(28.8, 40.9, 29.2, 41.2); // Istanbul
+/// let merged = set.query("na[amenity=cafe]", bbox).unwrap();
+/// for cafe in &merged.features {
+///     println!("{} from {}", cafe.feature.id, cafe.source.path.display());
+/// }
+/// ```
+pub struct GeoDeskSet {
+    members: Vec<Member>,
+    conflict_policy: ConflictPolicy,
+    identity: Identity,
+    missing_tiles: MissingTiles,
+}
+
+impl GeoDeskSet {
+    /// Open GOL files with default options; earlier paths take priority in
+    /// conflicts
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<Self, GeoDeskError> {
+        Self::open_with(paths, OpenOptions::new())
+    }
+
+    /// Open GOL files with the given options
+    ///
+    /// The missing-tile policy of `options` applies to the set as a whole.
+    pub fn open_with<P: AsRef<Path>>(
+        paths: &[P],
+        options: OpenOptions,
+    ) -> Result<Self, GeoDeskError> {
+        let member_options = options.clone().missing_tiles(MissingTiles::Flag);
+        let members = paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let geodesk = member_options.open(path)?;
+                let bounds = geodesk.metadata()?.bounds;
+                Ok(Member {
+                    geodesk,
+                    source: Source::new(index, path.as_ref()),
+                    bounds,
+                })
+            })
+            .collect::<Result<_, GeoDeskError>>()?;
+        Ok(Self {
+            members,
+            conflict_policy: ConflictPolicy::default(),
+            identity: Identity::default(),
+            missing_tiles: options.missing_tiles,
+        })
+    }
+
+    /// Set which copy is kept when stores return differing versions of a feature
+    pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Set what makes features from different stores the same feature
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Number of stores in the set
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Check if the set has no stores
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// The stores of the set, in priority order
+    pub fn stores(&self) -> impl Iterator<Item = (&Source, &GeoDesk)> {
+        self.members.iter().map(|m| (&m.source, &m.geodesk))
+    }
+
+    /// Query every store that covers part of `bbox` and merge the results
+    pub fn query(&self, goql_query: &str, bbox: BoundingBox) -> Result<Merged, GeoDeskError> {
+        let mut merger = Merger::new(self.conflict_policy).with_identity(self.identity);
+        let mut missing: Option<HashSet<TileId>> = None;
+        for member in self.members.iter().filter(|m| m.bounds.intersects(&bbox)) {
+            let result = member.geodesk.query(goql_query, bbox)?;
+            let member_missing: HashSet<TileId> = result.missing_tiles().iter().copied().collect();
+            missing = Some(match missing {
+                None => member_missing,
+                Some(m) => m.intersection(&member_missing).copied().collect(),
+            });
+            merger.add(&member.source, result.to_vec()?);
+        }
+
+        let mut merged = merger.finish();
+        if self.missing_tiles != MissingTiles::Ignore {
+            let mut tiles: Vec<TileId> = missing.unwrap_or_default().into_iter().collect();
+            tiles.sort_by_key(|t| (t.z, t.x, t.y));
+            if self.missing_tiles == MissingTiles::Error && !tiles.is_empty() {
+                return Err(GeoDeskError::TilesMissing { tiles });
+            }
+            merged.missing_tiles = tiles;
+        }
+        Ok(merged)
+    }
+}
@@ -0,0 +1,164 @@
+// Snapping points to the nearest way
+
+use crate::error::GeoDeskError;
+use crate::projection::METERS_PER_DEGREE;
+use crate::{BoundingBox, Feature, GeoDesk, Node};
+
+/// A point snapped onto a way, from [`GeoDesk::snap_to_road`]
+#[derive(Debug, Clone)]
+pub struct Snapped {
+    /// The nearest way, with its geometry
+    pub way: Feature,
+    /// The point on the way nearest to the one snapped
+    pub lon: f64,
+    pub lat: f64,
+    /// Distance between the two points in meters
+    pub distance: f64,
+    /// Index of the first node of the way segment the point is on
+    pub segment: usize,
+    /// How far along that segment the point lies, from 0 to 1
+    pub fraction: f64,
+}
+
+/// The point of segment a-b nearest to (lon, lat), as the fraction along
+/// the segment and the distance in meters
+///
+/// Distances are measured on a plane scaled to meters at the point's
+/// latitude, which is accurate to well below a meter over the short
+/// distances snapping deals with.
+fn nearest_on_segment(lon: f64, lat: f64, a: &Node, b: &Node) -> (f64, f64) {
+    let x_scale = lat.to_radians().cos() * METERS_PER_DEGREE;
+    let (px, py) = ((lon - a.lon) * x_scale, (lat - a.lat) * METERS_PER_DEGREE);
+    let (dx, dy) = (
+        (b.lon - a.lon) * x_scale,
+        (b.lat - a.lat) * METERS_PER_DEGREE,
+    );
+    let length2 = dx * dx + dy * dy;
+    let t = if length2 > 0.0 {
+        ((px * dx + py * dy) / length2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (t, (px - t * dx).hypot(py - t * dy))
+}
+
+/// Snap a point onto the nearest of the given ways, if any is within
+/// `max_meters`
+pub fn snap<I>(lon: f64, lat: f64, max_meters: f64, ways: I) -> Option<Snapped>
+where
+    I: IntoIterator<Item = Feature>,
+{
+    // Distance, segment and fraction of the nearest way so far
+    let mut best: Option<(Feature, f64, usize, f64)> = None;
+    for way in ways {
+        let nearest = way
+            .nodes
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let (t, distance) = nearest_on_segment(lon, lat, &pair[0], &pair[1]);
+                (distance, i, t)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((distance, segment, t)) = nearest {
+            if distance <= max_meters && best.as_ref().is_none_or(|b| distance < b.1) {
+                best = Some((way, distance, segment, t));
+            }
+        }
+    }
+    best.map(|(way, distance, segment, fraction)| {
+        let (a, b) = (way.nodes[segment], way.nodes[segment + 1]);
+        Snapped {
+            lon: a.lon + (b.lon - a.lon) * fraction,
+            lat: a.lat + (b.lat - a.lat) * fraction,
+            way,
+            distance,
+            segment,
+            fraction,
+        }
+    })
+}
+
+impl GeoDesk {
+    /// Snap a point to the nearest road (any way tagged `highway`) within
+    /// `max_meters`, such as a GPS fix or a geocoded address
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// if let Some(snapped) = geodesk.snap_to_road(12.5683, 55.6761, 50.0).unwrap() {
+    ///     println!(
+    ///         "{} at {:.6}, {:.6}, {:.1} m away",
+    ///         snapped.way.name, snapped.lon, snapped.lat, snapped.distance
+    ///     );
+    /// }
+    /// ```
+    pub fn snap_to_road(
+        &self,
+        lon: f64,
+        lat: f64,
+        max_meters: f64,
+    ) -> Result<Option<Snapped>, GeoDeskError> {
+        self.snap_to_way("w[highway]", lon, lat, max_meters)
+    }
+
+    /// Snap a point to the nearest way matching a GOQL query within
+    /// `max_meters`, such as `w[highway][highway!=footway,cycleway]` for
+    /// roads open to cars or `w[railway=rail]` for train positions
+    pub fn snap_to_way(
+        &self,
+        goql_query: &str,
+        lon: f64,
+        lat: f64,
+        max_meters: f64,
+    ) -> Result<Option<Snapped>, GeoDeskError> {
+        let bbox = BoundingBox::around(lon, lat, max_meters);
+        let ways = self.query(goql_query, bbox)?.to_vec()?;
+        Ok(snap(lon, lat, max_meters, ways))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn way(id: i64, nodes: &[(f64, f64)]) -> Feature {
+        Feature {
+            id,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: nodes[0].0,
+            lat: nodes[0].1,
+            tags: Vec::new(),
+            nodes: nodes
+                .iter()
+                .enumerate()
+                .map(|(i, &(lon, lat))| Node {
+                    id: i as i64,
+                    lon,
+                    lat,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_snap() {
+        // Two east-west roads at the equator, 0.001° (about 111 m) apart
+        let ways = vec![
+            way(1, &[(0.0, 0.0), (0.001, 0.0), (0.002, 0.0)]),
+            way(2, &[(0.0, 0.001), (0.002, 0.001)]),
+        ];
+        let snapped = snap(0.0015, 0.0002, 50.0, ways.clone()).unwrap();
+        assert_eq!(snapped.way.id, 1);
+        assert_eq!(snapped.segment, 1);
+        assert!((snapped.fraction - 0.5).abs() < 1e-9);
+        assert!((snapped.lon - 0.0015).abs() < 1e-12 && snapped.lat.abs() < 1e-12);
+        assert!((snapped.distance - 22.264).abs() < 0.01);
+
+        assert_eq!(snap(0.001, 0.0008, 50.0, ways.clone()).unwrap().way.id, 2);
+        assert!(snap(0.001, 0.0005, 50.0, ways).is_none());
+    }
+}
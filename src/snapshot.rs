@@ -0,0 +1,85 @@
+// Consistent view of a store across several queries
+
+use crate::error::GeoDeskError;
+use crate::{ffi, BoundingBox, FeatureIter, GeoDesk, QueryResult};
+use std::sync::RwLockReadGuard;
+
+/// A handle under which a series of queries all see the same data revision
+///
+/// While a snapshot is alive, updates applied through this crate wait until
+/// it is dropped. Changes made to the file by another process can't be held
+/// back; if the revision moves, the next query on the snapshot fails with
+/// [`GeoDeskError::RevisionChanged`] rather than mixing data versions.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::{GeoDesk, BoundingBox};
+/// use geodesk_rs::tiles::TileId;
+///
+/// let geodesk = GeoDesk::open("world.gol").unwrap();
+/// let bbox = TileId::new(14, 8800, 5130).unwrap().bounds();
+///
+/// let snapshot = geodesk.snapshot();
+/// let roads = snapshot.query("w[highway]", bbox).unwrap();
+/// let boundaries = snapshot.query("a[boundary=administrative]", bbox).unwrap();
+/// let pois = snapshot.query("na[amenity]", bbox).unwrap();
+/// println!("rendered revision {}", snapshot.revision());
+/// ```
+pub struct Snapshot<'a> {
+    geodesk: &'a GeoDesk,
+    revision: u32,
+    _guard: RwLockReadGuard<'a, ()>,
+}
+
+impl<'a> Snapshot<'a> {
+    /// The data revision every query of this snapshot sees
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
+    /// Run a GOQL query (see [`GeoDesk::query`])
+    pub fn query(&self, goql_query: &str, bbox: BoundingBox) -> Result<QueryResult, GeoDeskError> {
+        self.check()?;
+        let result = self.geodesk.query(goql_query, bbox)?;
+        self.check()?;
+        Ok(result)
+    }
+
+    /// Stream a GOQL query (see [`GeoDesk::query_iter`])
+    ///
+    /// The revision is checked when the iterator is created; consume it
+    /// before starting further queries on the snapshot.
+    pub fn query_iter(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+    ) -> Result<FeatureIter, GeoDeskError> {
+        self.check()?;
+        self.geodesk.query_iter(goql_query, bbox)
+    }
+
+    fn check(&self) -> Result<(), GeoDeskError> {
+        let found = ffi::bridge::store_revision(&self.geodesk.store);
+        if found == self.revision {
+            Ok(())
+        } else {
+            Err(GeoDeskError::RevisionChanged {
+                expected: self.revision,
+                found,
+            })
+        }
+    }
+}
+
+impl GeoDesk {
+    /// Start a series of queries that all see the same data revision
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        // The lock guards no data, so a panicked holder leaves nothing broken
+        let guard = self.update_lock.read().unwrap_or_else(|e| e.into_inner());
+        Snapshot {
+            geodesk: self,
+            revision: ffi::bridge::store_revision(&self.store),
+            _guard: guard,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Features from a GOL or a stand-in, for code that shouldn't depend on a
+// GOL file being present
+
+use crate::error::GeoDeskError;
+use crate::query::Query;
+use crate::testing::bboxes;
+use crate::updates::within;
+use crate::{BoundingBox, Feature, FeatureType, GeoDesk};
+use std::ops::ControlFlow;
+
+/// Where features come from: a [`GeoDesk`], or a [`MockSource`] in tests
+///
+/// # Example
+/// ```
+/// use geodesk_rs::source::{FeatureSource, MockSource};
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::{Feature, GeoDeskError};
+///
+/// // Application logic written against the trait...
+/// fn cafe_names<S: FeatureSource>(source: &S) -> Result<Vec<String>, GeoDeskError> {
+///     let cafes = source.query("na[amenity=cafe]", bboxes::COPENHAGEN)?;
+///     Ok(cafes.into_iter().map(|cafe| cafe.name).collect())
+/// }
+///
+/// // ...runs on a GOL in production and on fixtures in tests
+/// let source = MockSource::new(vec![Feature {
+///     id: 1,
+///     type_name: "node".into(),
+///     name: "Kaffebar".into(),
+///     lon: 12.57,
+///     lat: 55.68,
+///     tags: vec![("amenity".into(), "cafe".into())],
+///     nodes: Vec::new(),
+/// }]);
+/// assert_eq!(cafe_names(&source).unwrap(), ["Kaffebar"]);
+/// ```
+pub trait FeatureSource {
+    /// Features matching a GOQL query in a bounding box
+    fn query(&self, goql: &str, bbox: BoundingBox) -> Result<Vec<Feature>, GeoDeskError>;
+
+    /// Number of features matching a GOQL query in a bounding box
+    fn count(&self, goql: &str, bbox: BoundingBox) -> Result<usize, GeoDeskError> {
+        Ok(self.query(goql, bbox)?.len())
+    }
+
+    /// The feature with an OSM type and id, if there is one
+    fn feature(&self, feature_type: FeatureType, id: i64) -> Result<Option<Feature>, GeoDeskError>;
+}
+
+impl GeoDesk {
+    /// The feature with an OSM type and id, if the GOL has it
+    ///
+    /// GOLs have no index by id, so this scans every feature of the type;
+    /// use it for occasional lookups, not in loops.
+    pub fn feature(
+        &self,
+        feature_type: FeatureType,
+        id: i64,
+    ) -> Result<Option<Feature>, GeoDeskError> {
+        let goql_query = match feature_type {
+            FeatureType::Node => "n",
+            FeatureType::Way => "wa",
+            FeatureType::Relation => "ra",
+        };
+        let mut found = None;
+        self.for_each(goql_query, bboxes::WORLD, |feature| {
+            if feature.id() == id && feature.feature_type() == feature_type {
+                found = Some(feature.to_feature());
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+        found.transpose()
+    }
+}
+
+impl FeatureSource for GeoDesk {
+    fn query(&self, goql: &str, bbox: BoundingBox) -> Result<Vec<Feature>, GeoDeskError> {
+        GeoDesk::query(self, goql, bbox)?.to_vec()
+    }
+
+    fn count(&self, goql: &str, bbox: BoundingBox) -> Result<usize, GeoDeskError> {
+        Ok(GeoDesk::query(self, goql, bbox)?.count())
+    }
+
+    fn feature(&self, feature_type: FeatureType, id: i64) -> Result<Option<Feature>, GeoDeskError> {
+        GeoDesk::feature(self, feature_type, id)
+    }
+}
+
+/// Features held in memory, queried in Rust
+///
+/// Queries are limited to what [`Query`] can parse: a single selector
+/// without regular expressions.
+#[derive(Debug, Clone, Default)]
+pub struct MockSource {
+    features: Vec<Feature>,
+}
+
+impl MockSource {
+    pub fn new(features: Vec<Feature>) -> Self {
+        Self { features }
+    }
+
+    /// Add a feature
+    pub fn push(&mut self, feature: Feature) {
+        self.features.push(feature);
+    }
+}
+
+impl FeatureSource for MockSource {
+    fn query(&self, goql: &str, bbox: BoundingBox) -> Result<Vec<Feature>, GeoDeskError> {
+        let query: Query = goql
+            .parse()
+            .map_err(|e| GeoDeskError::Engine(format!("{}", e)))?;
+        Ok(self
+            .features
+            .iter()
+            .filter(|feature| query.matches(feature) && within(feature, &bbox))
+            .cloned()
+            .collect())
+    }
+
+    fn feature(&self, feature_type: FeatureType, id: i64) -> Result<Option<Feature>, GeoDeskError> {
+        Ok(self
+            .features
+            .iter()
+            .find(|feature| feature.id == id && feature.type_name == feature_type.as_str())
+            .cloned())
+    }
+}
+
+impl FromIterator<Feature> for MockSource {
+    fn from_iter<I: IntoIterator<Item = Feature>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn feature(id: i64, type_name: &str, tags: &[(&str, &str)], coords: &[(f64, f64)]) -> Feature {
+        Feature {
+            id,
+            type_name: type_name.to_string(),
+            name: String::new(),
+            lon: coords[0].0,
+            lat: coords[0].1,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: if type_name == "way" {
+                coords
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(lon, lat))| Node {
+                        id: i as i64 + 1,
+                        lon,
+                        lat,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    #[test]
+    fn test_mock_source() {
+        let source: MockSource = [
+            feature(1, "node", &[("amenity", "cafe")], &[(0.5, 0.5)]),
+            feature(2, "node", &[("amenity", "cafe")], &[(5.0, 5.0)]),
+            feature(
+                1,
+                "way",
+                &[("highway", "primary")],
+                &[(-1.0, 0.5), (0.5, 0.5)],
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let bbox = BoundingBox::new(0.0, 0.0, 1.0, 1.0);
+
+        let cafes = source.query("n[amenity=cafe]", bbox).unwrap();
+        assert_eq!(cafes.len(), 1);
+        assert_eq!(source.count("w[highway]", bbox).unwrap(), 1);
+        assert_eq!(source.count("w[highway=secondary]", bbox).unwrap(), 0);
+        assert!(source.query("n[amenity=cafe],w", bbox).is_err());
+
+        let way = source.feature(FeatureType::Way, 1).unwrap().unwrap();
+        assert_eq!(way.type_name, "way");
+        assert!(source.feature(FeatureType::Relation, 1).unwrap().is_none());
+    }
+}
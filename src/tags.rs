@@ -0,0 +1,335 @@
+// Typed access to tag values
+
+use crate::Feature;
+use std::fmt;
+
+/// How strictly numeric tag values are parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberParsing {
+    /// Accept only plain decimal numbers such as `12` or `-3.5`
+    #[default]
+    Strict,
+    /// Also accept the common variations found in OSM data: a comma as the
+    /// decimal separator (`3,5`), thousands separators (`1,200,000` or
+    /// `1,200.5`), an approximation prefix (`~40`) and a trailing unit or
+    /// remark (`50 km/h`, `12m`)
+    Tolerant,
+}
+
+/// Error for a tag value that could not be parsed as a number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNumberError {
+    pub value: String,
+}
+
+impl fmt::Display for ParseNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a number", self.value)
+    }
+}
+
+impl std::error::Error for ParseNumberError {}
+
+/// Error for a tag value that is not a recognized boolean
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBoolError {
+    pub value: String,
+}
+
+impl fmt::Display for ParseBoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not yes/no", self.value)
+    }
+}
+
+impl std::error::Error for ParseBoolError {}
+
+/// Parse a tag value as a number
+///
+/// # Example
+/// ```
+/// use geodesk_rs::tags::{parse_number, NumberParsing};
+///
+/// assert_eq!(parse_number("3,5", NumberParsing::Tolerant), Ok(3.5));
+/// assert!(parse_number("3,5", NumberParsing::Strict).is_err());
+/// ```
+pub fn parse_number(value: &str, parsing: NumberParsing) -> Result<f64, ParseNumberError> {
+    let error = || ParseNumberError {
+        value: value.to_string(),
+    };
+    let trimmed = value.trim();
+    let parsed = match parsing {
+        NumberParsing::Strict => trimmed.parse::<f64>().ok(),
+        NumberParsing::Tolerant => parse_tolerant(trimmed),
+    };
+    match parsed {
+        Some(n) if n.is_finite() => Ok(n),
+        _ => Err(error()),
+    }
+}
+
+fn parse_tolerant(s: &str) -> Option<f64> {
+    let s = s.trim_start_matches(['~', '≈']).trim_start();
+
+    // Numeric prefix: sign, digits and separators; anything after is a unit
+    let end = s
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || c == ',' || (i == 0 && c == '-')))
+        .map_or(s.len(), |(i, _)| i);
+    let number = s[..end].trim_end_matches([',', '.']);
+    if number.is_empty() || number == "-" {
+        return None;
+    }
+
+    let commas = number.matches(',').count();
+    let normalized = if commas == 0 {
+        number.to_string()
+    } else if number.contains('.') || commas > 1 {
+        // Commas are thousands separators
+        number.replace(',', "")
+    } else {
+        // A single comma is a decimal separator
+        number.replace(',', ".")
+    };
+    normalized.parse().ok()
+}
+
+/// Parse a boolean tag value (`yes`/`true`/`1` or `no`/`false`/`0`)
+pub fn parse_bool(value: &str) -> Result<bool, ParseBoolError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "yes" | "true" | "1" => Ok(true),
+        "no" | "false" | "0" => Ok(false),
+        _ => Err(ParseBoolError {
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// Parse a speed such as `maxspeed`, returning km/h
+///
+/// Plain numbers are km/h; `mph`, `knots` and the `km/h`, `kmh` and `kph`
+/// spellings are recognized. Of several `;`-separated values the first is
+/// used. Non-numeric values (`none`, `walk`, `DE:urban`) are errors, since
+/// their meaning depends on context.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::tags::parse_speed;
+///
+/// assert_eq!(parse_speed("50"), Ok(50.0));
+/// assert!((parse_speed("30 mph").unwrap() - 48.28).abs() < 0.01);
+/// ```
+pub fn parse_speed(value: &str) -> Result<f64, ParseNumberError> {
+    let first = value.split(';').next().unwrap_or_default();
+    let (number, unit) = split_unit(first);
+    let factor = match unit.to_ascii_lowercase().as_str() {
+        "" | "km/h" | "kmh" | "kph" => 1.0,
+        "mph" => 1.609_344,
+        "knots" | "kn" => 1.852,
+        _ => return Err(number_error(value)),
+    };
+    Ok(parse_number(number, NumberParsing::Strict).map_err(|_| number_error(value))? * factor)
+}
+
+/// Parse a length such as `height` or `width`, returning meters
+///
+/// Plain numbers are meters; `m`, `cm`, `km`, `ft`, `mi` and the
+/// feet-and-inches notation (`6'3"`) are recognized.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::tags::parse_length;
+///
+/// assert_eq!(parse_length("12 m"), Ok(12.0));
+/// assert!((parse_length("6'3\"").unwrap() - 1.905).abs() < 1e-9);
+/// ```
+pub fn parse_length(value: &str) -> Result<f64, ParseNumberError> {
+    let trimmed = value.trim();
+    if let Some((feet, inches)) = trimmed.split_once('\'') {
+        let inches = inches.trim().trim_end_matches('"').trim();
+        let feet = parse_number(feet, NumberParsing::Strict).map_err(|_| number_error(value))?;
+        let inches = if inches.is_empty() {
+            0.0
+        } else {
+            parse_number(inches, NumberParsing::Strict).map_err(|_| number_error(value))?
+        };
+        return Ok(feet * 0.3048 + inches * 0.0254);
+    }
+    let (number, unit) = split_unit(trimmed);
+    let factor = match unit.to_ascii_lowercase().as_str() {
+        "" | "m" => 1.0,
+        "cm" => 0.01,
+        "km" => 1000.0,
+        "ft" => 0.3048,
+        "mi" => 1609.344,
+        _ => return Err(number_error(value)),
+    };
+    Ok(parse_number(number, NumberParsing::Strict).map_err(|_| number_error(value))? * factor)
+}
+
+/// Split `"50 km/h"` or `"12m"` into the number and the unit
+fn split_unit(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    let end = s
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && c == '-')))
+        .map_or(s.len(), |(i, _)| i);
+    (&s[..end], s[end..].trim())
+}
+
+fn number_error(value: &str) -> ParseNumberError {
+    ParseNumberError {
+        value: value.to_string(),
+    }
+}
+
+/// Summary of parsing a numeric tag across many features
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumericReport {
+    /// Number of features whose value was parsed
+    pub parsed: usize,
+    /// Number of features without the tag
+    pub missing: usize,
+    /// Features whose value could not be parsed, as (feature id, raw value)
+    pub unparseable: Vec<(i64, String)>,
+}
+
+impl NumericReport {
+    /// Parse `key` on every feature and record the outcome
+    pub fn from_features<'a, I>(features: I, key: &str, parsing: NumberParsing) -> Self
+    where
+        I: IntoIterator<Item = &'a Feature>,
+    {
+        let mut report = Self::default();
+        for feature in features {
+            match feature.tag(key) {
+                None => report.missing += 1,
+                Some(value) => match parse_number(value, parsing) {
+                    Ok(_) => report.parsed += 1,
+                    Err(_) => report.unparseable.push((feature.id, value.to_string())),
+                },
+            }
+        }
+        report
+    }
+}
+
+impl Feature {
+    /// Get a tag value as a number
+    ///
+    /// Returns `Ok(None)` if the tag is absent and an error if it is present
+    /// but cannot be parsed with the given strictness.
+    pub fn tag_number(
+        &self,
+        key: &str,
+        parsing: NumberParsing,
+    ) -> Result<Option<f64>, ParseNumberError> {
+        self.tag(key)
+            .map(|value| parse_number(value, parsing))
+            .transpose()
+    }
+
+    /// Get a tag value as a whole number
+    pub fn tag_as_i64(&self, key: &str) -> Result<Option<i64>, ParseNumberError> {
+        self.tag(key)
+            .map(|value| value.trim().parse().map_err(|_| number_error(value)))
+            .transpose()
+    }
+
+    /// Get a tag value as a number (strict parsing)
+    pub fn tag_as_f64(&self, key: &str) -> Result<Option<f64>, ParseNumberError> {
+        self.tag_number(key, NumberParsing::Strict)
+    }
+
+    /// Get a tag value as a boolean (see [`parse_bool`])
+    pub fn tag_as_bool(&self, key: &str) -> Result<Option<bool>, ParseBoolError> {
+        self.tag(key).map(parse_bool).transpose()
+    }
+
+    /// The `maxspeed` tag in km/h (see [`parse_speed`])
+    pub fn maxspeed(&self) -> Result<Option<f64>, ParseNumberError> {
+        self.tag("maxspeed").map(parse_speed).transpose()
+    }
+
+    /// The `height` tag in meters (see [`parse_length`])
+    pub fn height(&self) -> Result<Option<f64>, ParseNumberError> {
+        self.tag("height").map(parse_length).transpose()
+    }
+
+    /// The `width` tag in meters (see [`parse_length`])
+    pub fn width(&self) -> Result<Option<f64>, ParseNumberError> {
+        self.tag("width").map(parse_length).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_parsing() {
+        assert_eq!(parse_number(" 42 ", NumberParsing::Strict), Ok(42.0));
+        assert_eq!(parse_number("-3.5", NumberParsing::Strict), Ok(-3.5));
+        assert!(parse_number("50 km/h", NumberParsing::Strict).is_err());
+        assert!(parse_number("NaN", NumberParsing::Strict).is_err());
+    }
+
+    #[test]
+    fn test_tolerant_parsing() {
+        let tolerant = |s| parse_number(s, NumberParsing::Tolerant);
+        assert_eq!(tolerant("3,5"), Ok(3.5));
+        assert_eq!(tolerant("1,200,000"), Ok(1_200_000.0));
+        assert_eq!(tolerant("1,200.5"), Ok(1200.5));
+        assert_eq!(tolerant("50 km/h"), Ok(50.0));
+        assert_eq!(tolerant("~12m"), Ok(12.0));
+        assert!(tolerant("yes").is_err());
+        assert!(tolerant("-").is_err());
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(parse_speed("50 km/h"), Ok(50.0));
+        assert_eq!(parse_speed("20;30"), Ok(20.0));
+        assert!((parse_speed("10 knots").unwrap() - 18.52).abs() < 1e-9);
+        assert!(parse_speed("none").is_err());
+        assert!(parse_speed("DE:urban").is_err());
+
+        assert_eq!(parse_length("3.5"), Ok(3.5));
+        assert_eq!(parse_length("250cm"), Ok(2.5));
+        assert!((parse_length("10 ft").unwrap() - 3.048).abs() < 1e-9);
+        assert!((parse_length("6'").unwrap() - 1.8288).abs() < 1e-9);
+        assert!(parse_length("tall").is_err());
+
+        assert_eq!(parse_bool("Yes"), Ok(true));
+        assert_eq!(parse_bool("0"), Ok(false));
+        assert!(parse_bool("limited").is_err());
+    }
+
+    #[test]
+    fn test_report() {
+        let feature = |id, value: Option<&str>| Feature {
+            id,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: value
+                .map(|v| vec![("population".to_string(), v.to_string())])
+                .unwrap_or_default(),
+            nodes: Vec::new(),
+        };
+        let features = [
+            feature(1, Some("1200")),
+            feature(2, Some("about 300")),
+            feature(3, None),
+        ];
+        let report = NumericReport::from_features(&features, "population", NumberParsing::Tolerant);
+        assert_eq!(report.parsed, 1);
+        assert_eq!(report.missing, 1);
+        assert_eq!(report.unparseable, vec![(2, "about 300".to_string())]);
+        assert_eq!(
+            features[0].tag_number("population", NumberParsing::Strict),
+            Ok(Some(1200.0))
+        );
+    }
+}
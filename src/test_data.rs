@@ -0,0 +1,142 @@
+// A small GOL for tests, doctests and benchmarks, downloaded once and
+// verified against a pinned checksum
+
+use crate::error::GeoDeskError;
+use crate::GeoDesk;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Test data is published with the releases of the crate
+const DEFAULT_URL: &str =
+    "https://github.com/Sifir-io/geodesk-rs/releases/download/test-data/monaco.gol";
+
+/// SHA-256 of the published Monaco GOL; until it is pinned,
+/// GEODESK_TEST_GOL_SHA256 must supply the checksum of the download
+const PINNED_SHA256: Option<&str> = None;
+
+/// The Monaco GOL used by tests, in [`bboxes::MONACO`](crate::testing::bboxes::MONACO)
+///
+/// The file is looked up in this order:
+/// - `GEODESK_TEST_GOL`, the path of a GOL to use as is
+/// - a verified copy from an earlier download, kept in `GEODESK_TEST_DATA`
+///   (by default `geodesk-test-data` in the temporary directory)
+/// - a download from `GEODESK_TEST_GOL_URL` (by default the project's
+///   releases), checked against the pinned SHA-256 or
+///   `GEODESK_TEST_GOL_SHA256`
+///
+/// Tests and doctests should open it with [`TestGol::try_open`], so they
+/// are skipped rather than failed where the data can't be had, such as
+/// offline without `GEODESK_TEST_GOL`.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::test_data::TestGol;
+/// use geodesk_rs::testing::bboxes;
+///
+/// let Some(geodesk) = TestGol::try_open() else { return };
+/// let cafes = geodesk.query("na[amenity=cafe]", bboxes::MONACO).unwrap();
+/// assert!(cafes.count() > 0);
+/// ```
+pub struct TestGol;
+
+impl TestGol {
+    /// Path of the test GOL, downloading it first if needed
+    pub fn path() -> Result<PathBuf, GeoDeskError> {
+        if let Some(path) = env::var_os("GEODESK_TEST_GOL") {
+            return Ok(PathBuf::from(path));
+        }
+        let expected = match env::var("GEODESK_TEST_GOL_SHA256") {
+            Ok(sha) => sha.to_lowercase(),
+            Err(_) => PINNED_SHA256
+                .ok_or_else(|| {
+                    GeoDeskError::Engine(
+                        "No pinned checksum for the test GOL; set GEODESK_TEST_GOL \
+                         or GEODESK_TEST_GOL_SHA256"
+                            .into(),
+                    )
+                })?
+                .to_string(),
+        };
+        let dir = env::var_os("GEODESK_TEST_DATA")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| env::temp_dir().join("geodesk-test-data"));
+        // Named by checksum, so a verified copy never needs checking again
+        let path = dir.join(format!(
+            "monaco-{}.gol",
+            &expected[..expected.len().min(16)]
+        ));
+        if path.is_file() {
+            return Ok(path);
+        }
+
+        let url = env::var("GEODESK_TEST_GOL_URL").unwrap_or_else(|_| DEFAULT_URL.to_string());
+        let mut data = Vec::new();
+        ureq::get(&url)
+            .call()
+            .map_err(|e| GeoDeskError::Engine(format!("Download of {} failed: {}", url, e)))?
+            .into_reader()
+            .read_to_end(&mut data)?;
+        verify(&data, &expected).map_err(|actual| {
+            GeoDeskError::Engine(format!(
+                "Checksum mismatch for {} (expected {}, got {})",
+                url, expected, actual
+            ))
+        })?;
+
+        // Write under a temporary name first, so concurrent test processes
+        // never open a partial file
+        fs::create_dir_all(&dir)?;
+        let partial = path.with_extension(format!("{}.part", std::process::id()));
+        fs::write(&partial, &data)?;
+        if let Err(e) = fs::rename(&partial, &path) {
+            let _ = fs::remove_file(&partial);
+            if !path.is_file() {
+                return Err(e.into());
+            }
+        }
+        Ok(path)
+    }
+
+    /// Open the test GOL, downloading it first if needed
+    pub fn open() -> Result<GeoDesk, GeoDeskError> {
+        GeoDesk::open(Self::path()?)
+    }
+
+    /// Open the test GOL, or return `None` with a note on stderr if it is
+    /// unavailable, for tests that skip without data
+    pub fn try_open() -> Option<GeoDesk> {
+        match Self::open() {
+            Ok(geodesk) => Some(geodesk),
+            Err(e) => {
+                eprintln!("Skipped, test GOL unavailable: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Check data against a SHA-256 checksum, returning the actual one if it
+/// doesn't match
+fn verify(data: &[u8], expected: &str) -> Result<(), String> {
+    let actual = format!("{:x}", Sha256::digest(data));
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(actual)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let sha = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(verify(b"hello", sha), Ok(()));
+        assert_eq!(verify(b"hello!", sha).unwrap_err().len(), 64);
+    }
+}
@@ -0,0 +1,124 @@
+// Helpers for examples, tests and benchmarks
+
+use crate::cancel::QueryControl;
+use crate::error::GeoDeskError;
+use crate::geocode::sort_by_admin_level;
+use crate::query::quote;
+use crate::refs::{Detail, FeatureRefs};
+use crate::{ffi, BoundingBox, GeoDesk};
+use std::collections::HashMap;
+
+/// Bounding boxes of well-known places
+///
+/// # Example
+/// ```
+/// use geodesk_rs::testing::bboxes;
+///
+/// assert_eq!(bboxes::by_name("new york"), Some(bboxes::NEW_YORK));
+/// ```
+pub mod bboxes {
+    use crate::BoundingBox;
+
+    macro_rules! bboxes {
+        ($($name:ident = ($w:expr, $s:expr, $e:expr, $n:expr);)*) => {
+            $(
+                pub const $name: BoundingBox = BoundingBox {
+                    west: $w,
+                    south: $s,
+                    east: $e,
+                    north: $n,
+                };
+            )*
+
+            /// Look up a bounding box by place name (case, spaces and
+            /// underscores are ignored)
+            pub fn by_name(name: &str) -> Option<BoundingBox> {
+                let key: String = name
+                    .chars()
+                    .filter(|c| !matches!(c, ' ' | '_' | '-'))
+                    .flat_map(char::to_uppercase)
+                    .collect();
+                match key.as_str() {
+                    $(k if k == stringify!($name).replace('_', "") => Some($name),)*
+                    _ => None,
+                }
+            }
+        };
+    }
+
+    bboxes! {
+        WORLD = (-180.0, -90.0, 180.0, 90.0);
+        BERLIN = (13.09, 52.34, 13.76, 52.68);
+        COPENHAGEN = (12.45, 55.61, 12.65, 55.73);
+        ISTANBUL = (28.8, 40.9, 29.2, 41.2);
+        LONDON = (-0.51, 51.28, 0.33, 51.69);
+        MONACO = (7.40, 43.72, 7.44, 43.76);
+        MONTREAL = (-73.9781, 45.4042, -73.4766, 45.7042);
+        NEW_YORK = (-74.26, 40.48, -73.70, 40.92);
+        PARIS = (2.22, 48.82, 2.47, 48.90);
+        SAN_FRANCISCO = (-122.52, 37.70, -122.35, 37.83);
+        SYDNEY = (150.52, -34.12, 151.34, -33.58);
+        TOKYO = (139.56, 35.53, 139.92, 35.82);
+    }
+}
+
+impl GeoDesk {
+    /// Bounding box of the administrative area with the given name
+    ///
+    /// Matches `name` or `name:en`; if several areas share the name, the
+    /// one with the lowest `admin_level` (the largest) wins. Only tiles
+    /// present in the GOL are searched.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let bbox = geodesk.bbox_of("Copenhagen").unwrap().expect("in the GOL");
+    /// let cafes = geodesk.query("na[amenity=cafe]", bbox).unwrap();
+    /// ```
+    pub fn bbox_of(&self, name: &str) -> Result<Option<BoundingBox>, GeoDeskError> {
+        let goql = format!(
+            "a[boundary=administrative][name={0}],a[boundary=administrative][name:en={0}]",
+            quote(name)
+        );
+        let world = bboxes::WORLD;
+        let list = ffi::bridge::query_handles(
+            &self.store,
+            &goql,
+            world.west,
+            world.south,
+            world.east,
+            world.north,
+            &QueryControl::new(None, None),
+        )?;
+        let refs = FeatureRefs::new(list, Vec::new());
+        // Bounds are read from the same handles, keyed like the features
+        let mut areas = Vec::with_capacity(refs.len());
+        let mut bounds = HashMap::new();
+        for area in refs.iter() {
+            let feature = area.hydrate(Detail::TAGS)?.feature;
+            bounds.insert((feature.feature_type(), feature.id), area.bounds());
+            areas.push(feature);
+        }
+        sort_by_admin_level(&mut areas);
+        Ok(areas
+            .first()
+            .map(|area| bounds[&(area.feature_type(), area.id)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_by_name() {
+        assert_eq!(bboxes::by_name("Copenhagen"), Some(bboxes::COPENHAGEN));
+        assert_eq!(
+            bboxes::by_name("san_francisco"),
+            Some(bboxes::SAN_FRANCISCO)
+        );
+        assert_eq!(bboxes::by_name("Atlantis"), None);
+    }
+}
@@ -0,0 +1,251 @@
+// Queries of a large area split into smaller boxes, and deduplication of
+// features that span several of them
+
+use crate::error::GeoDeskError;
+use crate::provenance::{Identity, IdentityKey};
+use crate::{BoundingBox, Feature, FeatureIter, GeoDesk};
+use std::collections::HashSet;
+
+/// Split a bounding box into a grid of `columns` by `rows` cells, row by row
+/// from the south-west
+pub(crate) fn grid(bbox: BoundingBox, columns: usize, rows: usize) -> Vec<BoundingBox> {
+    let width = (bbox.east - bbox.west) / columns as f64;
+    let height = (bbox.north - bbox.south) / rows as f64;
+    let mut cells = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            let west = bbox.west + column as f64 * width;
+            let south = bbox.south + row as f64 * height;
+            // The last cell of a row or column ends exactly on the edge
+            let east = if column + 1 == columns {
+                bbox.east
+            } else {
+                west + width
+            };
+            let north = if row + 1 == rows {
+                bbox.north
+            } else {
+                south + height
+            };
+            cells.push(BoundingBox::new(west, south, east, north));
+        }
+    }
+    cells
+}
+
+/// Remembers the features seen across several queries, so that ways and
+/// relations crossing the edges of their bounding boxes are kept once
+///
+/// Features are identified by an [`Identity`], type and id unless set with
+/// [`Deduplicator::with_identity`]. Memory grows with the number of
+/// distinct features seen, at 16 bytes each.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::tiled::Deduplicator;
+/// use geodesk_rs::{BoundingBox, GeoDesk};
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+/// let mut dedup = Deduplicator::new();
+/// for bbox in [
+///     BoundingBox::new(12.45, 55.61, 12.55, 55.73),
+///     BoundingBox::new(12.55, 55.61, 12.65, 55.73),
+/// ] {
+///     let roads = geodesk.query("w[highway]", bbox).unwrap().to_vec().unwrap();
+///     for road in dedup.filter(roads) {
+///         println!("{}", road.id);
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Deduplicator {
+    identity: Identity,
+    seen: HashSet<IdentityKey>,
+}
+
+impl Deduplicator {
+    /// Create a deduplicator that has seen no features
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set what makes two features the same; call before recording any
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Record a feature, returning `true` if it was not seen before
+    pub fn insert(&mut self, feature: &Feature) -> bool {
+        self.seen.insert(self.identity.key(feature))
+    }
+
+    /// Check if a feature was seen, without recording it
+    pub fn contains(&self, feature: &Feature) -> bool {
+        self.seen.contains(&self.identity.key(feature))
+    }
+
+    /// Keep only the features not seen before, recording them
+    pub fn filter(&mut self, features: Vec<Feature>) -> Vec<Feature> {
+        features.into_iter().filter(|f| self.insert(f)).collect()
+    }
+
+    /// Number of distinct features seen
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Check if no features were seen
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// Forget all features seen
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
+
+/// Streaming iterator over the features of [`GeoDesk::query_tiled`]
+///
+/// Cells are queried one after another as the iterator advances, so only
+/// one cell's cursor is open at a time.
+pub struct TiledFeatures<'a> {
+    geodesk: &'a GeoDesk,
+    goql_query: String,
+    cells: std::vec::IntoIter<BoundingBox>,
+    current: Option<FeatureIter>,
+    dedup: Deduplicator,
+}
+
+impl TiledFeatures<'_> {
+    /// Set what makes features found by several cells the same; by
+    /// default their type and id
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.dedup = self.dedup.with_identity(identity);
+        self
+    }
+
+    /// Number of distinct features returned so far
+    pub fn returned(&self) -> usize {
+        self.dedup.len()
+    }
+}
+
+impl Iterator for TiledFeatures<'_> {
+    type Item = Result<Feature, GeoDeskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(features) = &mut self.current {
+                match features.next() {
+                    Some(Ok(feature)) => {
+                        if self.dedup.insert(&feature) {
+                            return Some(Ok(feature));
+                        }
+                        continue;
+                    }
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => self.current = None,
+                }
+            }
+            let cell = self.cells.next()?;
+            match self.geodesk.query_iter(&self.goql_query, cell) {
+                Ok(features) => self.current = Some(features),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl GeoDesk {
+    /// Stream the features of a large area, queried as a grid of
+    /// `columns` by `rows` cells, each feature once
+    ///
+    /// Features spanning several cells are returned with the first cell
+    /// that finds them, and the order differs from that of
+    /// [`GeoDesk::query`]. Zero columns or rows are taken as one.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("planet.gol").unwrap();
+    /// let mut count = 0;
+    /// for river in geodesk.query_tiled("w[waterway=river]", bboxes::WORLD, 36, 18).unwrap() {
+    ///     river.unwrap();
+    ///     count += 1;
+    /// }
+    /// println!("{} rivers", count);
+    /// ```
+    pub fn query_tiled(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        columns: usize,
+        rows: usize,
+    ) -> Result<TiledFeatures<'_>, GeoDeskError> {
+        self.check_tiles(bbox)?;
+        Ok(TiledFeatures {
+            geodesk: self,
+            goql_query: goql_query.to_string(),
+            cells: grid(bbox, columns.max(1), rows.max(1)).into_iter(),
+            current: None,
+            dedup: Deduplicator::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let cells = grid(BoundingBox::new(0.0, 0.0, 1.0, 0.3), 3, 2);
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[0], BoundingBox::new(0.0, 0.0, 1.0 / 3.0, 0.15));
+        assert_eq!(cells[5].east, 1.0);
+        assert_eq!(cells[5].north, 0.3);
+        assert_eq!(cells[4].west, cells[3].east);
+    }
+
+    #[test]
+    fn test_deduplicator() {
+        let feature = |type_name: &str, id| Feature {
+            id,
+            type_name: type_name.to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: Vec::new(),
+            nodes: Vec::new(),
+        };
+        let mut dedup = Deduplicator::new();
+        let first = dedup.filter(vec![feature("way", 1), feature("node", 1)]);
+        assert_eq!(first.len(), 2);
+        let second = dedup.filter(vec![feature("way", 1), feature("way", 2)]);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].id, 2);
+        assert!(dedup.contains(&feature("node", 1)));
+        assert_eq!(dedup.len(), 3);
+        dedup.clear();
+        assert!(dedup.is_empty());
+
+        let mut by_id = Deduplicator::new().with_identity(Identity::Id);
+        assert_eq!(
+            by_id
+                .filter(vec![feature("way", 1), feature("node", 1)])
+                .len(),
+            1
+        );
+        let mut by_geometry = Deduplicator::new().with_identity(Identity::Geometry);
+        assert_eq!(
+            by_geometry
+                .filter(vec![feature("node", 1), feature("node", 2)])
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,300 @@
+// Tile addressing and the tile coverage of a GOL
+
+use crate::error::GeoDeskError;
+use crate::testing::bboxes;
+use crate::{ffi, BoundingBox, GeoDesk, QueryResult};
+use std::f64::consts::PI;
+
+/// Zoom level of the smallest tiles in a GOL built with default settings
+pub const LEAF_ZOOM: u8 = 12;
+
+/// Address of a tile in the XYZ (slippy map) scheme
+///
+/// GOL files use the same grid, so this identifies both vector tiles and the
+/// tiles a GOL is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    /// Create a tile id, returning `None` if `x`/`y` are outside the zoom level
+    pub fn new(z: u8, x: u32, y: u32) -> Option<Self> {
+        if z > 31 {
+            return None;
+        }
+        let n = 1u64 << z;
+        if u64::from(x) >= n || u64::from(y) >= n {
+            return None;
+        }
+        Some(Self { z, x, y })
+    }
+
+    /// The tile at zoom level `z` that contains a WGS84 coordinate
+    pub fn containing(lon: f64, lat: f64, z: u8) -> Self {
+        let z = z.min(31);
+        let n = (1u64 << z) as f64;
+        let lat = lat.clamp(-85.051_128_78, 85.051_128_78).to_radians();
+        let x = (lon + 180.0) / 360.0 * n;
+        let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+        let max = n - 1.0;
+        Self {
+            z,
+            x: x.floor().clamp(0.0, max) as u32,
+            y: y.floor().clamp(0.0, max) as u32,
+        }
+    }
+
+    /// This tile and the tiles in `rings` rings around it
+    ///
+    /// The neighborhood is a square block of up to `(2 * rings + 1)²` tiles,
+    /// cut off at the edges of the grid (it does not wrap across the
+    /// antimeridian), in row-major order.
+    pub fn neighborhood(&self, rings: u32) -> Vec<TileId> {
+        let max = ((1u64 << self.z) - 1) as u32;
+        let (x0, x1) = (
+            self.x.saturating_sub(rings),
+            self.x.saturating_add(rings).min(max),
+        );
+        let (y0, y1) = (
+            self.y.saturating_sub(rings),
+            self.y.saturating_add(rings).min(max),
+        );
+        (y0..=y1)
+            .flat_map(|y| (x0..=x1).map(move |x| TileId { z: self.z, x, y }))
+            .collect()
+    }
+
+    /// Bounding box of this tile in WGS84 coordinates
+    pub fn bounds(&self) -> BoundingBox {
+        let n = (1u64 << self.z) as f64;
+        let lon = |x: f64| x / n * 360.0 - 180.0;
+        let lat = |y: f64| (PI * (1.0 - 2.0 * y / n)).sinh().atan().to_degrees();
+        BoundingBox::new(
+            lon(self.x as f64),
+            lat(self.y as f64 + 1.0),
+            lon(self.x as f64 + 1.0),
+            lat(self.y as f64),
+        )
+    }
+
+    /// Project a WGS84 coordinate onto this tile's integer grid
+    #[cfg(feature = "mvt")]
+    pub(crate) fn project(&self, lon: f64, lat: f64, extent: u32) -> (i32, i32) {
+        let n = (1u64 << self.z) as f64;
+        let lat = lat.clamp(-85.051_128_78, 85.051_128_78).to_radians();
+        let x = (lon + 180.0) / 360.0 * n;
+        let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+        (
+            ((x - self.x as f64) * extent as f64).round() as i32,
+            ((y - self.y as f64) * extent as f64).round() as i32,
+        )
+    }
+}
+
+impl From<ffi::bridge::TileData> for TileId {
+    fn from(data: ffi::bridge::TileData) -> Self {
+        Self {
+            z: data.zoom,
+            x: data.column,
+            y: data.row,
+        }
+    }
+}
+
+/// How queries behave when their area includes tiles missing from the GOL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingTiles {
+    /// Fail with [`GeoDeskError::TilesMissing`]
+    #[default]
+    Error,
+    /// Return what is available and report the missing tiles on the result
+    Flag,
+    /// Return what is available without checking
+    Ignore,
+}
+
+/// Which tiles of the GOL's tile index are present and which are not
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TileCoverage {
+    pub present: Vec<TileId>,
+    pub missing: Vec<TileId>,
+}
+
+impl TileCoverage {
+    /// Check if every tile is present
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// Fraction of tiles that are present (1.0 for an empty index)
+    pub fn ratio(&self) -> f64 {
+        let total = self.present.len() + self.missing.len();
+        if total == 0 {
+            1.0
+        } else {
+            self.present.len() as f64 / total as f64
+        }
+    }
+}
+
+impl GeoDesk {
+    /// List the present and missing tiles of the whole GOL
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let coverage = geodesk.tile_coverage().unwrap();
+    /// println!("{:.1}% of tiles present", coverage.ratio() * 100.0);
+    /// ```
+    pub fn tile_coverage(&self) -> Result<TileCoverage, GeoDeskError> {
+        self.tile_coverage_in(bboxes::WORLD)
+    }
+
+    /// Query the features in the leaf tile containing a location and in
+    /// `rings` rings of tiles around it
+    ///
+    /// This is a coarse, cheap stand-in for a radius query: the search area
+    /// is a block of whole tiles (about 5-10 km per tile at mid latitudes)
+    /// and no distances are computed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("world.gol").unwrap();
+    /// let nearby = geodesk
+    ///     .query_tiles_around("na[amenity=hospital]", 12.57, 55.68, 1)
+    ///     .unwrap();
+    /// println!("{} hospitals in the 3x3 tiles around Copenhagen", nearby.count());
+    /// ```
+    pub fn query_tiles_around(
+        &self,
+        goql_query: &str,
+        lon: f64,
+        lat: f64,
+        rings: u32,
+    ) -> Result<QueryResult, GeoDeskError> {
+        let tiles = TileId::containing(lon, lat, LEAF_ZOOM).neighborhood(rings);
+        // Row-major order puts the north-west corner first and the
+        // south-east corner last
+        let (first, last) = (tiles[0].bounds(), tiles[tiles.len() - 1].bounds());
+        let bbox = BoundingBox::new(first.west, last.south, last.east, first.north);
+        self.query(goql_query, bbox)
+    }
+
+    /// List the present and missing tiles that intersect a bounding box
+    pub fn tile_coverage_in(&self, bbox: BoundingBox) -> Result<TileCoverage, GeoDeskError> {
+        let mut coverage = TileCoverage::default();
+        for tile in self.tiles(bbox)? {
+            if tile.present {
+                coverage.present.push(tile.into());
+            } else {
+                coverage.missing.push(tile.into());
+            }
+        }
+        Ok(coverage)
+    }
+
+    /// Apply the missing-tile policy to a query area, returning the tiles to
+    /// report on the result
+    pub(crate) fn check_tiles(&self, bbox: BoundingBox) -> Result<Vec<TileId>, GeoDeskError> {
+        #[cfg(feature = "remote")]
+        self.prefetch(bbox)?;
+        if self.missing_tiles == MissingTiles::Ignore || self.is_complete()? {
+            return Ok(Vec::new());
+        }
+        let missing: Vec<TileId> = self
+            .tiles(bbox)?
+            .into_iter()
+            .filter(|t| !t.present)
+            .map(TileId::from)
+            .collect();
+        match self.missing_tiles {
+            MissingTiles::Error if !missing.is_empty() => {
+                Err(GeoDeskError::TilesMissing { tiles: missing })
+            }
+            _ => Ok(missing),
+        }
+    }
+
+    /// Whether the GOL has all of its tiles; computed once per store
+    fn is_complete(&self) -> Result<bool, GeoDeskError> {
+        if let Some(&complete) = self.complete.get() {
+            return Ok(complete);
+        }
+        let complete = self.tile_coverage()?.is_complete();
+        Ok(*self.complete.get_or_init(|| complete))
+    }
+
+    pub(crate) fn tiles(
+        &self,
+        bbox: BoundingBox,
+    ) -> Result<Vec<ffi::bridge::TileData>, GeoDeskError> {
+        Ok(ffi::bridge::store_tiles(
+            &self.store,
+            bbox.west,
+            bbox.south,
+            bbox.east,
+            bbox.north,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_bounds() {
+        let bbox = TileId::new(1, 1, 0).unwrap().bounds();
+        assert_eq!(bbox.west, 0.0);
+        assert_eq!(bbox.east, 180.0);
+        assert!((bbox.north - 85.0511).abs() < 1e-4);
+        assert!(bbox.south.abs() < 1e-9);
+        assert!(TileId::new(1, 2, 0).is_none());
+    }
+
+    #[test]
+    fn test_containing_and_neighborhood() {
+        let tile = TileId::containing(12.6, 55.676, 12);
+        assert_eq!((tile.x, tile.y), (2191, 1282));
+        assert_eq!(
+            TileId::containing(180.0, -90.0, 2),
+            TileId::new(2, 3, 3).unwrap()
+        );
+
+        let ring = tile.neighborhood(1);
+        assert_eq!(ring.len(), 9);
+        assert_eq!(ring[4], tile);
+        // Cut off at the corner of the grid
+        assert_eq!(TileId::new(3, 0, 0).unwrap().neighborhood(2).len(), 9);
+    }
+
+    #[test]
+    fn test_coverage_ratio() {
+        let tile = |x| TileId::new(2, x, 1).unwrap();
+        let coverage = TileCoverage {
+            present: vec![tile(0), tile(1), tile(2)],
+            missing: vec![tile(3)],
+        };
+        assert!(!coverage.is_complete());
+        assert_eq!(coverage.ratio(), 0.75);
+        assert_eq!(TileCoverage::default().ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_tiles_missing_message() {
+        let error = GeoDeskError::TilesMissing {
+            tiles: vec![TileId::new(12, 2200, 1280).unwrap()],
+        };
+        assert_eq!(
+            error.to_string(),
+            "1 tile(s) missing from the GOL (first: 12/2200/1280)"
+        );
+    }
+}
@@ -0,0 +1,375 @@
+// Public transport (PTv2) routes, stops and lines, assembled from route and
+// route_master relations
+
+use crate::error::GeoDeskError;
+use crate::projection::haversine;
+use crate::query::quote;
+use crate::routes::{Route, RouteStop, StopRole};
+use crate::{ffi, BoundingBox, Feature, FeatureType, GeoDesk};
+use std::collections::HashMap;
+
+/// Farthest a platform may be from a stop position to be paired with it,
+/// in meters
+const MAX_PAIRING_DISTANCE: f64 = 100.0;
+
+/// Query for the features that serve as stops, PTv2 or legacy
+const STOP_QUERY: &str = "na[public_transport=stop_position,platform], \
+                          n[highway=bus_stop], n[railway=stop,halt,tram_stop], \
+                          n[amenity=ferry_terminal]";
+
+/// A stop of a transit route: where the vehicle halts, where passengers
+/// wait, or both
+#[derive(Debug, Clone)]
+pub struct TransitStop {
+    /// The `stop` member, with its tags
+    pub stop_position: Option<Feature>,
+    /// The `platform` member, with its tags
+    pub platform: Option<Feature>,
+    /// Whether passengers may board here (no `_exit_only` role)
+    pub entry: bool,
+    /// Whether passengers may alight here (no `_entry_only` role)
+    pub exit: bool,
+}
+
+impl TransitStop {
+    /// Name of the platform, or else of the stop position
+    pub fn name(&self) -> Option<&str> {
+        [&self.platform, &self.stop_position]
+            .into_iter()
+            .flatten()
+            .map(|f| f.name.as_str())
+            .find(|name| !name.is_empty())
+    }
+
+    /// Location of the stop position, or else of the platform
+    pub fn location(&self) -> (f64, f64) {
+        let feature = self
+            .stop_position
+            .as_ref()
+            .or(self.platform.as_ref())
+            .expect("a stop has a stop position or a platform");
+        (feature.lon, feature.lat)
+    }
+
+    fn accepts(&self, feature: &Feature, role: StopRole) -> bool {
+        let (own, other) = match role {
+            StopRole::Stop => (&self.stop_position, &self.platform),
+            StopRole::Platform => (&self.platform, &self.stop_position),
+        };
+        let Some(other) = other else {
+            return false;
+        };
+        own.is_none()
+            && (feature.name.is_empty() || other.name.is_empty() || feature.name == other.name)
+            && haversine(feature.lon, feature.lat, other.lon, other.lat) <= MAX_PAIRING_DISTANCE
+    }
+}
+
+/// A `type=route` relation of a public transport mode, with its stops
+/// paired up
+#[derive(Debug, Clone)]
+pub struct TransitRoute {
+    /// The route relation with its ordered ways
+    pub route: Route,
+    /// Stops in the order the route serves them
+    pub stops: Vec<TransitStop>,
+}
+
+impl TransitRoute {
+    /// Pair the stop positions and platforms of a route into stops,
+    /// looking up their features with `lookup`
+    ///
+    /// PTv2 lists each stop position followed by its platform; a member is
+    /// paired with the one before it if that is of the other kind, within
+    /// 100 m and without a different name. Members `lookup` doesn't find
+    /// keep their location, without tags.
+    pub fn new<F>(route: Route, mut lookup: F) -> Self
+    where
+        F: FnMut(&RouteStop) -> Option<Feature>,
+    {
+        let mut stops: Vec<TransitStop> = Vec::new();
+        for member in &route.stops {
+            let feature = lookup(member).unwrap_or_else(|| Feature {
+                id: member.id,
+                type_name: member.type_name.clone(),
+                name: String::new(),
+                lon: member.lon,
+                lat: member.lat,
+                tags: Vec::new(),
+                nodes: Vec::new(),
+            });
+            let entry = !member.exit_only;
+            let exit = !member.entry_only;
+            let stop = match stops.last_mut() {
+                Some(stop) if stop.accepts(&feature, member.role) => stop,
+                _ => {
+                    stops.push(TransitStop {
+                        stop_position: None,
+                        platform: None,
+                        entry,
+                        exit,
+                    });
+                    stops.last_mut().expect("just pushed")
+                }
+            };
+            stop.entry &= entry;
+            stop.exit &= exit;
+            match member.role {
+                StopRole::Stop => stop.stop_position = Some(feature),
+                StopRole::Platform => stop.platform = Some(feature),
+            }
+        }
+        Self { route, stops }
+    }
+
+    /// The relation's `route` tag (`bus`, `tram`, `train`, ...)
+    pub fn mode(&self) -> Option<&str> {
+        self.route.relation.tag("route")
+    }
+
+    /// Value of the `ref` tag (the line number)
+    pub fn reference(&self) -> Option<&str> {
+        self.route.reference()
+    }
+
+    /// Value of the `from` tag
+    pub fn from(&self) -> Option<&str> {
+        self.route.relation.tag("from")
+    }
+
+    /// Value of the `to` tag
+    pub fn to(&self) -> Option<&str> {
+        self.route.relation.tag("to")
+    }
+
+    /// Value of the `network` tag
+    pub fn network(&self) -> Option<&str> {
+        self.route.relation.tag("network")
+    }
+
+    /// Value of the `operator` tag
+    pub fn operator(&self) -> Option<&str> {
+        self.route.relation.tag("operator")
+    }
+
+    /// Value of the `colour` tag, such as `#0055A4`
+    pub fn colour(&self) -> Option<&str> {
+        self.route.relation.tag("colour")
+    }
+}
+
+/// A `type=route_master` relation: a line with its variants (usually one
+/// route per direction)
+#[derive(Debug, Clone)]
+pub struct TransitLine {
+    /// The route_master relation, with its tags
+    pub master: Feature,
+    /// The member routes found in the query area, in member order
+    pub routes: Vec<TransitRoute>,
+}
+
+impl TransitLine {
+    /// Value of the `ref` tag of the line, or else of its first route
+    pub fn reference(&self) -> Option<&str> {
+        self.master
+            .tag("ref")
+            .or_else(|| self.routes.first().and_then(TransitRoute::reference))
+    }
+}
+
+impl GeoDesk {
+    /// Route relations of a public transport mode (`bus`, `tram`,
+    /// `subway`, `train`, `ferry`, ...) in a bounding box, with their stop
+    /// positions and platforms paired into stops
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// for route in geodesk.transit_routes(bboxes::COPENHAGEN, "bus").unwrap() {
+    ///     let stops: Vec<&str> = route.stops.iter().filter_map(|s| s.name()).collect();
+    ///     println!("{}: {}", route.reference().unwrap_or("?"), stops.join(" - "));
+    /// }
+    /// ```
+    pub fn transit_routes(
+        &self,
+        bbox: BoundingBox,
+        mode: &str,
+    ) -> Result<Vec<TransitRoute>, GeoDeskError> {
+        let routes = self.routes(bbox, mode)?;
+        let mut extent: Option<BoundingBox> = None;
+        for stop in routes.iter().flat_map(|r| &r.stops) {
+            let e = extent.get_or_insert(BoundingBox::new(stop.lon, stop.lat, stop.lon, stop.lat));
+            e.west = e.west.min(stop.lon);
+            e.south = e.south.min(stop.lat);
+            e.east = e.east.max(stop.lon);
+            e.north = e.north.max(stop.lat);
+        }
+        let mut features: HashMap<(FeatureType, i64), Feature> = HashMap::new();
+        if let Some(extent) = extent {
+            for feature in self.query(STOP_QUERY, extent)?.to_vec()? {
+                features.insert((feature.feature_type(), feature.id), feature);
+            }
+        }
+        Ok(routes
+            .into_iter()
+            .map(|route| {
+                TransitRoute::new(route, |stop| {
+                    features
+                        .get(&(FeatureType::from(stop.type_name.as_str()), stop.id))
+                        .cloned()
+                })
+            })
+            .collect())
+    }
+
+    /// Lines (`route_master` relations) of a public transport mode in a
+    /// bounding box, with those of their routes that are in it
+    pub fn transit_lines(
+        &self,
+        bbox: BoundingBox,
+        mode: &str,
+    ) -> Result<Vec<TransitLine>, GeoDeskError> {
+        let query = format!("r[type=route_master][route_master={}]", quote(mode));
+        let members = self.relation_members(&query, bbox)?;
+        let mut routes: HashMap<i64, TransitRoute> = self
+            .transit_routes(bbox, mode)?
+            .into_iter()
+            .map(|route| (route.route.relation.id, route))
+            .collect();
+        Ok(self
+            .query(&query, bbox)?
+            .to_vec()?
+            .into_iter()
+            .map(|master| {
+                let routes = members
+                    .get(&master.id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|id| routes.remove(id))
+                    .collect();
+                TransitLine { master, routes }
+            })
+            .collect())
+    }
+
+    /// Ids of the relation members of the relations matching a query
+    fn relation_members(
+        &self,
+        query: &str,
+        bbox: BoundingBox,
+    ) -> Result<HashMap<i64, Vec<i64>>, GeoDeskError> {
+        self.check_tiles(bbox)?;
+        let _active = self.activity.begin();
+        let relations = ffi::bridge::query_relation_members(
+            &self.store,
+            query,
+            bbox.west,
+            bbox.south,
+            bbox.east,
+            bbox.north,
+        )?;
+        Ok(relations
+            .into_iter()
+            .map(|relation| {
+                let ids = relation
+                    .members
+                    .into_iter()
+                    .filter(|m| m.type_name == "relation")
+                    .map(|m| m.id)
+                    .collect();
+                (relation.id, ids)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{MemberGeometry, RelationGeometry};
+
+    fn member(id: i64, role: &str, lon: f64) -> MemberGeometry {
+        MemberGeometry {
+            id,
+            type_name: "node".to_string(),
+            role: role.to_string(),
+            lon,
+            lat: 55.0,
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_stop_pairing() {
+        let relation = Feature {
+            id: 1,
+            type_name: "relation".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: vec![
+                ("route".to_string(), "bus".to_string()),
+                ("ref".to_string(), "5C".to_string()),
+            ],
+            nodes: Vec::new(),
+        };
+        // At this latitude 0.0001° of longitude is ~6 m, 0.005° ~320 m
+        let members = RelationGeometry {
+            id: 1,
+            members: vec![
+                member(10, "stop_entry_only", 12.0),
+                member(11, "platform_entry_only", 12.0001),
+                member(20, "stop", 12.01),
+                member(30, "platform", 12.015),
+                member(40, "stop_exit_only", 12.02),
+                member(41, "platform", 12.0201),
+            ],
+        };
+        let names: HashMap<i64, &str> = [(10, "Nørreport"), (11, "Nørreport"), (20, "Kultorvet")]
+            .into_iter()
+            .collect();
+        let route = TransitRoute::new(Route::new(relation, &members), |stop| {
+            let name = names.get(&stop.id)?;
+            Some(Feature {
+                id: stop.id,
+                type_name: stop.type_name.clone(),
+                name: name.to_string(),
+                lon: stop.lon,
+                lat: stop.lat,
+                tags: Vec::new(),
+                nodes: Vec::new(),
+            })
+        });
+
+        assert_eq!(route.mode(), Some("bus"));
+        assert_eq!(route.reference(), Some("5C"));
+        let stops: Vec<(Option<i64>, Option<i64>)> = route
+            .stops
+            .iter()
+            .map(|s| {
+                (
+                    s.stop_position.as_ref().map(|f| f.id),
+                    s.platform.as_ref().map(|f| f.id),
+                )
+            })
+            .collect();
+        // Platform 30 is unnamed but too far from stop 20 to belong to it
+        assert_eq!(
+            stops,
+            vec![
+                (Some(10), Some(11)),
+                (Some(20), None),
+                (None, Some(30)),
+                (Some(40), Some(41)),
+            ]
+        );
+        assert_eq!(route.stops[0].name(), Some("Nørreport"));
+        assert_eq!(route.stops[2].name(), None);
+        assert!(route.stops[0].entry && !route.stops[0].exit);
+        assert!(!route.stops[3].entry && route.stops[3].exit);
+        assert_eq!(route.stops[1].location(), (12.01, 55.0));
+    }
+}
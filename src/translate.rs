@@ -0,0 +1,552 @@
+// Translation between GOQL and Overpass QL, and of GOQL into SQL for
+// osm2pgsql databases
+
+use crate::query::{ParseQueryError, Query, TagFilter};
+use crate::BoundingBox;
+
+/// Translate a GOQL query into an equivalent Overpass QL query
+///
+/// Feature types map to `node`, `way` and `rel` statements; areas (`a`)
+/// become closed ways plus `multipolygon` and `boundary` relations. Numeric
+/// comparisons are expressed with `if:` evaluators. The query returns JSON
+/// with full geometries.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::translate::to_overpass;
+/// use geodesk_rs::BoundingBox;
+///
+/// let bbox = BoundingBox::new(12.45, 55.61, 12.65, 55.73);
+/// let ql = to_overpass("n[amenity=cafe][!name]", bbox).unwrap();
+/// assert_eq!(
+///     ql,
+///     "[out:json];\n(\n  node[\"amenity\"=\"cafe\"][!\"name\"](55.61,12.45,55.73,12.65);\n);\nout geom;"
+/// );
+/// ```
+pub fn to_overpass(goql: &str, bbox: BoundingBox) -> Result<String, ParseQueryError> {
+    let query: Query = goql.parse()?;
+    let mut filters = String::new();
+    let mut conditions = Vec::new();
+    for filter in query.filters() {
+        match filter {
+            TagFilter::Exists(key) => filters.push_str(&format!("[{}]", ql_string(key))),
+            TagFilter::Missing(key) => filters.push_str(&format!("[!{}]", ql_string(key))),
+            TagFilter::Equals(key, values) => filters.push_str(&ql_values(key, "", values)),
+            TagFilter::NotEquals(key, values) => filters.push_str(&ql_values(key, "!", values)),
+            TagFilter::Compare(key, op, n) => {
+                conditions.push(format!("number(t[{}]){}{}", ql_string(key), op.as_str(), n))
+            }
+        }
+    }
+    if !conditions.is_empty() {
+        filters.push_str(&format!("(if:{})", conditions.join("&&")));
+    }
+    let area = format!(
+        "({},{},{},{})",
+        bbox.south, bbox.west, bbox.north, bbox.east
+    );
+
+    let types = types_of(&query);
+    let mut statements = Vec::new();
+    if types.nodes {
+        statements.push(format!("node{}{}", filters, area));
+    }
+    if types.ways {
+        statements.push(format!("way{}{}", filters, area));
+    } else if types.areas {
+        statements.push(format!("way{}{}(if:is_closed())", filters, area));
+    }
+    if types.relations {
+        statements.push(format!("rel{}{}", filters, area));
+    } else if types.areas {
+        statements.push(format!(
+            "rel[type~\"^(multipolygon|boundary)$\"]{}{}",
+            filters, area
+        ));
+    }
+    let mut ql = String::from("[out:json];\n(\n");
+    for statement in statements {
+        ql.push_str(&format!("  {};\n", statement));
+    }
+    ql.push_str(");\nout geom;");
+    Ok(ql)
+}
+
+/// Tables and columns of an osm2pgsql database
+///
+/// The default matches osm2pgsql's `pgsql` output with `--hstore`:
+/// `planet_osm_point`, `planet_osm_line` and `planet_osm_polygon` tables
+/// with the geometry in `way` and tags without a column of their own in
+/// the `tags` hstore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlSchema {
+    /// Table name prefix
+    pub prefix: String,
+    /// Name of the geometry column
+    pub geometry_column: String,
+    /// Name of the hstore column holding the remaining tags
+    pub hstore_column: String,
+    /// Keys that have a column of their own
+    pub columns: Vec<String>,
+}
+
+impl Default for SqlSchema {
+    fn default() -> Self {
+        Self {
+            prefix: "planet_osm".to_string(),
+            geometry_column: "way".to_string(),
+            hstore_column: "tags".to_string(),
+            columns: [
+                "amenity", "building", "highway", "landuse", "leisure", "name", "natural",
+                "railway", "shop", "tourism", "waterway",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        }
+    }
+}
+
+impl SqlSchema {
+    /// Use a different table name prefix
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Set the keys that have a column of their own
+    pub fn with_columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn tag(&self, key: &str) -> String {
+        if self.columns.iter().any(|c| c == key) {
+            sql_identifier(key)
+        } else {
+            format!(
+                "({}->{})",
+                sql_identifier(&self.hstore_column),
+                sql_string(key)
+            )
+        }
+    }
+}
+
+/// Translate a GOQL query into a PostGIS query against an osm2pgsql database
+///
+/// Nodes are read from the `_point` table, ways from `_line`, areas from
+/// `_polygon`; relations are the rows with negative `osm_id` in `_line` and
+/// `_polygon`. Each row has the `osm_id` and the geometry column.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::translate::{to_sql, SqlSchema};
+///
+/// let sql = to_sql("n[amenity=cafe][cuisine]", &SqlSchema::default()).unwrap();
+/// assert_eq!(
+///     sql,
+///     "SELECT osm_id, \"way\" FROM \"planet_osm_point\" \
+///      WHERE \"amenity\" = 'cafe' AND (\"tags\"->'cuisine') IS NOT NULL"
+/// );
+/// ```
+pub fn to_sql(goql: &str, schema: &SqlSchema) -> Result<String, ParseQueryError> {
+    let query: Query = goql.parse()?;
+    let mut conditions: Vec<String> = query
+        .filters()
+        .iter()
+        .map(|filter| sql_condition(filter, schema))
+        .collect();
+    if conditions.is_empty() {
+        conditions.push("TRUE".to_string());
+    }
+    let types = types_of(&query);
+    let mut tables: Vec<(&str, Option<&str>)> = Vec::new();
+    if types.nodes {
+        tables.push(("point", None));
+    }
+    match (types.ways, types.relations) {
+        (true, true) => tables.push(("line", None)),
+        (true, false) => tables.push(("line", Some("osm_id > 0"))),
+        (false, true) => tables.push(("line", Some("osm_id < 0"))),
+        (false, false) => {}
+    }
+    if types.areas || types.relations {
+        tables.push(("polygon", (!types.areas).then_some("osm_id < 0")));
+    }
+
+    let selects: Vec<String> = tables
+        .into_iter()
+        .map(|(table, extra)| {
+            let mut all = conditions.clone();
+            all.extend(extra.map(String::from));
+            format!(
+                "SELECT osm_id, {} FROM {} WHERE {}",
+                sql_identifier(&schema.geometry_column),
+                sql_identifier(&format!("{}_{}", schema.prefix, table)),
+                all.join(" AND ")
+            )
+        })
+        .collect();
+    Ok(selects.join(" UNION ALL "))
+}
+
+impl Query {
+    /// Compile an Overpass QL query into a GOQL query
+    ///
+    /// A practical subset is accepted: `node`, `way`, `rel` and `nwr`
+    /// statements with tag filters (`[k]`, `[!k]`, `[k=v]`, `[k!=v]`, and
+    /// `[k~"^(a|b)$"]` alternations of plain values), optionally in a union
+    /// whose statements share the same filters. Settings, bounding boxes and
+    /// `out` statements are ignored. Anything GOQL can't express (other
+    /// regular expressions, `around`, `if:`, named sets) is an error.
+    ///
+    /// `way` selects both ways and areas, `rel` both relations and
+    /// multipolygon areas.
+    ///
+    /// # Example
+    /// ```
+    /// use geodesk_rs::query::Query;
+    ///
+    /// let query = Query::from_overpass(
+    ///     "[out:json];(node[amenity~\"^(cafe|bar)$\"](50.7,7.1,50.8,7.2); way[amenity~\"^(cafe|bar)$\"](50.7,7.1,50.8,7.2););out;",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(query.to_goql(), "nwa[amenity=cafe,bar]");
+    /// ```
+    pub fn from_overpass(ql: &str) -> Result<Query, ParseQueryError> {
+        let error = |reason: &str| ParseQueryError::new(ql, reason);
+        let mut types = String::new();
+        let mut filters: Option<Vec<TagFilter>> = None;
+        for statement in split_statements(ql) {
+            let statement = statement.trim_start_matches(['(', ')', ' ', '\t', '\n', '\r']);
+            if statement.is_empty() || statement.starts_with('[') || statement.starts_with("out") {
+                continue;
+            }
+            let (statement_types, statement_filters) =
+                parse_statement(statement).map_err(|r| error(&r))?;
+            for t in statement_types.chars() {
+                if !types.contains(t) {
+                    types.push(t);
+                }
+            }
+            match &filters {
+                None => filters = Some(statement_filters),
+                Some(f) if *f != statement_filters => {
+                    return Err(error("statements with different filters can't be combined"))
+                }
+                Some(_) => {}
+            }
+        }
+        let filters = filters.ok_or_else(|| error("no query statement"))?;
+        let types = if ["n", "w", "a", "r"].iter().all(|t| types.contains(t)) {
+            "*".to_string()
+        } else {
+            types
+        };
+        Ok(filters
+            .into_iter()
+            .fold(Query::new(&types), |query, filter| query.find(filter)))
+    }
+}
+
+/// Split Overpass QL at the `;` outside of strings
+fn split_statements(ql: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in ql.char_indices() {
+        match quote {
+            _ if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ';' => {
+                statements.push(&ql[start..i]);
+                start = i + 1;
+            }
+            None => {}
+        }
+    }
+    statements.push(&ql[start..]);
+    statements
+}
+
+fn parse_statement(statement: &str) -> Result<(&'static str, Vec<TagFilter>), String> {
+    let word_end = statement
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(statement.len());
+    let types = match &statement[..word_end] {
+        "node" => "n",
+        "way" => "wa",
+        "rel" | "relation" => "ra",
+        "nw" => "nwa",
+        "nr" => "nra",
+        "wr" => "wra",
+        "nwr" => "nwar",
+        other => return Err(format!("unsupported statement '{}'", other)),
+    };
+    let mut chars = statement[word_end..].chars().peekable();
+    let mut filters = Vec::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => filters.push(parse_ql_filter(&mut chars)?),
+            '(' => {
+                let inner: String = std::iter::from_fn(|| chars.next_if(|&c| c != ')')).collect();
+                chars.next();
+                let inner = inner.trim();
+                let is_bbox = inner == "{{bbox}}"
+                    || inner.split(',').count() == 4
+                        && inner.split(',').all(|n| n.trim().parse::<f64>().is_ok());
+                if !is_bbox {
+                    return Err(format!("unsupported filter '({})'", inner));
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => return Err(format!("unexpected '{}'", c)),
+        }
+    }
+    Ok((types, filters))
+}
+
+fn parse_ql_filter(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<TagFilter, String> {
+    let negated = chars.next_if_eq(&'!').is_some();
+    let key = parse_ql_token(chars)?;
+    let op: String =
+        std::iter::from_fn(|| chars.next_if(|c| matches!(c, '=' | '!' | '~'))).collect();
+    let filter = match (negated, op.as_str()) {
+        (true, "") => TagFilter::Missing(key),
+        (false, "") => TagFilter::Exists(key),
+        (false, "=") => TagFilter::Equals(key, vec![parse_ql_token(chars)?]),
+        (false, "!=") => TagFilter::NotEquals(key, vec![parse_ql_token(chars)?]),
+        (false, "~") => TagFilter::Equals(key, regex_values(&parse_ql_token(chars)?)?),
+        (false, "!~") => TagFilter::NotEquals(key, regex_values(&parse_ql_token(chars)?)?),
+        _ => return Err(format!("unsupported operator '{}'", op)),
+    };
+    match chars.next() {
+        Some(']') => Ok(filter),
+        Some(',') => Err("case-insensitive matching is not supported".to_string()),
+        _ => Err("expected ']'".to_string()),
+    }
+}
+
+fn parse_ql_token(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    let mut token = String::new();
+    match chars.peek() {
+        Some(&quote @ ('"' | '\'')) => {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('\\') => token.extend(chars.next()),
+                    Some(c) if c == quote => break,
+                    Some(c) => token.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+        }
+        _ => {
+            while let Some(c) =
+                chars.next_if(|&c| !matches!(c, '=' | '!' | '~' | ']' | ',') && !c.is_whitespace())
+            {
+                token.push(c);
+            }
+            if token.is_empty() {
+                return Err("expected a key or value".to_string());
+            }
+        }
+    }
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    Ok(token)
+}
+
+/// The values of a regular expression that only matches a list of plain
+/// values (`^value$` or `^(a|b|c)$`)
+fn regex_values(regex: &str) -> Result<Vec<String>, String> {
+    let unsupported = || format!("regular expression '{}' is not supported", regex);
+    let inner = regex
+        .strip_prefix('^')
+        .and_then(|r| r.strip_suffix('$'))
+        .ok_or_else(unsupported)?;
+    let inner = inner
+        .strip_prefix('(')
+        .and_then(|r| r.strip_suffix(')'))
+        .unwrap_or(inner);
+    let mut values = Vec::new();
+    for alternative in inner.split('|') {
+        let mut value = String::new();
+        let mut chars = alternative.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => value.push(chars.next().ok_or_else(unsupported)?),
+                c if ".+*?()[]{}^$".contains(c) => return Err(unsupported()),
+                c => value.push(c),
+            }
+        }
+        if value.is_empty() {
+            return Err(unsupported());
+        }
+        values.push(value);
+    }
+    Ok(values)
+}
+
+struct Types {
+    nodes: bool,
+    ways: bool,
+    areas: bool,
+    relations: bool,
+}
+
+fn types_of(query: &Query) -> Types {
+    let t = query.types();
+    let all = t.contains('*');
+    Types {
+        nodes: all || t.contains('n'),
+        ways: all || t.contains('w'),
+        areas: all || t.contains('a'),
+        relations: all || t.contains('r'),
+    }
+}
+
+fn sql_condition(filter: &TagFilter, schema: &SqlSchema) -> String {
+    match filter {
+        TagFilter::Exists(key) => format!("{} IS NOT NULL", schema.tag(key)),
+        TagFilter::Missing(key) => format!("{} IS NULL", schema.tag(key)),
+        TagFilter::Equals(key, values) if values.len() == 1 => {
+            format!("{} = {}", schema.tag(key), sql_string(&values[0]))
+        }
+        TagFilter::Equals(key, values) => {
+            format!("{} IN ({})", schema.tag(key), sql_list(values))
+        }
+        TagFilter::NotEquals(key, values) => {
+            let tag = schema.tag(key);
+            format!("({} IS NULL OR {} NOT IN ({}))", tag, tag, sql_list(values))
+        }
+        TagFilter::Compare(key, op, n) => {
+            let tag = schema.tag(key);
+            format!(
+                "(CASE WHEN {} ~ '^-?[0-9]+(\\.[0-9]+)?$' THEN {}::numeric END) {} {}",
+                tag,
+                tag,
+                op.as_str(),
+                n
+            )
+        }
+    }
+}
+
+fn ql_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn ql_values(key: &str, negation: &str, values: &[String]) -> String {
+    if let [value] = values {
+        return format!("[{}{}={}]", ql_string(key), negation, ql_string(value));
+    }
+    let alternatives: Vec<String> = values.iter().map(|v| regex_escape(v)).collect();
+    format!(
+        "[{}{}~{}]",
+        ql_string(key),
+        negation,
+        ql_string(&format!("^({})$", alternatives.join("|")))
+    )
+}
+
+fn regex_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn sql_identifier(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+fn sql_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn sql_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| sql_string(v))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overpass_translation() {
+        let bbox = BoundingBox::new(2.0, 48.0, 3.0, 49.0);
+        let ql = to_overpass("a[leisure=park,garden][!access][area>1000]", bbox).unwrap();
+        let filters = "[\"leisure\"~\"^(park|garden)$\"][!\"access\"](if:number(t[\"area\"])>1000)";
+        assert_eq!(
+            ql,
+            format!(
+                "[out:json];\n(\n  way{f}(48,2,49,3)(if:is_closed());\n  \
+                 rel[type~\"^(multipolygon|boundary)$\"]{f}(48,2,49,3);\n);\nout geom;",
+                f = filters
+            )
+        );
+        assert!(to_overpass("n[name~'x']", bbox).is_err());
+    }
+
+    #[test]
+    fn test_from_overpass() {
+        let query = Query::from_overpass(
+            "[out:json][timeout:25];\n\
+             node[\"highway\"=\"bus_stop\"][!shelter][\"name\"]({{bbox}});\n\
+             out body;",
+        )
+        .unwrap();
+        assert_eq!(query.to_goql(), "n[highway=bus_stop][!shelter][name]");
+
+        let query = Query::from_overpass("nwr[shop!~'^(vacant|no)$'];").unwrap();
+        assert_eq!(query.to_goql(), "*[shop!=vacant,no]");
+
+        // Round trip through to_overpass
+        let bbox = BoundingBox::new(2.0, 48.0, 3.0, 49.0);
+        let ql = to_overpass("n[amenity=cafe,bar][!name]", bbox).unwrap();
+        assert_eq!(
+            Query::from_overpass(&ql).unwrap().to_goql(),
+            "n[amenity=cafe,bar][!name]"
+        );
+
+        assert!(Query::from_overpass("node[name~\"^St\"];").is_err());
+        assert!(Query::from_overpass("node(around:100,50.7,7.1)[amenity];").is_err());
+        assert!(Query::from_overpass("node[amenity];way[highway];").is_err());
+        assert!(Query::from_overpass("out;").is_err());
+    }
+
+    #[test]
+    fn test_sql_translation() {
+        let schema = SqlSchema::default().with_columns(["highway"]);
+        let sql = to_sql("r[highway!=footway,path][lanes>=2]", &schema).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT osm_id, \"way\" FROM \"planet_osm_line\" WHERE \
+             (\"highway\" IS NULL OR \"highway\" NOT IN ('footway', 'path')) AND \
+             (CASE WHEN (\"tags\"->'lanes') ~ '^-?[0-9]+(\\.[0-9]+)?$' \
+             THEN (\"tags\"->'lanes')::numeric END) >= 2 AND osm_id < 0 \
+             UNION ALL SELECT osm_id, \"way\" FROM \"planet_osm_polygon\" WHERE \
+             (\"highway\" IS NULL OR \"highway\" NOT IN ('footway', 'path')) AND \
+             (CASE WHEN (\"tags\"->'lanes') ~ '^-?[0-9]+(\\.[0-9]+)?$' \
+             THEN (\"tags\"->'lanes')::numeric END) >= 2 AND osm_id < 0"
+        );
+    }
+}
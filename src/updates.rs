@@ -0,0 +1,508 @@
+// Overlaying OSM change files (.osc) on query results between GOL builds
+
+use crate::error::GeoDeskError;
+use crate::query::Query;
+use crate::{BoundingBox, Feature, FeatureType, GeoDesk, Node};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// A created or modified element as found in a change file
+#[derive(Debug, Clone, Default)]
+struct Element {
+    tags: Vec<(String, String)>,
+    /// Location of nodes
+    lon: f64,
+    lat: f64,
+    /// Node ids of ways
+    nodes: Vec<i64>,
+}
+
+/// Changes read from osmChange files, to overlay on query results from a
+/// GOL built before them
+///
+/// Read the minutely or hourly diffs published since the GOL was built in
+/// order; later changes to an element replace earlier ones. Compressed
+/// diffs (`.osc.gz`) must be decompressed first, for example by passing a
+/// `flate2::read::GzDecoder` to [`read_osc`](Self::read_osc).
+///
+/// GOLs can't be updated in place, so rebuild the GOL from time to time
+/// and start over with the diffs published after the new build.
+///
+/// # Example
+/// ```no_run
+/// use geodesk_rs::testing::bboxes;
+/// use geodesk_rs::updates::Updates;
+/// use geodesk_rs::GeoDesk;
+///
+/// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+/// let mut updates = Updates::new();
+/// for sequence in 6_100_000..6_100_060 {
+///     updates.read_osc_file(format!("diffs/{}.osc", sequence)).unwrap();
+/// }
+/// let cafes = geodesk
+///     .query_updated("na[amenity=cafe]", bboxes::COPENHAGEN, &updates)
+///     .unwrap();
+/// println!("{} cafes as of the last diff", cafes.len());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Updates {
+    changed: HashMap<(FeatureType, i64), Element>,
+    deleted: HashSet<(FeatureType, i64)>,
+}
+
+impl Updates {
+    /// An empty set of changes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read an osmChange file and apply its changes on top of those read
+    /// before
+    ///
+    /// Returns the number of elements created, modified or deleted by the
+    /// file. Malformed files fail with an I/O error of kind `InvalidData`.
+    pub fn read_osc<R: Read>(&mut self, mut reader: R) -> Result<usize, GeoDeskError> {
+        let mut xml = String::new();
+        reader.read_to_string(&mut xml)?;
+        self.parse(&xml)
+    }
+
+    /// Read an osmChange file from disk (see [`read_osc`](Self::read_osc))
+    pub fn read_osc_file<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, GeoDeskError> {
+        self.read_osc(BufReader::new(File::open(path)?))
+    }
+
+    /// Number of elements created or modified
+    pub fn changed(&self) -> usize {
+        self.changed.len()
+    }
+
+    /// Number of elements deleted
+    pub fn deleted(&self) -> usize {
+        self.deleted.len()
+    }
+
+    /// Check if no changes have been read
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.deleted.is_empty()
+    }
+
+    /// Check if the changes delete a feature
+    pub fn is_deleted(&self, feature_type: FeatureType, id: i64) -> bool {
+        self.deleted.contains(&(feature_type, id))
+    }
+
+    fn parse(&mut self, xml: &str) -> Result<usize, GeoDeskError> {
+        let mut count = 0;
+        let mut deleting = None;
+        // The element being read, with its type, id and contents
+        let mut current: Option<(FeatureType, i64, Element)> = None;
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start + 1..];
+            if let Some(comment) = rest.strip_prefix("!--") {
+                let end = comment
+                    .find("-->")
+                    .ok_or_else(|| malformed("unclosed comment"))?;
+                rest = &comment[end + 3..];
+                continue;
+            }
+            let end = rest.find('>').ok_or_else(|| malformed("unclosed tag"))?;
+            let tag = &rest[..end];
+            rest = &rest[end + 1..];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                match name.trim() {
+                    "create" | "modify" | "delete" => deleting = None,
+                    "node" | "way" | "relation" => {
+                        if let (Some((t, id, element)), Some(delete)) = (current.take(), deleting) {
+                            self.record(t, id, element, delete);
+                            count += 1;
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            let closed = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            let attributes = parse_attributes(attributes)?;
+            let attribute = |key: &str| {
+                attributes
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.as_str())
+            };
+            match name {
+                "create" | "modify" => deleting = Some(false),
+                "delete" => deleting = Some(true),
+                "node" | "way" | "relation" => {
+                    let Some(delete) = deleting else {
+                        return Err(malformed("element outside create, modify or delete"));
+                    };
+                    let id = number(attribute("id"))?;
+                    let mut element = Element::default();
+                    let t = FeatureType::from(name);
+                    if t == FeatureType::Node && !delete {
+                        element.lon = number(attribute("lon"))?;
+                        element.lat = number(attribute("lat"))?;
+                    }
+                    if closed {
+                        self.record(t, id, element, delete);
+                        count += 1;
+                    } else {
+                        current = Some((t, id, element));
+                    }
+                }
+                "tag" => {
+                    if let Some((_, _, element)) = &mut current {
+                        let key = attribute("k").ok_or_else(|| malformed("tag without k"))?;
+                        let value = attribute("v").unwrap_or_default();
+                        element.tags.push((key.to_string(), value.to_string()));
+                    }
+                }
+                "nd" => {
+                    if let Some((_, _, element)) = &mut current {
+                        element.nodes.push(number(attribute("ref"))?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(count)
+    }
+
+    fn record(&mut self, t: FeatureType, id: i64, element: Element, delete: bool) {
+        if delete {
+            self.changed.remove(&(t, id));
+            self.deleted.insert((t, id));
+        } else {
+            self.deleted.remove(&(t, id));
+            self.changed.insert((t, id), element);
+        }
+    }
+
+    /// Overlay the changes on the features a query returned from the GOL
+    ///
+    /// Deleted features are dropped, modified ones get their new tags and
+    /// geometry, and ways follow nodes that moved. Changed features that
+    /// no longer match the query or lie outside the bounding box are
+    /// dropped, and created or modified ones that now match are added.
+    ///
+    /// Change files only hold the elements that changed, so the nodes of
+    /// a changed way are located from the changes and from the ways in
+    /// `features`; nodes found in neither are left out of its geometry,
+    /// and new ways are only added if all their nodes are found. Changed
+    /// relations keep their previous location and are only added if
+    /// already in `features`.
+    pub fn apply(&self, query: &Query, bbox: BoundingBox, features: Vec<Feature>) -> Vec<Feature> {
+        let mut locations: HashMap<i64, (f64, f64)> = HashMap::new();
+        for feature in &features {
+            if feature.is_node() {
+                locations.insert(feature.id, (feature.lon, feature.lat));
+            }
+            for node in &feature.nodes {
+                locations.insert(node.id, (node.lon, node.lat));
+            }
+        }
+        for ((t, id), element) in &self.changed {
+            if *t == FeatureType::Node {
+                locations.insert(*id, (element.lon, element.lat));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut updated = Vec::with_capacity(features.len());
+        for mut feature in features {
+            let key = (feature.feature_type(), feature.id);
+            seen.insert(key);
+            if self.deleted.contains(&key) {
+                continue;
+            }
+            let changed = match self.changed.get(&key) {
+                Some(element) => {
+                    self.update(&mut feature, element, &locations);
+                    true
+                }
+                None => self.move_nodes(&mut feature, &locations),
+            };
+            if !changed || (query.matches(&feature) && within(&feature, &bbox)) {
+                updated.push(feature);
+            }
+        }
+
+        let mut added: Vec<_> = self
+            .changed
+            .iter()
+            .filter(|(key, _)| key.0 != FeatureType::Relation && !seen.contains(key))
+            .filter_map(|(&(t, id), element)| {
+                let mut feature = Feature {
+                    id,
+                    type_name: t.as_str().to_string(),
+                    name: String::new(),
+                    lon: 0.0,
+                    lat: 0.0,
+                    tags: Vec::new(),
+                    nodes: Vec::new(),
+                };
+                let complete = element.nodes.iter().all(|n| locations.contains_key(n));
+                self.update(&mut feature, element, &locations);
+                (complete && query.matches(&feature) && within(&feature, &bbox)).then_some(feature)
+            })
+            .collect();
+        added.sort_by(|a, b| (&a.type_name, a.id).cmp(&(&b.type_name, b.id)));
+        updated.extend(added);
+        updated
+    }
+
+    fn update(
+        &self,
+        feature: &mut Feature,
+        element: &Element,
+        locations: &HashMap<i64, (f64, f64)>,
+    ) {
+        feature.tags = element.tags.clone();
+        feature.name = feature.tag("name").unwrap_or_default().to_string();
+        match feature.feature_type() {
+            FeatureType::Node => (feature.lon, feature.lat) = (element.lon, element.lat),
+            FeatureType::Way => {
+                feature.nodes = element
+                    .nodes
+                    .iter()
+                    .filter_map(|id| {
+                        locations
+                            .get(id)
+                            .map(|&(lon, lat)| Node { id: *id, lon, lat })
+                    })
+                    .collect();
+                center(feature);
+            }
+            FeatureType::Relation => {}
+        }
+    }
+
+    // Move the nodes of a way that the changes moved; true if any did
+    fn move_nodes(&self, feature: &mut Feature, locations: &HashMap<i64, (f64, f64)>) -> bool {
+        let mut moved = false;
+        for node in &mut feature.nodes {
+            if !self.changed.contains_key(&(FeatureType::Node, node.id)) {
+                continue;
+            }
+            if let Some(&(lon, lat)) = locations.get(&node.id) {
+                moved |= (node.lon, node.lat) != (lon, lat);
+                (node.lon, node.lat) = (lon, lat);
+            }
+        }
+        if moved {
+            center(feature);
+        }
+        moved
+    }
+}
+
+/// Place a way at the center of its bounding box, as GeoDESK does
+fn center(feature: &mut Feature) {
+    if let Some(bounds) = node_bounds(&feature.nodes) {
+        feature.lon = (bounds.west + bounds.east) / 2.0;
+        feature.lat = (bounds.south + bounds.north) / 2.0;
+    }
+}
+
+fn node_bounds(nodes: &[Node]) -> Option<BoundingBox> {
+    let first = nodes.first()?;
+    let mut bounds = BoundingBox::new(first.lon, first.lat, first.lon, first.lat);
+    for node in nodes {
+        bounds.west = bounds.west.min(node.lon);
+        bounds.south = bounds.south.min(node.lat);
+        bounds.east = bounds.east.max(node.lon);
+        bounds.north = bounds.north.max(node.lat);
+    }
+    Some(bounds)
+}
+
+pub(crate) fn within(feature: &Feature, bbox: &BoundingBox) -> bool {
+    match node_bounds(&feature.nodes) {
+        Some(bounds) => bounds.intersects(bbox),
+        None => {
+            BoundingBox::new(feature.lon, feature.lat, feature.lon, feature.lat).intersects(bbox)
+        }
+    }
+}
+
+fn malformed(message: &str) -> GeoDeskError {
+    GeoDeskError::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Malformed osmChange file: {}", message),
+    ))
+}
+
+fn number<T: std::str::FromStr>(value: Option<&str>) -> Result<T, GeoDeskError> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| malformed("missing or invalid number"))
+}
+
+// Attributes of an XML tag, as (name, unescaped value) pairs
+fn parse_attributes(s: &str) -> Result<Vec<(String, String)>, GeoDeskError> {
+    let mut attributes = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let (name, value) = rest
+            .split_once('=')
+            .ok_or_else(|| malformed("attribute without value"))?;
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''));
+        let quote = quote.ok_or_else(|| malformed("unquoted attribute"))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| malformed("unclosed attribute"))?;
+        attributes.push((name.trim().to_string(), unescape(&value[1..end + 1])?));
+        rest = value[end + 2..].trim_start();
+    }
+    Ok(attributes)
+}
+
+fn unescape(s: &str) -> Result<String, GeoDeskError> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| malformed("unterminated entity"))?;
+        let entity = &rest[start + 1..start + end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|d| d.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+                    .ok_or_else(|| malformed("unknown entity"))?
+            }
+        };
+        out.push(c);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+impl GeoDesk {
+    /// Query the GOL and overlay changes read since it was built (see
+    /// [`Updates::apply`])
+    ///
+    /// The query is evaluated in Rust for changed features, so it must be
+    /// a single selector without regular expressions (see
+    /// [`Query`](crate::query::Query)).
+    pub fn query_updated(
+        &self,
+        goql_query: &str,
+        bbox: BoundingBox,
+        updates: &Updates,
+    ) -> Result<Vec<Feature>, GeoDeskError> {
+        let query: Query = goql_query
+            .parse()
+            .map_err(|e| GeoDeskError::Engine(format!("{}", e)))?;
+        let features = self.query(goql_query, bbox)?.to_vec()?;
+        Ok(updates.apply(&query, bbox, features))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OSC: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osmChange version="0.6" generator="test">
+  <modify>
+    <node id="1" version="2" lat="0.5" lon="0.25"/>
+    <way id="10" version="3">
+      <nd ref="1"/>
+      <nd ref="2"/>
+      <tag k="highway" v="residential"/>
+      <tag k="name" v="Smith &amp; Sons Road"/>
+    </way>
+  </modify>
+  <!-- a comment -->
+  <create>
+    <node id="3" version="1" lat="0.1" lon="0.1">
+      <tag k="amenity" v="cafe"/>
+    </node>
+  </create>
+  <delete>
+    <way id="11" version="5"/>
+  </delete>
+</osmChange>"#;
+
+    fn way(id: i64, tags: &[(&str, &str)], nodes: &[(i64, f64, f64)]) -> Feature {
+        Feature {
+            id,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: nodes
+                .iter()
+                .map(|&(id, lon, lat)| Node { id, lon, lat })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_apply_osc() {
+        let mut updates = Updates::new();
+        assert_eq!(updates.read_osc(OSC.as_bytes()).unwrap(), 4);
+        assert_eq!((updates.changed(), updates.deleted()), (3, 1));
+        assert!(updates.is_deleted(FeatureType::Way, 11));
+
+        let features = vec![
+            way(10, &[("highway", "track")], &[(1, 0.0, 0.0), (2, 1.0, 0.0)]),
+            way(
+                11,
+                &[("highway", "service")],
+                &[(4, 0.0, 0.0), (5, 0.0, 1.0)],
+            ),
+            way(12, &[("highway", "path")], &[(1, 0.0, 0.0), (6, 0.0, 1.0)]),
+        ];
+        let bbox = BoundingBox::new(0.0, 0.0, 1.0, 1.0);
+        let updated = updates.apply(&"w[highway]".parse().unwrap(), bbox, features.clone());
+        let ids: Vec<i64> = updated.iter().map(|f| f.id).collect();
+        assert_eq!(ids, [10, 12]);
+        assert_eq!(updated[0].name, "Smith & Sons Road");
+        assert_eq!(updated[0].nodes[0].lat, 0.5);
+        // Way 12 follows its moved node
+        assert_eq!(
+            (updated[1].nodes[0].lon, updated[1].nodes[0].lat),
+            (0.25, 0.5)
+        );
+
+        let cafes = updates.apply(&"n[amenity=cafe]".parse().unwrap(), bbox, Vec::new());
+        assert_eq!(cafes.len(), 1);
+        assert_eq!((cafes[0].id, cafes[0].lon), (3, 0.1));
+
+        // A later diff deleting the cafe wins
+        let later = r#"<osmChange><delete><node id="3"/></delete></osmChange>"#;
+        updates.read_osc(later.as_bytes()).unwrap();
+        assert!(updates
+            .apply(&"n[amenity=cafe]".parse().unwrap(), bbox, Vec::new())
+            .is_empty());
+        assert!(updates
+            .read_osc("<osmChange><node id=\"1\"/>".as_bytes())
+            .is_err());
+    }
+}
@@ -0,0 +1,192 @@
+// Checking and repairing broken way geometries
+
+use crate::{Feature, FeatureType, Node};
+use std::fmt;
+
+/// Keys of ways meant as areas even where mappers forgot to close them
+const AREA_KEYS: [&str; 3] = ["building", "landuse", "leisure"];
+
+/// A problem found by [`Feature::geometry_issues`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryIssue {
+    /// A coordinate that is not a number or out of range
+    InvalidCoordinates,
+    /// Consecutive nodes at the same location, with the number of nodes
+    /// that repeat the one before
+    DuplicateNodes(usize),
+    /// A way with fewer distinct locations than a line (two) or a closed
+    /// ring (three) needs
+    TooFewNodes,
+    /// A way tagged as an area whose first and last nodes differ
+    UnclosedRing,
+}
+
+impl fmt::Display for GeometryIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryIssue::InvalidCoordinates => f.write_str("invalid coordinates"),
+            GeometryIssue::DuplicateNodes(count) => write!(f, "{} duplicate nodes", count),
+            GeometryIssue::TooFewNodes => f.write_str("too few nodes"),
+            GeometryIssue::UnclosedRing => f.write_str("unclosed ring"),
+        }
+    }
+}
+
+fn valid_coordinates(lon: f64, lat: f64) -> bool {
+    (-180.0..=180.0).contains(&lon) && (-90.0..=90.0).contains(&lat)
+}
+
+fn same_location(a: &Node, b: &Node) -> bool {
+    a.lon == b.lon && a.lat == b.lat
+}
+
+impl Feature {
+    /// Problems with the feature's geometry that trip up GIS tools
+    ///
+    /// Ways read without geometry (with no nodes) are only checked for
+    /// their location.
+    pub fn geometry_issues(&self) -> Vec<GeometryIssue> {
+        let mut issues = Vec::new();
+        if !valid_coordinates(self.lon, self.lat)
+            || !self.nodes.iter().all(|n| valid_coordinates(n.lon, n.lat))
+        {
+            issues.push(GeometryIssue::InvalidCoordinates);
+        }
+        if self.feature_type() != FeatureType::Way || self.nodes.is_empty() {
+            return issues;
+        }
+        let duplicates = self
+            .nodes
+            .windows(2)
+            .filter(|pair| same_location(&pair[0], &pair[1]))
+            .count();
+        if duplicates > 0 {
+            issues.push(GeometryIssue::DuplicateNodes(duplicates));
+        }
+        let distinct = self.nodes.len() - duplicates;
+        let closed = self.nodes.len() > 1 && self.is_closed();
+        // A closed ring repeats its first location at the end
+        if distinct < 2 || (closed && distinct < 4) {
+            issues.push(GeometryIssue::TooFewNodes);
+        }
+        if !closed && self.meant_as_area() {
+            issues.push(GeometryIssue::UnclosedRing);
+        }
+        issues
+    }
+
+    /// Check that the feature's geometry has none of the
+    /// [`GeometryIssue`]s
+    pub fn geometry_valid(&self) -> bool {
+        self.geometry_issues().is_empty()
+    }
+
+    /// Fix the issues that can be fixed: drop nodes repeating the location
+    /// of the one before, and close ways tagged as areas by repeating
+    /// their first node
+    ///
+    /// Invalid coordinates and ways with too few nodes are left as they
+    /// are; check [`geometry_valid`](Self::geometry_valid) afterwards to
+    /// skip them. Returns `true` if the geometry changed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::testing::bboxes;
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("denmark.gol").unwrap();
+    /// let buildings = geodesk.query("w[building]", bboxes::COPENHAGEN).unwrap();
+    /// for mut building in buildings.to_vec().unwrap() {
+    ///     building.repair();
+    ///     if !building.geometry_valid() {
+    ///         eprintln!("skipping way {}: {:?}", building.id, building.geometry_issues());
+    ///         continue;
+    ///     }
+    ///     // Write the building out
+    /// }
+    /// ```
+    pub fn repair(&mut self) -> bool {
+        if self.feature_type() != FeatureType::Way || self.nodes.is_empty() {
+            return false;
+        }
+        let before = self.nodes.len();
+        self.nodes.dedup_by(|b, a| same_location(a, b));
+        let mut changed = self.nodes.len() != before;
+        if self.nodes.len() >= 3 && !self.is_closed() && self.meant_as_area() {
+            self.nodes.push(self.nodes[0]);
+            changed = true;
+        }
+        changed
+    }
+
+    fn is_closed(&self) -> bool {
+        match (self.nodes.first(), self.nodes.last()) {
+            (Some(first), Some(last)) => first.id == last.id || same_location(first, last),
+            _ => false,
+        }
+    }
+
+    fn meant_as_area(&self) -> bool {
+        match self.tag("area") {
+            Some("yes") => true,
+            Some("no") => false,
+            _ => AREA_KEYS.iter().any(|key| self.has_tag(key)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn way(tags: &[(&str, &str)], nodes: &[(i64, f64, f64)]) -> Feature {
+        Feature {
+            id: 1,
+            type_name: "way".to_string(),
+            name: String::new(),
+            lon: 0.5,
+            lat: 0.5,
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            nodes: nodes
+                .iter()
+                .map(|&(id, lon, lat)| Node { id, lon, lat })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_geometry_issues_and_repair() {
+        let mut building = way(
+            &[("building", "yes")],
+            &[(1, 0.0, 0.0), (2, 1.0, 0.0), (2, 1.0, 0.0), (3, 1.0, 1.0)],
+        );
+        assert_eq!(
+            building.geometry_issues(),
+            vec![
+                GeometryIssue::DuplicateNodes(1),
+                GeometryIssue::UnclosedRing
+            ]
+        );
+        assert!(building.repair());
+        assert_eq!(building.nodes.len(), 4);
+        assert_eq!(building.nodes[3].id, 1);
+        assert!(building.geometry_valid());
+        assert!(!building.repair());
+
+        // An unclosed road is fine, a single location is not
+        let road = way(&[("highway", "primary")], &[(1, 0.0, 0.0), (2, 1.0, 0.0)]);
+        assert!(road.geometry_valid());
+        let mut stub = way(&[("highway", "primary")], &[(1, 0.0, 0.0), (2, 0.0, 0.0)]);
+        stub.repair();
+        assert_eq!(stub.geometry_issues(), vec![GeometryIssue::TooFewNodes]);
+
+        let mut outside = way(&[], &[(1, 0.0, 0.0), (2, 0.0, 91.0)]);
+        outside.lat = 91.0;
+        assert!(outside
+            .geometry_issues()
+            .contains(&GeometryIssue::InvalidCoordinates));
+    }
+}
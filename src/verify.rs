@@ -0,0 +1,199 @@
+// Checking the structure of a GOL file, e.g. after copying it over a network
+
+use crate::error::GeoDeskError;
+use crate::testing::bboxes;
+use crate::tiles::TileId;
+use crate::GeoDesk;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Size of the pages a GOL file is made of; tiles start at page
+/// boundaries
+pub(crate) const PAGE_SIZE: u64 = 4096;
+
+/// Bytes read at the start of each tile to check that it holds data
+const PROBE_SIZE: usize = 8;
+
+/// What is wrong with a tile, from [`GeoDesk::verify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileProblem {
+    /// The tile starts at or beyond the end of the file
+    Truncated,
+    /// The start of the tile holds only zeros, as where a download that
+    /// preallocated the file never wrote the tile
+    Zeroed,
+    /// The tile index points this tile to the same page as another one
+    SharedPage(TileId),
+}
+
+impl fmt::Display for TileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileProblem::Truncated => f.write_str("truncated"),
+            TileProblem::Zeroed => f.write_str("zeroed"),
+            TileProblem::SharedPage(other) => {
+                write!(
+                    f,
+                    "shares its page with {}/{}/{}",
+                    other.z, other.x, other.y
+                )
+            }
+        }
+    }
+}
+
+/// Result of [`GeoDesk::verify`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerifyReport {
+    /// Size of the GOL file in bytes
+    pub file_size: u64,
+    /// Number of tiles present in the file, all of which were checked
+    pub tiles_checked: usize,
+    /// The file doesn't end at a page boundary, as if cut off
+    pub partial_page: bool,
+    /// Tiles with problems, in the order of the tile index
+    pub problems: Vec<(TileId, TileProblem)>,
+}
+
+impl VerifyReport {
+    /// Check if no problems were found
+    pub fn is_ok(&self) -> bool {
+        !self.partial_page && self.problems.is_empty()
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} tiles checked in {} bytes: ",
+            self.tiles_checked, self.file_size
+        )?;
+        if self.is_ok() {
+            return f.write_str("OK");
+        }
+        if self.partial_page {
+            f.write_str("file ends inside a page")?;
+            if !self.problems.is_empty() {
+                f.write_str(", ")?;
+            }
+        }
+        if !self.problems.is_empty() {
+            write!(f, "{} damaged tiles", self.problems.len())?;
+            for (tile, problem) in self.problems.iter().take(10) {
+                write!(f, "\n  {}/{}/{}: {}", tile.z, tile.x, tile.y, problem)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the tiles against the file they are stored in
+///
+/// Tiles are read with ordinary file reads rather than through the
+/// mapping the engine queries, which a truncated file would crash.
+fn check_tiles(path: &Path, tiles: &[(TileId, u32)]) -> Result<VerifyReport, GeoDeskError> {
+    let mut file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    let mut report = VerifyReport {
+        file_size,
+        tiles_checked: tiles.len(),
+        partial_page: file_size % PAGE_SIZE != 0,
+        problems: Vec::new(),
+    };
+    let mut owners: HashMap<u32, TileId> = HashMap::new();
+    for &(tile, page) in tiles {
+        if let Some(&other) = owners.get(&page) {
+            report.problems.push((tile, TileProblem::SharedPage(other)));
+            continue;
+        }
+        owners.insert(page, tile);
+        let offset = u64::from(page) * PAGE_SIZE;
+        if offset + PROBE_SIZE as u64 > file_size {
+            report.problems.push((tile, TileProblem::Truncated));
+            continue;
+        }
+        let mut probe = [0u8; PROBE_SIZE];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut probe)?;
+        if probe.iter().all(|&b| b == 0) {
+            report.problems.push((tile, TileProblem::Zeroed));
+        }
+    }
+    Ok(report)
+}
+
+impl GeoDesk {
+    /// Check the GOL for damage from an interrupted or corrupted transfer
+    ///
+    /// Every tile of the tile index that is present must start on a page
+    /// of its own inside the file, and the file must end at a page
+    /// boundary. GOLs don't store checksums of their tiles, so damage in
+    /// the middle of a tile goes unnoticed; compare the file's checksum
+    /// with the source's to rule that out as well.
+    ///
+    /// Only fails if the file can't be read; problems are listed in the
+    /// report.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use geodesk_rs::GeoDesk;
+    ///
+    /// let geodesk = GeoDesk::open("/data/incoming/planet.gol").unwrap();
+    /// let report = geodesk.verify().unwrap();
+    /// if !report.is_ok() {
+    ///     eprintln!("{}", report);
+    ///     std::process::exit(1);
+    /// }
+    /// ```
+    pub fn verify(&self) -> Result<VerifyReport, GeoDeskError> {
+        let tiles: Vec<(TileId, u32)> = self
+            .tiles(bboxes::WORLD)?
+            .into_iter()
+            .filter(|tile| tile.present)
+            .map(|tile| (TileId::from(tile), tile.page))
+            .collect();
+        check_tiles(&self.debug_state().path, &tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_check_tiles() {
+        let path = std::env::temp_dir().join(format!("geodesk-verify-{}.gol", std::process::id()));
+        // A header page, a tile on page 1 and a zeroed page 2
+        let mut data = vec![1u8; PAGE_SIZE as usize * 3];
+        data[PAGE_SIZE as usize * 2..].fill(0);
+        File::create(&path).unwrap().write_all(&data).unwrap();
+
+        let tile = |x| TileId { z: 12, x, y: 0 };
+        let tiles = [(tile(0), 1), (tile(1), 2), (tile(2), 1), (tile(3), 7)];
+        let report = check_tiles(&path, &tiles).unwrap();
+        assert_eq!(report.tiles_checked, 4);
+        assert!(!report.partial_page);
+        assert_eq!(
+            report.problems,
+            [
+                (tile(1), TileProblem::Zeroed),
+                (tile(2), TileProblem::SharedPage(tile(0))),
+                (tile(3), TileProblem::Truncated),
+            ]
+        );
+
+        File::create(&path)
+            .unwrap()
+            .write_all(&data[..5000])
+            .unwrap();
+        let report = check_tiles(&path, &tiles[..1]).unwrap();
+        assert!(report.partial_page && report.problems.is_empty());
+        assert!(!report.is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,392 @@
+// Aggregating features per zone (census tracts, delivery areas, ...)
+
+use crate::error::GeoDeskError;
+use crate::refs::FeatureRef;
+use crate::tags::{parse_number, NumberParsing};
+use crate::{BoundingBox, Feature, GeoDesk};
+use std::ops::ControlFlow;
+
+/// A zone outline: an exterior ring and optional holes, as (lon, lat)
+/// pairs; rings may be open or closed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<(f64, f64)>,
+    pub holes: Vec<Vec<(f64, f64)>>,
+}
+
+impl Polygon {
+    /// A polygon without holes
+    pub fn new(exterior: Vec<(f64, f64)>) -> Self {
+        Self {
+            exterior,
+            holes: Vec::new(),
+        }
+    }
+
+    /// Bounding box of the exterior ring
+    pub fn bounds(&self) -> BoundingBox {
+        let mut bbox = BoundingBox::new(f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for &(lon, lat) in &self.exterior {
+            bbox.west = bbox.west.min(lon);
+            bbox.south = bbox.south.min(lat);
+            bbox.east = bbox.east.max(lon);
+            bbox.north = bbox.north.max(lat);
+        }
+        bbox
+    }
+
+    /// Check if a point lies inside the exterior ring and outside the holes
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        ring_contains(&self.exterior, lon, lat)
+            && !self.holes.iter().any(|h| ring_contains(h, lon, lat))
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<&geo_types::Polygon<f64>> for Polygon {
+    fn from(polygon: &geo_types::Polygon<f64>) -> Self {
+        let ring = |line: &geo_types::LineString<f64>| line.coords().map(|c| (c.x, c.y)).collect();
+        Self {
+            exterior: ring(polygon.exterior()),
+            holes: polygon.interiors().iter().map(ring).collect(),
+        }
+    }
+}
+
+/// Even-odd test of a point against a ring
+fn ring_contains(ring: &[(f64, f64)], lon: f64, lat: f64) -> bool {
+    let mut inside = false;
+    let mut j = ring.len().wrapping_sub(1);
+    for i in 0..ring.len() {
+        let ((xi, yi), (xj, yj)) = (ring[i], ring[j]);
+        if (yi > lat) != (yj > lat) && lon < (xj - xi) * (lat - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// What [`zonal_stats`] aggregates besides the feature count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Only count the features
+    Count,
+    /// Also sum up a numeric tag (`capacity`, `population`, ...); values
+    /// are parsed tolerantly and features without a number are only counted
+    Tag(String),
+}
+
+/// Aggregates of the features inside one zone
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ZoneStats {
+    /// Features located in the zone
+    pub count: u64,
+    /// Features with a value for [`Aggregate::Tag`]
+    pub values: u64,
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl ZoneStats {
+    /// Average of the values, if there are any
+    pub fn mean(&self) -> Option<f64> {
+        (self.values > 0).then(|| self.sum / self.values as f64)
+    }
+
+    fn add(&mut self, value: Option<f64>) {
+        self.count += 1;
+        if let Some(value) = value {
+            self.values += 1;
+            self.sum += value;
+            self.min = Some(self.min.map_or(value, |m| m.min(value)));
+            self.max = Some(self.max.map_or(value, |m| m.max(value)));
+        }
+    }
+}
+
+/// Grid over the zones' bounding boxes, listing the zones overlapping
+/// each cell
+struct ZoneIndex {
+    bounds: BoundingBox,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+    zone_bounds: Vec<BoundingBox>,
+}
+
+impl ZoneIndex {
+    fn new(zones: &[Polygon]) -> Self {
+        let zone_bounds: Vec<BoundingBox> = zones.iter().map(Polygon::bounds).collect();
+        let bounds = zone_bounds.iter().fold(
+            BoundingBox::new(f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |a, b| {
+                BoundingBox::new(
+                    a.west.min(b.west),
+                    a.south.min(b.south),
+                    a.east.max(b.east),
+                    a.north.max(b.north),
+                )
+            },
+        );
+        // About one zone per cell
+        let side = (zones.len() as f64).sqrt().ceil().max(1.0) as usize;
+        let mut index = Self {
+            bounds,
+            columns: side,
+            rows: side,
+            cells: vec![Vec::new(); side * side],
+            zone_bounds,
+        };
+        for i in 0..index.zone_bounds.len() {
+            let zone = index.zone_bounds[i];
+            let (Some((c0, r0)), Some((c1, r1))) = (
+                index.cell(zone.west, zone.south),
+                index.cell(zone.east, zone.north),
+            ) else {
+                continue;
+            };
+            for row in r0..=r1 {
+                for column in c0..=c1 {
+                    index.cells[row * index.columns + column].push(i);
+                }
+            }
+        }
+        index
+    }
+
+    /// Column and row of the cell containing a point
+    fn cell(&self, lon: f64, lat: f64) -> Option<(usize, usize)> {
+        let b = &self.bounds;
+        if !(lon >= b.west && lon <= b.east && lat >= b.south && lat <= b.north) {
+            return None;
+        }
+        let scale = |v: f64, min: f64, max: f64, n: usize| {
+            let f = if max > min {
+                (v - min) / (max - min)
+            } else {
+                0.0
+            };
+            ((f * n as f64) as usize).min(n - 1)
+        };
+        Some((
+            scale(lon, b.west, b.east, self.columns),
+            scale(lat, b.south, b.north, self.rows),
+        ))
+    }
+
+    /// Add a feature to the stats of the zones containing it
+    fn add(
+        &self,
+        feature: &Feature,
+        zones: &[Polygon],
+        aggregate: &Aggregate,
+        stats: &mut [ZoneStats],
+    ) {
+        let value = match aggregate {
+            Aggregate::Count => None,
+            Aggregate::Tag(key) => feature
+                .tag_number(key, NumberParsing::Tolerant)
+                .ok()
+                .flatten(),
+        };
+        self.add_at(feature.lon, feature.lat, value, zones, stats);
+    }
+
+    /// Add a feature by its location and aggregated value
+    fn add_at(
+        &self,
+        lon: f64,
+        lat: f64,
+        value: Option<f64>,
+        zones: &[Polygon],
+        stats: &mut [ZoneStats],
+    ) {
+        for zone in self.candidates(lon, lat) {
+            if zones[zone].contains(lon, lat) {
+                stats[zone].add(value);
+            }
+        }
+    }
+
+    /// Zones whose bounding box contains a point
+    fn candidates(&self, lon: f64, lat: f64) -> impl Iterator<Item = usize> + '_ {
+        self.cell(lon, lat)
+            .map(|(column, row)| self.cells[row * self.columns + column].as_slice())
+            .unwrap_or_default()
+            .iter()
+            .copied()
+            .filter(move |&i| {
+                let z = &self.zone_bounds[i];
+                lon >= z.west && lon <= z.east && lat >= z.south && lat <= z.north
+            })
+    }
+}
+
+/// Count (and sum up a tag of) the features located in each zone
+///
+/// A feature belongs to every zone that contains its location (the center
+/// of ways and relations). The result has one entry per zone, in order.
+///
+/// # Example
+/// ```
+/// use geodesk_rs::zonal::{zonal_stats, Aggregate, Polygon};
+/// use geodesk_rs::Feature;
+///
+/// let zones = vec![
+///     Polygon::new(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]),
+///     Polygon::new(vec![(1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0)]),
+/// ];
+/// let parking = Feature {
+///     id: 1,
+///     type_name: "node".to_string(),
+///     name: String::new(),
+///     lon: 0.5,
+///     lat: 0.5,
+///     tags: vec![("capacity".to_string(), "40".to_string())],
+///     nodes: Vec::new(),
+/// };
+/// let stats = zonal_stats([&parking], &zones, &Aggregate::Tag("capacity".to_string()));
+/// assert_eq!(stats[0].sum, 40.0);
+/// assert_eq!(stats[1].count, 0);
+/// ```
+pub fn zonal_stats<'a, I>(features: I, zones: &[Polygon], aggregate: &Aggregate) -> Vec<ZoneStats>
+where
+    I: IntoIterator<Item = &'a Feature>,
+{
+    let index = ZoneIndex::new(zones);
+    let mut stats = vec![ZoneStats::default(); zones.len()];
+    for feature in features {
+        index.add(feature, zones, aggregate, &mut stats);
+    }
+    stats
+}
+
+impl GeoDesk {
+    /// Run a GOQL query over the area of a set of zones and aggregate the
+    /// matches per zone (see [`zonal_stats`])
+    ///
+    /// In [`LowMemoryMode`](crate::options::LowMemoryMode), the matches
+    /// are visited as [`FeatureRef`]s, reading only their location and
+    /// the aggregated tag; otherwise they are collected first.
+    pub fn zonal_stats(
+        &self,
+        goql_query: &str,
+        zones: &[Polygon],
+        aggregate: &Aggregate,
+    ) -> Result<Vec<ZoneStats>, GeoDeskError> {
+        if zones.is_empty() {
+            return Ok(Vec::new());
+        }
+        let index = ZoneIndex::new(zones);
+        let mut stats = vec![ZoneStats::default(); zones.len()];
+        if self.limits.low_memory.is_none() {
+            for feature in self.features(goql_query, index.bounds)? {
+                index.add(&feature?, zones, aggregate, &mut stats);
+            }
+            return Ok(stats);
+        }
+        let mut failed = None;
+        self.for_each(goql_query, index.bounds, |feature: FeatureRef<'_>| {
+            let value = match aggregate {
+                Aggregate::Count => None,
+                Aggregate::Tag(key) => match feature.tag(key) {
+                    Ok(value) => value.and_then(|v| parse_number(&v, NumberParsing::Tolerant).ok()),
+                    Err(e) => {
+                        failed = Some(e);
+                        return ControlFlow::Break(());
+                    }
+                },
+            };
+            index.add_at(feature.lon(), feature.lat(), value, zones, &mut stats);
+            ControlFlow::Continue(())
+        })?;
+        match failed {
+            Some(e) => Err(e),
+            None => Ok(stats),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(id: i64, lon: f64, lat: f64, capacity: &str) -> Feature {
+        Feature {
+            id,
+            type_name: "node".to_string(),
+            name: String::new(),
+            lon,
+            lat,
+            tags: vec![("capacity".to_string(), capacity.to_string())],
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_zonal_stats() {
+        let square = |x: f64| vec![(x, 0.0), (x + 2.0, 0.0), (x + 2.0, 2.0), (x, 2.0), (x, 0.0)];
+        let mut with_hole = Polygon::new(square(0.0));
+        with_hole
+            .holes
+            .push(vec![(0.5, 0.5), (1.0, 0.5), (1.0, 1.0), (0.5, 1.0)]);
+        let zones = vec![
+            with_hole,
+            Polygon::new(square(2.0)),
+            Polygon::new(square(1.0)),
+        ];
+        let features = vec![
+            point(1, 0.25, 0.25, "10"),
+            point(2, 0.75, 0.75, "99"), // In the hole
+            point(3, 1.5, 1.5, "5,5"),
+            point(4, 3.0, 1.0, "many"),
+            point(5, 9.0, 9.0, "1"), // Outside all zones
+        ];
+        let stats = zonal_stats(&features, &zones, &Aggregate::Tag("capacity".to_string()));
+
+        assert_eq!(stats[0].count, 2);
+        assert_eq!(stats[0].sum, 15.5);
+        assert_eq!(stats[0].mean(), Some(7.75));
+        assert_eq!((stats[0].min, stats[0].max), (Some(5.5), Some(10.0)));
+        assert_eq!((stats[1].count, stats[1].values), (1, 0));
+        assert_eq!(stats[1].mean(), None);
+        assert_eq!(stats[2].count, 1);
+
+        let counts = zonal_stats(&features, &zones, &Aggregate::Count);
+        assert_eq!(counts[0].values, 0);
+        assert_eq!(counts[0].count, 2);
+    }
+
+    #[cfg(feature = "test-data")]
+    #[test]
+    fn test_low_memory_zonal_stats() {
+        use crate::options::{LowMemoryMode, OpenOptions};
+
+        let Ok(path) = crate::test_data::TestGol::path() else {
+            return;
+        };
+        let geodesk = GeoDesk::open(&path).unwrap();
+        let small = GeoDesk::open_with(
+            &path,
+            OpenOptions::new().low_memory(LowMemoryMode::default()),
+        )
+        .unwrap();
+        let zones = vec![Polygon::new(vec![
+            (7.40, 43.72),
+            (7.44, 43.72),
+            (7.44, 43.76),
+            (7.40, 43.76),
+        ])];
+        let aggregate = Aggregate::Tag("capacity".to_string());
+        assert_eq!(
+            small
+                .zonal_stats("na[amenity=parking]", &zones, &aggregate)
+                .unwrap(),
+            geodesk
+                .zonal_stats("na[amenity=parking]", &zones, &aggregate)
+                .unwrap()
+        );
+    }
+}